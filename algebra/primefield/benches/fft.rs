@@ -8,7 +8,7 @@ use zkp_primefield::{
         fft_vec_recursive, get_twiddles,
         small::{radix_2, radix_2_twiddle, radix_4, radix_8},
     },
-    Fft, FieldElement, Root,
+    Fft, FieldElement, GoldilocksField, Root,
 };
 
 const SMALL: [usize; 9] = [4, 16, 64, 256, 1024, 4_096, 16_384, 65_536, 262_144];
@@ -102,6 +102,17 @@ fn fft_large(crit: &mut Criterion) {
     );
 }
 
+fn fft_goldilocks(crit: &mut Criterion) {
+    // Exclude from unit tests (single iter is long)
+    if cfg!(test) {
+        return;
+    }
+    log_size_bench(crit, "FFT Goldilocks size", &LARGE, move |bench, size| {
+        let mut values: Vec<GoldilocksField> = repeat_with(random).take(size).collect();
+        bench.iter(|| values.fft())
+    });
+}
+
 fn fft_threads(crit: &mut Criterion) {
     let size = if cfg!(test) { 16_384 } else { 4_194_304 };
     log_thread_bench(crit, "FFT threads", size, move |bench| {
@@ -114,5 +125,6 @@ pub fn group(crit: &mut Criterion) {
     fft_base(crit);
     fft_rec_small(crit);
     fft_large(crit);
+    fft_goldilocks(crit);
     fft_threads(crit);
 }
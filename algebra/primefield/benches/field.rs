@@ -1,7 +1,9 @@
 #![warn(clippy::all)]
 use criterion::{black_box, Criterion};
 use rand::prelude::*;
-use zkp_primefield::{FieldElement, Inv, SquareInline, SquareRoot};
+use zkp_primefield::{
+    BabyBearField, FieldElement, GoldilocksField, Inv, SliceOps, SquareInline, SquareRoot,
+};
 
 fn field_add(crit: &mut Criterion) {
     crit.bench_function("Field add", move |bench| {
@@ -36,6 +38,65 @@ fn field_mul(crit: &mut Criterion) {
     });
 }
 
+fn small_field_mul(crit: &mut Criterion) {
+    crit.bench_function("Goldilocks mul", move |bench| {
+        let a: &GoldilocksField = &random();
+        let b: &GoldilocksField = &random();
+        bench.iter(|| {
+            black_box(black_box(a) * black_box(b));
+        })
+    });
+    crit.bench_function("BabyBear mul", move |bench| {
+        let a: &BabyBearField = &random();
+        let b: &BabyBearField = &random();
+        bench.iter(|| {
+            black_box(black_box(a) * black_box(b));
+        })
+    });
+}
+
+fn field_slice_mul(crit: &mut Criterion) {
+    crit.bench_function("Field slice mul 1024", move |bench| {
+        let mut a: Vec<FieldElement> = (0..1024).map(|_| random()).collect();
+        let b: Vec<FieldElement> = (0..1024).map(|_| random()).collect();
+        bench.iter(|| FieldElement::mul_assign_slice(black_box(&mut a), black_box(&b)))
+    });
+    crit.bench_function("Field slice butterfly 1024", move |bench| {
+        let mut a: Vec<FieldElement> = (0..1024).map(|_| random()).collect();
+        let mut b: Vec<FieldElement> = (0..1024).map(|_| random()).collect();
+        let w: FieldElement = random();
+        bench.iter(|| {
+            FieldElement::butterfly_slice(black_box(&mut a), black_box(&mut b), black_box(&w))
+        })
+    });
+    // The same operations an element at a time, for comparison
+    crit.bench_function("Field loop mul 1024", move |bench| {
+        let mut a: Vec<FieldElement> = (0..1024).map(|_| random()).collect();
+        let b: Vec<FieldElement> = (0..1024).map(|_| random()).collect();
+        bench.iter(|| {
+            for (a, b) in black_box(&mut a).iter_mut().zip(black_box(&b)) {
+                *a *= b;
+            }
+        })
+    });
+    crit.bench_function("Field loop butterfly 1024", move |bench| {
+        let mut a: Vec<FieldElement> = (0..1024).map(|_| random()).collect();
+        let mut b: Vec<FieldElement> = (0..1024).map(|_| random()).collect();
+        let w: FieldElement = random();
+        bench.iter(|| {
+            let w = black_box(&w);
+            for (a, b) in black_box(&mut a)
+                .iter_mut()
+                .zip(black_box(&mut b).iter_mut())
+            {
+                let t = &*b * w;
+                *b = &*a - &t;
+                *a += t;
+            }
+        })
+    });
+}
+
 fn field_square(crit: &mut Criterion) {
     crit.bench_function("Field square", move |bench| {
         let a: &FieldElement = &random();
@@ -69,6 +130,8 @@ pub fn group(crit: &mut Criterion) {
     field_sub(crit);
     field_neg(crit);
     field_mul(crit);
+    small_field_mul(crit);
+    field_slice_mul(crit);
     field_square(crit);
     field_inv(crit);
     field_sqrt(crit);
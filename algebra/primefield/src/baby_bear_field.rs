@@ -0,0 +1,98 @@
+use crate::{Extendable, Parameters, PrimeField};
+
+/// The 31-bit field with modulus $2^{31} - 2^{27} + 1$
+///
+/// It has a two-adicity of 27, so FFTs up to size $2^{27}$ are supported.
+// TODO: Fix naming
+#[allow(clippy::module_name_repetitions)]
+pub type BabyBearField = PrimeField<BabyBear>;
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct BabyBear();
+
+impl Parameters for BabyBear {
+    type UInt = u32;
+
+    /// 31, in montgomery form.
+    const GENERATOR: u32 = 0x0fff_ffbe;
+    const M64: u64 = 0xc7c0_0000_77ff_ffff;
+    const MODULUS: u32 = 0x7800_0001;
+    const ORDER: u32 = 0x7800_0000;
+    const R1: u32 = 0x0fff_fffe;
+    const R2: u32 = 0x45dd_dde3;
+    const R3: u32 = 0x12f3_7bfb;
+}
+
+/// $X^2 - 11$ is irreducible since 11 is not a square.
+impl Extendable<2> for BabyBearField {
+    fn nonresidue() -> Self {
+        Self::from(11_u8)
+    }
+}
+
+/// $X^3 - 2$ is irreducible since 2 is not a cube.
+impl Extendable<3> for BabyBearField {
+    fn nonresidue() -> Self {
+        Self::from(2_u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft::permute, Fft, Inv, One, Pow, Root, SquareRoot, Zero};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(BabyBearField::from(31_u8), BabyBearField::generator());
+        assert_eq!(BabyBearField::one().to_uint(), 1);
+        assert_eq!(-BabyBearField::one(), BabyBearField::from(BabyBear::ORDER));
+        assert!(!BabyBearField::generator().is_quadratic_residue());
+    }
+
+    #[test]
+    fn test_roots_of_unity() {
+        let root = BabyBearField::root(1_usize << 27).unwrap();
+        assert!(root.pow(1_usize << 27).is_one());
+        assert!(!root.pow(1_usize << 26).is_one());
+        assert_eq!(BabyBearField::root(1_usize << 28), None);
+    }
+
+    proptest!(
+        #[test]
+        fn test_mul(a: BabyBearField, b: BabyBearField) {
+            let expected =
+                u64::from(a.to_uint()) * u64::from(b.to_uint()) % u64::from(BabyBear::MODULUS);
+            prop_assert_eq!(u64::from((&a * &b).to_uint()), expected);
+        }
+
+        #[test]
+        fn test_add_sub(a: BabyBearField, b: BabyBearField) {
+            let expected =
+                (u64::from(a.to_uint()) + u64::from(b.to_uint())) % u64::from(BabyBear::MODULUS);
+            prop_assert_eq!(u64::from((&a + &b).to_uint()), expected);
+            prop_assert_eq!(&(&a - &b) + &b, a);
+        }
+
+        #[test]
+        fn test_inv(a: BabyBearField) {
+            match a.inv() {
+                None => prop_assert!(a.is_zero()),
+                Some(inverse) => prop_assert!((a * inverse).is_one()),
+            }
+        }
+
+        #[test]
+        fn test_fft(values in (0_usize..=10).prop_flat_map(|size| {
+            prop::collection::vec(any::<BabyBearField>(), 1_usize << size)
+        })) {
+            let mut result = values.clone();
+            result.fft();
+            permute(&mut result);
+            result.ifft();
+            permute(&mut result);
+            prop_assert_eq!(result, values);
+        }
+    );
+}
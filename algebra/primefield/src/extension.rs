@@ -0,0 +1,709 @@
+// Using `Self` makes things less readable here.
+#![allow(clippy::use_self)]
+// False positives, see <https://github.com/rust-lang/rust/issues/55058>
+#![allow(single_use_lifetimes)]
+
+use crate::{
+    AddInline, FieldLike, Inv, MulInline, NegInline, One, Pow, Root, SliceOps, SquareInline,
+    SquareRoot, SubInline, Zero,
+};
+use std::{
+    iter::{Product, Sum},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+/// Fields that admit a binomial extension of degree `D`.
+pub trait Extendable<const D: usize>: Sized {
+    /// A constant $W$ such that $X^D - W$ is irreducible over the field.
+    fn nonresidue() -> Self;
+}
+
+/// The extension field $F[X] / (X^D - W)$
+///
+/// Elements are stored as their coefficients in the basis
+/// $1, X, \dots, X^{D - 1}$. Extension elements are required when the base
+/// field is too small to sample challenges from with sufficient soundness.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Ext<F, const D: usize> {
+    coefficients: [F; D],
+}
+
+impl<F, const D: usize> Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    pub fn new(coefficients: [F; D]) -> Self {
+        Self { coefficients }
+    }
+
+    pub fn coefficients(&self) -> &[F; D] {
+        &self.coefficients
+    }
+
+    /// Embeds a base field element.
+    pub fn from_base(value: F) -> Self {
+        let mut result = Self::zero();
+        result.coefficients[0] = value;
+        result
+    }
+
+    /// The generator $X$ of the extension.
+    pub fn generator() -> Self {
+        Self::new(std::array::from_fn(|i| {
+            if i == 1 {
+                F::one()
+            } else {
+                F::zero()
+            }
+        }))
+    }
+
+    /// Returns `Some` if the element is in the base field.
+    pub fn to_base(&self) -> Option<F> {
+        if self.coefficients[1..].iter().all(F::is_zero) {
+            Some(self.coefficients[0].clone())
+        } else {
+            None
+        }
+    }
+
+    /// Multiply by a base field element.
+    pub fn mul_base(&self, rhs: &F) -> Self {
+        Self::new(std::array::from_fn(|i| {
+            self.coefficients[i].mul_inline(rhs)
+        }))
+    }
+
+    // Multiply by X, reducing X^D to W.
+    fn shift(&self) -> Self {
+        Self::new(std::array::from_fn(|i| {
+            if i == 0 {
+                self.coefficients[D - 1].mul_inline(&F::nonresidue())
+            } else {
+                self.coefficients[i - 1].clone()
+            }
+        }))
+    }
+
+    // Solves for `x` with `self * x = 1` using Gaussian elimination on the
+    // matrix of multiplication by `self`. Column `j` holds `self * X^j`.
+    fn inverse(&self) -> Option<Self> {
+        if self.is_zero() {
+            return None;
+        }
+        let mut column = self.clone();
+        let mut columns: [[F; D]; D] = std::array::from_fn(|_| {
+            let next = column.shift();
+            std::mem::replace(&mut column, next).coefficients
+        });
+        let mut rhs: [F; D] = Self::one().coefficients;
+
+        // Row operations, applied to all columns and the right hand side.
+        for pivot in 0..D {
+            // The matrix is invertible, so a non-zero pivot exists.
+            let row = (pivot..D).find(|&row| !columns[pivot][row].is_zero())?;
+            for column in &mut columns {
+                column.swap(pivot, row);
+            }
+            rhs.swap(pivot, row);
+            let scale = F::one() / &columns[pivot][pivot];
+            for column in &mut columns {
+                column[pivot] *= &scale;
+            }
+            rhs[pivot] *= &scale;
+            for row in 0..D {
+                if row == pivot || columns[pivot][row].is_zero() {
+                    continue;
+                }
+                let factor = columns[pivot][row].clone();
+                for column in &mut columns {
+                    let delta = column[pivot].mul_inline(&factor);
+                    column[row] -= delta;
+                }
+                let delta = rhs[pivot].mul_inline(&factor);
+                rhs[row] -= delta;
+            }
+        }
+        Some(Self::new(rhs))
+    }
+}
+
+impl<F, const D: usize> From<F> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    fn from(value: F) -> Self {
+        Self::from_base(value)
+    }
+}
+
+impl<F, const D: usize> Default for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<F, const D: usize> Zero for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    #[inline(always)]
+    fn zero() -> Self {
+        Self::new(std::array::from_fn(|_| F::zero()))
+    }
+
+    #[inline(always)]
+    fn is_zero(&self) -> bool {
+        self.coefficients.iter().all(F::is_zero)
+    }
+}
+
+impl<F, const D: usize> One for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    #[inline(always)]
+    fn one() -> Self {
+        Self::from_base(F::one())
+    }
+
+    #[inline(always)]
+    fn is_one(&self) -> bool {
+        self.coefficients[0].is_one() && self.coefficients[1..].iter().all(F::is_zero)
+    }
+}
+
+impl<F, const D: usize> AddInline<&Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    #[inline(always)]
+    fn add_inline(&self, rhs: &Self) -> Self {
+        Self::new(std::array::from_fn(|i| {
+            self.coefficients[i].add_inline(&rhs.coefficients[i])
+        }))
+    }
+}
+
+impl<F, const D: usize> SubInline<&Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    #[inline(always)]
+    fn sub_inline(&self, rhs: &Self) -> Self {
+        Self::new(std::array::from_fn(|i| {
+            self.coefficients[i].sub_inline(&rhs.coefficients[i])
+        }))
+    }
+}
+
+impl<F, const D: usize> NegInline for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    #[inline(always)]
+    fn neg_inline(&self) -> Self {
+        Self::new(std::array::from_fn(|i| self.coefficients[i].neg_inline()))
+    }
+}
+
+impl<F, const D: usize> MulInline<&Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    // Schoolbook multiplication. The coefficients of X^{D + i} are
+    // reduced to W * X^i.
+    #[inline(always)]
+    fn mul_inline(&self, rhs: &Self) -> Self {
+        let mut low: [F; D] = std::array::from_fn(|_| F::zero());
+        let mut high: [F; D] = std::array::from_fn(|_| F::zero());
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in rhs.coefficients.iter().enumerate() {
+                let product = a.mul_inline(b);
+                if i + j < D {
+                    low[i + j] += product;
+                } else {
+                    high[i + j - D] += product;
+                }
+            }
+        }
+        let nonresidue = F::nonresidue();
+        for (low, high) in low.iter_mut().zip(high.iter()) {
+            *low += high.mul_inline(&nonresidue);
+        }
+        Self::new(low)
+    }
+}
+
+impl<F, const D: usize> SquareInline for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    #[inline(always)]
+    fn square_inline(&self) -> Self {
+        self.mul_inline(self)
+    }
+}
+
+impl<F, const D: usize> SliceOps for Ext<F, D> where F: FieldLike + Extendable<D> {}
+
+impl<F, const D: usize> Inv for &Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    type Output = Option<Ext<F, D>>;
+
+    fn inv(self) -> Self::Output {
+        self.inverse()
+    }
+}
+
+impl<F, const D: usize> Pow<usize> for &Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    type Output = Ext<F, D>;
+
+    fn pow(self, exponent: usize) -> Self::Output {
+        let mut result = Self::Output::one();
+        let mut square = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result *= &square;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                square.square_assign();
+            }
+        }
+        result
+    }
+}
+
+/// Roots of unity are taken from the base field.
+impl<F, const D: usize> Root<usize> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    fn root(order: usize) -> Option<Self> {
+        F::root(order).map(Self::from_base)
+    }
+}
+
+/// Square roots in quadratic extensions
+///
+/// An element is a square iff its norm $a_0^2 - W a_1^2$ is a square in the
+/// base field.
+impl<F> SquareRoot for Ext<F, 2>
+where
+    F: FieldLike + Extendable<2> + SquareRoot,
+{
+    fn is_quadratic_residue(&self) -> bool {
+        let [a0, a1] = &self.coefficients;
+        (a0.square() - a1.square() * F::nonresidue()).is_quadratic_residue()
+    }
+
+    fn square_root(&self) -> Option<Self> {
+        let [a0, a1] = &self.coefficients;
+        if a1.is_zero() {
+            // Either a0 is a square or a0 / W is.
+            return if a0.is_quadratic_residue() {
+                a0.square_root().map(Self::from_base)
+            } else {
+                (a0.clone() / F::nonresidue())
+                    .square_root()
+                    .map(|root| Self::new([F::zero(), root]))
+            };
+        }
+        let norm = a0.square() - a1.square() * F::nonresidue();
+        let norm_root = norm.square_root()?;
+
+        // The two candidates for x0^2 multiply to W a1^2 / 4, which is not a
+        // square, so exactly one of them is.
+        let half = F::one() / (F::one() + F::one());
+        let mut t = a0.add_inline(&norm_root) * &half;
+        if !t.is_quadratic_residue() {
+            t = a0.sub_inline(&norm_root) * &half;
+        }
+        let x0 = t.square_root()?;
+        let x1 = a1.mul_inline(&half) / &x0;
+        Some(Self::new([x0, x1]))
+    }
+}
+
+macro_rules! assign_ops_from_trait {
+    ($op_trait:ident, $op_fn:ident, $trait:ident, $trait_assign_fn:ident) => {
+        impl<F, const D: usize> $op_trait<Ext<F, D>> for Ext<F, D>
+        where
+            F: FieldLike + Extendable<D>,
+        {
+            #[inline(always)] // Simple wrapper in hot path
+            fn $op_fn(&mut self, rhs: Ext<F, D>) {
+                <Ext<F, D> as $trait<&Ext<F, D>>>::$trait_assign_fn(self, &rhs)
+            }
+        }
+
+        impl<F, const D: usize> $op_trait<&Ext<F, D>> for Ext<F, D>
+        where
+            F: FieldLike + Extendable<D>,
+        {
+            #[inline(always)] // Simple wrapper in hot path
+            fn $op_fn(&mut self, rhs: &Ext<F, D>) {
+                <Ext<F, D> as $trait<&Ext<F, D>>>::$trait_assign_fn(self, rhs)
+            }
+        }
+    };
+}
+
+macro_rules! self_ops_from_trait {
+    ($op_trait:ident, $op_fn:ident, $trait:ident, $trait_fn:ident, $trait_assign_fn:ident) => {
+        impl<F, const D: usize> $op_trait<&Ext<F, D>> for &Ext<F, D>
+        where
+            F: FieldLike + Extendable<D>,
+        {
+            type Output = Ext<F, D>;
+
+            #[inline(always)] // Simple wrapper in hot path
+            fn $op_fn(self, rhs: &Self::Output) -> Self::Output {
+                <Self::Output as $trait<&Self::Output>>::$trait_fn(self, rhs)
+            }
+        }
+
+        impl<F, const D: usize> $op_trait<&Ext<F, D>> for Ext<F, D>
+        where
+            F: FieldLike + Extendable<D>,
+        {
+            type Output = Ext<F, D>;
+
+            #[inline(always)] // Simple wrapper in hot path
+            fn $op_fn(mut self, rhs: &Self::Output) -> Self::Output {
+                <Self::Output as $trait<&Self::Output>>::$trait_assign_fn(&mut self, rhs);
+                self
+            }
+        }
+
+        impl<F, const D: usize> $op_trait<Ext<F, D>> for &Ext<F, D>
+        where
+            F: FieldLike + Extendable<D>,
+        {
+            type Output = Ext<F, D>;
+
+            #[inline(always)] // Simple wrapper in hot path
+            fn $op_fn(self, rhs: Self::Output) -> Self::Output {
+                <Self::Output as $trait<&Self::Output>>::$trait_fn(self, &rhs)
+            }
+        }
+
+        impl<F, const D: usize> $op_trait<Ext<F, D>> for Ext<F, D>
+        where
+            F: FieldLike + Extendable<D>,
+        {
+            type Output = Ext<F, D>;
+
+            #[inline(always)] // Simple wrapper in hot path
+            fn $op_fn(mut self, rhs: Self::Output) -> Self::Output {
+                <Self::Output as $trait<&Self::Output>>::$trait_assign_fn(&mut self, &rhs);
+                self
+            }
+        }
+    };
+}
+
+assign_ops_from_trait!(AddAssign, add_assign, AddInline, add_assign);
+assign_ops_from_trait!(SubAssign, sub_assign, SubInline, sub_assign);
+assign_ops_from_trait!(MulAssign, mul_assign, MulInline, mul_assign);
+self_ops_from_trait!(Add, add, AddInline, add, add_assign);
+self_ops_from_trait!(Sub, sub, SubInline, sub, sub_assign);
+self_ops_from_trait!(Mul, mul, MulInline, mul, mul_assign);
+
+impl<F, const D: usize> Neg for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    type Output = Ext<F, D>;
+
+    #[inline(always)]
+    fn neg(self) -> Self::Output {
+        <Self::Output as NegInline>::neg(&self)
+    }
+}
+
+impl<F, const D: usize> Neg for &Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    type Output = Ext<F, D>;
+
+    #[inline(always)]
+    fn neg(self) -> Self::Output {
+        <Self::Output as NegInline>::neg(self)
+    }
+}
+
+impl<F, const D: usize> DivAssign<&Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    // Division suspiciously requires multiplication
+    #[allow(clippy::suspicious_op_assign_impl)]
+    fn div_assign(&mut self, rhs: &Self) {
+        *self *= rhs.inv().expect("Division by zero")
+    }
+}
+
+impl<F, const D: usize> DivAssign<Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    fn div_assign(&mut self, rhs: Self) {
+        *self /= &rhs
+    }
+}
+
+impl<F, const D: usize> Div<&Ext<F, D>> for &Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    type Output = Ext<F, D>;
+
+    // Division suspiciously requires multiplication
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: &Self::Output) -> Self::Output {
+        self * rhs.inv().expect("Division by zero")
+    }
+}
+
+impl<F, const D: usize> Div<Ext<F, D>> for &Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    type Output = Ext<F, D>;
+
+    fn div(self, rhs: Self::Output) -> Self::Output {
+        self / &rhs
+    }
+}
+
+impl<F, const D: usize> Div<&Ext<F, D>> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    type Output = Ext<F, D>;
+
+    fn div(self, rhs: &Self::Output) -> Self::Output {
+        &self / rhs
+    }
+}
+
+impl<F, const D: usize> Div<Ext<F, D>> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    type Output = Ext<F, D>;
+
+    fn div(self, rhs: Self::Output) -> Self::Output {
+        &self / &rhs
+    }
+}
+
+impl<F, const D: usize> Sum<Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl<'a, F, const D: usize> Sum<&'a Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |a, b| a + b)
+    }
+}
+
+impl<F, const D: usize> Product<Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::one(), Mul::mul)
+    }
+}
+
+impl<'a, F, const D: usize> Product<&'a Self> for Ext<F, D>
+where
+    F: FieldLike + Extendable<D>,
+{
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self::one(), |a, b| a * b)
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<F, const D: usize> rand::distributions::Distribution<Ext<F, D>>
+    for rand::distributions::Standard
+where
+    F: FieldLike + Extendable<D>,
+    Self: rand::distributions::Distribution<F>,
+{
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Ext<F, D> {
+        Ext::new(std::array::from_fn(|_| {
+            <Self as rand::distributions::Distribution<F>>::sample(self, rng)
+        }))
+    }
+}
+
+#[cfg(any(test, feature = "proptest"))]
+impl<F, const D: usize> proptest::arbitrary::Arbitrary for Ext<F, D>
+where
+    F: FieldLike + Extendable<D> + proptest::arbitrary::Arbitrary + 'static,
+{
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::{collection::vec, prelude::*};
+        vec(any::<F>(), D)
+            .prop_map(|coefficients| {
+                let mut coefficients = coefficients.into_iter();
+                Self::new(std::array::from_fn(|_| coefficients.next().unwrap()))
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft::permute, BabyBearField, Fft, FieldElement, GoldilocksField};
+    use proptest::prelude::*;
+
+    type Quadratic = Ext<GoldilocksField, 2>;
+    type Cubic = Ext<BabyBearField, 3>;
+    type ProthQuadratic = Ext<FieldElement, 2>;
+
+    #[test]
+    fn test_nonresidues() {
+        assert!(!FieldElement::nonresidue().is_quadratic_residue());
+        assert!(!<GoldilocksField as Extendable<2>>::nonresidue().is_quadratic_residue());
+        assert!(!<BabyBearField as Extendable<2>>::nonresidue().is_quadratic_residue());
+        // W is not a cube iff W^((p - 1) / 3) != 1
+        let order = 0xffff_ffff_0000_0000_usize / 3;
+        let w = <GoldilocksField as Extendable<3>>::nonresidue();
+        assert!(!w.pow(order).is_one());
+        let w = <BabyBearField as Extendable<3>>::nonresidue();
+        assert!(!w.pow(0x7800_0000_usize / 3).is_one());
+    }
+
+    #[test]
+    fn test_generator() {
+        let x = Cubic::generator();
+        assert_eq!(
+            x.pow(3),
+            Cubic::from(<BabyBearField as Extendable<3>>::nonresidue())
+        );
+        assert_eq!(Quadratic::generator().to_base(), None);
+        assert_eq!(Quadratic::one().to_base(), Some(GoldilocksField::one()));
+    }
+
+    #[test]
+    fn test_fft_roundtrip() {
+        let original: Vec<Quadratic> = (0_u8..16)
+            .map(|i| Quadratic::new([i.into(), (i ^ 0xff).into()]))
+            .collect();
+        let mut values = original.clone();
+        values.fft();
+        permute(&mut values);
+        values.ifft();
+        permute(&mut values);
+        assert_eq!(values, original);
+    }
+
+    proptest!(
+        #[test]
+        fn test_add_sub(a: Quadratic, b: Quadratic) {
+            prop_assert_eq!(&(&a + &b) - &b, a);
+        }
+
+        #[test]
+        fn test_mul_commutative(a: Cubic, b: Cubic) {
+            prop_assert_eq!(&a * &b, &b * &a);
+        }
+
+        #[test]
+        fn test_mul_associative(a: Cubic, b: Cubic, c: Cubic) {
+            prop_assert_eq!(&(&a * &b) * &c, &a * &(&b * &c));
+        }
+
+        #[test]
+        fn test_distributive(a: Quadratic, b: Quadratic, c: Quadratic) {
+            prop_assert_eq!(&a * &(&b + &c), &a * &b + &a * &c);
+        }
+
+        #[test]
+        fn test_mul_base(a: Cubic, b: BabyBearField) {
+            prop_assert_eq!(a.mul_base(&b), &a * Cubic::from(b));
+        }
+
+        #[test]
+        fn test_inv_quadratic(a: Quadratic) {
+            match a.inv() {
+                None => prop_assert!(a.is_zero()),
+                Some(inverse) => prop_assert!((a * inverse).is_one()),
+            }
+        }
+
+        #[test]
+        fn test_inv_cubic(a: Cubic) {
+            match a.inv() {
+                None => prop_assert!(a.is_zero()),
+                Some(inverse) => prop_assert!((a * inverse).is_one()),
+            }
+        }
+
+        #[test]
+        fn test_inv_proth(a: ProthQuadratic) {
+            match a.inv() {
+                None => prop_assert!(a.is_zero()),
+                Some(inverse) => prop_assert!((a * inverse).is_one()),
+            }
+        }
+
+        #[test]
+        fn test_pow(a: Cubic, n: u8, m: u8) {
+            let (n, m) = (usize::from(n), usize::from(m));
+            prop_assert_eq!(a.pow(n) * a.pow(m), a.pow(n + m));
+        }
+
+        #[test]
+        fn test_square_root(a: Quadratic) {
+            let square = a.square();
+            prop_assert!(square.is_quadratic_residue());
+            let root = square.square_root().unwrap();
+            prop_assert!(root == a || root == -&a);
+        }
+
+        #[test]
+        fn test_square_root_base(a: GoldilocksField) {
+            // Every base field element is a square in the extension.
+            let root = Quadratic::from(a.clone()).square_root().unwrap();
+            prop_assert_eq!(root.square(), Quadratic::from(a));
+        }
+
+        #[test]
+        fn test_non_residue(a: Quadratic) {
+            let non_residue = a.square() * Quadratic::generator().pow(7);
+            if !a.is_zero() {
+                prop_assert!(!non_residue.is_quadratic_residue());
+                prop_assert_eq!(non_residue.square_root(), None);
+            }
+        }
+    );
+}
@@ -0,0 +1,429 @@
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use super::{field_from_usize, get_twiddles, permute_index};
+use crate::{FieldLike, Inv, Pow, RefFieldLike};
+use log::trace;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::cmp::max;
+
+/// Minimum number of elements handled by a single task.
+// TODO: Write benchmark and tune
+#[cfg(feature = "rayon")]
+const MIN_CHUNK_SIZE: usize = 1024;
+
+/// Precomputed twiddle factors shared between transforms
+///
+/// The permuted twiddles for size $n$ are a prefix of those for any larger
+/// size, so a single table serves all transforms up to its size.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TwiddleCache<Field> {
+    forward: Vec<Field>,
+    inverse: Vec<Field>,
+}
+
+impl<Field> TwiddleCache<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    /// Precomputes twiddles for transforms up to `size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not a power of two or the field has no root of
+    /// unity of that order.
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two());
+        let root = Field::root(size).expect("No root of unity for input length");
+        let inverse_root = root.pow(size - 1);
+        Self {
+            forward: get_twiddles(&root, size),
+            inverse: get_twiddles(&inverse_root, size),
+        }
+    }
+
+    /// The largest supported transform size.
+    pub fn size(&self) -> usize {
+        max(1, 2 * self.forward.len())
+    }
+
+    /// In-place FFTs of column-interleaved values on a coset.
+    ///
+    /// The `values` hold `width` columns of coefficients, with the row `i`
+    /// in `values[i * width..(i + 1) * width]`. Each column is replaced by
+    /// its evaluations on `shift` times the subgroup, in permuted order like
+    /// [`crate::Fft::fft_cofactor`].
+    pub fn fft_coset(&self, values: &mut [Field], width: usize, shift: &Field) {
+        trace!("BEGIN Coset FFT");
+        scale_rows(values, width, &Field::one(), shift);
+        self.forward(values, width);
+        trace!("END Coset FFT");
+    }
+
+    /// Inverse of [`TwiddleCache::fft_coset`].
+    ///
+    /// Takes evaluations in permuted order and returns coefficients in
+    /// natural order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shift` is zero.
+    pub fn ifft_coset(&self, values: &mut [Field], width: usize, shift: &Field) {
+        trace!("BEGIN Coset inverse FFT");
+        self.inverse(values, width);
+        let size = rows(values, width);
+        let inverse_size = field_from_usize::<Field>(size)
+            .inv()
+            .expect("No inverse length for empty list");
+        let inverse_shift = shift.inv().expect("Can not invert shift");
+        scale_rows(values, width, &inverse_size, &inverse_shift);
+        trace!("END Coset inverse FFT");
+    }
+
+    /// Low degree extension of coefficient columns.
+    ///
+    /// Returns the column-interleaved evaluations of all `columns` on `shift`
+    /// times the subgroup of `blowup` times their length, in permuted order.
+    pub fn lde(&self, columns: &[&[Field]], blowup: usize, shift: &Field) -> Vec<Field> {
+        let length = columns.first().map_or(0, |column| column.len());
+        let mut result = vec![Field::zero(); columns.len() * length * blowup];
+        self.lde_into(columns, blowup, shift, &mut result);
+        result
+    }
+
+    /// Like [`TwiddleCache::lde`], but writes to `destination`.
+    ///
+    /// The evaluations are computed one coset of the original size at a time,
+    /// so the cache only needs to support that size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the columns differ in length, `blowup` is not a power of two
+    /// or `destination` has the wrong size.
+    pub fn lde_into(
+        &self,
+        columns: &[&[Field]],
+        blowup: usize,
+        shift: &Field,
+        destination: &mut [Field],
+    ) {
+        trace!("BEGIN Low degree extension");
+        let width = columns.len();
+        let length = columns.first().map_or(0, |column| column.len());
+        assert!(columns.iter().all(|column| column.len() == length));
+        assert!(blowup.is_power_of_two());
+        assert_eq!(destination.len(), width * length * blowup);
+        if width == 0 || length == 0 {
+            return;
+        }
+        let coset = |(i, chunk): (usize, &mut [Field])| {
+            self.lde_coset_into(columns, blowup, shift, i, chunk);
+        };
+        #[cfg(feature = "rayon")]
+        destination
+            .par_chunks_mut(width * length)
+            .enumerate()
+            .for_each(coset);
+        #[cfg(not(feature = "rayon"))]
+        destination
+            .chunks_mut(width * length)
+            .enumerate()
+            .for_each(coset);
+        trace!("END Low degree extension");
+    }
+
+    /// A single coset of [`TwiddleCache::lde_into`].
+    ///
+    /// Writes the `coset`-th chunk of the result, which holds the evaluations
+    /// on a coset of the original size, to `destination`. This allows
+    /// computing and consuming an extension one coset at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the columns differ in length, `blowup` is not a power of two,
+    /// `coset` is not less than `blowup` or `destination` has the wrong size.
+    pub fn lde_coset_into(
+        &self,
+        columns: &[&[Field]],
+        blowup: usize,
+        shift: &Field,
+        coset: usize,
+        destination: &mut [Field],
+    ) {
+        let width = columns.len();
+        let length = columns.first().map_or(0, |column| column.len());
+        assert!(columns.iter().all(|column| column.len() == length));
+        assert!(blowup.is_power_of_two());
+        assert!(coset < blowup);
+        assert_eq!(destination.len(), width * length);
+        if width == 0 || length == 0 {
+            return;
+        }
+        let generator = Field::root(length * blowup).expect("No root of unity for extended length");
+        let cofactor = shift * generator.pow(permute_index(blowup, coset));
+        for (row, values) in destination.chunks_mut(width).enumerate() {
+            for (value, column) in values.iter_mut().zip(columns.iter()) {
+                *value = column[row].clone();
+            }
+        }
+        self.fft_coset(destination, width, &cofactor);
+    }
+
+    // Cooley-Tukey with natural order input and permuted output.
+    fn forward(&self, values: &mut [Field], width: usize) {
+        let size = rows(values, width);
+        assert!(size <= self.size());
+        let mut half = size / 2;
+        let mut blocks = 1;
+        while half > 0 {
+            stage(
+                values,
+                half * width,
+                &self.forward[..blocks],
+                Field::butterfly_slice,
+            );
+            half /= 2;
+            blocks *= 2;
+        }
+    }
+
+    // Gentleman-Sande undoing `forward` stage by stage, without scaling.
+    fn inverse(&self, values: &mut [Field], width: usize) {
+        let size = rows(values, width);
+        assert!(size <= self.size());
+        let mut half = 1;
+        let mut blocks = size / 2;
+        while blocks > 0 {
+            stage(
+                values,
+                half * width,
+                &self.inverse[..blocks],
+                inverse_butterfly,
+            );
+            half *= 2;
+            blocks /= 2;
+        }
+    }
+}
+
+/// In-place FFTs of column-interleaved values on a coset.
+///
+/// See [`TwiddleCache::fft_coset`]. Use a [`TwiddleCache`] directly to reuse
+/// the twiddles between calls.
+pub fn fft_coset<Field>(values: &mut [Field], width: usize, shift: &Field)
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    TwiddleCache::new(rows(values, width)).fft_coset(values, width, shift);
+}
+
+/// In-place inverse of [`fft_coset`].
+///
+/// See [`TwiddleCache::ifft_coset`].
+pub fn ifft_coset<Field>(values: &mut [Field], width: usize, shift: &Field)
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    TwiddleCache::new(rows(values, width)).ifft_coset(values, width, shift);
+}
+
+/// Low degree extension of coefficient columns.
+///
+/// See [`TwiddleCache::lde`].
+pub fn lde<Field>(columns: &[&[Field]], blowup: usize, shift: &Field) -> Vec<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    let length = columns.first().map_or(1, |column| max(1, column.len()));
+    TwiddleCache::new(length).lde(columns, blowup, shift)
+}
+
+fn rows<Field>(values: &[Field], width: usize) -> usize {
+    assert!(width > 0);
+    assert_eq!(values.len() % width, 0);
+    let size = values.len() / width;
+    assert!(size.is_power_of_two());
+    size
+}
+
+// Multiplies row `i` by `factor * step^i`.
+fn scale_rows<Field>(values: &mut [Field], width: usize, factor: &Field, step: &Field)
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    #[cfg(feature = "rayon")]
+    let chunk_rows = max(MIN_CHUNK_SIZE / width, 1);
+    #[cfg(not(feature = "rayon"))]
+    let chunk_rows = 0;
+    let scale = |(i, chunk): (usize, &mut [Field])| {
+        let mut scale = factor * step.pow(i * chunk_rows);
+        for row in chunk.chunks_mut(width) {
+            Field::scale_slice(row, &scale);
+            scale *= step;
+        }
+    };
+    #[cfg(feature = "rayon")]
+    values
+        .par_chunks_mut(chunk_rows * width)
+        .enumerate()
+        .for_each(scale);
+    #[cfg(not(feature = "rayon"))]
+    scale((0, values));
+}
+
+// Applies a butterfly to each pair of halves of blocks of size `2 * half`,
+// with the twiddle for each block.
+fn stage<Field>(
+    values: &mut [Field],
+    half: usize,
+    twiddles: &[Field],
+    butterfly: fn(&mut [Field], &mut [Field], &Field),
+) where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    debug_assert_eq!(values.len(), 2 * half * twiddles.len());
+    #[cfg(feature = "rayon")]
+    values
+        .par_chunks_mut(2 * half)
+        .zip(twiddles.par_iter())
+        .for_each(|(block, twiddle)| {
+            let (left, right) = block.split_at_mut(half);
+            left.par_chunks_mut(MIN_CHUNK_SIZE)
+                .zip(right.par_chunks_mut(MIN_CHUNK_SIZE))
+                .for_each(|(left, right)| butterfly(left, right, twiddle));
+        });
+    #[cfg(not(feature = "rayon"))]
+    for (block, twiddle) in values.chunks_mut(2 * half).zip(twiddles.iter()) {
+        let (left, right) = block.split_at_mut(half);
+        butterfly(left, right, twiddle);
+    }
+}
+
+// (x, y) -> (x + y, (x - y) / w), twice the inverse of the forward butterfly
+fn inverse_butterfly<Field>(left: &mut [Field], right: &mut [Field], inverse_twiddle: &Field)
+where
+    Field: FieldLike,
+{
+    Field::butterfly_slice(left, right, &Field::one());
+    Field::scale_slice(right, inverse_twiddle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fft, FieldElement, GoldilocksField, One, Zero};
+    use proptest::{collection::vec, prelude::*};
+
+    // Interleave columns of equal length
+    fn interleave<Field: Clone>(columns: &[Vec<Field>]) -> Vec<Field> {
+        let length = columns[0].len();
+        (0..length)
+            .flat_map(|row| columns.iter().map(move |column| column[row].clone()))
+            .collect()
+    }
+
+    fn arb_columns(log_size: usize) -> impl Strategy<Value = Vec<Vec<FieldElement>>> {
+        vec(vec(any::<FieldElement>(), 1 << log_size), 1..4)
+    }
+
+    #[test]
+    fn test_twiddle_prefix() {
+        let large = TwiddleCache::<GoldilocksField>::new(64);
+        let small = TwiddleCache::<GoldilocksField>::new(8);
+        assert_eq!(large.forward[..4], small.forward[..]);
+        assert_eq!(large.inverse[..4], small.inverse[..]);
+        assert_eq!(TwiddleCache::<GoldilocksField>::new(1).size(), 1);
+    }
+
+    #[test]
+    fn test_lde_empty() {
+        let columns: [&[FieldElement]; 0] = [];
+        assert!(lde(&columns, 4, &FieldElement::generator()).is_empty());
+    }
+
+    proptest!(
+        #[test]
+        fn test_fft_coset(log_size in 0_usize..7, seed: FieldElement, shift: FieldElement) {
+            let size = 1 << log_size;
+            let columns: Vec<Vec<FieldElement>> = (0..3_usize)
+                .map(|c| (0..size).map(|i| &seed * FieldElement::from(i + c * size + 1)).collect())
+                .collect();
+            let mut values = interleave(&columns);
+            fft_coset(&mut values, 3, &shift);
+            let expected: Vec<_> = columns
+                .iter()
+                .map(|column| {
+                    let mut column = column.clone();
+                    column.fft_cofactor(&shift);
+                    column
+                })
+                .collect();
+            prop_assert_eq!(values, interleave(&expected));
+        }
+
+        #[test]
+        fn test_ifft_coset(columns in arb_columns(5), shift: FieldElement) {
+            prop_assume!(!shift.is_zero());
+            let width = columns.len();
+            let original = interleave(&columns);
+            let mut values = original.clone();
+            fft_coset(&mut values, width, &shift);
+            ifft_coset(&mut values, width, &shift);
+            prop_assert_eq!(values, original);
+        }
+
+        #[test]
+        fn test_lde(columns in arb_columns(4), log_blowup in 0_usize..4) {
+            let blowup = 1 << log_blowup;
+            let shift = FieldElement::generator();
+            let slices: Vec<&[FieldElement]> = columns.iter().map(Vec::as_slice).collect();
+            let result = lde(&slices, blowup, &shift);
+            // Compare with a single large transform of the padded coefficients
+            let expected: Vec<_> = columns
+                .iter()
+                .map(|column| {
+                    let mut padded = column.clone();
+                    padded.resize(column.len() * blowup, FieldElement::zero());
+                    padded.fft_cofactor(&shift);
+                    padded
+                })
+                .collect();
+            prop_assert_eq!(result, interleave(&expected));
+        }
+
+        #[test]
+        fn test_lde_coset(columns in arb_columns(4), log_blowup in 0_usize..4) {
+            let blowup = 1 << log_blowup;
+            let shift = FieldElement::generator();
+            let slices: Vec<&[FieldElement]> = columns.iter().map(Vec::as_slice).collect();
+            let expected = lde(&slices, blowup, &shift);
+            let cache = TwiddleCache::new(columns[0].len());
+            let mut coset = vec![FieldElement::zero(); columns.len() * columns[0].len()];
+            for (i, chunk) in expected.chunks(coset.len()).enumerate() {
+                cache.lde_coset_into(&slices, blowup, &shift, i, &mut coset);
+                prop_assert_eq!(&coset, chunk);
+            }
+        }
+
+        #[test]
+        fn test_ifft_goldilocks(values in vec(any::<GoldilocksField>(), 32)) {
+            // Natural order inverse of the permuted forward transform
+            let mut expected = values.clone();
+            expected.fft();
+            let mut result = expected;
+            ifft_coset(&mut result, 1, &GoldilocksField::one());
+            prop_assert_eq!(result, values);
+        }
+    );
+}
@@ -9,6 +9,7 @@
 #[allow(clippy::wildcard_imports)]
 use std::prelude::v1::*;
 
+mod batch;
 mod bit_reverse;
 mod prefetch;
 mod recursive;
@@ -27,6 +28,7 @@ use std::cmp::max;
 
 // Re-exports
 // TODO: Only re-export for bench
+pub use batch::{fft_coset, ifft_coset, lde, TwiddleCache};
 pub use bit_reverse::{permute, permute_index};
 #[cfg(feature = "memadvise")]
 pub use memadvise::Advice;
@@ -65,7 +67,7 @@ pub use transpose::transpose_square_stretch;
 /// Blanket implementation of [`Fft`] for all slices of a [`FieldLike`]
 impl<Field> Fft<Field> for [Field]
 where
-    Field: FieldLike + From<usize> + Send + Sync,
+    Field: FieldLike + Send + Sync,
     for<'a> &'a Field: RefFieldLike<Field>,
 {
     fn fft(&mut self) {
@@ -77,14 +79,12 @@ where
         let inverse_root = Field::root(self.len())
             .expect("No root of unity for input length")
             .pow(self.len() - 1);
-        let inverse_length = Field::from(self.len())
+        let inverse_length = field_from_usize::<Field>(self.len())
             .inv()
             .expect("No inverse length for empty list");
         self.fft_root(&inverse_root);
         trace!("BEGIN Inverse shift");
-        for e in self.iter_mut() {
-            *e *= &inverse_length;
-        }
+        Field::scale_slice(self, &inverse_length);
         trace!("END Inverse shift");
     }
 
@@ -155,6 +155,22 @@ where
     }
 }
 
+/// Convert by double-and-add, as not all fields implement `From<usize>`.
+fn field_from_usize<Field>(n: usize) -> Field
+where
+    Field: FieldLike,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    let mut result = Field::zero();
+    for i in (0..usize::BITS).rev() {
+        result = &result + &result;
+        if (n >> i) & 1 == 1 {
+            result += Field::one();
+        }
+    }
+    result
+}
+
 // TODO: Memoize
 pub fn get_twiddles<Field>(root: &Field, size: usize) -> Vec<Field>
 where
@@ -0,0 +1,114 @@
+use crate::{Extendable, Parameters, PrimeField};
+
+/// The 64-bit field with modulus $2^{64} - 2^{32} + 1$
+///
+/// It has a two-adicity of 32, so FFTs up to size $2^{32}$ are supported.
+// TODO: Fix naming
+#[allow(clippy::module_name_repetitions)]
+pub type GoldilocksField = PrimeField<Goldilocks>;
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct Goldilocks();
+
+impl Parameters for Goldilocks {
+    type UInt = u64;
+
+    /// 7, in montgomery form.
+    const GENERATOR: u64 = 0x0000_0006_ffff_fff9;
+    const M64: u64 = 0xffff_fffe_ffff_ffff;
+    const MODULUS: u64 = 0xffff_ffff_0000_0001;
+    const ORDER: u64 = 0xffff_ffff_0000_0000;
+    const R1: u64 = 0x0000_0000_ffff_ffff;
+    const R2: u64 = 0xffff_fffe_0000_0001;
+    const R3: u64 = 0x0000_0000_0000_0001;
+}
+
+/// $X^2 - 7$ is irreducible since 7 is not a square.
+impl Extendable<2> for GoldilocksField {
+    fn nonresidue() -> Self {
+        Self::from(7_u8)
+    }
+}
+
+/// $X^3 - 2$ is irreducible since 2 is not a cube.
+impl Extendable<3> for GoldilocksField {
+    fn nonresidue() -> Self {
+        Self::from(2_u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft::permute, Fft, Inv, One, Pow, Root, SquareInline, SquareRoot, Zero};
+    use proptest::prelude::*;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn mul_mod(a: u64, b: u64) -> u64 {
+        (u128::from(a) * u128::from(b) % u128::from(Goldilocks::MODULUS)) as u64
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(GoldilocksField::from(7_u8), GoldilocksField::generator());
+        assert_eq!(GoldilocksField::one().to_uint(), 1);
+        assert_eq!(
+            -GoldilocksField::one(),
+            GoldilocksField::from(Goldilocks::ORDER)
+        );
+        assert!(!GoldilocksField::generator().is_quadratic_residue());
+    }
+
+    #[test]
+    fn test_roots_of_unity() {
+        let root = GoldilocksField::root(1_usize << 32).unwrap();
+        assert!(root.pow(1_usize << 32).is_one());
+        assert!(!root.pow(1_usize << 31).is_one());
+        assert_eq!(GoldilocksField::root(1_usize << 33), None);
+    }
+
+    proptest!(
+        #[test]
+        fn test_add(a: GoldilocksField, b: GoldilocksField) {
+            let expected = (u128::from(a.to_uint()) + u128::from(b.to_uint()))
+                % u128::from(Goldilocks::MODULUS);
+            prop_assert_eq!(u128::from((a + b).to_uint()), expected);
+        }
+
+        #[test]
+        fn test_sub(a: GoldilocksField, b: GoldilocksField) {
+            prop_assert_eq!(&(&a - &b) + &b, a);
+        }
+
+        #[test]
+        fn test_mul(a: GoldilocksField, b: GoldilocksField) {
+            prop_assert_eq!((&a * &b).to_uint(), mul_mod(a.to_uint(), b.to_uint()));
+        }
+
+        #[test]
+        fn test_inv(a: GoldilocksField) {
+            match a.inv() {
+                None => prop_assert!(a.is_zero()),
+                Some(inverse) => prop_assert!((a * inverse).is_one()),
+            }
+        }
+
+        #[test]
+        fn test_square_root(a: GoldilocksField) {
+            let root = a.square().square_root().unwrap();
+            prop_assert!(root == a || root == -a);
+        }
+
+        #[test]
+        fn test_fft(values in (0_usize..=10).prop_flat_map(|size| {
+            prop::collection::vec(any::<GoldilocksField>(), 1_usize << size)
+        })) {
+            let mut result = values.clone();
+            result.fft();
+            permute(&mut result);
+            result.ifft();
+            permute(&mut result);
+            prop_assert_eq!(result, values);
+        }
+    );
+}
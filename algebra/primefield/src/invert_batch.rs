@@ -10,29 +10,39 @@
 use std::prelude::v1::*;
 
 use crate::{FieldLike, Inv, RefFieldLike};
+use std::cmp::min;
+
+// Number of independent running products. Each is a column of the values,
+// so the multiplications can use `SliceOps` on rows.
+const STRANDS: usize = 8;
 
 pub fn invert_batch_src_dst<Field>(source: &[Field], destination: &mut [Field])
 where
-    Field: FieldLike + From<usize> + std::fmt::Debug,
+    Field: FieldLike + std::fmt::Debug,
     for<'a> &'a Field: RefFieldLike<Field>,
 {
     assert_eq!(source.len(), destination.len());
-    let mut accumulator = Field::one();
-    for (src, dst) in source.iter().zip(destination.iter_mut()) {
-        *dst = accumulator.clone();
-        accumulator *= &*src;
+    let mut accumulator = vec![Field::one(); min(STRANDS, source.len())];
+    for (src, dst) in source.chunks(STRANDS).zip(destination.chunks_mut(STRANDS)) {
+        dst.clone_from_slice(&accumulator[..dst.len()]);
+        Field::mul_assign_slice(&mut accumulator[..src.len()], src);
     }
-    accumulator = accumulator.inv().expect("Division by zero in batch invert");
-    for (src, dst) in source.iter().zip(destination.iter_mut()).rev() {
-        *dst *= &accumulator;
-        accumulator *= &*src;
+    let products = accumulator;
+    let mut accumulator = vec![Field::zero(); products.len()];
+    invert_sequential(&products, &mut accumulator);
+    for (src, dst) in source
+        .chunks(STRANDS)
+        .zip(destination.chunks_mut(STRANDS))
+        .rev()
+    {
+        Field::mul_assign_slice(dst, &accumulator[..dst.len()]);
+        Field::mul_assign_slice(&mut accumulator[..src.len()], src);
     }
-    // OPT: We can avoid the last multiplication
 }
 
 pub fn invert_batch<Field>(source: &[Field]) -> Vec<Field>
 where
-    Field: FieldLike + From<usize> + std::fmt::Debug,
+    Field: FieldLike + std::fmt::Debug,
     for<'a> &'a Field: RefFieldLike<Field>,
 {
     let mut result = vec![Field::zero(); source.len()];
@@ -40,10 +50,30 @@ where
     result
 }
 
+// Montgomery's trick with a single running product
+fn invert_sequential<Field>(source: &[Field], destination: &mut [Field])
+where
+    Field: FieldLike + std::fmt::Debug,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    assert_eq!(source.len(), destination.len());
+    let mut accumulator = Field::one();
+    for (src, dst) in source.iter().zip(destination.iter_mut()) {
+        *dst = accumulator.clone();
+        accumulator *= src;
+    }
+    accumulator = accumulator.inv().expect("Division by zero in batch invert");
+    for (src, dst) in source.iter().zip(destination.iter_mut()).rev() {
+        *dst *= &accumulator;
+        accumulator *= src;
+    }
+    // OPT: We can avoid the last multiplication
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{FieldElement, Zero};
+    use crate::{FieldElement, GoldilocksField, Zero};
     use proptest::prelude::*;
 
     proptest!(
@@ -56,5 +86,15 @@ mod tests {
                 .zip(x.iter())
                 .all(|(a_inv, a)| *a_inv == a.inv().unwrap()));
         }
+
+        #[test]
+        fn test_batch_inv_goldilocks(x: Vec<GoldilocksField>) {
+            prop_assume!(!x.iter().any(GoldilocksField::is_zero));
+
+            prop_assert!(invert_batch(x.as_slice())
+                .iter()
+                .zip(x.iter())
+                .all(|(a_inv, a)| *a_inv == a.inv().unwrap()));
+        }
     );
 }
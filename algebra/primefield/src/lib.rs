@@ -45,13 +45,17 @@
 // TODO: Provide two versions of hot functions `_inlined` and plain.
 #![allow(clippy::inline_always)]
 
+mod baby_bear_field;
 mod convert;
+mod extension;
 pub mod fft;
 pub mod geometric_series;
+mod goldilocks_field;
 mod invert_batch;
 mod ops;
 #[cfg(feature = "parity_codec")]
 mod parity_codec;
+pub mod polynomial;
 mod prime_field;
 #[cfg(any(test, feature = "proptest"))]
 mod proptest;
@@ -70,6 +74,13 @@ pub use zkp_u256::MontgomeryParameters;
 // The smallest 252-bit Proth field
 pub use proth_field::FieldElement;
 
+// Small fields for faster arithmetic
+pub use baby_bear_field::{BabyBear, BabyBearField};
+pub use goldilocks_field::{Goldilocks, GoldilocksField};
+
+// Extension fields for sampling challenges over small fields
+pub use extension::{Ext, Extendable};
+
 pub use invert_batch::{invert_batch, invert_batch_src_dst};
 
 // Re-exports dependencies that are part of the public interface
@@ -77,7 +88,7 @@ pub use zkp_u256 as u256;
 
 // Export and re-export traits
 // TODO: Create a prelude module that contains all the useful ones
-pub use traits::{Fft, FieldLike, RefFieldLike, Root, SquareRoot};
+pub use traits::{Fft, FieldLike, RefFieldLike, Root, SliceOps, SquareRoot};
 pub use zkp_u256::{AddInline, Inv, MulInline, NegInline, One, Pow, SquareInline, SubInline, Zero};
 
 pub use uint::UInt;
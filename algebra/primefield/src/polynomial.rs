@@ -0,0 +1,617 @@
+//! Dense univariate polynomials over any field
+//!
+//! [`Polynomial`] is not yet the canonical polynomial type of the workspace.
+//! The `zkp-stark` prover, verifier and constraint expressions, and the
+//! examples, still use `zkp_stark::DensePolynomial`, which keeps the
+//! coefficients in scratch memory for large traces. It converts to and from
+//! `Polynomial<FieldElement>` for the algorithms here, such as division and
+//! interpolation.
+// False positives, see <https://github.com/rust-lang/rust/issues/55058>
+#![allow(single_use_lifetimes)]
+
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use crate::{fft::permute, Fft, FieldLike, Inv, Pow, RefFieldLike};
+use std::{
+    cmp::min,
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+};
+
+/// Below this size products are computed without FFTs.
+// TODO: Write benchmark and tune
+const FFT_THRESHOLD: usize = 64;
+
+/// Dense univariate polynomials over a field
+///
+/// Coefficients are stored starting from the constant term and without
+/// trailing zeros, so the zero polynomial has no coefficients.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Polynomial<Field>(Vec<Field>);
+
+impl<Field> Polynomial<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    pub fn new(coefficients: Vec<Field>) -> Self {
+        let mut result = Self(coefficients);
+        result.normalize();
+        result
+    }
+
+    pub fn zero() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn constant(value: Field) -> Self {
+        Self::new(vec![value])
+    }
+
+    /// The polynomial $\prod_i (X - r_i)$
+    pub fn from_roots(roots: &[Field]) -> Self {
+        if roots.is_empty() {
+            Self::constant(Field::one())
+        } else {
+            SubproductTree::new(roots).polynomial
+        }
+    }
+
+    /// The vanishing polynomial $X^n - s^n$ of the coset $s \cdot \langle
+    /// \omega_n \rangle$
+    pub fn vanishing(size: usize, shift: &Field) -> Self {
+        assert!(size > 0);
+        let mut coefficients = vec![Field::zero(); size + 1];
+        coefficients[0] = shift.pow(size).neg();
+        coefficients[size] = Field::one();
+        Self(coefficients)
+    }
+
+    pub fn coefficients(&self) -> &[Field] {
+        &self.0
+    }
+
+    pub fn into_coefficients(self) -> Vec<Field> {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The degree, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.0.len().checked_sub(1)
+    }
+
+    pub fn leading_coefficient(&self) -> Option<&Field> {
+        self.0.last()
+    }
+
+    pub fn evaluate(&self, x: &Field) -> Field {
+        let mut result = Field::zero();
+        for coefficient in self.0.iter().rev() {
+            result *= x;
+            result += coefficient;
+        }
+        result
+    }
+
+    /// Evaluates at many points using a subproduct tree.
+    ///
+    /// This takes $O(n \log^2 n)$ operations, compared to $O(n^2)$ for
+    /// repeated [`Polynomial::evaluate`].
+    pub fn evaluate_many(&self, points: &[Field]) -> Vec<Field> {
+        if points.is_empty() {
+            return Vec::new();
+        }
+        let mut result = Vec::with_capacity(points.len());
+        SubproductTree::new(points).evaluate(self, &mut result);
+        result
+    }
+
+    /// Multiply by a constant.
+    pub fn scale(&self, factor: &Field) -> Self {
+        Self::new(self.0.iter().map(|c| c * factor).collect())
+    }
+
+    /// Divides with remainder, returning `None` for a zero divisor.
+    ///
+    /// The remainder has a degree less than the divisor.
+    pub fn div_rem(&self, divisor: &Self) -> Option<(Self, Self)> {
+        let inverse = divisor.leading_coefficient()?.inv()?;
+        let size = divisor.0.len();
+        if self.0.len() < size {
+            return Some((Self::zero(), self.clone()));
+        }
+        let mut remainder = self.0.clone();
+        let mut quotient = vec![Field::zero(); self.0.len() - size + 1];
+        for i in (0..quotient.len()).rev() {
+            let factor = &remainder[i + size - 1] * &inverse;
+            for (r, d) in remainder[i..i + size].iter_mut().zip(divisor.0.iter()) {
+                *r -= d * &factor;
+            }
+            quotient[i] = factor;
+        }
+        remainder.truncate(size - 1);
+        Some((Self::new(quotient), Self::new(remainder)))
+    }
+
+    /// Divides with remainder by [`Polynomial::vanishing`] in linear time.
+    pub fn div_rem_vanishing(&self, size: usize, shift: &Field) -> (Self, Self) {
+        assert!(size > 0);
+        if self.0.len() <= size {
+            return (Self::zero(), self.clone());
+        }
+        // With a = q (X^n - c) + r we have q_{k - n} = a_k + c q_k.
+        let constant = shift.pow(size);
+        let mut quotient = vec![Field::zero(); self.0.len() - size];
+        for k in (size..self.0.len()).rev() {
+            let mut coefficient = self.0[k].clone();
+            if k < quotient.len() {
+                coefficient += &constant * &quotient[k];
+            }
+            quotient[k - size] = coefficient;
+        }
+        let remainder = self.0[..size]
+            .iter()
+            .enumerate()
+            .map(|(k, a)| {
+                if k < quotient.len() {
+                    a + &constant * &quotient[k]
+                } else {
+                    a.clone()
+                }
+            })
+            .collect();
+        (Self::new(quotient), Self::new(remainder))
+    }
+
+    /// The formal derivative
+    pub fn derivative(&self) -> Self {
+        let mut factor = Field::zero();
+        Self::new(
+            self.0
+                .iter()
+                .skip(1)
+                .map(|c| {
+                    factor += Field::one();
+                    c * &factor
+                })
+                .collect(),
+        )
+    }
+
+    /// The composition $p(q(X))$
+    pub fn compose(&self, inner: &Self) -> Self {
+        let mut result = Self::zero();
+        for coefficient in self.0.iter().rev() {
+            result = result * inner + &Self::constant(coefficient.clone());
+        }
+        result
+    }
+
+    /// The monic greatest common divisor, or zero if both are zero.
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let remainder = a % &b;
+            a = b;
+            b = remainder;
+        }
+        a.into_monic()
+    }
+
+    /// Lagrange interpolation through `(x_i, y_i)`.
+    ///
+    /// Takes $O(n^2)$ operations. Returns `None` if the `x_i` are not
+    /// distinct.
+    pub fn lagrange(xs: &[Field], ys: &[Field]) -> Option<Self> {
+        assert_eq!(xs.len(), ys.len());
+        let vanishing = Self::from_roots(xs);
+        let mut result = vec![Field::zero(); xs.len()];
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let basis = vanishing.div_linear(x);
+            let weight = y * basis.evaluate(x).inv()?;
+            for (r, c) in result.iter_mut().zip(basis.0.iter()) {
+                *r += c * &weight;
+            }
+        }
+        Some(Self::new(result))
+    }
+
+    /// Interpolation through `(x_i, y_i)` using a subproduct tree.
+    ///
+    /// Takes $O(n \log^2 n)$ operations. Returns `None` if the `x_i` are not
+    /// distinct.
+    pub fn interpolate(xs: &[Field], ys: &[Field]) -> Option<Self> {
+        assert_eq!(xs.len(), ys.len());
+        if xs.is_empty() {
+            return Some(Self::zero());
+        }
+        let tree = SubproductTree::new(xs);
+        // The Lagrange basis denominators are the derivative of the
+        // vanishing polynomial at the points.
+        let mut denominators = Vec::with_capacity(xs.len());
+        tree.evaluate(&tree.polynomial.derivative(), &mut denominators);
+        let weights = ys
+            .iter()
+            .zip(denominators.iter())
+            .map(|(y, d)| d.inv().map(|d| y * d))
+            .collect::<Option<Vec<_>>>()?;
+        Some(tree.combine(&weights))
+    }
+
+    fn normalize(&mut self) {
+        while self.0.last().is_some_and(Field::is_zero) {
+            let _ = self.0.pop();
+        }
+    }
+
+    fn into_monic(self) -> Self {
+        match self.leading_coefficient().and_then(Inv::inv) {
+            Some(inverse) => self.scale(&inverse),
+            None => self,
+        }
+    }
+
+    // Quotient of division by X - x, using synthetic division.
+    fn div_linear(&self, x: &Field) -> Self {
+        let mut quotient = vec![Field::zero(); self.0.len().saturating_sub(1)];
+        let mut carry = Field::zero();
+        for (q, a) in quotient.iter_mut().zip(self.0.iter().skip(1)).rev() {
+            carry = a + &carry * x;
+            *q = carry.clone();
+        }
+        Self::new(quotient)
+    }
+}
+
+fn mul_schoolbook<Field>(a: &[Field], b: &[Field]) -> Vec<Field>
+where
+    Field: FieldLike,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    let mut result = vec![Field::zero(); a.len() + b.len() - 1];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            result[i + j] += a * b;
+        }
+    }
+    result
+}
+
+fn mul_fft<Field>(a: &[Field], b: &[Field]) -> Vec<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    let length = a.len() + b.len() - 1;
+    let size = length.next_power_of_two();
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.resize(size, Field::zero());
+    b.resize(size, Field::zero());
+    a.fft();
+    b.fft();
+    for (a, b) in a.iter_mut().zip(b.iter()) {
+        *a *= b;
+    }
+    // The FFT output is permuted, and the inverse expects it not to be.
+    permute(&mut a);
+    a.ifft();
+    permute(&mut a);
+    a.truncate(length);
+    a
+}
+
+// Operators take the left hand side by value. Implementing them for
+// `&Polynomial<Field>` makes inference of `&_: Add<&_>` recurse through
+// `RefFieldLike` and overflow.
+impl<Field> Mul<&Self> for Polynomial<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: &Self) -> Self {
+        if self.is_zero() || rhs.is_zero() {
+            return Self::zero();
+        }
+        let size = (self.0.len() + rhs.0.len() - 1).next_power_of_two();
+        if min(self.0.len(), rhs.0.len()) < FFT_THRESHOLD || Field::root(size).is_none() {
+            Self::new(mul_schoolbook(&self.0, &rhs.0))
+        } else {
+            Self::new(mul_fft(&self.0, &rhs.0))
+        }
+    }
+}
+
+impl<Field> Add<&Self> for Polynomial<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    type Output = Self;
+
+    fn add(mut self, rhs: &Self) -> Self {
+        if self.0.len() < rhs.0.len() {
+            self.0.resize(rhs.0.len(), Field::zero());
+        }
+        for (a, b) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *a += b;
+        }
+        self.normalize();
+        self
+    }
+}
+
+impl<Field> Sub<&Self> for Polynomial<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    type Output = Self;
+
+    fn sub(mut self, rhs: &Self) -> Self {
+        if self.0.len() < rhs.0.len() {
+            self.0.resize(rhs.0.len(), Field::zero());
+        }
+        for (a, b) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *a -= b;
+        }
+        self.normalize();
+        self
+    }
+}
+
+impl<Field> Neg for Polynomial<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    type Output = Self;
+
+    fn neg(mut self) -> Self {
+        for coefficient in &mut self.0 {
+            coefficient.neg_assign();
+        }
+        self
+    }
+}
+
+impl<Field> Div<&Self> for Polynomial<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    type Output = Self;
+
+    fn div(self, rhs: &Self) -> Self {
+        self.div_rem(rhs).expect("Division by zero polynomial").0
+    }
+}
+
+impl<Field> Rem<&Self> for Polynomial<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    type Output = Self;
+
+    fn rem(self, rhs: &Self) -> Self {
+        self.div_rem(rhs).expect("Division by zero polynomial").1
+    }
+}
+
+/// Binary tree of the products $\prod_i (X - x_i)$ over ranges of points
+struct SubproductTree<Field> {
+    polynomial: Polynomial<Field>,
+    children:   Option<Box<(SubproductTree<Field>, SubproductTree<Field>)>>,
+}
+
+impl<Field> SubproductTree<Field>
+where
+    Field: FieldLike + Send + Sync,
+    for<'a> &'a Field: RefFieldLike<Field>,
+{
+    fn new(points: &[Field]) -> Self {
+        assert!(!points.is_empty());
+        if points.len() == 1 {
+            Self {
+                polynomial: Polynomial::new(vec![points[0].neg(), Field::one()]),
+                children:   None,
+            }
+        } else {
+            let (left, right) = points.split_at(points.len() / 2);
+            let left = Self::new(left);
+            let right = Self::new(right);
+            Self {
+                polynomial: left.polynomial.clone() * &right.polynomial,
+                children:   Some(Box::new((left, right))),
+            }
+        }
+    }
+
+    // The number of points, which is the degree of the product
+    fn len(&self) -> usize {
+        self.polynomial.0.len() - 1
+    }
+
+    // Pushes evaluations at the points by reducing modulo the subproducts.
+    fn evaluate(&self, polynomial: &Polynomial<Field>, result: &mut Vec<Field>) {
+        let remainder = polynomial
+            .div_rem(&self.polynomial)
+            .expect("Subproducts are not zero")
+            .1;
+        match &self.children {
+            None => result.push(remainder.0.first().cloned().unwrap_or_else(Field::zero)),
+            Some(children) => {
+                children.0.evaluate(&remainder, result);
+                children.1.evaluate(&remainder, result);
+            }
+        }
+    }
+
+    // Computes $\sum_i w_i \prod_{j \neq i} (X - x_j)$.
+    fn combine(&self, weights: &[Field]) -> Polynomial<Field> {
+        match &self.children {
+            None => Polynomial::constant(weights[0].clone()),
+            Some(children) => {
+                let (left, right) = &**children;
+                let (left_weights, right_weights) = weights.split_at(left.len());
+                let left_sum = left.combine(left_weights);
+                let right_sum = right.combine(right_weights);
+                left_sum * &right.polynomial + &(right_sum * &left.polynomial)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldElement, GoldilocksField, One, Root, Zero};
+    use proptest::{collection::vec, prelude::*};
+
+    type Poly = Polynomial<FieldElement>;
+
+    fn arb_poly(max_size: usize) -> impl Strategy<Value = Poly> {
+        vec(any::<FieldElement>(), 0..max_size).prop_map(Poly::new)
+    }
+
+    fn arb_points(max_size: usize) -> impl Strategy<Value = Vec<FieldElement>> {
+        vec(any::<FieldElement>(), 1..max_size).prop_map(|mut points| {
+            points.sort_by_key(FieldElement::to_uint);
+            points.dedup();
+            points
+        })
+    }
+
+    #[test]
+    fn test_normalize() {
+        let p = Poly::new(vec![FieldElement::one(), FieldElement::zero()]);
+        assert_eq!(p.degree(), Some(0));
+        assert_eq!(Poly::new(vec![FieldElement::zero()]), Poly::zero());
+        assert_eq!(Poly::zero().degree(), None);
+    }
+
+    #[test]
+    fn test_vanishing() {
+        let root = FieldElement::root(8).unwrap();
+        let shift = FieldElement::generator();
+        let vanishing = Poly::vanishing(8, &shift);
+        for i in 0_usize..8 {
+            assert!(vanishing.evaluate(&(&shift * root.pow(i))).is_zero());
+        }
+    }
+
+    #[test]
+    fn test_small_field_fft_mul() {
+        let a: Vec<GoldilocksField> = (1_u8..=100).map(GoldilocksField::from).collect();
+        let b: Vec<GoldilocksField> = (1_u8..=70).rev().map(GoldilocksField::from).collect();
+        assert_eq!(mul_fft(&a, &b), mul_schoolbook(&a, &b));
+    }
+
+    proptest!(
+        #[test]
+        fn test_mul_fft(a in vec(any::<FieldElement>(), 1..150), b in vec(any::<FieldElement>(), 1..150)) {
+            prop_assert_eq!(mul_fft(&a, &b), mul_schoolbook(&a, &b));
+        }
+
+        #[test]
+        fn test_mul_evaluate(a in arb_poly(100), b in arb_poly(100), x: FieldElement) {
+            prop_assert_eq!((a.clone() * &b).evaluate(&x), a.evaluate(&x) * b.evaluate(&x));
+        }
+
+        #[test]
+        fn test_add_sub(a in arb_poly(20), b in arb_poly(20)) {
+            prop_assert_eq!(a.clone() + &b - &b, a.clone());
+            prop_assert_eq!(a.clone() - &a, Poly::zero());
+            prop_assert_eq!(-a.clone() + &a, Poly::zero());
+        }
+
+        #[test]
+        fn test_div_rem(a in arb_poly(80), b in arb_poly(40)) {
+            match a.div_rem(&b) {
+                None => prop_assert!(b.is_zero()),
+                Some((quotient, remainder)) => {
+                    prop_assert_eq!(quotient * &b + &remainder, a);
+                    prop_assert!(remainder.degree() < b.degree());
+                }
+            }
+        }
+
+        #[test]
+        fn test_div_rem_vanishing(a in arb_poly(80), size in 1_usize..40, shift: FieldElement) {
+            let expected = a.div_rem(&Poly::vanishing(size, &shift)).unwrap();
+            prop_assert_eq!(a.div_rem_vanishing(size, &shift), expected);
+        }
+
+        #[test]
+        fn test_from_roots(roots in vec(any::<FieldElement>(), 0..20)) {
+            let p = Poly::from_roots(&roots);
+            prop_assert_eq!(p.degree(), Some(roots.len()));
+            for root in &roots {
+                prop_assert!(p.evaluate(root).is_zero());
+            }
+        }
+
+        #[test]
+        fn test_evaluate_many(p in arb_poly(50), points in vec(any::<FieldElement>(), 0..50)) {
+            let expected: Vec<_> = points.iter().map(|x| p.evaluate(x)).collect();
+            prop_assert_eq!(p.evaluate_many(&points), expected);
+        }
+
+        #[test]
+        fn test_lagrange(xs in arb_points(30), ys in vec(any::<FieldElement>(), 30)) {
+            let ys = &ys[..xs.len()];
+            let p = Poly::lagrange(&xs, ys).unwrap();
+            prop_assert!(p.0.len() <= xs.len());
+            for (x, y) in xs.iter().zip(ys.iter()) {
+                prop_assert_eq!(&p.evaluate(x), y);
+            }
+            prop_assert_eq!(Poly::interpolate(&xs, ys), Some(p));
+        }
+
+        #[test]
+        fn test_interpolate_duplicate(x: FieldElement, y: FieldElement) {
+            let xs = [x.clone(), x];
+            let ys = [y.clone(), y];
+            prop_assert_eq!(Poly::lagrange(&xs, &ys), None);
+            prop_assert_eq!(Poly::interpolate(&xs, &ys), None);
+        }
+
+        #[test]
+        fn test_compose(a in arb_poly(10), b in arb_poly(10), x: FieldElement) {
+            prop_assert_eq!(a.compose(&b).evaluate(&x), a.evaluate(&b.evaluate(&x)));
+        }
+
+        #[test]
+        fn test_derivative(a in arb_poly(20), b in arb_poly(20)) {
+            // Product rule
+            let expected = a.derivative() * &b + &(a.clone() * &b.derivative());
+            prop_assert_eq!((a * &b).derivative(), expected);
+        }
+
+        #[test]
+        fn test_gcd(a in arb_poly(10), b in arb_poly(10), c in arb_poly(10)) {
+            prop_assume!(!c.is_zero());
+            let a = a * &c;
+            let b = b * &c;
+            let gcd = a.gcd(&b);
+            prop_assert!((gcd.clone() % &c).is_zero());
+            if !gcd.is_zero() {
+                prop_assert!(gcd.leading_coefficient().unwrap().is_one());
+                prop_assert!((a % &gcd).is_zero());
+                prop_assert!((b % &gcd).is_zero());
+            }
+        }
+    );
+}
@@ -4,15 +4,15 @@
 #[allow(clippy::wildcard_imports)]
 use std::{fmt, prelude::v1::*};
 
-use crate::{Root, SquareRoot, UInt as FieldUInt};
+use crate::{Root, SliceOps, SquareRoot, UInt as FieldUInt};
 use std::{
     hash::{Hash, Hasher},
     marker::PhantomData,
     ops::Shr,
 };
 use zkp_u256::{
-    AddInline, Binary, DivRem, Inv, Montgomery as _, MontgomeryParameters, MulInline, NegInline,
-    One, Pow, SquareInline, SubInline, Zero, U256,
+    AddInline, Binary, DivRem, Inv, Montgomery as _, MontgomeryParameters, MontgomerySlice,
+    MulInline, NegInline, One, Pow, SquareInline, SubInline, Zero, U256,
 };
 
 /// A finite field of prime order.
@@ -30,6 +30,8 @@ use zkp_u256::{
 /// `proptest` support `Parameters` needs to be `'static + Send` (which it
 /// really should anyway).
 // Derive fails for Clone, PartialEq, Eq, Hash
+// Transparent so slices can be passed to `MontgomerySlice`
+#[repr(transparent)]
 pub struct PrimeField<P: Parameters> {
     // TODO: un-pub. They are pub so FieldElement can have const-fn constructors.
     pub uint:        P::UInt,
@@ -37,6 +39,10 @@ pub struct PrimeField<P: Parameters> {
 }
 
 /// Required constant parameters for the prime field
+///
+/// The Montgomery radix is `2^256` for `U256`. For machine words it is the
+/// word size, i.e. `2^64` for `u64` and `2^32` for `u32`, and `M64` is
+/// truncated to the word size.
 // TODO: Fix naming
 #[allow(clippy::module_name_repetitions)]
 // UInt can not have interior mutability
@@ -230,9 +236,10 @@ impl<P: Parameters> One for PrimeField<P> {
 impl<P: Parameters> AddInline<&Self> for PrimeField<P> {
     #[inline(always)]
     fn add_inline(&self, rhs: &Self) -> Self {
-        let result = self.as_montgomery().add_inline(rhs.as_montgomery());
-        let result = result.reduce_1_inline::<Montgomery<P>>();
-        Self::from_montgomery(result)
+        Self::from_montgomery(
+            self.as_montgomery()
+                .add_mod_inline::<Montgomery<P>>(rhs.as_montgomery()),
+        )
     }
 }
 
@@ -278,6 +285,47 @@ impl<P: Parameters> MulInline<&Self> for PrimeField<P> {
     }
 }
 
+impl<P: Parameters> SliceOps for PrimeField<P> {
+    fn add_assign_slice(values: &mut [Self], rhs: &[Self]) {
+        P::UInt::add_mod_slice::<Montgomery<P>>(as_uints_mut(values), as_uints(rhs));
+    }
+
+    fn sub_assign_slice(values: &mut [Self], rhs: &[Self]) {
+        P::UInt::sub_mod_slice::<Montgomery<P>>(as_uints_mut(values), as_uints(rhs));
+    }
+
+    fn mul_assign_slice(values: &mut [Self], rhs: &[Self]) {
+        P::UInt::mul_redc_slice::<Montgomery<P>>(as_uints_mut(values), as_uints(rhs));
+    }
+
+    fn scale_slice(values: &mut [Self], factor: &Self) {
+        P::UInt::mul_redc_scalar_slice::<Montgomery<P>>(
+            as_uints_mut(values),
+            factor.as_montgomery(),
+        );
+    }
+
+    fn butterfly_slice(left: &mut [Self], right: &mut [Self], twiddle: &Self) {
+        P::UInt::butterfly_redc_slice::<Montgomery<P>>(
+            as_uints_mut(left),
+            as_uints_mut(right),
+            twiddle.as_montgomery(),
+        );
+    }
+}
+
+// `PrimeField` is `repr(transparent)` over `P::UInt`, so the slices have the
+// same layout. The values stay in Montgomery form.
+#[allow(unsafe_code)]
+fn as_uints<P: Parameters>(values: &[PrimeField<P>]) -> &[P::UInt] {
+    unsafe { std::slice::from_raw_parts(values.as_ptr().cast(), values.len()) }
+}
+
+#[allow(unsafe_code)]
+fn as_uints_mut<P: Parameters>(values: &mut [PrimeField<P>]) -> &mut [P::UInt] {
+    unsafe { std::slice::from_raw_parts_mut(values.as_mut_ptr().cast(), values.len()) }
+}
+
 impl<P: Parameters> Inv for &PrimeField<P> {
     type Output = Option<PrimeField<P>>;
 
@@ -454,10 +502,11 @@ impl<P: Parameters<UInt = U256>> From<&PrimeField<P>> for U256 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::FieldElement;
+    use crate::{BabyBearField, FieldElement};
     use itertools::repeat_n;
     use num_traits::ToPrimitive;
-    use proptest::prelude::*;
+    use proptest::{collection::vec, prelude::*};
+    use std::cmp::min;
     use zkp_macros_decl::{field_element, u256h};
     use zkp_u256::U256;
 
@@ -622,8 +671,56 @@ mod tests {
             let r = s.square_root().unwrap();
             prop_assert!(r == a || r == -a);
         }
+
+        #[test]
+        fn slice_ops(a in vec(any::<FieldElement>(), 0..40), b in vec(any::<FieldElement>(), 0..40), w: FieldElement) {
+            check_slice_ops(&a, &b, &w)?;
+        }
+
+        #[test]
+        fn slice_ops_baby_bear(a in vec(any::<BabyBearField>(), 0..40), b in vec(any::<BabyBearField>(), 0..40), w: BabyBearField) {
+            check_slice_ops(&a, &b, &w)?;
+        }
     );
 
+    fn check_slice_ops<P: Parameters>(
+        a: &[PrimeField<P>],
+        b: &[PrimeField<P>],
+        w: &PrimeField<P>,
+    ) -> Result<(), TestCaseError> {
+        let length = min(a.len(), b.len());
+        let (a, b) = (&a[..length], &b[..length]);
+        let zip = |f: fn(&PrimeField<P>, &PrimeField<P>) -> PrimeField<P>| {
+            a.iter().zip(b).map(|(a, b)| f(a, b)).collect::<Vec<_>>()
+        };
+
+        let mut result = a.to_vec();
+        PrimeField::add_assign_slice(&mut result, b);
+        prop_assert!(result == zip(|a, b| a + b));
+
+        let mut result = a.to_vec();
+        PrimeField::sub_assign_slice(&mut result, b);
+        prop_assert!(result == zip(|a, b| a - b));
+
+        let mut result = a.to_vec();
+        PrimeField::mul_assign_slice(&mut result, b);
+        prop_assert!(result == zip(|a, b| a * b));
+
+        let mut result = a.to_vec();
+        PrimeField::scale_slice(&mut result, w);
+        prop_assert!(result == a.iter().map(|a| a * w).collect::<Vec<_>>());
+
+        let mut left = a.to_vec();
+        let mut right = b.to_vec();
+        PrimeField::butterfly_slice(&mut left, &mut right, w);
+        let twiddled = b.iter().map(|b| b * w).collect::<Vec<_>>();
+        for (((left, right), a), t) in left.iter().zip(&right).zip(a).zip(&twiddled) {
+            prop_assert!(*left == a + t);
+            prop_assert!(*right == a - t);
+        }
+        Ok(())
+    }
+
     #[test]
     fn zeroth_root_of_unity() {
         assert_eq!(FieldElement::root(0).unwrap(), FieldElement::one());
@@ -1,4 +1,4 @@
-use crate::{Parameters, PrimeField};
+use crate::{Extendable, Parameters, PrimeField};
 use std::marker::PhantomData;
 use zkp_macros_decl::u256h;
 use zkp_u256::{to_montgomery_const, U256};
@@ -48,3 +48,11 @@ impl FieldElement {
         }
     }
 }
+
+/// $X^2 - 3$ is irreducible since 3 is not a square. Cubic binomial
+/// extensions do not exist as every element is a cube.
+impl Extendable<2> for FieldElement {
+    fn nonresidue() -> Self {
+        Self::from(3_u8)
+    }
+}
@@ -49,6 +49,7 @@ pub trait FieldLike:
     + FieldAssignOps
     + for<'a> FieldAssignOps<&'a Self>
     + Root<usize>
+    + SliceOps
 {
 }
 impl<T> FieldLike for T where
@@ -68,9 +69,64 @@ impl<T> FieldLike for T where
         + FieldAssignOps
         + for<'a> FieldAssignOps<&'a Self>
         + Root<usize>
+        + SliceOps
 {
 }
 
+/// Element-wise operations on slices
+///
+/// The default implementations process one element at a time. Prime fields
+/// forward to [`zkp_u256::MontgomerySlice`], which can use vector
+/// instructions to process several elements at once.
+///
+/// # Panics
+///
+/// The binary operations panic if the slices differ in length.
+pub trait SliceOps:
+    Sized + for<'a> AddInline<&'a Self> + for<'a> SubInline<&'a Self> + for<'a> MulInline<&'a Self>
+{
+    /// Adds `rhs` to `values`.
+    fn add_assign_slice(values: &mut [Self], rhs: &[Self]) {
+        assert_eq!(values.len(), rhs.len());
+        for (value, rhs) in values.iter_mut().zip(rhs) {
+            value.add_assign_inline(rhs);
+        }
+    }
+
+    /// Subtracts `rhs` from `values`.
+    fn sub_assign_slice(values: &mut [Self], rhs: &[Self]) {
+        assert_eq!(values.len(), rhs.len());
+        for (value, rhs) in values.iter_mut().zip(rhs) {
+            value.sub_assign_inline(rhs);
+        }
+    }
+
+    /// Multiplies `values` by `rhs`.
+    fn mul_assign_slice(values: &mut [Self], rhs: &[Self]) {
+        assert_eq!(values.len(), rhs.len());
+        for (value, rhs) in values.iter_mut().zip(rhs) {
+            value.mul_assign_inline(rhs);
+        }
+    }
+
+    /// Multiplies all `values` by `factor`.
+    fn scale_slice(values: &mut [Self], factor: &Self) {
+        for value in values.iter_mut() {
+            value.mul_assign_inline(factor);
+        }
+    }
+
+    /// Radix-2 butterflies `(u, v) -> (u + w v, u - w v)` with twiddle `w`.
+    fn butterfly_slice(left: &mut [Self], right: &mut [Self], twiddle: &Self) {
+        assert_eq!(left.len(), right.len());
+        for (u, v) in left.iter_mut().zip(right.iter_mut()) {
+            let t = v.mul_inline(twiddle);
+            *v = u.sub_inline(&t);
+            u.add_assign_inline(&t);
+        }
+    }
+}
+
 // TODO: Custom by-reference traits for Pow and Inv
 pub trait RefFieldLike<Base>:
     Inv<Output = Option<Base>>
@@ -2,7 +2,7 @@
 #![allow(single_use_lifetimes)]
 
 use crate::{AddInline, One, SubInline, Zero};
-use zkp_u256::Montgomery;
+use zkp_u256::MontgomerySlice;
 
 /// Requirements for the base unsigned integer type
 // TODO: Fix naming
@@ -17,7 +17,7 @@ pub trait UInt:
     + One
     + for<'a> AddInline<&'a Self>
     + for<'a> SubInline<&'a Self>
-    + Montgomery
+    + MontgomerySlice
 {
 }
 impl<T> UInt for T where
@@ -28,6 +28,6 @@ impl<T> UInt for T where
         + One
         + for<'a> AddInline<&'a T>
         + for<'a> SubInline<&'a T>
-        + Montgomery
+        + MontgomerySlice
 {
 }
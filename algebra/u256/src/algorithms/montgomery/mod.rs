@@ -2,6 +2,7 @@ use crate::{MontgomeryParameters, SquareFullInline, U256};
 
 pub(crate) mod generic;
 pub(crate) mod proth;
+pub(crate) mod slice;
 
 // TODO: Special algorithms for Solinas and Crandall primes
 // <https://en.wikipedia.org/wiki/Solinas_prime>
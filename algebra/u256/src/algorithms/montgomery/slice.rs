@@ -0,0 +1,77 @@
+// Scalar element-wise Montgomery arithmetic on slices
+//
+// These are the default implementations of `MontgomerySlice` and the fallback
+// of the vectorized versions for the remainder and unsupported CPUs.
+use crate::{AddInline, Montgomery, MontgomeryParameters, SubInline};
+
+pub(crate) fn add_mod_slice<U, M>(values: &mut [U], rhs: &[U])
+where
+    U: Montgomery,
+    M: MontgomeryParameters<UInt = U>,
+{
+    assert_eq!(values.len(), rhs.len());
+    for (value, rhs) in values.iter_mut().zip(rhs) {
+        *value = value.add_mod_inline::<M>(rhs);
+    }
+}
+
+pub(crate) fn sub_mod_slice<U, M>(values: &mut [U], rhs: &[U])
+where
+    U: Montgomery + PartialOrd + for<'a> AddInline<&'a U> + for<'a> SubInline<&'a U>,
+    M: MontgomeryParameters<UInt = U>,
+{
+    assert_eq!(values.len(), rhs.len());
+    for (value, rhs) in values.iter_mut().zip(rhs) {
+        *value = sub_mod_inline::<U, M>(value, rhs);
+    }
+}
+
+pub(crate) fn mul_redc_slice<U, M>(values: &mut [U], rhs: &[U])
+where
+    U: Montgomery,
+    M: MontgomeryParameters<UInt = U>,
+{
+    assert_eq!(values.len(), rhs.len());
+    for (value, rhs) in values.iter_mut().zip(rhs) {
+        *value = value.mul_redc_inline::<M>(rhs);
+    }
+}
+
+pub(crate) fn mul_redc_scalar_slice<U, M>(values: &mut [U], factor: &U)
+where
+    U: Montgomery,
+    M: MontgomeryParameters<UInt = U>,
+{
+    for value in values.iter_mut() {
+        *value = value.mul_redc_inline::<M>(factor);
+    }
+}
+
+pub(crate) fn butterfly_redc_slice<U, M>(left: &mut [U], right: &mut [U], twiddle: &U)
+where
+    U: Montgomery + PartialOrd + for<'a> AddInline<&'a U> + for<'a> SubInline<&'a U>,
+    M: MontgomeryParameters<UInt = U>,
+{
+    assert_eq!(left.len(), right.len());
+    for (u, v) in left.iter_mut().zip(right.iter_mut()) {
+        let t = v.mul_redc_inline::<M>(twiddle);
+        *v = sub_mod_inline::<U, M>(u, &t);
+        *u = u.add_mod_inline::<M>(&t);
+    }
+}
+
+#[inline(always)]
+fn sub_mod_inline<U, M>(lhs: &U, rhs: &U) -> U
+where
+    U: Montgomery + PartialOrd + for<'a> AddInline<&'a U> + for<'a> SubInline<&'a U>,
+    M: MontgomeryParameters<UInt = U>,
+{
+    let borrow = rhs > lhs;
+    let mut result = lhs.sub_inline(rhs);
+    if borrow {
+        // `U` should not have interior mutability.
+        #[allow(clippy::borrow_interior_mutable_const)]
+        result.add_assign_inline(&M::MODULUS);
+    }
+    result
+}
@@ -1,10 +1,14 @@
 mod knuth_division;
 mod lehmer_gcd;
+mod slice;
 
 use crate::U256;
 
 pub(crate) use knuth_division::{divrem_nby1, divrem_nbym};
 pub(crate) use lehmer_gcd::{gcd, gcd_extended, inv_mod};
+pub(crate) use slice::{
+    add_mod_slice, butterfly_redc_slice, mul_redc_scalar_slice, mul_redc_slice, sub_mod_slice,
+};
 
 /// Reduce at most once
 #[inline(always)]
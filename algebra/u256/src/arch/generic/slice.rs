@@ -0,0 +1,38 @@
+// One element at a time
+use crate::{algorithms::montgomery::slice, MontgomeryParameters, U256};
+
+pub(crate) fn add_mod_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    slice::add_mod_slice::<U256, M>(values, rhs);
+}
+
+pub(crate) fn sub_mod_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    slice::sub_mod_slice::<U256, M>(values, rhs);
+}
+
+pub(crate) fn mul_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    slice::mul_redc_slice::<U256, M>(values, rhs);
+}
+
+pub(crate) fn mul_redc_scalar_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    factor: &U256,
+) {
+    slice::mul_redc_scalar_slice::<U256, M>(values, factor);
+}
+
+pub(crate) fn butterfly_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+    left: &mut [U256],
+    right: &mut [U256],
+    twiddle: &U256,
+) {
+    slice::butterfly_redc_slice::<U256, M>(left, right, twiddle);
+}
@@ -11,6 +11,9 @@ mod x86_64;
 ))]
 mod x86_64_adx;
 
+#[cfg(target_arch = "x86_64")]
+mod x86_64_avx;
+
 // TODO: ARM64 and WASM
 
 // Re-export most specific architecture
@@ -33,3 +36,10 @@ pub(crate) use x86_64::*;
     target_feature = "adx"
 )))]
 pub(crate) use x86_64_adx::*;
+
+// Vectorized slice operations with runtime CPU feature detection
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) use x86_64_avx::{
+    add_mod_slice, butterfly_redc_slice, mul_redc_scalar_slice, mul_redc_slice, sub_mod_slice,
+};
@@ -0,0 +1,311 @@
+// Four elements at a time using AVX2
+//
+// Each element is ten limbs of 26 bits, one element per 64-bit lane. The
+// 32-bit multiplies give full 52-bit limb products, which are accumulated
+// without carries. The accumulators stay well below 2^64.
+use super::{split, SHIFT};
+use crate::{MontgomeryParameters, U256};
+use core::arch::x86_64::{
+    __m256i, _mm256_add_epi64, _mm256_and_si256, _mm256_andnot_si256, _mm256_loadu_si256,
+    _mm256_mul_epu32, _mm256_or_si256, _mm256_permute2x128_si256, _mm256_set1_epi64x,
+    _mm256_setzero_si256, _mm256_sll_epi64, _mm256_srl_epi64, _mm256_srli_epi64,
+    _mm256_storeu_si256, _mm256_sub_epi64, _mm256_unpackhi_epi64, _mm256_unpacklo_epi64,
+    _mm_cvtsi32_si128,
+};
+
+const LANES: usize = 4;
+const LIMBS: usize = 10;
+const WORDS: usize = 4;
+// The shift intrinsics take an `i32`
+const BITS: i32 = 26;
+#[allow(clippy::cast_sign_loss)]
+const LIMB_BITS: u32 = BITS as u32;
+const MASK: i64 = (1 << BITS) - 1;
+
+type Packed = [__m256i; LIMBS];
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn add_mod_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let mut done = 0;
+    for (values, rhs) in values.chunks_exact_mut(LANES).zip(rhs.chunks_exact(LANES)) {
+        let sum = add(&load(values, 0), &load(rhs, 0), &modulus);
+        store(&sum, values);
+        done += LANES;
+    }
+    done
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn sub_mod_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let mut done = 0;
+    for (values, rhs) in values.chunks_exact_mut(LANES).zip(rhs.chunks_exact(LANES)) {
+        let difference = sub(&load(values, 0), &load(rhs, 0), &modulus);
+        store(&difference, values);
+        done += LANES;
+    }
+    done
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn mul_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let m0 = _mm256_set1_epi64x(m0::<M>());
+    let mut done = 0;
+    for (values, rhs) in values.chunks_exact_mut(LANES).zip(rhs.chunks_exact(LANES)) {
+        let product = mul(&load(values, SHIFT), &load(rhs, 0), &modulus, m0);
+        store(&product, values);
+        done += LANES;
+    }
+    done
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn mul_redc_scalar_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    factor: &U256,
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let m0 = _mm256_set1_epi64x(m0::<M>());
+    let factor = splat(factor, SHIFT);
+    let mut done = 0;
+    for values in values.chunks_exact_mut(LANES) {
+        let product = mul(&factor, &load(values, 0), &modulus, m0);
+        store(&product, values);
+        done += LANES;
+    }
+    done
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn butterfly_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+    left: &mut [U256],
+    right: &mut [U256],
+    twiddle: &U256,
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let m0 = _mm256_set1_epi64x(m0::<M>());
+    let twiddle = splat(twiddle, SHIFT);
+    let mut done = 0;
+    for (left, right) in left
+        .chunks_exact_mut(LANES)
+        .zip(right.chunks_exact_mut(LANES))
+    {
+        let u = load(left, 0);
+        let t = mul(&twiddle, &load(right, 0), &modulus, m0);
+        store(&add(&u, &t, &modulus), left);
+        store(&sub(&u, &t, &modulus), right);
+        done += LANES;
+    }
+    done
+}
+
+// -MODULUS^(-1) mod 2^26, it is less than 2^26 so the cast does not wrap.
+#[allow(clippy::cast_possible_wrap)]
+fn m0<M: MontgomeryParameters<UInt = U256>>() -> i64 {
+    M::M64 as i64 & MASK
+}
+
+// Loads four values and splits `value << shift` into limbs in registers
+// The number of limbs is small
+#[allow(clippy::cast_possible_truncation)]
+#[inline(always)]
+unsafe fn load(values: &[U256], shift: u32) -> Packed {
+    debug_assert_eq!(values.len(), LANES);
+    let mut words = [_mm256_setzero_si256(); WORDS];
+    for (words, value) in words.iter_mut().zip(values.iter()) {
+        *words = _mm256_loadu_si256(value.as_limbs().as_ptr().cast());
+    }
+    let words = transpose(&words);
+    let mask = _mm256_set1_epi64x(MASK);
+    let mut result = [_mm256_setzero_si256(); LIMBS];
+    for (i, result) in result.iter_mut().enumerate() {
+        let position = i as u32 * LIMB_BITS;
+        let limb = if position < shift {
+            shl(words[0], shift - position)
+        } else {
+            let position = position - shift;
+            let (word, offset) = ((position / 64) as usize, position % 64);
+            let mut limb = shr(words[word], offset);
+            if offset + LIMB_BITS > 64 && word + 1 < WORDS {
+                limb = _mm256_or_si256(limb, shl(words[word + 1], 64 - offset));
+            }
+            limb
+        };
+        *result = _mm256_and_si256(limb, mask);
+    }
+    result
+}
+
+// Joins the limbs in registers and stores four values
+// The number of limbs is small
+#[allow(clippy::cast_possible_truncation)]
+#[inline(always)]
+unsafe fn store(packed: &Packed, values: &mut [U256]) {
+    debug_assert_eq!(values.len(), LANES);
+    let mut words = [_mm256_setzero_si256(); WORDS];
+    for (i, limb) in packed.iter().enumerate() {
+        let position = i as u32 * LIMB_BITS;
+        let (word, offset) = ((position / 64) as usize, position % 64);
+        words[word] = _mm256_or_si256(words[word], shl(*limb, offset));
+        if offset + LIMB_BITS > 64 && word + 1 < WORDS {
+            words[word + 1] = _mm256_or_si256(words[word + 1], shr(*limb, 64 - offset));
+        }
+    }
+    for (value, words) in values.iter_mut().zip(transpose(&words).iter()) {
+        let mut limbs = [0_u64; WORDS];
+        _mm256_storeu_si256(limbs.as_mut_ptr().cast(), *words);
+        *value = U256::from_limbs(limbs);
+    }
+}
+
+// Transposes a 4x4 matrix of 64-bit words, it is its own inverse
+#[inline(always)]
+unsafe fn transpose(rows: &[__m256i; WORDS]) -> [__m256i; WORDS] {
+    let low01 = _mm256_unpacklo_epi64(rows[0], rows[1]);
+    let high01 = _mm256_unpackhi_epi64(rows[0], rows[1]);
+    let low23 = _mm256_unpacklo_epi64(rows[2], rows[3]);
+    let high23 = _mm256_unpackhi_epi64(rows[2], rows[3]);
+    [
+        _mm256_permute2x128_si256::<0x20>(low01, low23),
+        _mm256_permute2x128_si256::<0x20>(high01, high23),
+        _mm256_permute2x128_si256::<0x31>(low01, low23),
+        _mm256_permute2x128_si256::<0x31>(high01, high23),
+    ]
+}
+
+// Shifts by a count that is constant after inlining. Counts are at most 64 so
+// the casts do not wrap.
+#[allow(clippy::cast_possible_wrap)]
+#[inline(always)]
+unsafe fn shl(value: __m256i, count: u32) -> __m256i {
+    _mm256_sll_epi64(value, _mm_cvtsi32_si128(count as i32))
+}
+
+#[allow(clippy::cast_possible_wrap)]
+#[inline(always)]
+unsafe fn shr(value: __m256i, count: u32) -> __m256i {
+    _mm256_srl_epi64(value, _mm_cvtsi32_si128(count as i32))
+}
+
+// Limbs are less than 2^26 so the casts do not wrap.
+#[allow(clippy::cast_possible_wrap)]
+#[inline(always)]
+unsafe fn splat(value: &U256, shift: u32) -> Packed {
+    let limbs = split::<LIMBS>(value, LIMB_BITS, shift);
+    let mut result = [_mm256_setzero_si256(); LIMBS];
+    for (result, limb) in result.iter_mut().zip(limbs.iter()) {
+        *result = _mm256_set1_epi64x(*limb as i64);
+    }
+    result
+}
+
+#[inline(always)]
+unsafe fn add(a: &Packed, b: &Packed, modulus: &Packed) -> Packed {
+    let mask = _mm256_set1_epi64x(MASK);
+    let mut sum = [_mm256_setzero_si256(); LIMBS];
+    let mut carry = _mm256_setzero_si256();
+    for ((sum, a), b) in sum.iter_mut().zip(a.iter()).zip(b.iter()) {
+        let limb = _mm256_add_epi64(_mm256_add_epi64(*a, *b), carry);
+        carry = _mm256_srli_epi64::<BITS>(limb);
+        *sum = _mm256_and_si256(limb, mask);
+    }
+    reduce_1(&sum, modulus)
+}
+
+#[inline(always)]
+unsafe fn sub(a: &Packed, b: &Packed, modulus: &Packed) -> Packed {
+    let zero = _mm256_setzero_si256();
+    let mask = _mm256_set1_epi64x(MASK);
+    let mut difference = [zero; LIMBS];
+    let mut borrow = zero;
+    for ((difference, a), b) in difference.iter_mut().zip(a.iter()).zip(b.iter()) {
+        let limb = _mm256_sub_epi64(_mm256_sub_epi64(*a, *b), borrow);
+        borrow = _mm256_srli_epi64::<63>(limb);
+        *difference = _mm256_and_si256(limb, mask);
+    }
+    // Add the modulus in the lanes that borrowed
+    let negative = _mm256_sub_epi64(zero, borrow);
+    let mut carry = zero;
+    for (difference, modulus) in difference.iter_mut().zip(modulus.iter()) {
+        let limb = _mm256_add_epi64(
+            _mm256_add_epi64(*difference, _mm256_and_si256(*modulus, negative)),
+            carry,
+        );
+        carry = _mm256_srli_epi64::<BITS>(limb);
+        *difference = _mm256_and_si256(limb, mask);
+    }
+    difference
+}
+
+// Montgomery multiplication with `R = 2^260`. The extra factor `2^4` is
+// compensated by passing `a` shifted left by `SHIFT`, which is less than
+// `2^260` and keeps the result below `2 * MODULUS`.
+#[inline(always)]
+unsafe fn mul(a: &Packed, b: &Packed, modulus: &Packed, m0: __m256i) -> Packed {
+    let zero = _mm256_setzero_si256();
+    let mask = _mm256_set1_epi64x(MASK);
+    let mut t = [zero; LIMBS];
+    for a in a {
+        for (t, b) in t.iter_mut().zip(b.iter()) {
+            *t = _mm256_add_epi64(*t, _mm256_mul_epu32(*a, *b));
+        }
+        // Only the low 32 bits of `t[0]` are used, which suffices mod 2^26
+        let m = _mm256_and_si256(_mm256_mul_epu32(t[0], m0), mask);
+        for (t, modulus) in t.iter_mut().zip(modulus.iter()) {
+            *t = _mm256_add_epi64(*t, _mm256_mul_epu32(m, *modulus));
+        }
+        // The lowest limb is now divisible by 2^26, shift it out
+        t[1] = _mm256_add_epi64(t[1], _mm256_srli_epi64::<BITS>(t[0]));
+        t.copy_within(1.., 0);
+        t[LIMBS - 1] = zero;
+    }
+    normalize(&mut t);
+    reduce_1(&t, modulus)
+}
+
+// Propagates carries so all limbs are less than 2^26
+#[inline(always)]
+unsafe fn normalize(value: &mut Packed) {
+    let mask = _mm256_set1_epi64x(MASK);
+    for j in 1..LIMBS {
+        let carry = _mm256_srli_epi64::<BITS>(value[j - 1]);
+        value[j - 1] = _mm256_and_si256(value[j - 1], mask);
+        value[j] = _mm256_add_epi64(value[j], carry);
+    }
+}
+
+// Subtracts the modulus from values less than twice the modulus
+#[inline(always)]
+unsafe fn reduce_1(value: &Packed, modulus: &Packed) -> Packed {
+    let zero = _mm256_setzero_si256();
+    let mask = _mm256_set1_epi64x(MASK);
+    let mut difference = [zero; LIMBS];
+    let mut borrow = zero;
+    for ((difference, value), modulus) in
+        difference.iter_mut().zip(value.iter()).zip(modulus.iter())
+    {
+        let limb = _mm256_sub_epi64(_mm256_sub_epi64(*value, *modulus), borrow);
+        borrow = _mm256_srli_epi64::<63>(limb);
+        *difference = _mm256_and_si256(limb, mask);
+    }
+    // Keep the original value in the lanes that borrowed
+    let keep = _mm256_sub_epi64(zero, borrow);
+    for (difference, value) in difference.iter_mut().zip(value.iter()) {
+        *difference = _mm256_or_si256(
+            _mm256_and_si256(keep, *value),
+            _mm256_andnot_si256(keep, *difference),
+        );
+    }
+    difference
+}
@@ -0,0 +1,347 @@
+// Eight elements at a time using AVX-512 IFMA
+//
+// Each element is five limbs of 52 bits, one element per 64-bit lane. The
+// 52-bit multiply-add instructions produce the low and high halves of the
+// limb products directly, so the accumulators never overflow.
+use super::{split, SHIFT};
+use crate::{MontgomeryParameters, U256};
+use core::arch::x86_64::{
+    __m512i, _mm256_loadu_si256, _mm256_storeu_si256, _mm512_add_epi64, _mm512_and_si512,
+    _mm512_andnot_si512, _mm512_castsi256_si512, _mm512_castsi512_si256, _mm512_extracti64x4_epi64,
+    _mm512_inserti64x4, _mm512_madd52hi_epu64, _mm512_madd52lo_epu64, _mm512_or_si512,
+    _mm512_permutex2var_epi64, _mm512_set1_epi64, _mm512_setr_epi64, _mm512_setzero_si512,
+    _mm512_sll_epi64, _mm512_srl_epi64, _mm512_srli_epi64, _mm512_sub_epi64, _mm_cvtsi32_si128,
+};
+
+const LANES: usize = 8;
+const LIMBS: usize = 5;
+const WORDS: usize = 4;
+const BITS: u32 = 52;
+const MASK: i64 = (1 << BITS) - 1;
+
+type Packed = [__m512i; LIMBS];
+
+#[target_feature(enable = "avx512f,avx512ifma")]
+pub(super) unsafe fn add_mod_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let mut done = 0;
+    for (values, rhs) in values.chunks_exact_mut(LANES).zip(rhs.chunks_exact(LANES)) {
+        let sum = add(&load(values, 0), &load(rhs, 0), &modulus);
+        store(&sum, values);
+        done += LANES;
+    }
+    done
+}
+
+#[target_feature(enable = "avx512f,avx512ifma")]
+pub(super) unsafe fn sub_mod_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let mut done = 0;
+    for (values, rhs) in values.chunks_exact_mut(LANES).zip(rhs.chunks_exact(LANES)) {
+        let difference = sub(&load(values, 0), &load(rhs, 0), &modulus);
+        store(&difference, values);
+        done += LANES;
+    }
+    done
+}
+
+#[target_feature(enable = "avx512f,avx512ifma")]
+pub(super) unsafe fn mul_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let m0 = _mm512_set1_epi64(m0::<M>());
+    let mut done = 0;
+    for (values, rhs) in values.chunks_exact_mut(LANES).zip(rhs.chunks_exact(LANES)) {
+        let product = mul(&load(values, SHIFT), &load(rhs, 0), &modulus, m0);
+        store(&product, values);
+        done += LANES;
+    }
+    done
+}
+
+#[target_feature(enable = "avx512f,avx512ifma")]
+pub(super) unsafe fn mul_redc_scalar_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    factor: &U256,
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let m0 = _mm512_set1_epi64(m0::<M>());
+    let factor = splat(factor, SHIFT);
+    let mut done = 0;
+    for values in values.chunks_exact_mut(LANES) {
+        let product = mul(&factor, &load(values, 0), &modulus, m0);
+        store(&product, values);
+        done += LANES;
+    }
+    done
+}
+
+#[target_feature(enable = "avx512f,avx512ifma")]
+pub(super) unsafe fn butterfly_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+    left: &mut [U256],
+    right: &mut [U256],
+    twiddle: &U256,
+) -> usize {
+    let modulus = splat(&M::MODULUS, 0);
+    let m0 = _mm512_set1_epi64(m0::<M>());
+    let twiddle = splat(twiddle, SHIFT);
+    let mut done = 0;
+    for (left, right) in left
+        .chunks_exact_mut(LANES)
+        .zip(right.chunks_exact_mut(LANES))
+    {
+        let u = load(left, 0);
+        let t = mul(&twiddle, &load(right, 0), &modulus, m0);
+        store(&add(&u, &t, &modulus), left);
+        store(&sub(&u, &t, &modulus), right);
+        done += LANES;
+    }
+    done
+}
+
+// -MODULUS^(-1) mod 2^52, it is less than 2^52 so the cast does not wrap.
+#[allow(clippy::cast_possible_wrap)]
+fn m0<M: MontgomeryParameters<UInt = U256>>() -> i64 {
+    M::M64 as i64 & MASK
+}
+
+// Loads eight values and splits `value << shift` into limbs in registers
+// The number of limbs is small
+#[allow(clippy::cast_possible_truncation)]
+#[inline(always)]
+unsafe fn load(values: &[U256], shift: u32) -> Packed {
+    debug_assert_eq!(values.len(), LANES);
+    let mut pairs = [_mm512_setzero_si512(); WORDS];
+    for (pair, values) in pairs.iter_mut().zip(values.chunks_exact(2)) {
+        let low = _mm256_loadu_si256(values[0].as_limbs().as_ptr().cast());
+        let high = _mm256_loadu_si256(values[1].as_limbs().as_ptr().cast());
+        *pair = _mm512_inserti64x4::<1>(_mm512_castsi256_si512(low), high);
+    }
+    let words = transpose(&pairs);
+    let mask = _mm512_set1_epi64(MASK);
+    let mut result = [_mm512_setzero_si512(); LIMBS];
+    for (i, result) in result.iter_mut().enumerate() {
+        let position = i as u32 * BITS;
+        let limb = if position < shift {
+            shl(words[0], shift - position)
+        } else {
+            let position = position - shift;
+            let (word, offset) = ((position / 64) as usize, position % 64);
+            let mut limb = shr(words[word], offset);
+            if offset + BITS > 64 && word + 1 < WORDS {
+                limb = _mm512_or_si512(limb, shl(words[word + 1], 64 - offset));
+            }
+            limb
+        };
+        *result = _mm512_and_si512(limb, mask);
+    }
+    result
+}
+
+// Joins the limbs in registers and stores eight values
+// The number of limbs is small
+#[allow(clippy::cast_possible_truncation)]
+#[inline(always)]
+unsafe fn store(packed: &Packed, values: &mut [U256]) {
+    debug_assert_eq!(values.len(), LANES);
+    let mut words = [_mm512_setzero_si512(); WORDS];
+    for (i, limb) in packed.iter().enumerate() {
+        let position = i as u32 * BITS;
+        let (word, offset) = ((position / 64) as usize, position % 64);
+        words[word] = _mm512_or_si512(words[word], shl(*limb, offset));
+        if offset + BITS > 64 && word + 1 < WORDS {
+            words[word + 1] = _mm512_or_si512(words[word + 1], shr(*limb, 64 - offset));
+        }
+    }
+    for (values, pair) in values.chunks_exact_mut(2).zip(untranspose(&words).iter()) {
+        let mut low = [0_u64; WORDS];
+        let mut high = [0_u64; WORDS];
+        _mm256_storeu_si256(low.as_mut_ptr().cast(), _mm512_castsi512_si256(*pair));
+        _mm256_storeu_si256(
+            high.as_mut_ptr().cast(),
+            _mm512_extracti64x4_epi64::<1>(*pair),
+        );
+        values[0] = U256::from_limbs(low);
+        values[1] = U256::from_limbs(high);
+    }
+}
+
+// Word indices into two vectors for the two steps of `transpose`
+#[inline(always)]
+unsafe fn interleave() -> (__m512i, __m512i, __m512i, __m512i) {
+    (
+        _mm512_setr_epi64(0, 4, 8, 12, 1, 5, 9, 13),
+        _mm512_setr_epi64(2, 6, 10, 14, 3, 7, 11, 15),
+        _mm512_setr_epi64(0, 1, 2, 3, 8, 9, 10, 11),
+        _mm512_setr_epi64(4, 5, 6, 7, 12, 13, 14, 15),
+    )
+}
+
+// Transposes eight values stored in pairs into one vector per word
+#[inline(always)]
+unsafe fn transpose(pairs: &[__m512i; WORDS]) -> [__m512i; WORDS] {
+    let (words_01, words_23, low, high) = interleave();
+    // Words 0 and 1 or 2 and 3 of four values each
+    let a = _mm512_permutex2var_epi64(pairs[0], words_01, pairs[1]);
+    let b = _mm512_permutex2var_epi64(pairs[0], words_23, pairs[1]);
+    let c = _mm512_permutex2var_epi64(pairs[2], words_01, pairs[3]);
+    let d = _mm512_permutex2var_epi64(pairs[2], words_23, pairs[3]);
+    [
+        _mm512_permutex2var_epi64(a, low, c),
+        _mm512_permutex2var_epi64(a, high, c),
+        _mm512_permutex2var_epi64(b, low, d),
+        _mm512_permutex2var_epi64(b, high, d),
+    ]
+}
+
+// Inverse of `transpose`
+#[inline(always)]
+unsafe fn untranspose(words: &[__m512i; WORDS]) -> [__m512i; WORDS] {
+    let (words_01, words_23, low, high) = interleave();
+    let a = _mm512_permutex2var_epi64(words[0], low, words[1]);
+    let b = _mm512_permutex2var_epi64(words[2], low, words[3]);
+    let c = _mm512_permutex2var_epi64(words[0], high, words[1]);
+    let d = _mm512_permutex2var_epi64(words[2], high, words[3]);
+    [
+        _mm512_permutex2var_epi64(a, words_01, b),
+        _mm512_permutex2var_epi64(a, words_23, b),
+        _mm512_permutex2var_epi64(c, words_01, d),
+        _mm512_permutex2var_epi64(c, words_23, d),
+    ]
+}
+
+// Shifts by a count that is constant after inlining. Counts are at most 64 so
+// the casts do not wrap.
+#[allow(clippy::cast_possible_wrap)]
+#[inline(always)]
+unsafe fn shl(value: __m512i, count: u32) -> __m512i {
+    _mm512_sll_epi64(value, _mm_cvtsi32_si128(count as i32))
+}
+
+#[allow(clippy::cast_possible_wrap)]
+#[inline(always)]
+unsafe fn shr(value: __m512i, count: u32) -> __m512i {
+    _mm512_srl_epi64(value, _mm_cvtsi32_si128(count as i32))
+}
+
+// Limbs are less than 2^52 so the casts do not wrap.
+#[allow(clippy::cast_possible_wrap)]
+#[inline(always)]
+unsafe fn splat(value: &U256, shift: u32) -> Packed {
+    let limbs = split::<LIMBS>(value, BITS, shift);
+    let mut result = [_mm512_setzero_si512(); LIMBS];
+    for (result, limb) in result.iter_mut().zip(limbs.iter()) {
+        *result = _mm512_set1_epi64(*limb as i64);
+    }
+    result
+}
+
+#[inline(always)]
+unsafe fn add(a: &Packed, b: &Packed, modulus: &Packed) -> Packed {
+    let mask = _mm512_set1_epi64(MASK);
+    let mut sum = [_mm512_setzero_si512(); LIMBS];
+    let mut carry = _mm512_setzero_si512();
+    for ((sum, a), b) in sum.iter_mut().zip(a.iter()).zip(b.iter()) {
+        let limb = _mm512_add_epi64(_mm512_add_epi64(*a, *b), carry);
+        carry = _mm512_srli_epi64::<BITS>(limb);
+        *sum = _mm512_and_si512(limb, mask);
+    }
+    reduce_1(&sum, modulus)
+}
+
+#[inline(always)]
+unsafe fn sub(a: &Packed, b: &Packed, modulus: &Packed) -> Packed {
+    let zero = _mm512_setzero_si512();
+    let mask = _mm512_set1_epi64(MASK);
+    let mut difference = [zero; LIMBS];
+    let mut borrow = zero;
+    for ((difference, a), b) in difference.iter_mut().zip(a.iter()).zip(b.iter()) {
+        let limb = _mm512_sub_epi64(_mm512_sub_epi64(*a, *b), borrow);
+        borrow = _mm512_srli_epi64::<63>(limb);
+        *difference = _mm512_and_si512(limb, mask);
+    }
+    // Add the modulus in the lanes that borrowed
+    let negative = _mm512_sub_epi64(zero, borrow);
+    let mut carry = zero;
+    for (difference, modulus) in difference.iter_mut().zip(modulus.iter()) {
+        let limb = _mm512_add_epi64(
+            _mm512_add_epi64(*difference, _mm512_and_si512(*modulus, negative)),
+            carry,
+        );
+        carry = _mm512_srli_epi64::<BITS>(limb);
+        *difference = _mm512_and_si512(limb, mask);
+    }
+    difference
+}
+
+// Montgomery multiplication with `R = 2^260`. The extra factor `2^4` is
+// compensated by passing `a` shifted left by `SHIFT`, which is less than
+// `2^260` and keeps the result below `2 * MODULUS`.
+#[inline(always)]
+unsafe fn mul(a: &Packed, b: &Packed, modulus: &Packed, m0: __m512i) -> Packed {
+    let zero = _mm512_setzero_si512();
+    let mut t = [zero; LIMBS + 1];
+    for a in a {
+        for (j, b) in b.iter().enumerate() {
+            t[j] = _mm512_madd52lo_epu64(t[j], *a, *b);
+            t[j + 1] = _mm512_madd52hi_epu64(t[j + 1], *a, *b);
+        }
+        let m = _mm512_madd52lo_epu64(zero, t[0], m0);
+        for (j, modulus) in modulus.iter().enumerate() {
+            t[j] = _mm512_madd52lo_epu64(t[j], m, *modulus);
+            t[j + 1] = _mm512_madd52hi_epu64(t[j + 1], m, *modulus);
+        }
+        // The lowest limb is now divisible by 2^52, shift it out
+        t[1] = _mm512_add_epi64(t[1], _mm512_srli_epi64::<BITS>(t[0]));
+        t.copy_within(1.., 0);
+        t[LIMBS] = zero;
+    }
+    let mut result = [zero; LIMBS];
+    result.copy_from_slice(&t[..LIMBS]);
+    normalize(&mut result);
+    reduce_1(&result, modulus)
+}
+
+// Propagates carries so all limbs are less than 2^52
+#[inline(always)]
+unsafe fn normalize(value: &mut Packed) {
+    let mask = _mm512_set1_epi64(MASK);
+    for j in 1..LIMBS {
+        let carry = _mm512_srli_epi64::<BITS>(value[j - 1]);
+        value[j - 1] = _mm512_and_si512(value[j - 1], mask);
+        value[j] = _mm512_add_epi64(value[j], carry);
+    }
+}
+
+// Subtracts the modulus from values less than twice the modulus
+#[inline(always)]
+unsafe fn reduce_1(value: &Packed, modulus: &Packed) -> Packed {
+    let zero = _mm512_setzero_si512();
+    let mask = _mm512_set1_epi64(MASK);
+    let mut difference = [zero; LIMBS];
+    let mut borrow = zero;
+    for ((difference, value), modulus) in
+        difference.iter_mut().zip(value.iter()).zip(modulus.iter())
+    {
+        let limb = _mm512_sub_epi64(_mm512_sub_epi64(*value, *modulus), borrow);
+        borrow = _mm512_srli_epi64::<63>(limb);
+        *difference = _mm512_and_si512(limb, mask);
+    }
+    // Keep the original value in the lanes that borrowed
+    let keep = _mm512_sub_epi64(zero, borrow);
+    for (difference, value) in difference.iter_mut().zip(value.iter()) {
+        *difference = _mm512_or_si512(
+            _mm512_and_si512(keep, *value),
+            _mm512_andnot_si512(keep, *difference),
+        );
+    }
+    difference
+}
@@ -0,0 +1,354 @@
+// Allow `core::arch` intrinsics in this module.
+#![allow(unsafe_code)]
+
+// Vectorized Montgomery arithmetic on slices of `U256`
+//
+// Elements are transposed so each vector lane holds one element, split into
+// limbs small enough to multiply without carries. The transpose and the limb
+// shifts happen in registers, once when a vector of elements is loaded and
+// once when it is stored; all operations in between work on the limbs. The
+// backend is selected at runtime when `std` is available and at compile time
+// otherwise. Slices are processed a vector at a time, the remainder by the
+// generic implementation.
+//
+// For the instructions and their latencies
+// See <https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html>
+// See <https://uops.info/table.html>
+// For Montgomery multiplication with IFMA
+// See <https://eprint.iacr.org/2018/335.pdf>
+
+mod avx2;
+mod avx512;
+
+use super::generic;
+use crate::{MontgomeryParameters, U256};
+
+// Both backends use 260 bit limbs and hence `R = 2^260`. Multiplying one
+// operand by `2^(260 - 256)` gives the same result as `R = 2^256`.
+const SHIFT: u32 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Backend {
+    Avx512,
+    Avx2,
+    Generic,
+}
+
+impl Backend {
+    #[cfg(feature = "std")]
+    fn detect() -> Self {
+        if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512ifma") {
+            Self::Avx512
+        } else if is_x86_feature_detected!("avx2") {
+            Self::Avx2
+        } else {
+            Self::Generic
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn detect() -> Self {
+        if cfg!(all(
+            target_feature = "avx512f",
+            target_feature = "avx512ifma"
+        )) {
+            Self::Avx512
+        } else if cfg!(target_feature = "avx2") {
+            Self::Avx2
+        } else {
+            Self::Generic
+        }
+    }
+}
+
+pub(crate) fn add_mod_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    add_mod_slice_with::<M>(Backend::detect(), values, rhs);
+}
+
+pub(crate) fn sub_mod_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    sub_mod_slice_with::<M>(Backend::detect(), values, rhs);
+}
+
+pub(crate) fn mul_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    mul_redc_slice_with::<M>(Backend::detect(), values, rhs);
+}
+
+pub(crate) fn mul_redc_scalar_slice<M: MontgomeryParameters<UInt = U256>>(
+    values: &mut [U256],
+    factor: &U256,
+) {
+    mul_redc_scalar_slice_with::<M>(Backend::detect(), values, factor);
+}
+
+pub(crate) fn butterfly_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+    left: &mut [U256],
+    right: &mut [U256],
+    twiddle: &U256,
+) {
+    butterfly_redc_slice_with::<M>(Backend::detect(), left, right, twiddle);
+}
+
+// The `unsafe` blocks below are sound because `backend` is only a vector
+// backend when the CPU supports it.
+
+fn add_mod_slice_with<M: MontgomeryParameters<UInt = U256>>(
+    backend: Backend,
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    assert_eq!(values.len(), rhs.len());
+    let done = match backend {
+        Backend::Avx512 => unsafe { avx512::add_mod_slice::<M>(values, rhs) },
+        Backend::Avx2 => unsafe { avx2::add_mod_slice::<M>(values, rhs) },
+        Backend::Generic => 0,
+    };
+    generic::add_mod_slice::<M>(&mut values[done..], &rhs[done..]);
+}
+
+fn sub_mod_slice_with<M: MontgomeryParameters<UInt = U256>>(
+    backend: Backend,
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    assert_eq!(values.len(), rhs.len());
+    let done = match backend {
+        Backend::Avx512 => unsafe { avx512::sub_mod_slice::<M>(values, rhs) },
+        Backend::Avx2 => unsafe { avx2::sub_mod_slice::<M>(values, rhs) },
+        Backend::Generic => 0,
+    };
+    generic::sub_mod_slice::<M>(&mut values[done..], &rhs[done..]);
+}
+
+fn mul_redc_slice_with<M: MontgomeryParameters<UInt = U256>>(
+    backend: Backend,
+    values: &mut [U256],
+    rhs: &[U256],
+) {
+    assert_eq!(values.len(), rhs.len());
+    let done = match backend {
+        Backend::Avx512 => unsafe { avx512::mul_redc_slice::<M>(values, rhs) },
+        Backend::Avx2 => unsafe { avx2::mul_redc_slice::<M>(values, rhs) },
+        Backend::Generic => 0,
+    };
+    generic::mul_redc_slice::<M>(&mut values[done..], &rhs[done..]);
+}
+
+fn mul_redc_scalar_slice_with<M: MontgomeryParameters<UInt = U256>>(
+    backend: Backend,
+    values: &mut [U256],
+    factor: &U256,
+) {
+    let done = match backend {
+        Backend::Avx512 => unsafe { avx512::mul_redc_scalar_slice::<M>(values, factor) },
+        Backend::Avx2 => unsafe { avx2::mul_redc_scalar_slice::<M>(values, factor) },
+        Backend::Generic => 0,
+    };
+    generic::mul_redc_scalar_slice::<M>(&mut values[done..], factor);
+}
+
+fn butterfly_redc_slice_with<M: MontgomeryParameters<UInt = U256>>(
+    backend: Backend,
+    left: &mut [U256],
+    right: &mut [U256],
+    twiddle: &U256,
+) {
+    assert_eq!(left.len(), right.len());
+    let done = match backend {
+        Backend::Avx512 => unsafe { avx512::butterfly_redc_slice::<M>(left, right, twiddle) },
+        Backend::Avx2 => unsafe { avx2::butterfly_redc_slice::<M>(left, right, twiddle) },
+        Backend::Generic => 0,
+    };
+    generic::butterfly_redc_slice::<M>(&mut left[done..], &mut right[done..], twiddle);
+}
+
+/// Splits `value << shift` into `N` limbs of `bits` bits.
+// The number of limbs is small
+#[allow(clippy::cast_possible_truncation)]
+#[inline(always)]
+fn split<const N: usize>(value: &U256, bits: u32, shift: u32) -> [u64; N] {
+    debug_assert!(shift < 64);
+    debug_assert!(N as u32 * bits >= 256 + shift);
+    let limbs = value.as_limbs();
+    let mut words = [0_u64; 5];
+    words[0] = limbs[0] << shift;
+    for i in 1..4 {
+        words[i] = (limbs[i] << shift) | (limbs[i - 1] >> 1 >> (63 - shift));
+    }
+    words[4] = limbs[3] >> 1 >> (63 - shift);
+    let mask = (1_u64 << bits) - 1;
+    let mut result = [0_u64; N];
+    for (i, limb) in result.iter_mut().enumerate() {
+        let position = i as u32 * bits;
+        let (word, offset) = ((position / 64) as usize, position % 64);
+        let mut value = words[word] >> offset;
+        if offset + bits > 64 && word + 1 < words.len() {
+            value |= words[word + 1] << (64 - offset);
+        }
+        *limb = value & mask;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Montgomery;
+    use proptest::{collection::vec, prelude::*};
+    use zkp_macros_decl::u256h;
+
+    // Joins `N` limbs of `bits` bits, the inverse of `split` with zero shift.
+    // The number of limbs is small
+    #[allow(clippy::cast_possible_truncation)]
+    fn join<const N: usize>(limbs: &[u64; N], bits: u32) -> U256 {
+        let mut words = [0_u64; 4];
+        for (i, limb) in limbs.iter().enumerate() {
+            let position = i as u32 * bits;
+            let (word, offset) = ((position / 64) as usize, position % 64);
+            if word < words.len() {
+                words[word] |= limb << offset;
+            }
+            if offset + bits > 64 && word + 1 < words.len() {
+                words[word + 1] |= limb >> (64 - offset);
+            }
+        }
+        U256::from_limbs(words)
+    }
+
+    // The Stark field, a Proth prime
+    struct Proth();
+
+    impl MontgomeryParameters for Proth {
+        type UInt = U256;
+
+        const M64: u64 = 0xffff_ffff_ffff_ffff;
+        const MODULUS: U256 =
+            u256h!("0800000000000011000000000000000000000000000000000000000000000001");
+        const R1: U256 = u256h!("07fffffffffffdf0ffffffffffffffffffffffffffffffffffffffffffffffe1");
+        const R2: U256 = u256h!("07ffd4ab5e008810ffffffffff6f800000000001330ffffffffffd737e000401");
+        const R3: U256 = u256h!("038e5f79873c0a6df47d84f8363000187545706677ffcc06cc7177d1406df18e");
+    }
+
+    // Curve25519 field, 2^255 - 19
+    struct Curve25519();
+
+    impl MontgomeryParameters for Curve25519 {
+        type UInt = U256;
+
+        const M64: u64 = 0x86bc_a1af_286b_ca1b;
+        const MODULUS: U256 =
+            u256h!("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed");
+        const R1: U256 = u256h!("0000000000000000000000000000000000000000000000000000000000000026");
+        const R2: U256 = u256h!("00000000000000000000000000000000000000000000000000000000000005a4");
+        const R3: U256 = u256h!("000000000000000000000000000000000000000000000000000000000000d658");
+    }
+
+    fn backends() -> Vec<Backend> {
+        let mut backends = vec![Backend::Generic];
+        if is_x86_feature_detected!("avx2") {
+            backends.push(Backend::Avx2);
+        }
+        if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512ifma") {
+            backends.push(Backend::Avx512);
+        }
+        backends
+    }
+
+    fn reduced<M: MontgomeryParameters<UInt = U256>>(values: &[U256]) -> Vec<U256> {
+        values.iter().map(|value| value % &M::MODULUS).collect()
+    }
+
+    fn test_slices<M: MontgomeryParameters<UInt = U256>>(
+        a: &[U256],
+        b: &[U256],
+        factor: &U256,
+    ) -> Result<(), TestCaseError> {
+        let length = a.len().min(b.len());
+        let a = reduced::<M>(&a[..length]);
+        let b = reduced::<M>(&b[..length]);
+        let factor = factor % &M::MODULUS;
+        let zip = |f: fn(&U256, &U256) -> U256| {
+            a.iter()
+                .zip(b.iter())
+                .map(|(a, b)| f(a, b))
+                .collect::<Vec<_>>()
+        };
+        let sums = zip(U256::add_mod_inline::<M>);
+        let differences = zip(|a, b| generic::reduce_1(&(a + &M::MODULUS - b), &M::MODULUS));
+        let products = zip(U256::mul_redc_inline::<M>);
+        let scaled = a
+            .iter()
+            .map(|a| a.mul_redc_inline::<M>(&factor))
+            .collect::<Vec<_>>();
+        let twiddled = b
+            .iter()
+            .map(|b| b.mul_redc_inline::<M>(&factor))
+            .collect::<Vec<_>>();
+        for backend in backends() {
+            let mut result = a.clone();
+            add_mod_slice_with::<M>(backend, &mut result, &b);
+            prop_assert_eq!(&result, &sums, "{:?}", backend);
+
+            let mut result = a.clone();
+            sub_mod_slice_with::<M>(backend, &mut result, &b);
+            prop_assert_eq!(&result, &differences, "{:?}", backend);
+
+            let mut result = a.clone();
+            mul_redc_slice_with::<M>(backend, &mut result, &b);
+            prop_assert_eq!(&result, &products, "{:?}", backend);
+
+            let mut result = a.clone();
+            mul_redc_scalar_slice_with::<M>(backend, &mut result, &factor);
+            prop_assert_eq!(&result, &scaled, "{:?}", backend);
+
+            let mut left = a.clone();
+            let mut right = b.clone();
+            butterfly_redc_slice_with::<M>(backend, &mut left, &mut right, &factor);
+            for (((left, right), a), t) in left.iter().zip(&right).zip(&a).zip(&twiddled) {
+                prop_assert_eq!(left, &a.add_mod_inline::<M>(t), "{:?}", backend);
+                prop_assert_eq!(right.add_mod_inline::<M>(t), a.clone(), "{:?}", backend);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_join() {
+        let value = u256h!("0800000000000011000000000000000000000000000000000000000000000001");
+        assert_eq!(join(&split::<5>(&value, 52, 0), 52), value);
+        assert_eq!(join(&split::<10>(&value, 26, 0), 26), value);
+        assert_eq!(
+            join(&split::<5>(&value, 52, SHIFT), 52),
+            value.clone() << SHIFT as usize
+        );
+    }
+
+    #[test]
+    fn test_extremes() {
+        let maximum = &Proth::MODULUS - U256::ONE;
+        let a = vec![maximum.clone(); 13];
+        let b = vec![maximum; 13];
+        test_slices::<Proth>(&a, &b, &U256::ZERO).unwrap();
+        test_slices::<Proth>(&a, &b, &(&Proth::MODULUS - U256::ONE)).unwrap();
+    }
+
+    proptest!(
+        #[test]
+        fn test_proth(a in vec(any::<U256>(), 0..40), b in vec(any::<U256>(), 0..40), factor: U256) {
+            test_slices::<Proth>(&a, &b, &factor)?;
+        }
+
+        #[test]
+        fn test_generic(a in vec(any::<U256>(), 0..40), b in vec(any::<U256>(), 0..40), factor: U256) {
+            test_slices::<Curve25519>(&a, &b, &factor)?;
+        }
+    );
+}
@@ -1,7 +1,10 @@
 use crate::{
     algorithms::{mul_redc_inline, redc_inline, square_redc_inline},
-    arch::{gcd, gcd_extended, reduce_1},
-    InvMod, Montgomery, MontgomeryParameters, GCD, U256,
+    arch::{
+        add_mod_slice, butterfly_redc_slice, gcd, gcd_extended, mul_redc_scalar_slice,
+        mul_redc_slice, reduce_1, sub_mod_slice,
+    },
+    AddFullInline, InvMod, Montgomery, MontgomeryParameters, MontgomerySlice, SubInline, GCD, U256,
 };
 
 impl GCD for U256 {
@@ -26,6 +29,19 @@ impl Montgomery for U256 {
         reduce_1(self, &M::MODULUS)
     }
 
+    // For moduli of 2^255 or more the sum can carry out of the 256 bits. It
+    // is then larger than the modulus, and the wrapping subtraction gives the
+    // reduced value.
+    #[inline(always)]
+    fn add_mod_inline<M: MontgomeryParameters<UInt = U256>>(&self, rhs: &Self) -> Self {
+        let (sum, carry) = self.add_full_inline(rhs);
+        if carry > 0 {
+            sum.sub_inline(&M::MODULUS)
+        } else {
+            reduce_1(&sum, &M::MODULUS)
+        }
+    }
+
     #[inline(always)]
     fn redc_inline<M: MontgomeryParameters<UInt = U256>>(lo: &Self, hi: &Self) -> Self {
         redc_inline::<M>(lo, hi)
@@ -47,3 +63,79 @@ impl Montgomery for U256 {
         self.inv_mod(&M::MODULUS).map(|ni| ni.mul_redc::<M>(&M::R3))
     }
 }
+
+// Uses vector instructions where available
+impl MontgomerySlice for U256 {
+    fn add_mod_slice<M: MontgomeryParameters<UInt = U256>>(values: &mut [Self], rhs: &[Self]) {
+        add_mod_slice::<M>(values, rhs);
+    }
+
+    fn sub_mod_slice<M: MontgomeryParameters<UInt = U256>>(values: &mut [Self], rhs: &[Self]) {
+        sub_mod_slice::<M>(values, rhs);
+    }
+
+    fn mul_redc_slice<M: MontgomeryParameters<UInt = U256>>(values: &mut [Self], rhs: &[Self]) {
+        mul_redc_slice::<M>(values, rhs);
+    }
+
+    fn mul_redc_scalar_slice<M: MontgomeryParameters<UInt = U256>>(
+        values: &mut [Self],
+        factor: &Self,
+    ) {
+        mul_redc_scalar_slice::<M>(values, factor);
+    }
+
+    fn butterfly_redc_slice<M: MontgomeryParameters<UInt = U256>>(
+        left: &mut [Self],
+        right: &mut [Self],
+        twiddle: &Self,
+    ) {
+        butterfly_redc_slice::<M>(left, right, twiddle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use zkp_macros_decl::u256h;
+
+    // 2^256 - 189, the largest 256 bit prime
+    struct Large();
+
+    impl MontgomeryParameters for Large {
+        type UInt = U256;
+
+        const M64: u64 = 0xa53f_a94f_ea53_fa95;
+        const MODULUS: U256 =
+            u256h!("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff43");
+        const R1: U256 = u256h!("00000000000000000000000000000000000000000000000000000000000000bd");
+        const R2: U256 = u256h!("0000000000000000000000000000000000000000000000000000000000008b89");
+        const R3: U256 = u256h!("0000000000000000000000000000000000000000000000000000000000670425");
+    }
+
+    #[test]
+    fn test_add_mod_carry() {
+        let max = &Large::MODULUS - U256::ONE;
+        assert_eq!(
+            max.add_mod_inline::<Large>(&max),
+            &Large::MODULUS - U256::from(2_u64)
+        );
+        assert_eq!(max.add_mod_inline::<Large>(&U256::ONE), U256::ZERO);
+    }
+
+    proptest!(
+        #[test]
+        fn add_mod_large(a: U256, b: U256) {
+            let a = a % &Large::MODULUS;
+            let b = b % &Large::MODULUS;
+            let complement = &Large::MODULUS - &b;
+            let expected = if a >= complement {
+                &a - &complement
+            } else {
+                &a + &b
+            };
+            prop_assert_eq!(a.add_mod_inline::<Large>(&b), expected);
+        }
+    );
+}
@@ -68,6 +68,7 @@ mod rand;
 mod traits;
 mod u256;
 mod u256_traits;
+mod word;
 
 // TODO: Create a BinaryRing trait that represents numbers modulo some power of
 // two.
@@ -79,8 +80,9 @@ pub use algorithms::{adc, div_2_1, mac, msb, sbb, to_montgomery_const};
 pub use num_traits::{Bounded, Inv, MulAdd, MulAddAssign, One, Pow, Zero};
 pub use traits::{
     AddFullInline, AddInline, Binary, BinaryAssignRef, BinaryOps, BinaryRing, DivRem, InvMod,
-    Montgomery, MontgomeryParameters, MulFullInline, MulInline, NegInline, SquareFullInline,
-    SquareInline, SubFromFullInline, SubFromInline, SubFullInline, SubInline, GCD,
+    Montgomery, MontgomeryParameters, MontgomerySlice, MulFullInline, MulInline, NegInline,
+    SquareFullInline, SquareInline, SubFromFullInline, SubFromInline, SubFullInline, SubInline,
+    GCD,
 };
 
 #[cfg(not(feature = "std"))]
@@ -1,5 +1,5 @@
 mod binary;
-use crate::Zero;
+use crate::{algorithms::montgomery::slice, Zero};
 
 // TODO: Similar OpInline tratis for BitAnd, BitOr, BitXor, ...
 
@@ -314,6 +314,10 @@ pub trait Montgomery: Zero {
         Self::reduce_1_inline::<M>(self)
     }
 
+    /// Modular addition of two reduced numbers
+    /// **Note.** Implementers *must* add the `#[inline(always)]` attribute
+    fn add_mod_inline<M: MontgomeryParameters<UInt = Self>>(&self, rhs: &Self) -> Self;
+
     /// **Note.** Implementers *must* add the `#[inline(always)]` attribute
     fn redc_inline<M: MontgomeryParameters<UInt = Self>>(lo: &Self, hi: &Self) -> Self;
 
@@ -357,6 +361,53 @@ pub trait Montgomery: Zero {
     }
 }
 
+/// Element-wise Montgomery arithmetic on slices
+///
+/// All values are required to be reduced modulo `M::MODULUS`. The default
+/// implementations process one element at a time, implementations can
+/// override them to process several elements at once using vector
+/// instructions.
+///
+/// # Panics
+///
+/// The binary operations panic if the slices differ in length.
+pub trait MontgomerySlice:
+    Montgomery + PartialOrd + for<'a> AddInline<&'a Self> + for<'a> SubInline<&'a Self>
+{
+    /// Modular addition of `rhs` to `values`.
+    fn add_mod_slice<M: MontgomeryParameters<UInt = Self>>(values: &mut [Self], rhs: &[Self]) {
+        slice::add_mod_slice::<Self, M>(values, rhs);
+    }
+
+    /// Modular subtraction of `rhs` from `values`.
+    fn sub_mod_slice<M: MontgomeryParameters<UInt = Self>>(values: &mut [Self], rhs: &[Self]) {
+        slice::sub_mod_slice::<Self, M>(values, rhs);
+    }
+
+    /// Montgomery multiplication of `values` by `rhs`.
+    fn mul_redc_slice<M: MontgomeryParameters<UInt = Self>>(values: &mut [Self], rhs: &[Self]) {
+        slice::mul_redc_slice::<Self, M>(values, rhs);
+    }
+
+    /// Montgomery multiplication of `values` by a single `factor`.
+    fn mul_redc_scalar_slice<M: MontgomeryParameters<UInt = Self>>(
+        values: &mut [Self],
+        factor: &Self,
+    ) {
+        slice::mul_redc_scalar_slice::<Self, M>(values, factor);
+    }
+
+    /// Radix-2 butterflies `(u, v) -> (u + w v, u - w v)` with `w v` a
+    /// Montgomery multiplication.
+    fn butterfly_redc_slice<M: MontgomeryParameters<UInt = Self>>(
+        left: &mut [Self],
+        right: &mut [Self],
+        twiddle: &Self,
+    ) {
+        slice::butterfly_redc_slice::<Self, M>(left, right, twiddle);
+    }
+}
+
 // TODO: Mega-trait for binary rings like U256 that PrimeField can use
 
 // False positive, we re-export the trait.
@@ -0,0 +1,253 @@
+// Machine word implementations of the arithmetic traits
+//
+// These allow `u32` and `u64` to be used as the `UInt` of small prime
+// fields. Montgomery form uses `R = 2^32` and `R = 2^64` respectively, and
+// `MontgomeryParameters::M64` is truncated to the word size. Reductions are
+// done in the double width type, so moduli may use all the bits of the word.
+use crate::{
+    AddInline, DivRem, InvMod, Montgomery, MontgomeryParameters, MontgomerySlice, SubInline,
+};
+
+macro_rules! impl_word {
+    ($word:ty, $double:ty, $signed:ty) => {
+        impl AddInline<&$word> for $word {
+            /// Wrapping addition
+            #[inline(always)]
+            fn add_inline(&self, rhs: &$word) -> Self {
+                self.wrapping_add(*rhs)
+            }
+        }
+
+        impl SubInline<&$word> for $word {
+            /// Wrapping subtraction
+            #[inline(always)]
+            fn sub_inline(&self, rhs: &$word) -> Self {
+                self.wrapping_sub(*rhs)
+            }
+        }
+
+        impl DivRem<&$word> for $word {
+            type Quotient = Self;
+            type Remainder = Self;
+
+            #[inline(always)]
+            fn div_rem(&self, rhs: &$word) -> Option<(Self, Self)> {
+                Some((self.checked_div(*rhs)?, self % rhs))
+            }
+        }
+
+        impl InvMod for $word {
+            /// Computes the inverse modulo a given modulus
+            // The signed double width type can hold all intermediate values
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            fn inv_mod(&self, modulus: &Self) -> Option<Self> {
+                if *modulus == 0 {
+                    return None;
+                }
+                // Extended Euclid, only tracking the coefficient of `self`
+                let mut r0 = <$signed>::from(*modulus);
+                let mut r1 = <$signed>::from(self % modulus);
+                let mut t0: $signed = 0;
+                let mut t1: $signed = 1;
+                while r1 != 0 {
+                    let q = r0 / r1;
+                    let r = r0 - q * r1;
+                    r0 = r1;
+                    r1 = r;
+                    let t = t0 - q * t1;
+                    t0 = t1;
+                    t1 = t;
+                }
+                if r0 != 1 {
+                    return None;
+                }
+                if t0 < 0 {
+                    t0 += <$signed>::from(*modulus);
+                }
+                Some(t0 as $word)
+            }
+        }
+
+        impl Montgomery for $word {
+            #[inline(always)]
+            fn reduce_1_inline<M: MontgomeryParameters<UInt = $word>>(&self) -> Self {
+                if *self >= M::MODULUS {
+                    self - M::MODULUS
+                } else {
+                    *self
+                }
+            }
+
+            #[inline(always)]
+            fn add_mod_inline<M: MontgomeryParameters<UInt = $word>>(&self, rhs: &Self) -> Self {
+                let (sum, carry) = self.overflowing_add(*rhs);
+                if carry || sum >= M::MODULUS {
+                    sum.wrapping_sub(M::MODULUS)
+                } else {
+                    sum
+                }
+            }
+
+            // `M64` is truncated to the word size. The result is less than
+            // twice the modulus, so it fits the word after one subtraction.
+            #[allow(
+                trivial_numeric_casts,
+                clippy::cast_possible_truncation,
+                clippy::unnecessary_cast
+            )]
+            #[inline(always)]
+            fn redc_inline<M: MontgomeryParameters<UInt = $word>>(lo: &Self, hi: &Self) -> Self {
+                let m = lo.wrapping_mul(M::M64 as $word);
+                let t = (<$double>::from(*lo) + <$double>::from(m) * <$double>::from(M::MODULUS))
+                    >> <$word>::BITS;
+                let t = t + <$double>::from(*hi);
+                if t >= <$double>::from(M::MODULUS) {
+                    (t - <$double>::from(M::MODULUS)) as $word
+                } else {
+                    t as $word
+                }
+            }
+
+            #[inline(always)]
+            fn square_redc_inline<M: MontgomeryParameters<UInt = $word>>(&self) -> Self {
+                self.mul_redc_inline::<M>(self)
+            }
+
+            // Splitting the double width product into words
+            #[allow(clippy::cast_possible_truncation)]
+            #[inline(always)]
+            fn mul_redc_inline<M: MontgomeryParameters<UInt = $word>>(&self, rhs: &Self) -> Self {
+                let product = <$double>::from(*self) * <$double>::from(*rhs);
+                let lo = product as $word;
+                let hi = (product >> <$word>::BITS) as $word;
+                Self::redc_inline::<M>(&lo, &hi)
+            }
+
+            // Inline to reduce to `inv_mod` + `mul_redc`
+            #[inline(always)]
+            fn inv_redc<M: MontgomeryParameters<UInt = $word>>(&self) -> Option<Self> {
+                self.inv_mod(&M::MODULUS).map(|ni| ni.mul_redc::<M>(&M::R3))
+            }
+        }
+
+        impl MontgomerySlice for $word {}
+    };
+}
+
+impl_word!(u32, u64, i64);
+impl_word!(u64, u128, i128);
+
+impl DivRem<u64> for u32 {
+    type Quotient = Self;
+    type Remainder = u64;
+
+    // The quotient is at most `self`
+    #[allow(clippy::cast_possible_truncation)]
+    #[inline(always)]
+    fn div_rem(&self, rhs: u64) -> Option<(Self, u64)> {
+        let numerator = u64::from(*self);
+        Some((numerator.checked_div(rhs)? as u32, numerator % rhs))
+    }
+}
+
+impl DivRem<u64> for u64 {
+    type Quotient = Self;
+    type Remainder = Self;
+
+    #[inline(always)]
+    fn div_rem(&self, rhs: u64) -> Option<(Self, Self)> {
+        self.div_rem(&rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Goldilocks, 2^64 - 2^32 + 1
+    struct Goldilocks();
+
+    impl MontgomeryParameters for Goldilocks {
+        type UInt = u64;
+
+        const M64: u64 = 0xffff_fffe_ffff_ffff;
+        const MODULUS: u64 = 0xffff_ffff_0000_0001;
+        const R1: u64 = 0xffff_ffff;
+        const R2: u64 = 0xffff_fffe_0000_0001;
+        const R3: u64 = 0x1;
+    }
+
+    // BabyBear, 2^31 - 2^27 + 1
+    struct BabyBear();
+
+    impl MontgomeryParameters for BabyBear {
+        type UInt = u32;
+
+        const M64: u64 = 0xc7c0_0000_77ff_ffff;
+        const MODULUS: u32 = 0x7800_0001;
+        const R1: u32 = 0x0fff_fffe;
+        const R2: u32 = 0x45dd_dde3;
+        const R3: u32 = 0x12f3_7bfb;
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn mul_mod_64(a: u64, b: u64, modulus: u64) -> u64 {
+        (u128::from(a) * u128::from(b) % u128::from(modulus)) as u64
+    }
+
+    #[test]
+    fn test_inv_mod() {
+        assert_eq!(3_u64.inv_mod(&7), Some(5));
+        assert_eq!(4_u32.inv_mod(&8), None);
+        assert_eq!(0_u64.inv_mod(&Goldilocks::MODULUS), None);
+        assert_eq!(1_u32.inv_mod(&0), None);
+    }
+
+    proptest!(
+        #[test]
+        fn test_mul_mod_64(a: u64, b: u64) {
+            let a = a % Goldilocks::MODULUS;
+            let b = b % Goldilocks::MODULUS;
+            prop_assert_eq!(
+                a.mul_mod::<Goldilocks>(&b),
+                mul_mod_64(a, b, Goldilocks::MODULUS)
+            );
+        }
+
+        #[test]
+        fn test_mul_mod_32(a: u32, b: u32) {
+            let a = a % BabyBear::MODULUS;
+            let b = b % BabyBear::MODULUS;
+            let expected = mul_mod_64(a.into(), b.into(), BabyBear::MODULUS.into());
+            prop_assert_eq!(u64::from(a.mul_mod::<BabyBear>(&b)), expected);
+        }
+
+        #[test]
+        fn test_montgomery_roundtrip(a: u64) {
+            let a = a % Goldilocks::MODULUS;
+            let mont = a.to_montgomery::<Goldilocks>();
+            prop_assert_eq!(mont, mul_mod_64(a, Goldilocks::R1, Goldilocks::MODULUS));
+            prop_assert_eq!(mont.from_montgomery::<Goldilocks>(), a);
+        }
+
+        #[test]
+        fn test_add_mod(a: u64, b: u64) {
+            let a = a % Goldilocks::MODULUS;
+            let b = b % Goldilocks::MODULUS;
+            let expected = (u128::from(a) + u128::from(b)) % u128::from(Goldilocks::MODULUS);
+            prop_assert_eq!(u128::from(a.add_mod_inline::<Goldilocks>(&b)), expected);
+        }
+
+        #[test]
+        fn test_inv_redc(a: u32) {
+            let a = a % BabyBear::MODULUS;
+            let inverse = a.inv_redc::<BabyBear>();
+            if a == 0 {
+                prop_assert_eq!(inverse, None);
+            } else {
+                prop_assert_eq!(a.mul_redc::<BabyBear>(&inverse.unwrap()), BabyBear::R1);
+            }
+        }
+    );
+}
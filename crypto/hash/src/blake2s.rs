@@ -0,0 +1,32 @@
+use crate::hasher::Hasher;
+use blake2s_simd::State;
+
+/// Blake2s with a 256-bit digest.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Blake2s(State);
+
+impl Blake2s {
+    #[must_use]
+    pub fn new() -> Self {
+        Self(State::new())
+    }
+}
+
+impl Hasher for Blake2s {
+    fn update(&mut self, input: &[u8]) {
+        let _ = self.0.update(input);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        let mut result: [u8; 32] = [0; 32];
+        result.copy_from_slice(self.0.finalize().as_bytes());
+        result
+    }
+}
+
+impl Default for Blake2s {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,4 +1,6 @@
 #[cfg(feature = "std")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "std")]
 use std::fmt;
 
 #[derive(Clone, Default, PartialEq, Eq)]
@@ -22,3 +24,47 @@ impl fmt::Debug for Hash {
         write!(f, "Hash(0x{:})", hex::encode(self.0))
     }
 }
+
+// Human readable formats get a `0x` prefixed hex string, like `U256`.
+#[cfg(feature = "std")]
+impl Serialize for Hash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            format!("0x{}", hex::encode(self.0)).serialize(serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> Deserialize<'a> for Hash {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let string = String::deserialize(deserializer)?;
+            let mut bytes = [0_u8; 32];
+            hex::decode_to_slice(string.trim_start_matches("0x"), &mut bytes)
+                .map_err(de::Error::custom)?;
+            Ok(Self(bytes))
+        } else {
+            <[u8; 32]>::deserialize(deserializer).map(Self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde() {
+        let expected = Hash::new([0x5a; 32]);
+        let serialized = serde_json::to_string(&expected).unwrap();
+        assert_eq!(
+            serialized,
+            "\"0x5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a\""
+        );
+        let actual: Hash = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,64 @@
+use crate::hash::Hash;
+
+/// A 256-bit hash function.
+///
+/// Implementations are used both for Merkle tree commitments and for the
+/// Fiat-Shamir channel. Commitments use [`Hasher::hash`], which allows an
+/// implementation to truncate or mask its output, while the channel uses the
+/// full digest from [`Hasher::finalize`].
+pub trait Hasher: Default {
+    fn update(&mut self, input: &[u8]);
+
+    /// The full 256-bit digest.
+    fn finalize(self) -> [u8; 32];
+
+    /// The digest used for commitments.
+    fn hash(self) -> Hash {
+        Hash::new(self.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Blake2s, Keccak256, MaskedKeccak, Sha256};
+    use zkp_macros_decl::hex;
+
+    fn digest<H: Hasher>() -> H {
+        let mut hasher = H::default();
+        hasher.update(b"");
+        hasher
+    }
+
+    #[test]
+    fn test_empty_digests() {
+        assert_eq!(
+            digest::<Keccak256>().finalize(),
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            digest::<MaskedKeccak>().finalize(),
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            digest::<Blake2s>().finalize(),
+            hex!("69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9")
+        );
+        assert_eq!(
+            digest::<Sha256>().finalize(),
+            hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+    }
+
+    #[test]
+    fn test_masked_hash() {
+        assert_eq!(
+            digest::<MaskedKeccak>().hash().as_bytes(),
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653000000000000000000000000")
+        );
+        assert_eq!(
+            digest::<Keccak256>().hash().as_bytes(),
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use crate::hasher::Hasher;
+use tiny_keccak::{Hasher as _, Keccak};
+
+/// Keccak-256, the hash function used by the EVM.
+#[derive(Clone)]
+pub struct Keccak256(Keccak);
+
+impl Keccak256 {
+    #[must_use]
+    pub fn new() -> Self {
+        Self(Keccak::v256())
+    }
+}
+
+impl Hasher for Keccak256 {
+    fn update(&mut self, input: &[u8]) {
+        self.0.update(input)
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        let mut result: [u8; 32] = [0; 32];
+        self.0.finalize(&mut result);
+        result
+    }
+}
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Debug for Keccak256 {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "Keccak256(...)")
+    }
+}
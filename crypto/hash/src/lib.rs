@@ -44,8 +44,16 @@
 #[cfg(not(feature = "std"))]
 extern crate no_std_compat as std;
 
+mod blake2s;
 mod hash;
 mod hashable;
+mod hasher;
+mod keccak;
 mod masked_keccak;
+mod poseidon;
+mod sha256;
 
-pub use crate::{hash::Hash, hashable::Hashable, masked_keccak::MaskedKeccak};
+pub use crate::{
+    blake2s::Blake2s, hash::Hash, hashable::Hashable, hasher::Hasher, keccak::Keccak256,
+    masked_keccak::MaskedKeccak, poseidon::Poseidon, sha256::Sha256,
+};
@@ -0,0 +1,231 @@
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use crate::hasher::Hasher;
+use lazy_static::lazy_static;
+use tiny_keccak::{Hasher as _, Keccak};
+use zkp_primefield::{FieldElement, Pow, Zero};
+use zkp_u256::U256;
+
+lazy_static! {
+    static ref ROUND_CONSTANTS: Vec<[FieldElement; 3]> = (0..Poseidon::ROUNDS)
+        .map(|round| {
+            [
+                round_constant(3 * round),
+                round_constant(3 * round + 1),
+                round_constant(3 * round + 2),
+            ]
+        })
+        .collect();
+}
+
+/// The `index`-th round constant: Keccak-256 of `"Poseidon"` followed by the
+/// big-endian index, reduced into the field.
+fn round_constant(index: usize) -> FieldElement {
+    let mut result = [0_u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(b"Poseidon");
+    keccak.update(&(index as u64).to_be_bytes());
+    keccak.finalize(&mut result);
+    FieldElement::from_uint_reduce(&U256::from_bytes_be(&result))
+}
+
+/// The Poseidon hash over the STARK field.
+///
+/// The permutation has a state of three field elements, uses $x^3$ as the
+/// S-box and has 8 full and 83 partial rounds. The partial rounds apply the
+/// S-box to the first element only.
+///
+/// The hasher is a sponge with a rate of two elements and the capacity
+/// initialized to the number of bytes absorbed. Input bytes are read as 32
+/// byte big-endian field elements in Montgomery form, which is how
+/// `FieldElement` and `Hash` values are hashed. A Merkle tree node is
+/// therefore a single permutation. The output is the first element of the
+/// state, in the same representation.
+///
+/// Chunks of 32 bytes that are not below the modulus are reduced, and the
+/// multiples of the modulus that were subtracted are added to the capacity
+/// before the permutation. This keeps the absorption injective without
+/// changing the hash of canonical inputs.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Poseidon(Vec<u8>);
+
+impl Poseidon {
+    pub const FULL_ROUNDS: usize = 8;
+    pub const PARTIAL_ROUNDS: usize = 83;
+    pub const ROUNDS: usize = Self::FULL_ROUNDS + Self::PARTIAL_ROUNDS;
+
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The constants added to the state at the start of each round.
+    pub fn round_constants() -> &'static [[FieldElement; 3]] {
+        &ROUND_CONSTANTS
+    }
+
+    /// Whether the S-box is applied to the full state in round `round`.
+    ///
+    /// The first and last `FULL_ROUNDS / 2` rounds are full rounds.
+    pub fn is_full_round(round: usize) -> bool {
+        let partial = Self::FULL_ROUNDS / 2..Self::FULL_ROUNDS / 2 + Self::PARTIAL_ROUNDS;
+        !partial.contains(&round)
+    }
+
+    /// Multiply by the MDS matrix
+    ///
+    /// $$
+    /// \begin{bmatrix} 3 & 1 & 1 \\\\ 1 & -1 & 1 \\\\ 1 & 1 & -2 \end{bmatrix}
+    /// $$
+    pub fn mds(state: &[FieldElement; 3]) -> [FieldElement; 3] {
+        let [a, b, c] = state;
+        let sum = a + b + c;
+        [&sum + a + a, &sum - b - b, &sum - c - c - c]
+    }
+
+    /// Apply a single round of the permutation.
+    pub fn round(round: usize, state: &mut [FieldElement; 3]) {
+        for (value, constant) in state.iter_mut().zip(&Self::round_constants()[round]) {
+            *value += constant;
+        }
+        if Self::is_full_round(round) {
+            for value in state.iter_mut() {
+                *value = value.pow(3_usize);
+            }
+        } else {
+            state[0] = state[0].pow(3_usize);
+        }
+        *state = Self::mds(state);
+    }
+
+    /// Apply the permutation.
+    pub fn permute(state: &mut [FieldElement; 3]) {
+        for round in 0..Self::ROUNDS {
+            Self::round(round, state);
+        }
+    }
+
+    /// The field element for up to 32 bytes, padded with zeros, and how many
+    /// times the modulus was subtracted to reduce it.
+    fn element(bytes: &[u8]) -> (FieldElement, u64) {
+        let mut padded = [0_u8; 32];
+        padded[..bytes.len()].copy_from_slice(bytes);
+        let mut uint = U256::from_bytes_be(&padded);
+        let mut quotient = 0;
+        while uint >= FieldElement::MODULUS {
+            uint -= &FieldElement::MODULUS;
+            quotient += 1;
+        }
+        (FieldElement::from_montgomery(uint), quotient)
+    }
+}
+
+impl Hasher for Poseidon {
+    fn update(&mut self, input: &[u8]) {
+        self.0.extend_from_slice(input);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        let mut state = [
+            FieldElement::zero(),
+            FieldElement::zero(),
+            FieldElement::from(self.0.len()),
+        ];
+        for block in self.0.chunks(64) {
+            let mut elements = block.chunks(32).map(Self::element);
+            let (element, mut quotients) = elements.next().unwrap();
+            state[0] += element;
+            if let Some((element, quotient)) = elements.next() {
+                state[1] += element;
+                quotients |= quotient << 5;
+            }
+            // Chunks that are not canonical are told apart from their reduced
+            // value in the capacity, above the length.
+            if quotients > 0 {
+                state[2] += FieldElement::from_montgomery(U256::from(quotients) << 64);
+            }
+            Self::permute(&mut state);
+        }
+        state[0].as_montgomery().to_bytes_be()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+    use zkp_macros_decl::{field_element, hex};
+    use zkp_primefield::One;
+
+    #[test]
+    fn test_round_constants() {
+        let constants = Poseidon::round_constants();
+        assert_eq!(constants.len(), Poseidon::ROUNDS);
+        assert_eq!(
+            constants[0][0],
+            field_element!("054c331189c0467b62a497fa3b30aa517851574fb8be571995adf11e394b08c2")
+        );
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut state = [
+            FieldElement::zero(),
+            FieldElement::one(),
+            FieldElement::from(2_usize),
+        ];
+        Poseidon::permute(&mut state);
+        assert_eq!(state, [
+            field_element!("04a22b9563a2c3d977c15ea3c104d4a9b29a32ed8ebd9279220188c9be5e52a4"),
+            field_element!("0523eed2aea07cac22d6159fce96c2fea8f8a712181571e839191feda4e4d0d3"),
+            field_element!("05fce5017440693ba6d805ab405562211563b03981f18ddddb98a989cf936b89"),
+        ]);
+    }
+
+    #[test]
+    fn test_hash() {
+        let mut hasher = Poseidon::new();
+        hasher.update(&hex!(
+            "0000000000000000000000000000000000000000000000000000000000000001"
+        ));
+        hasher.update(&hex!(
+            "0000000000000000000000000000000000000000000000000000000000000002"
+        ));
+        assert_eq!(
+            hasher.hash().as_bytes(),
+            hex!("0453a5a316b21da3d7f17fb04246f8325e9a04347acd3884db5bc2c85dff82d9")
+        );
+    }
+
+    #[test]
+    fn test_non_canonical_input() {
+        let x = U256::from(5_u64);
+        let x_plus_p = &x + &FieldElement::MODULUS;
+        let mut hasher = Poseidon::new();
+        hasher.update(&x.to_bytes_be());
+        let mut other = Poseidon::new();
+        other.update(&x_plus_p.to_bytes_be());
+        assert_ne!(hasher.hash(), other.hash());
+    }
+
+    #[test]
+    fn test_node_is_one_permutation() {
+        let left = FieldElement::from(5_usize);
+        let right = FieldElement::from(7_usize);
+        let mut hasher = Poseidon::new();
+        hasher.update(left.hash_with::<Poseidon>().as_bytes());
+        hasher.update(right.hash_with::<Poseidon>().as_bytes());
+
+        let mut state = [left, right, FieldElement::from(64_usize)];
+        Poseidon::permute(&mut state);
+        assert_eq!(
+            hasher.hash().as_bytes(),
+            &state[0].as_montgomery().to_bytes_be()[..]
+        );
+    }
+}
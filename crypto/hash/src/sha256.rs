@@ -0,0 +1,26 @@
+use crate::hasher::Hasher;
+use sha2::Digest;
+
+/// SHA-256, for environments with a native precompile.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Sha256(sha2::Sha256);
+
+impl Sha256 {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Hasher for Sha256 {
+    fn update(&mut self, input: &[u8]) {
+        self.0.update(input)
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        let mut result: [u8; 32] = [0; 32];
+        result.copy_from_slice(&self.0.finalize());
+        result
+    }
+}
@@ -13,7 +13,7 @@ const SIZES: [usize; 1] = [64];
 fn merkle_tree_size(crit: &mut Criterion) {
     log_size_bench(crit, "Merkle tree size", &SIZES, move |bench, size| {
         let leaves: Vec<_> = (0..size).map(U256::from).collect();
-        bench.iter(|| black_box(Tree::from_leaves(black_box(leaves.clone()))))
+        bench.iter(|| black_box(Tree::<_>::from_leaves(black_box(leaves.clone()))))
     });
 }
 
@@ -21,7 +21,7 @@ fn merkle_tree_threads(crit: &mut Criterion) {
     let size: usize = *SIZES.last().unwrap();
     log_thread_bench(crit, "Merkle tree threads", size, move |bench| {
         let leaves: Vec<_> = (0..size).map(U256::from).collect();
-        bench.iter(|| black_box(Tree::from_leaves(black_box(leaves.clone()))))
+        bench.iter(|| black_box(Tree::<_>::from_leaves(black_box(leaves.clone()))))
     });
 }
 
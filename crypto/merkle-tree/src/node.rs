@@ -1,12 +1,12 @@
-use zkp_hash::{Hash, Hashable, MaskedKeccak};
+use zkp_hash::{Hash, Hashable, Hasher};
 
 #[derive(Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub(crate) struct Node<'a>(pub(crate) &'a Hash, pub(crate) &'a Hash);
 
 impl Hashable for Node<'_> {
-    fn hash(&self) -> Hash {
-        let mut hasher = MaskedKeccak::new();
+    fn hash_with<H: Hasher>(&self) -> Hash {
+        let mut hasher = H::default();
         hasher.update(self.0.as_bytes());
         hasher.update(self.1.as_bytes());
         hasher.hash()
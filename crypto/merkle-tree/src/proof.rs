@@ -6,21 +6,22 @@ use std::prelude::v1::*;
 
 use crate::{Commitment, Error, Index, Node, Result};
 use itertools::Itertools;
-use std::collections::VecDeque;
+use std::{collections::VecDeque, marker::PhantomData};
 use zkp_error_utils::require;
-use zkp_hash::{Hash, Hashable};
+use zkp_hash::{Hash, Hashable, Hasher, MaskedKeccak};
 
 // Note: we can merge and split proofs. Based on indices we can
 // compute which values are redundant.
 #[derive(Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Proof {
+pub struct Proof<H = MaskedKeccak> {
     commitment: Commitment,
     indices:    Vec<usize>,
     hashes:     Vec<Hash>,
+    hasher:     PhantomData<H>,
 }
 
-impl Proof {
+impl<H: Hasher> Proof<H> {
     pub fn from_hashes(
         commitment: &Commitment,
         indices: &[usize],
@@ -36,6 +37,7 @@ impl Proof {
             commitment: commitment.clone(),
             indices:    indices.to_vec(),
             hashes:     hashes.to_vec(),
+            hasher:     PhantomData,
         })
     }
 
@@ -52,7 +54,7 @@ impl Proof {
             .iter()
             .map(|(index, leaf)| {
                 Index::from_size_offset(self.commitment.size(), *index)
-                    .map(|index| (index, leaf.hash()))
+                    .map(|index| (index, leaf.hash_with::<H>()))
             })
             .collect::<Result<Vec<_>>>()?;
         nodes.sort_unstable_by_key(|(index, _)| *index);
@@ -82,19 +84,19 @@ impl Proof {
                         if current.sibling().unwrap() == *next {
                             // Merge left with next
                             let _ = nodes.pop_front();
-                            Node(&hash, &next_hash).hash()
+                            Node(&hash, &next_hash).hash_with::<H>()
                         } else {
                             // Left not merged with next
                             // TODO: Find a way to merge this branch with the next.
-                            Node(&hash, pop()?).hash()
+                            Node(&hash, pop()?).hash_with::<H>()
                         }
                     } else {
                         // Left not merged with next
-                        Node(&hash, pop()?).hash()
+                        Node(&hash, pop()?).hash_with::<H>()
                     }
                 } else {
                     // Right not merged with previous (or we would have skipped)
-                    Node(pop()?, &hash).hash()
+                    Node(pop()?, &hash).hash_with::<H>()
                 };
                 // Queue the new parent node for the next iteration
                 nodes.push_back((parent, node))
@@ -1,9 +1,15 @@
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
 use crate::{Commitment, Error, Index, Node, Proof, Result, VectorCommitment};
 use log::{info, trace};
-use std::collections::VecDeque;
+use std::{collections::VecDeque, marker::PhantomData, ops::Range};
 use zkp_error_utils::require;
-use zkp_hash::{Hash, Hashable};
-use zkp_mmap_vec::MmapVec;
+use zkp_hash::{Hash, Hashable, Hasher, MaskedKeccak};
+use zkp_mmap_vec::{InScratch, MmapVec, Scratch};
 
 #[cfg(feature = "std")]
 use rayon::prelude::*;
@@ -21,41 +27,92 @@ where
 }
 
 // Utility function to compute the first layer of the tree from the leaves
-fn compute<C: VectorCommitment>(leaves: &C, index: Index) -> Hash {
+fn compute<C: VectorCommitment, H: Hasher>(leaves: &C, index: Index) -> Hash {
     let leaf_depth = Index::depth_for_size(leaves.len());
     assert!(index.depth() <= leaf_depth);
     if index.depth() == leaf_depth {
-        leaves.leaf_hash(index.offset())
+        leaves.leaf_hash::<H>(index.offset())
     } else {
         Node(
-            &compute(leaves, index.left_child()),
-            &compute(leaves, index.right_child()),
+            &compute::<C, H>(leaves, index.left_child()),
+            &compute::<C, H>(leaves, index.right_child()),
         )
-        .hash()
+        .hash_with::<H>()
     }
 }
 
+// Hashes the nodes at `depth` with offsets in `range` from their children
+fn hash_layer<H: Hasher>(nodes: &mut [Hash], depth: usize, range: Range<usize>) {
+    // TODO: This makes assumptions about how Index works.
+    let (tree, previous) =
+        nodes.split_at_mut(Index::from_depth_offset(depth + 1, 0).unwrap().as_index());
+    let offset = range.start;
+    let current = &mut tree[Index::layer_range(depth)][range];
+    for_each(current, |(i, hash)| {
+        let i = offset + i;
+        *hash = Node(&previous[i << 1], &previous[i << 1 | 1]).hash_with::<H>();
+    });
+}
+
 /// Merkle tree
 ///
 /// The tree will become the owner of the `Container`. This is necessary because
 /// when low layer-omission is implemented we need immutable access to the
 /// leaves. If shared ownership is required the `Container` can be an `Rc<_>`.
+///
+/// Nodes are hashed using `H`, which defaults to [`MaskedKeccak`].
 // OPT: Do not store leaf hashes but re-create.
 // OPT: Allow up to `n` lower layers to be skipped.
-// TODO: Make hash depend on type.
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Tree<Container: VectorCommitment> {
+pub struct Tree<Container: VectorCommitment, H = MaskedKeccak> {
     commitment: Commitment,
     nodes:      MmapVec<Hash>,
     leaves:     Container,
+    hasher:     PhantomData<H>,
 }
 
-impl<Container: VectorCommitment> Tree<Container> {
+impl<Container: VectorCommitment, H: Hasher> Tree<Container, H> {
     pub fn from_leaves(leaves: Container) -> Result<Self> {
         Self::from_leaves_skip_layers(leaves, 1)
     }
 
     pub fn from_leaves_skip_layers(leaves: Container, skip_layers: usize) -> Result<Self> {
+        Self::from_leaves_in(leaves, skip_layers, &Scratch::default())
+    }
+
+    /// Like [`Tree::from_leaves_skip_layers`], but with the nodes allocated
+    /// in `scratch`.
+    pub fn from_leaves_in(
+        leaves: Container,
+        skip_layers: usize,
+        scratch: &Scratch,
+    ) -> Result<Self> {
+        let size = leaves.len();
+        Self::from_chunks_in(leaves, skip_layers, size, scratch, |_, _| {})
+    }
+
+    /// Like [`Tree::from_leaves_in`], but with the leaves produced while the
+    /// tree is built.
+    ///
+    /// The leaves are hashed in consecutive chunks of `chunk_size`, or of
+    /// the number of leaves below a stored node if that is larger. Right
+    /// before a chunk is hashed, `fill` is called with the range of the chunk
+    /// to compute its leaves. The nodes above a chunk are computed before the
+    /// next chunk is filled, so each leaf is only read while it is recent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is not a power of two.
+    pub fn from_chunks_in<F>(
+        mut leaves: Container,
+        skip_layers: usize,
+        chunk_size: usize,
+        scratch: &Scratch,
+        mut fill: F,
+    ) -> Result<Self>
+    where
+        F: FnMut(&mut Container, Range<usize>),
+    {
         info!(
             "Computing Merkle tree of size {} ({} skip layer)",
             leaves.len(),
@@ -68,13 +125,15 @@ impl<Container: VectorCommitment> Tree<Container> {
                 // TODO: Ideally give the empty tree a unique flag value.
                 // Size zero commitment always exists
                 commitment: Commitment::from_size_hash(size, &Hash::default()).unwrap(),
-                nodes: MmapVec::with_capacity(0),
+                nodes: MmapVec::with_capacity_in_scratch(0, scratch),
                 leaves,
+                hasher: PhantomData,
             });
         }
         // TODO: Support non power of two sizes
         require!(size.is_power_of_two(), Error::NumLeavesNotPowerOfTwo);
         require!(size <= Index::max_size(), Error::TreeToLarge);
+        assert!(chunk_size.is_power_of_two());
 
         // Allocate result
         let leaf_depth = Index::depth_for_size(size);
@@ -84,38 +143,49 @@ impl<Container: VectorCommitment> Tree<Container> {
             let max_index = Index::from_depth_offset(depth, Index::size_at_depth(depth) - 1)
                 .unwrap()
                 .as_index();
-            let mut nodes = MmapVec::with_capacity(max_index + 1);
+            let mut nodes = MmapVec::with_capacity_in_scratch(max_index + 1, scratch);
             for _ in 0..=max_index {
                 nodes.push(Hash::default());
             }
             nodes
         } else {
-            MmapVec::with_capacity(0)
+            MmapVec::with_capacity_in_scratch(0, scratch)
         };
 
-        // Hash the tree nodes
-        // OPT: Instead of layer at a time, have each thread compute a subtree.
+        // Hash the tree nodes one chunk of leaves at a time
         if leaf_depth >= skip_layers {
             let depth = leaf_depth - skip_layers;
-            let leaf_layer = &mut nodes[Index::layer_range(depth)];
-            // First layer
-            for_each(leaf_layer, |(i, hash)| {
-                *hash = compute(&leaves, Index::from_depth_offset(depth, i).unwrap())
-            });
-            // Upper layers
-            for depth in (0..depth).rev() {
-                // TODO: This makes assumptions about how Index works.
-                let (tree, previous) =
-                    nodes.split_at_mut(Index::from_depth_offset(depth + 1, 0).unwrap().as_index());
-                let current = &mut tree[Index::layer_range(depth)];
-                for_each(current, |(i, hash)| {
-                    *hash = Node(&previous[i << 1], &previous[i << 1 | 1]).hash()
+            let chunk_size = chunk_size.max(1 << skip_layers).min(size);
+            // Number of layers of nodes above a chunk, including its first
+            let chunk_depth = Index::depth_for_size(chunk_size >> skip_layers);
+            for start in (0..size).step_by(chunk_size) {
+                let chunk = start..start + chunk_size;
+                fill(&mut leaves, chunk.clone());
+                // First layer
+                let first = (chunk.start >> skip_layers)..(chunk.end >> skip_layers);
+                let leaf_layer = &mut nodes[Index::layer_range(depth)][first.clone()];
+                for_each(leaf_layer, |(i, hash)| {
+                    *hash = compute::<_, H>(
+                        &leaves,
+                        Index::from_depth_offset(depth, first.start + i).unwrap(),
+                    );
                 });
+                // Layers above it up to the root of the chunk
+                for layer in 1..=chunk_depth {
+                    let range = (first.start >> layer)..(first.end >> layer);
+                    hash_layer::<H>(&mut nodes, depth - layer, range);
+                }
+            }
+            // Upper layers
+            for depth in (0..depth - chunk_depth).rev() {
+                hash_layer::<H>(&mut nodes, depth, 0..Index::size_at_depth(depth));
             }
+        } else {
+            fill(&mut leaves, 0..size);
         }
 
         let root_hash = if nodes.is_empty() {
-            compute(&leaves, Index::root())
+            compute::<_, H>(&leaves, Index::root())
         } else {
             nodes[0].clone()
         };
@@ -125,6 +195,7 @@ impl<Container: VectorCommitment> Tree<Container> {
             commitment,
             nodes,
             leaves,
+            hasher: PhantomData,
         })
     }
 
@@ -150,18 +221,18 @@ impl<Container: VectorCommitment> Tree<Container> {
         } else {
             assert!(index.depth() <= self.leaf_depth());
             if index.depth() == self.leaf_depth() {
-                self.leaves.leaf_hash(index.offset())
+                self.leaves.leaf_hash::<H>(index.offset())
             } else {
                 Node(
                     &self.node_hash(index.left_child()),
                     &self.node_hash(index.right_child()),
                 )
-                .hash()
+                .hash_with::<H>()
             }
         }
     }
 
-    pub fn open(&self, indices: &[usize]) -> Result<Proof> {
+    pub fn open(&self, indices: &[usize]) -> Result<Proof<H>> {
         let indices = self.commitment().sort_indices(indices)?;
         let proof_indices: Vec<usize> = indices.iter().map(|i| i.offset()).collect();
         let mut indices: VecDeque<Index> = indices.into_iter().collect();
@@ -199,6 +270,7 @@ impl<Container: VectorCommitment> Tree<Container> {
 mod tests {
     use super::*;
     use proptest::prelude::*;
+    use zkp_hash::{Blake2s, Keccak256, Poseidon, Sha256};
     use zkp_macros_decl::hex;
     use zkp_u256::U256;
 
@@ -210,7 +282,7 @@ mod tests {
             .collect();
 
         // Build the tree
-        let tree = Tree::from_leaves(leaves).unwrap();
+        let tree: Tree<_> = Tree::from_leaves(leaves).unwrap();
         let root = tree.commitment();
         assert_eq!(
             root.hash().as_bytes(),
@@ -242,7 +314,7 @@ mod tests {
         let non_root = Hash::new(hex!(
             "ed112f44bc944f33e2567f86eea202350913b11c000000000000000000000000"
         ));
-        let non_proof = Proof::from_hashes(
+        let non_proof: Proof = Proof::from_hashes(
             &Commitment::from_size_hash(root.size(), &non_root).unwrap(),
             &indices,
             &proof.hashes(),
@@ -259,7 +331,7 @@ mod tests {
         let indices: Vec<usize> = vec![];
         let leaves: Vec<U256> = vec![];
 
-        let tree = Tree::from_leaves(leaves).unwrap();
+        let tree: Tree<_> = Tree::from_leaves(leaves).unwrap();
         let root = tree.commitment();
 
         // Open indices
@@ -285,7 +357,7 @@ mod tests {
                 .collect();
 
             // Build the tree
-            let tree = Tree::from_leaves_skip_layers(leaves, skip).unwrap();
+            let tree: Tree<_> = Tree::from_leaves_skip_layers(leaves, skip).unwrap();
             let root = tree.commitment();
 
             // Open indices
@@ -296,5 +368,70 @@ mod tests {
             let select_leaves: Vec<_> = indices.iter().map(|&i| (i, tree.leaf(i))).collect();
             prop_assert!(proof.verify(&select_leaves).is_ok());
         }
+
+        #[test]
+        fn test_merkle_tree_chunks(depth: usize, skip: usize, log_chunk: usize, seed: usize) {
+            let depth = depth % 9;
+            let skip = skip % 4;
+            let chunk_size = 1_usize << (log_chunk % 10);
+            let num_leaves = 1_usize << depth;
+            let leaves: Vec<_> = (0..num_leaves)
+                .map(|i| U256::from(seed + i.pow(3)))
+                .collect();
+            let expected: Tree<_> = Tree::from_leaves_skip_layers(leaves.clone(), skip).unwrap();
+
+            // Produce the leaves while building the tree
+            let mut filled = vec![false; num_leaves];
+            let tree: Tree<_> = Tree::from_chunks_in(
+                vec![U256::ZERO; num_leaves],
+                skip,
+                chunk_size,
+                &Scratch::default(),
+                |chunk, range| {
+                    for i in range {
+                        assert!(!filled[i]);
+                        filled[i] = true;
+                        chunk[i] = leaves[i].clone();
+                    }
+                },
+            )
+            .unwrap();
+            prop_assert!(filled.iter().all(|&filled| filled));
+            prop_assert_eq!(tree.commitment().hash(), expected.commitment().hash());
+            prop_assert_eq!(&tree.nodes[..], &expected.nodes[..]);
+        }
+
+        #[test]
+        fn test_merkle_tree_hashers(depth: usize, indices: Vec<usize>, seed: usize) {
+            let depth = depth % 9 + 1;
+            let num_leaves = 1_usize << depth;
+            let indices: Vec<_> = indices.iter().map(|&i| i % num_leaves).collect();
+            let leaves: Vec<_> = (0..num_leaves)
+                .map(|i| U256::from(seed + i.pow(3)))
+                .collect();
+            let select_leaves: Vec<_> = indices.iter().map(|&i| (i, leaves[i].clone())).collect();
+
+            let keccak: Tree<_, Keccak256> = Tree::from_leaves(leaves.clone()).unwrap();
+            let blake: Tree<_, Blake2s> = Tree::from_leaves(leaves.clone()).unwrap();
+            let sha: Tree<_, Sha256> = Tree::from_leaves(leaves.clone()).unwrap();
+            let poseidon: Tree<_, Poseidon> = Tree::from_leaves(leaves).unwrap();
+            prop_assert!(keccak
+                .open(&indices)
+                .unwrap()
+                .verify(&select_leaves)
+                .is_ok());
+            prop_assert!(blake.open(&indices).unwrap().verify(&select_leaves).is_ok());
+            prop_assert!(sha.open(&indices).unwrap().verify(&select_leaves).is_ok());
+            prop_assert!(poseidon
+                .open(&indices)
+                .unwrap()
+                .verify(&select_leaves)
+                .is_ok());
+
+            // Roots differ between hash functions
+            prop_assert_ne!(keccak.commitment().hash(), blake.commitment().hash());
+            prop_assert_ne!(keccak.commitment().hash(), sha.commitment().hash());
+            prop_assert_ne!(keccak.commitment().hash(), poseidon.commitment().hash());
+        }
     );
 }
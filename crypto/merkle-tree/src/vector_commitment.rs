@@ -5,7 +5,7 @@
 use std::prelude::v1::*;
 
 use crate::{Commitment, Result, Tree};
-use zkp_hash::{Hash, Hashable};
+use zkp_hash::{Hash, Hashable, Hasher};
 
 #[cfg(feature = "mmap")]
 use crate::mmap_vec::MmapVec;
@@ -25,11 +25,11 @@ where
 
     fn leaf(&self, index: usize) -> Self::Leaf;
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        self.leaf(index).hash()
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        self.leaf(index).hash_with::<H>()
     }
 
-    fn commit(self) -> Result<(Commitment, Tree<Self>)> {
+    fn commit<H: Hasher>(self) -> Result<(Commitment, Tree<Self, H>)> {
         let tree = Tree::from_leaves(self)?;
         let commitment = tree.commitment().clone();
         Ok((commitment, tree))
@@ -49,8 +49,8 @@ impl<Leaf: Hashable + Clone + Sync> VectorCommitment for Vec<Leaf> {
         self[index].clone()
     }
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        self[index].hash()
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        self[index].hash_with::<H>()
     }
 }
 
@@ -66,7 +66,7 @@ impl<Leaf: Hashable + Clone + Sync> VectorCommitment for MmapVec<Leaf> {
         self[index].clone()
     }
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        self[index].hash()
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        self[index].hash_with::<H>()
     }
 }
@@ -0,0 +1,344 @@
+use crate::Result;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use zkp_primefield::{fft::permute, Fft, FieldElement, One, Pow, Root, SquareInline};
+use zkp_stark::{
+    Constraints, DensePolynomial, Provable, PublicInput, RationalExpression, Scratch, TraceTable,
+    Verifiable,
+};
+
+/// Constraint system that can be selected by name on the command line
+///
+/// The claim and witness are read from JSON files.
+pub(crate) struct Example {
+    pub(crate) name:        &'static str,
+    pub(crate) about:       &'static str,
+    pub(crate) constraints: fn(&Value) -> Result<Constraints>,
+    pub(crate) trace:       fn(&Value, &Value, &Scratch) -> Result<TraceTable>,
+}
+
+pub(crate) const EXAMPLES: &[Example] = &[
+    Example {
+        name:        "fibonacci",
+        about:       "Knowledge of a second value such that the Fibonacci sequence reaches \
+                      `value` at `index`",
+        constraints: constraints::<Fibonacci>,
+        trace:       trace::<Fibonacci, FibonacciWitness>,
+    },
+    Example {
+        name:        "mimc",
+        about:       "The cubic MiMC permutation takes `before` to `after` in `rounds` rounds",
+        constraints: constraints::<MiMC>,
+        trace:       trace::<MiMC, ()>,
+    },
+    Example {
+        name:        "vdf",
+        about:       "Repeated cubing in a quadratic extension takes `(c0_start, c1_start)` to \
+                      `(c0_end, c1_end)` in `rounds` rounds",
+        constraints: constraints::<Vdf>,
+        trace:       trace::<Vdf, ()>,
+    },
+];
+
+pub(crate) fn find(name: &str) -> Result<&'static Example> {
+    EXAMPLES
+        .iter()
+        .find(|example| example.name == name)
+        .ok_or_else(|| {
+            let examples = EXAMPLES
+                .iter()
+                .map(|example| format!("  {}: {}", example.name, example.about))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                "Unknown example {}. Registered examples are:\n{}",
+                name, examples
+            )
+            .into()
+        })
+}
+
+fn constraints<C>(claim: &Value) -> Result<Constraints>
+where
+    C: Verifiable + DeserializeOwned,
+{
+    Ok(C::deserialize(claim)?.bound_constraints())
+}
+
+fn trace<C, W>(claim: &Value, witness: &Value, scratch: &Scratch) -> Result<TraceTable>
+where
+    C: Provable<W> + DeserializeOwned,
+    W: DeserializeOwned,
+{
+    let claim = C::deserialize(claim)?;
+    let witness = W::deserialize(witness)?;
+    Ok(claim.trace_in(witness, scratch))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Fibonacci {
+    index: usize,
+    value: FieldElement,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct FibonacciWitness {
+    secret: FieldElement,
+}
+
+impl Fibonacci {
+    fn trace_length(&self) -> usize {
+        (self.index + 1).next_power_of_two().max(2)
+    }
+}
+
+impl PublicInput for Fibonacci {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.index.into(), self.value.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["index".into(), "value".into()]
+    }
+}
+
+impl Verifiable for Fibonacci {
+    fn public_cells(&self) -> Vec<Option<(usize, usize)>> {
+        vec![None, Some((0, self.index))]
+    }
+
+    fn constraints(&self) -> Constraints {
+        use RationalExpression::*;
+
+        // Constraint repetitions
+        let trace_length = self.trace_length();
+        let trace_generator = FieldElement::root(trace_length).unwrap();
+        let g = Constant(trace_generator);
+        let on_row = |index| (X - g.pow(index)).inv();
+        let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1);
+
+        Constraints::from_expressions((trace_length, 2), Vec::new(), vec![
+            (Trace(0, 1) - Trace(1, 0)) * every_row(),
+            (Trace(1, 1) - Trace(0, 0) - Trace(1, 0)) * every_row(),
+            (Trace(0, 0) - 1) * on_row(0),
+        ])
+        .unwrap()
+    }
+}
+
+impl Provable<FibonacciWitness> for Fibonacci {
+    fn trace(&self, witness: FibonacciWitness) -> TraceTable {
+        self.trace_in(witness, &Scratch::default())
+    }
+
+    fn trace_in(&self, witness: FibonacciWitness, scratch: &Scratch) -> TraceTable {
+        let trace_length = self.trace_length();
+        let mut trace = TraceTable::new_in(trace_length, 2, scratch);
+        trace[(0, 0)] = FieldElement::one();
+        trace[(0, 1)] = witness.secret;
+        for i in 0..(trace_length - 1) {
+            trace[(i + 1, 0)] = trace[(i, 1)].clone();
+            trace[(i + 1, 1)] = &trace[(i, 0)] + &trace[(i, 1)];
+        }
+        trace
+    }
+}
+
+// Round constants of the MiMC example, repeating every 16 rounds
+const MIMC_CONSTANTS: [u64; 16] = [
+    0x2a,
+    0x2b,
+    0xaa,
+    0x08a1,
+    0x402a,
+    0x0001_3107,
+    0x0004_45aa,
+    0x000c_90dd,
+    0x0020_002a,
+    0x0048_fb53,
+    0x0098_96aa,
+    0x0129_59e9,
+    0x0222_c02a,
+    0x03bd_774f,
+    0x0648_7baa,
+    0x0a2f_1b45,
+];
+
+/// See `crypto/stark/examples/mimc_cubic.rs`
+#[derive(Clone, Debug, Deserialize)]
+struct MiMC {
+    rounds: usize,
+    before: FieldElement,
+    after:  FieldElement,
+}
+
+impl MiMC {
+    fn trace_length(&self) -> usize {
+        (self.rounds + 1)
+            .next_power_of_two()
+            .max(MIMC_CONSTANTS.len())
+    }
+
+    fn round_constant(round: usize) -> FieldElement {
+        MIMC_CONSTANTS[round % MIMC_CONSTANTS.len()].into()
+    }
+}
+
+impl PublicInput for MiMC {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.rounds.into(), self.before.clone(), self.after.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["rounds".into(), "before".into(), "after".into()]
+    }
+}
+
+impl Verifiable for MiMC {
+    fn public_cells(&self) -> Vec<Option<(usize, usize)>> {
+        vec![None, Some((0, 0)), Some((0, self.rounds))]
+    }
+
+    fn constraints(&self) -> Constraints {
+        use RationalExpression::*;
+
+        // Constraint repetitions
+        let trace_length = self.trace_length();
+        let trace_generator = FieldElement::root(trace_length).unwrap();
+        let g = Constant(trace_generator);
+        let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1);
+
+        // Periodic column with the round constants
+        let mut coefficients = (0..MIMC_CONSTANTS.len())
+            .map(Self::round_constant)
+            .collect::<Vec<_>>();
+        coefficients.ifft();
+        permute(&mut coefficients);
+        let round_constant = Polynomial(
+            DensePolynomial::new(&coefficients),
+            Box::new(X.pow(trace_length / MIMC_CONSTANTS.len())),
+        );
+
+        Constraints::from_expressions((trace_length, 1), Vec::new(), vec![
+            (Trace(0, 1) - Trace(0, 0).pow(3) - round_constant) * every_row(),
+        ])
+        .unwrap()
+    }
+}
+
+impl Provable<()> for MiMC {
+    fn trace(&self, witness: ()) -> TraceTable {
+        self.trace_in(witness, &Scratch::default())
+    }
+
+    fn trace_in(&self, _witness: (), scratch: &Scratch) -> TraceTable {
+        let trace_length = self.trace_length();
+        let mut trace = TraceTable::new_in(trace_length, 1, scratch);
+        trace[(0, 0)] = self.before.clone();
+        for i in 0..(trace_length - 1) {
+            trace[(i + 1, 0)] = trace[(i, 0)].pow(3_usize) + Self::round_constant(i);
+        }
+        trace
+    }
+}
+
+// Non-residue defining the quadratic extension of the VDF example
+const VDF_NON_RESIDUE: u64 = 3;
+
+/// See `crypto/stark/examples/vdf.rs`
+#[derive(Clone, Debug, Deserialize)]
+struct Vdf {
+    rounds:   usize,
+    c0_start: FieldElement,
+    c1_start: FieldElement,
+    c0_end:   FieldElement,
+    c1_end:   FieldElement,
+}
+
+impl Vdf {
+    fn trace_length(&self) -> usize {
+        (self.rounds + 1).next_power_of_two().max(2)
+    }
+}
+
+impl PublicInput for Vdf {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![
+            self.rounds.into(),
+            self.c0_start.clone(),
+            self.c1_start.clone(),
+            self.c0_end.clone(),
+            self.c1_end.clone(),
+        ]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec![
+            "rounds".into(),
+            "c0_start".into(),
+            "c1_start".into(),
+            "c0_end".into(),
+            "c1_end".into(),
+        ]
+    }
+}
+
+impl Verifiable for Vdf {
+    fn public_cells(&self) -> Vec<Option<(usize, usize)>> {
+        vec![
+            None,
+            Some((0, 0)),
+            Some((1, 0)),
+            Some((0, self.rounds)),
+            Some((1, self.rounds)),
+        ]
+    }
+
+    fn constraints(&self) -> Constraints {
+        use RationalExpression::*;
+
+        // Constraint repetitions
+        let trace_length = self.trace_length();
+        let trace_generator = FieldElement::root(trace_length).unwrap();
+        let g = Constant(trace_generator);
+        let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1);
+        let r = || Constant(VDF_NON_RESIDUE.into());
+
+        Constraints::from_expressions((trace_length, 4), Vec::new(), vec![
+            // Square (Trace(0,0), Trace(1, 0)) into (Trace(2,0), Trace(3,0))
+            (Trace(0, 0) * Trace(0, 0) + r() * Trace(1, 0) * Trace(1, 0) - Trace(2, 0))
+                * every_row(),
+            (Trace(0, 0) * Trace(1, 0) * 2 - Trace(3, 0)) * every_row(),
+            // Multiply the square by the value and put the result on the next row
+            (Trace(0, 0) * Trace(2, 0) + r() * Trace(1, 0) * Trace(3, 0) - Trace(0, 1))
+                * every_row(),
+            (Trace(0, 0) * Trace(3, 0) + Trace(1, 0) * Trace(2, 0) - Trace(1, 1)) * every_row(),
+        ])
+        .unwrap()
+    }
+}
+
+impl Provable<()> for Vdf {
+    fn trace(&self, witness: ()) -> TraceTable {
+        self.trace_in(witness, &Scratch::default())
+    }
+
+    fn trace_in(&self, _witness: (), scratch: &Scratch) -> TraceTable {
+        let trace_length = self.trace_length();
+        let r = FieldElement::from(VDF_NON_RESIDUE);
+        let mut trace = TraceTable::new_in(trace_length, 4, scratch);
+        trace[(0, 0)] = self.c0_start.clone();
+        trace[(0, 1)] = self.c1_start.clone();
+        for i in 0..trace_length {
+            trace[(i, 2)] = trace[(i, 0)].square() + &r * trace[(i, 1)].square();
+            trace[(i, 3)] = FieldElement::from(2) * &trace[(i, 0)] * &trace[(i, 1)];
+            if i + 1 < trace_length {
+                trace[(i + 1, 0)] =
+                    &trace[(i, 0)] * &trace[(i, 2)] + &r * &trace[(i, 1)] * &trace[(i, 3)];
+                trace[(i + 1, 1)] =
+                    &trace[(i, 0)] * &trace[(i, 3)] + &trace[(i, 1)] * &trace[(i, 2)];
+            }
+        }
+        trace
+    }
+}
@@ -0,0 +1,720 @@
+// This sequence needs to be repeated in each project as a workaround.
+//       See https://github.com/rust-lang/cargo/issues/5034
+// For clippy lints see: https://rust-lang.github.io/rust-clippy/master
+// For rustc lints see: https://doc.rust-lang.org/rustc/lints/index.html
+#![forbid(unsafe_code)]
+#![warn(
+    // Enable sets of warnings
+    clippy::all,
+    clippy::pedantic,
+    clippy::cargo,
+    rust_2018_idioms,
+    future_incompatible,
+    unused,
+
+    // Additional unused warnings (not included in `unused`)
+    unused_lifetimes,
+    unused_qualifications,
+    unused_results,
+
+    // Additional misc. warnings
+    anonymous_parameters,
+    deprecated_in_future,
+    elided_lifetimes_in_paths,
+    explicit_outlives_requirements,
+    keyword_idents,
+    macro_use_extern_crate,
+    // missing_docs,
+    missing_doc_code_examples,
+    private_doc_tests,
+    single_use_lifetimes,
+    trivial_casts,
+    trivial_numeric_casts,
+    unreachable_pub,
+    variant_size_differences
+)]
+#![warn(missing_debug_implementations)]
+// rand_xoshiro v0.4.0 is required for a zkp-stark example and v0.3.1 for criterion
+#![allow(clippy::multiple_crate_versions)]
+// TODO: To many false positives
+#![allow(clippy::enum_glob_use)]
+
+mod example;
+
+use serde::de::DeserializeOwned;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+use zkp_primefield::FieldElement;
+use zkp_stark::{
+    check_constraints_report, generate, parse_air, prove_checked, verify_detailed, Constraints,
+    Decommitment, Objective, Proof, Scratch, Security, StructuredProof, TraceTable,
+};
+
+pub(crate) type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zkp-stark", about = "Create, verify and inspect STARK proofs.")]
+struct Options {
+    // The number of occurrences of the `v/verbose` flag
+    /// Verbose mode (-v, -vv, -vvv, etc.)
+    #[structopt(short, long, parse(from_occurrences), global = true)]
+    verbose: u8,
+
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+// Where the constraints come from. This is a plain comment because structopt
+// would use a doc comment as the description of every subcommand.
+#[derive(StructOpt, Debug)]
+struct Source {
+    /// AIR file with the constraints
+    #[structopt(
+        long,
+        parse(from_os_str),
+        required_unless = "example",
+        conflicts_with = "example"
+    )]
+    air: Option<PathBuf>,
+
+    /// Name of a registered example
+    #[structopt(long, requires = "claim")]
+    example: Option<String>,
+
+    /// JSON file with the claim for the example
+    #[structopt(long, parse(from_os_str))]
+    claim: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Create a proof
+    Prove {
+        #[structopt(flatten)]
+        source: Source,
+
+        /// JSON file with the witness. For AIR files this is the trace as a
+        /// list of rows.
+        #[structopt(long, parse(from_os_str))]
+        witness: PathBuf,
+
+        /// File to write the proof to
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+
+        /// Directory for intermediate values that do not fit the memory
+        /// budget. The operating system pages them in and out as the prover
+        /// uses them, so the files need room for all of them.
+        #[structopt(long, parse(from_os_str))]
+        scratch: Option<PathBuf>,
+
+        /// Memory budget in megabytes for intermediate values kept in memory.
+        /// It also reduces the stored Merkle tree layers.
+        #[structopt(long)]
+        memory_budget: Option<usize>,
+
+        /// Check that the trace satisfies the constraints before proving
+        #[structopt(long)]
+        check_trace: bool,
+    },
+    /// Check that the witness satisfies the constraints and report every
+    /// failing row and constraint
+    Check {
+        #[structopt(flatten)]
+        source: Source,
+
+        /// JSON file with the witness
+        #[structopt(long, parse(from_os_str))]
+        witness: PathBuf,
+
+        /// Print the report as JSON
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Verify a proof
+    Verify {
+        #[structopt(flatten)]
+        source: Source,
+
+        /// Proof file
+        #[structopt(long, parse(from_os_str))]
+        proof: PathBuf,
+    },
+    /// Show the sections of a proof and their sizes
+    Inspect {
+        #[structopt(flatten)]
+        source: Source,
+
+        /// Proof file
+        #[structopt(long, parse(from_os_str))]
+        proof: PathBuf,
+
+        /// Print the full proof as JSON
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Show the security level and maximum proof size
+    Estimate {
+        #[structopt(flatten)]
+        source: Source,
+
+        /// Select the parameters for this many bits of conjectured security
+        #[structopt(long)]
+        target_security: Option<usize>,
+
+        /// Select the parameters for this many bits of proven security
+        #[structopt(long)]
+        target_proven_security: Option<usize>,
+
+        /// What to minimize when selecting parameters: proof-size,
+        /// prover-time or verifier-cost
+        #[structopt(long, default_value = "proof-size")]
+        objective: Objective,
+    },
+    /// Generate Solidity verifier contracts
+    Solidity {
+        #[structopt(flatten)]
+        source: Source,
+
+        /// Directory to write the contracts to
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+
+        /// Name of the constraint system in the contracts
+        #[structopt(long, default_value = "Stark")]
+        name: String,
+    },
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let file = fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_reader(file).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+fn read_proof(path: &Path) -> Result<Proof> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Proof::from_bytes(bytes))
+}
+
+impl Source {
+    fn constraints(&self) -> Result<Constraints> {
+        match (&self.air, &self.example) {
+            (Some(path), _) => {
+                let source =
+                    fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                parse_air(&source).map_err(|e| format!("{}:{}", path.display(), e).into())
+            }
+            (None, Some(name)) => (example::find(name)?.constraints)(&self.claim()?),
+            (None, None) => Err("Either --air or --example is required".into()),
+        }
+    }
+
+    fn claim(&self) -> Result<serde_json::Value> {
+        let path = self.claim.as_ref().ok_or("The example needs a --claim")?;
+        read_json(path)
+    }
+
+    fn trace(
+        &self,
+        constraints: &Constraints,
+        witness: &Path,
+        scratch: &Scratch,
+    ) -> Result<TraceTable> {
+        match &self.example {
+            Some(name) => (example::find(name)?.trace)(&self.claim()?, &read_json(witness)?, scratch),
+            None => {
+                let rows: Vec<Vec<FieldElement>> = read_json(witness)?;
+                if rows.len() != constraints.trace_nrows() {
+                    return Err(format!(
+                        "The trace has {} rows, but the constraints require {}",
+                        rows.len(),
+                        constraints.trace_nrows()
+                    )
+                    .into());
+                }
+                let mut trace =
+                    TraceTable::new_in(rows.len(), constraints.trace_ncolumns(), scratch);
+                for (i, row) in rows.into_iter().enumerate() {
+                    if row.len() != constraints.trace_ncolumns() {
+                        return Err(format!(
+                            "Row {} of the trace has {} columns, but the constraints require {}",
+                            i,
+                            row.len(),
+                            constraints.trace_ncolumns()
+                        )
+                        .into());
+                    }
+                    for (j, value) in row.into_iter().enumerate() {
+                        trace[(i, j)] = value;
+                    }
+                }
+                Ok(trace)
+            }
+        }
+    }
+}
+
+fn print_sections(proof: &Proof, structured: &StructuredProof) {
+    // Hashes and field elements are both 32 bytes
+    let items = |name: &str, count: usize| (name.to_owned(), count, 32 * count);
+    let decommitment = |d: &Decommitment| d.values.len() + d.salts.len() + d.hashes.len();
+    let mut sections = vec![
+        items("trace commitments", 1 + structured.stage_commitments.len()),
+        items("constraint commitment", 1),
+        items("trace oods values", structured.trace_oods_values.len()),
+        items(
+            "constraint oods values",
+            structured.constraint_oods_values.len(),
+        ),
+        items("fri commitments", structured.fri_commitments.len()),
+        items(
+            "last layer coefficients",
+            structured.last_layer_coefficients.len(),
+        ),
+        ("proof of work nonce".to_owned(), 1, 8),
+    ];
+    for (i, d) in std::iter::once(&structured.trace_decommitment)
+        .chain(&structured.stage_decommitments)
+        .enumerate()
+    {
+        sections.push(items(
+            &format!("trace stage {} decommitment", i),
+            decommitment(d),
+        ));
+    }
+    sections.push(items(
+        "constraint decommitment",
+        decommitment(&structured.constraint_decommitment),
+    ));
+    for (i, d) in structured.fri_decommitments.iter().enumerate() {
+        sections.push(items(
+            &format!("fri layer {} decommitment", i),
+            decommitment(d),
+        ));
+    }
+
+    println!("{:<32} {:>8} {:>10}", "section", "items", "bytes");
+    for (name, count, bytes) in sections {
+        println!("{:<32} {:>8} {:>10}", name, count, bytes);
+    }
+    println!("{:<32} {:>8} {:>10}", "total", "", proof.as_bytes().len());
+}
+
+fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Prove {
+            source,
+            witness,
+            output,
+            scratch,
+            memory_budget,
+            check_trace,
+        } => {
+            let memory_budget = memory_budget
+                .map(|megabytes| {
+                    megabytes
+                        .checked_mul(1_000_000)
+                        .ok_or_else(|| format!("Memory budget of {} MB is too large", megabytes))
+                })
+                .transpose()?;
+            let scratch = Scratch::new(scratch, memory_budget)?;
+            let constraints = source.constraints()?;
+            let trace = source.trace(&constraints, &witness, &scratch)?;
+            let proof = prove_checked(&constraints, &trace, check_trace)?;
+            fs::write(&output, proof.as_bytes())
+                .map_err(|e| format!("{}: {}", output.display(), e))?;
+            println!(
+                "Wrote a proof of {} bytes to {}",
+                proof.as_bytes().len(),
+                output.display()
+            );
+        }
+        Command::Verify { source, proof } => {
+            let constraints = source.constraints()?;
+            verify_detailed(&constraints, &read_proof(&proof)?)?;
+            println!("The proof is valid");
+        }
+        Command::Check {
+            source,
+            witness,
+            json,
+        } => {
+            let constraints = source.constraints()?;
+            let trace = source.trace(&constraints, &witness, &Scratch::default())?;
+            let report = check_constraints_report(&constraints, &trace);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report);
+            }
+            if !report.is_ok() {
+                return Err(format!("{} constraint failures", report.failures.len()).into());
+            }
+        }
+        Command::Inspect {
+            source,
+            proof,
+            json,
+        } => {
+            let constraints = source.constraints()?;
+            let proof = read_proof(&proof)?;
+            let structured = StructuredProof::from_proof(&constraints, &proof)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&structured)?);
+            } else {
+                print_sections(&proof, &structured);
+            }
+        }
+        Command::Estimate {
+            source,
+            target_security,
+            target_proven_security,
+            objective,
+        } => {
+            let mut constraints = source.constraints()?;
+            if target_security.is_some() || target_proven_security.is_some() {
+                let proven = target_proven_security.unwrap_or(0);
+                let target = Security {
+                    // Proven security is never above the conjectured security
+                    conjectured: target_security.unwrap_or(0).max(proven),
+                    proven,
+                };
+                constraints = constraints.with_target_security(target, objective)?;
+            }
+            let security = constraints.security();
+            println!(
+                "trace:           {} rows, {} columns",
+                constraints.trace_nrows(),
+                constraints.trace_ncolumns()
+            );
+            println!("constraints:     {}", constraints.len());
+            println!("degree:          {}", constraints.degree());
+            println!("blowup:          {}", constraints.blowup);
+            println!("queries:         {}", constraints.num_queries);
+            println!("pow bits:        {}", constraints.pow_bits);
+            println!("fri layout:      {:?}", constraints.fri_layout);
+            println!("security bits:   {} conjectured", security.conjectured);
+            println!("                 {} proven", security.proven);
+            println!("max proof size:  {} bytes", constraints.max_proof_size());
+        }
+        Command::Solidity {
+            source,
+            output,
+            name,
+        } => {
+            let constraints = source.constraints()?;
+            fs::create_dir_all(&output).map_err(|e| format!("{}: {}", output.display(), e))?;
+            let directory = output
+                .to_str()
+                .ok_or("The output directory is not valid unicode")?;
+            generate(&constraints, directory, &name)?;
+            println!("Wrote contracts for {} to {}", name, output.display());
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    // Parse command line options
+    let options = Options::from_args();
+
+    // Initialize logging
+    env_logger::Builder::new()
+        .filter_level(match options.verbose {
+            0 => log::LevelFilter::Error,
+            1 => log::LevelFilter::Warn,
+            2 => log::LevelFilter::Info,
+            3 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        })
+        .format_timestamp_micros()
+        .init();
+
+    if let Err(error) = run(options.command) {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkp_primefield::{One, Zero};
+
+    const FIBONACCI: &str = "rows 16\ncolumns 2\nseed \"fibonacci\"\n
+        transition trace(0, 1) = trace(1, 0)
+        transition trace(1, 1) = trace(0, 0) + trace(1, 0)
+        boundary trace(0, 0) = 1 at row 0
+        boundary trace(0, 0) = 0x59 at row 9";
+
+    fn directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("zkp-stark-cli-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn write(directory: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = directory.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn source_air(air: PathBuf) -> Source {
+        Source {
+            air:     Some(air),
+            example: None,
+            claim:   None,
+        }
+    }
+
+    #[test]
+    fn air_prove_verify_inspect() {
+        let directory = directory("air");
+        let air = write(&directory, "fibonacci.air", FIBONACCI);
+        let mut rows = vec![(1_u64, 2_u64)];
+        for i in 0..15 {
+            let (a, b) = rows[i];
+            rows.push((b, a + b));
+        }
+        let rows = rows
+            .iter()
+            .map(|(a, b)| format!("[\"{:#x}\", \"{:#x}\"]", a, b))
+            .collect::<Vec<_>>();
+        let witness = write(&directory, "trace.json", &format!("[{}]", rows.join(", ")));
+        let proof = directory.join("proof.bin");
+
+        run(Command::Prove {
+            source:        source_air(air.clone()),
+            witness:       witness.clone(),
+            output:        proof.clone(),
+            scratch:       None,
+            memory_budget: None,
+            check_trace:   true,
+        })
+        .unwrap();
+        run(Command::Verify {
+            source: source_air(air.clone()),
+            proof:  proof.clone(),
+        })
+        .unwrap();
+        run(Command::Inspect {
+            source: source_air(air.clone()),
+            proof:  proof.clone(),
+            json:   false,
+        })
+        .unwrap();
+        run(Command::Check {
+            source:  source_air(air.clone()),
+            witness: witness.clone(),
+            json:    false,
+        })
+        .unwrap();
+
+        // A wrong witness is reported
+        let mut wrong = rows.clone();
+        wrong[5] = "[\"0x1\", \"0x1\"]".to_owned();
+        let wrong = write(&directory, "wrong.json", &format!("[{}]", wrong.join(", ")));
+        assert!(run(Command::Check {
+            source:  source_air(air.clone()),
+            witness: wrong,
+            json:    true,
+        })
+        .is_err());
+
+        // A corrupted proof fails
+        let mut bytes = fs::read(&proof).unwrap();
+        bytes[0] ^= 1;
+        fs::write(&proof, &bytes).unwrap();
+        assert!(run(Command::Verify {
+            source: source_air(air.clone()),
+            proof,
+        })
+        .is_err());
+
+        // A trace of the wrong size is rejected
+        let witness = write(&directory, "short.json", "[[\"0x1\", \"0x2\"]]");
+        assert!(run(Command::Prove {
+            source: source_air(air),
+            witness,
+            output: directory.join("unused.bin"),
+            scratch: None,
+            memory_budget: None,
+            check_trace: true,
+        })
+        .is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn estimate() {
+        let directory = directory("estimate");
+        let air = write(
+            &directory,
+            "estimate.air",
+            "rows 1024\ncolumns 1\nconstraint trace(0, 0)",
+        );
+        run(Command::Estimate {
+            source:                 source_air(air.clone()),
+            target_security:        None,
+            target_proven_security: None,
+            objective:              Objective::ProofSize,
+        })
+        .unwrap();
+        run(Command::Estimate {
+            source:                 source_air(air.clone()),
+            target_security:        Some(60),
+            target_proven_security: None,
+            objective:              Objective::VerifierCost,
+        })
+        .unwrap();
+        run(Command::Estimate {
+            source:                 source_air(air),
+            target_security:        None,
+            target_proven_security: Some(40),
+            objective:              Objective::ProofSize,
+        })
+        .unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn example_prove_verify() {
+        let directory = directory("example");
+        let source = || {
+            Source {
+                air:     None,
+                example: Some("fibonacci".to_owned()),
+                claim:   Some(write(
+                    &directory,
+                    "claim.json",
+                    "{\"index\": 9, \"value\": \"0x59\"}",
+                )),
+            }
+        };
+        let witness = write(&directory, "witness.json", "{\"secret\": \"0x2\"}");
+        let proof = directory.join("proof.bin");
+        run(Command::Prove {
+            source: source(),
+            witness: witness.clone(),
+            output: proof.clone(),
+            scratch: Some(directory.clone()),
+            memory_budget: Some(0),
+            check_trace: true,
+        })
+        .unwrap();
+
+        // Unusable scratch directories and memory budgets are reported
+        assert!(run(Command::Prove {
+            source: source(),
+            witness: witness.clone(),
+            output: directory.join("unused.bin"),
+            scratch: Some(directory.join("missing")),
+            memory_budget: None,
+            check_trace: false,
+        })
+        .is_err());
+        assert!(run(Command::Prove {
+            source: source(),
+            witness,
+            output: directory.join("unused.bin"),
+            scratch: None,
+            memory_budget: Some(usize::MAX),
+            check_trace: false,
+        })
+        .is_err());
+        run(Command::Verify {
+            source: source(),
+            proof:  proof.clone(),
+        })
+        .unwrap();
+
+        // The claim is bound to the proof
+        let wrong = Source {
+            claim: Some(write(
+                &directory,
+                "wrong.json",
+                "{\"index\": 9, \"value\": \"0x58\"}",
+            )),
+            ..source()
+        };
+        assert!(run(Command::Verify {
+            source: wrong,
+            proof,
+        })
+        .is_err());
+
+        let unknown = Source {
+            example: Some("unknown".to_owned()),
+            ..source()
+        };
+        assert!(unknown.constraints().is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn example_mimc_prove_verify() {
+        let directory = directory("mimc");
+        let claim = |after: &FieldElement| {
+            format!(
+                "{{\"rounds\": 20, \"before\": \"0x1234\", \"after\": {}}}",
+                serde_json::to_string(after).unwrap()
+            )
+        };
+        // Run the permutation through the trace of the example
+        let mimc = example::find("mimc").unwrap();
+        let placeholder = serde_json::from_str(&claim(&FieldElement::zero())).unwrap();
+        let trace =
+            (mimc.trace)(&placeholder, &serde_json::Value::Null, &Scratch::default()).unwrap();
+        let after = trace[(20, 0)].clone();
+
+        let source = |name: &str, after: &FieldElement| {
+            Source {
+                air:     None,
+                example: Some("mimc".to_owned()),
+                claim:   Some(write(&directory, name, &claim(after))),
+            }
+        };
+        let witness = write(&directory, "witness.json", "null");
+        let proof = directory.join("proof.bin");
+        run(Command::Prove {
+            source:        source("claim.json", &after),
+            witness:       witness.clone(),
+            output:        proof.clone(),
+            scratch:       None,
+            memory_budget: None,
+            check_trace:   true,
+        })
+        .unwrap();
+        run(Command::Verify {
+            source: source("claim.json", &after),
+            proof:  proof.clone(),
+        })
+        .unwrap();
+        run(Command::Inspect {
+            source: source("claim.json", &after),
+            proof:  proof.clone(),
+            json:   false,
+        })
+        .unwrap();
+
+        // A wrong result is reported and not verified
+        let wrong = &after + FieldElement::one();
+        assert!(run(Command::Check {
+            source: source("wrong.json", &wrong),
+            witness,
+            json: false,
+        })
+        .is_err());
+        assert!(run(Command::Verify {
+            source: source("wrong.json", &wrong),
+            proof,
+        })
+        .is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -144,6 +144,7 @@ contract OodsPoly \{
 
             // Sum constraint polynomials
             {{ for c in constraints -}}
+            // {c.label}
             \{
                 let val := {c.expression}
                 res := addmod(res, mulmod(val, add(calldataload({c.first_coefficient_location}), mulmod(calldataload({c.second_coefficient_location}), {c.degree_adjustment_location}, {modulus})), {modulus}), {modulus})
@@ -5,6 +5,9 @@ pragma experimental ABIEncoderV2;
 import '../interfaces/ConstraintInterface.sol';
 import '../default_cs.sol';
 
+{{ for column in column_names -}}
+// Column {column.index}: {column.name}
+{{ endfor -}}
 abstract contract {name}Trace is DefaultConstraintSystem({constraint_degree}, {num_rows}, {num_cols}, {blowup}) \{
     function layout_col_major() internal pure override returns(uint256[] memory) \{
         uint256[] memory result = new uint256[]({column_layout_size});
@@ -2,7 +2,9 @@
 use criterion::{black_box, Criterion};
 use zkp_macros_decl::field_element;
 use zkp_primefield::{FieldElement, Root};
-use zkp_stark::{prove, verify, Constraints, Provable, RationalExpression, TraceTable, Verifiable};
+use zkp_stark::{
+    prove, verify, Constraints, Provable, PublicInput, RationalExpression, TraceTable, Verifiable,
+};
 use zkp_u256::U256;
 
 #[derive(Clone, PartialEq, Debug)]
@@ -16,14 +18,20 @@ pub(crate) struct Witness {
     pub(crate) secret: FieldElement,
 }
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.index.into(), self.value.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["index".into(), "value".into()]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.index.to_be_bytes().to_vec();
-        seed.extend_from_slice(&self.value.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = self.index.next_power_of_two();
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -32,7 +40,7 @@ impl Verifiable for Claim {
         let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1);
 
         // Constraints
-        Constraints::from_expressions((trace_length, 2), seed, vec![
+        Constraints::from_expressions((trace_length, 2), Vec::new(), vec![
             (Trace(0, 1) - Trace(1, 0)) * every_row(),
             (Trace(1, 1) - Trace(0, 0) - Trace(1, 0)) * every_row(),
             (Trace(0, 0) - 1) * on_row(0),
@@ -64,7 +72,7 @@ fn bench_prove(crit: &mut Criterion) {
     let witness = Witness {
         secret: field_element!("cafebabe"),
     };
-    let constraints = claim.constraints();
+    let constraints = claim.bound_constraints();
     let trace = claim.trace(&witness);
 
     crit.bench_function("Making an abstracted Fibonacci proof", move |bench| {
@@ -81,7 +89,7 @@ fn bench_verify(crit: &mut Criterion) {
         secret: field_element!("cafebabe"),
     };
 
-    let constraints = claim.constraints();
+    let constraints = claim.bound_constraints();
     let trace = claim.trace(&witness);
     let proof = prove(&constraints, &trace).unwrap();
 
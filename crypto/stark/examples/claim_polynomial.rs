@@ -1,8 +1,8 @@
 use zkp_macros_decl::field_element;
 use zkp_primefield::FieldElement;
 use zkp_stark::{
-    generate, proof_serialize, prove, Constraints, DensePolynomial, Provable, RationalExpression,
-    TraceTable, Verifiable,
+    generate, proof_serialize, prove, Constraints, DensePolynomial, Provable, PublicInput,
+    RationalExpression, TraceTable, Verifiable,
 };
 use zkp_u256::U256;
 
@@ -12,6 +12,12 @@ struct Claim(FieldElement);
 #[derive(Clone, Debug)]
 struct Witness(FieldElement);
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.0.clone()]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
@@ -41,12 +47,15 @@ impl Claim {
             .map(|x| x.substitute_claim(&claim_polynomials))
             .collect();
 
-        Constraints::from_expressions(
+        let mut constraints = Constraints::from_expressions(
             (2, 1),
             self.0.as_montgomery().to_bytes_be().to_vec(),
             expressions,
         )
-        .unwrap()
+        .unwrap();
+        // The Solidity verifier seeds the channel without the constraint digest
+        constraints.legacy_seed = true;
+        constraints
     }
 }
 
@@ -64,7 +73,8 @@ fn main() {
     proof_serialize(&concrete_system, &proof, &mut proof_string).unwrap();
     println!("{}", proof_string);
 
-    let system = claim.constraints();
+    let mut system = claim.constraints();
+    system.legacy_seed = true;
     let _ = generate(
         &system,
         "../stark-verifier-ethereum/contracts/claim_polynomial",
@@ -1,8 +1,8 @@
 use zkp_macros_decl::field_element;
 use zkp_primefield::FieldElement;
 use zkp_stark::{
-    generate, proof_serialize, prove, Constraints, Provable, RationalExpression, TraceTable,
-    Verifiable,
+    generate, proof_serialize, prove, Constraints, Provable, PublicInput, RationalExpression,
+    TraceTable, Verifiable,
 };
 use zkp_u256::U256;
 
@@ -12,6 +12,12 @@ struct Claim(FieldElement);
 #[derive(Clone, Debug)]
 struct Witness(FieldElement);
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.0.clone()]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
@@ -38,6 +44,8 @@ fn main() {
     let mut constraints = claim.constraints();
     constraints.num_queries = 2;
     constraints.pow_bits = 10;
+    // The Solidity verifier seeds the channel without the constraint digest
+    constraints.legacy_seed = true;
 
     let trace = claim.trace(&witness);
 
@@ -52,7 +60,8 @@ fn main() {
     .unwrap();
     println!("{}", proof_string);
 
-    let system = claim.constraints();
+    let mut system = claim.constraints();
+    system.legacy_seed = true;
 
     let _ = generate(
         &system,
@@ -4,9 +4,12 @@ use std::time::Instant;
 use zkp_macros_decl::field_element;
 use zkp_macros_decl::u256h;
 use zkp_primefield::{FieldElement, Root, SquareInline, One, Zero, PrimeField};
-use zkp_stark::{Constraints, Provable, RationalExpression, TraceTable, Verifiable};
+use zkp_stark::{
+    component::{Component, Lookup, PolynomialWriter},
+    Constraints, Provable, PublicInput, RationalExpression, TraceTable, Verifiable,
+};
 use zkp_u256::U256;
-use zkp_elliptic_curve::{BETA, ScalarFieldElement, GENERATOR};
+use zkp_elliptic_curve::{Affine, BETA, ScalarFieldElement};
 use zkp_primefield::u256::Binary;
 use std::borrow::Borrow;
 
@@ -20,15 +23,22 @@ struct PointOnCurveClaim {
     // nebo u, v v Edwardsove forme?
 }
 
+impl PublicInput for PointOnCurveClaim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.x.clone(), self.y.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["x".into(), "y".into()]
+    }
+}
+
 impl Verifiable for PointOnCurveClaim{
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        // let mut seed = (self.x + self.y).clone().as_montgomery().to_bytes_be().to_vec();
-
         // y*y ==? x*x*x + x + BETA
-        Constraints::from_expressions((2, 2), self.x.as_montgomery().to_bytes_be().to_vec(), vec![
+        Constraints::from_expressions((2, 2), Vec::new(), vec![
             (Trace(0, 0) - &self.x) / (X - 1),
             (Trace(1, 0) - &self.y) / (X - 1),
             (Trace(1, 0)*Trace(1, 0)
@@ -62,16 +72,33 @@ struct NDoublingPointClaim {
     index : usize,
 }
 
+impl PublicInput for NDoublingPointClaim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![
+            self.x_start.clone(),
+            self.y_start.clone(),
+            self.x_result.clone(),
+            self.y_result.clone(),
+            self.index.into(),
+        ]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec![
+            "x_start".into(),
+            "y_start".into(),
+            "x_result".into(),
+            "y_result".into(),
+            "index".into(),
+        ]
+    }
+}
+
 impl Verifiable for NDoublingPointClaim{
     // assuming y is not zero
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.index.to_be_bytes().to_vec();
-        seed.extend_from_slice(&self.x_start.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.y_start.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = (self.index+1).next_power_of_two();
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -81,7 +108,7 @@ impl Verifiable for NDoublingPointClaim{
 
         let m_trace : RationalExpression =  ((Trace(0,0) + Trace(0,0) + Trace(0,0)) * Trace(0,0) + Constant(FieldElement::one()) ) / ( Trace(1,0) + Trace(1,0));
 
-        Constraints::from_expressions((trace_length, 7), seed, vec![
+        Constraints::from_expressions((trace_length, 7), Vec::new(), vec![
             // Boundary Constraints
             (Trace(0, 0) - &self.x_start) * on_row(0),
             (Trace(1, 0) - &self.y_start) * on_row(0),
@@ -165,55 +192,6 @@ impl Provable<&Witness> for NDoublingPointClaim{
     }
 }
 
-// Montgomery ladder helper
-// similar to add-and-double scalar_mult() from elliptic_helpers.rs
-// Wikipedie
-// R0 ← 0
-// R1 ← P
-// for i from m downto 0 do
-//      if di = 0 then
-//          R1 ← point_add(R0, R1)
-//          R0 ← point_double(R0)
-//      else
-//          R0 ← point_add(R0, R1)
-//          R1 ← point_double(R1)
-// return R0
-// We make the assumption that zero will never be placed into N then add a col Q+N without knowing if it is right
-
-// pub fn scalar_mult(trace: &mut TraceTable, point: (FieldElement, FieldElement), scalar: &U256, start: usize, offset: usize, neg: bool)  {
-pub fn montgomery_scalar_mult(trace: &mut TraceTable, point: (FieldElement, FieldElement), scalar: &U256, start: usize, offset: usize)  {
-    // Add an extra copy of the point then set q to it's negative.
-    let mut n = point.clone();
-    let mut q: (FieldElement, FieldElement) = (field_element!("00"), field_element!("00"));// point at infinity
-    for i in 0..256 {
-        trace[(start+i, offset + 1)] = n.0.clone();
-        trace[(start+i, offset + 2)] = n.1.clone();
-        trace[(start+i, offset + 3)] = q.0.clone();
-        trace[(start+i, offset + 4)] = q.1.clone();
-
-
-
-        // here we need to go from MSB instead of LSB as in scalar_mult using the add_and_double alg
-        if scalar.bit(255 - i) {
-            trace[(start + i, offset)] = FieldElement::one();
-            n = add(&n.0, &n.1, &q.0, &q.1);
-            q = double(&q.0, &q.1);
-        } else {
-            trace[(start + i, offset)] = FieldElement::zero();
-            q = add(&n.0, &n.1, &q.0, &q.1);
-            n = double(&n.0, &n.1);
-        }
-
-        info!("Trace {:?}, 0: {:?}", i, trace[(i, 0)]);
-        info!("Trace {:?}, 1: {:?}", i, trace[(i, 1)]);
-        info!("Trace {:?}, 2: {:?}", i, trace[(i, 2)]);
-        info!("Trace {:?}, 3: {:?}", i, trace[(i, 3)]);
-        info!("Trace {:?}, 4: {:?}", i, trace[(i, 4)]);
-        info!("");
-
-    }
-}
-
 // Original from elliptic_helpers.rs
 // Note attempt to implement for zero too
 pub fn double(point_x: &FieldElement, point_y: &FieldElement) -> (FieldElement, FieldElement) {
@@ -247,117 +225,170 @@ pub fn add(x_p: &FieldElement, y_p: &FieldElement, x_q: &FieldElement, y_q: &Fie
     }
 }
 
-//#[derive(Clone, Debug)]
+#[derive(Clone, Debug)]
 struct MontgomeryWitness {
-    scalar: U256,
-    //Note: scalar.bit(i) = i-th LSB, in Montgomery alg., we work from 255th bit to 0th bit
+    x_start: FieldElement,
+    y_start: FieldElement,
+    // Note: scalar.bit(i) = i-th LSB, the ladder works from bit 254 to bit 0
+    scalar:  U256,
 }
 
-//#[derive(Clone, Debug)]
+#[derive(Clone, Debug)]
 struct MontgomeryClaim {
-    x_start : FieldElement,
-    y_start : FieldElement,
-    x_result : FieldElement,
-    y_result : FieldElement,
+    x_start:  FieldElement,
+    y_start:  FieldElement,
+    x_result: FieldElement,
+    y_result: FieldElement,
 }
 
-impl Provable<MontgomeryWitness> for MontgomeryClaim {
-    #[cfg(feature = "prover")]
-    fn trace(&self, witness : MontgomeryWitness) -> TraceTable {
-        let mut trace = TraceTable::new(256, 5);
-        let start_point = (self.x_start.clone(), self.y_start.clone());
+impl PublicInput for MontgomeryClaim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![
+            self.x_start.clone(),
+            self.y_start.clone(),
+            self.x_result.clone(),
+            self.y_result.clone(),
+        ]
+    }
 
-        montgomery_scalar_mult(&mut trace, start_point, &witness.scalar, 0, 0);
-        trace
+    fn public_names(&self) -> Vec<String> {
+        vec!["x_start".into(), "y_start".into(), "x_result".into(), "y_result".into()]
     }
 }
 
-impl Verifiable for MontgomeryClaim {
-    fn constraints(&self) -> Constraints {
+/// Montgomery ladder computing `(2^255 + scalar) * start`, one bit per row.
+///
+/// Row `i` holds bit `254 - i` of the scalar in column 0 and the ladder state
+/// `R0`, `R1` before that bit in columns 1 to 4. The ladder starts from the
+/// implicit leading one bit with `R0 = start` and `R1 = 2 * start`, so it
+/// never passes the point at infinity, and `R1 - R0 = start` throughout, so
+/// the additions never degenerate into doublings. The last row holds the
+/// result in `R0`.
+///
+/// Column 5 is the fixed table `0, 1, 1, ..., 1`. The bits are not
+/// constrained here; wrap the ladder in `Lookup::new(ladder, 0, 5)` to
+/// constrain them to the table.
+struct MontgomeryLadder;
+
+type Point = (FieldElement, FieldElement);
+
+impl MontgomeryLadder {
+    const ROWS: usize = 256;
+
+    // The bit and the ladder state R0, R1 on each row
+    fn rows(witness: &MontgomeryWitness) -> Vec<(bool, Point, Point)> {
+        assert!(!witness.scalar.bit(255));
+        let mut r0 = (witness.x_start.clone(), witness.y_start.clone());
+        let mut r1 = double(&r0.0, &r0.1);
+        let mut rows = Vec::with_capacity(Self::ROWS);
+        for i in 0..Self::ROWS - 1 {
+            let bit = witness.scalar.bit(254 - i);
+            rows.push((bit, r0.clone(), r1.clone()));
+            if bit {
+                r0 = add(&r0.0, &r0.1, &r1.0, &r1.1);
+                r1 = double(&r1.0, &r1.1);
+            } else {
+                r1 = add(&r0.0, &r0.1, &r1.0, &r1.1);
+                r0 = double(&r0.0, &r0.1);
+            }
+        }
+        rows.push((false, r0, r1));
+        rows
+    }
+}
+
+impl Component for MontgomeryLadder {
+    type Claim = MontgomeryClaim;
+    type Witness = MontgomeryWitness;
+
+    fn num_polynomials(&self) -> usize {
+        6
+    }
+
+    fn polynomial_size(&self) -> usize {
+        Self::ROWS
+    }
+
+    fn claim(&self, witness: &Self::Witness) -> Self::Claim {
+        let (_, (x_result, y_result), _) = Self::rows(witness).pop().unwrap();
+        MontgomeryClaim {
+            x_start: witness.x_start.clone(),
+            y_start: witness.y_start.clone(),
+            x_result,
+            y_result,
+        }
+    }
+
+    fn public_cells(&self, _claim: &Self::Claim) -> Vec<Option<(usize, usize)>> {
+        let last = Self::ROWS - 1;
+        vec![Some((1, 0)), Some((2, 0)), Some((1, last)), Some((2, last))]
+    }
+
+    fn constraints(&self, _claim: &Self::Claim) -> Vec<RationalExpression> {
         use RationalExpression::*;
         use zkp_primefield::Pow;
 
-        let trace_length = 256;
+        let trace_length = Self::ROWS;
         let trace_generator = FieldElement::root(trace_length).unwrap();
 
-        let mut seed = self.x_start.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.y_start.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.x_result.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.y_result.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
-        let g = Constant(trace_generator.clone());
-        let on_row = |index| (X - g.pow(index)).inv();
-        let on_hash_loop_rows = |a: RationalExpression| {
-            a * (X.pow(256) - Constant(trace_generator.pow(256 * (trace_length - 1))))
-                / (X.pow(trace_length) - FieldElement::one())
-        };
-
-        let row_double_R0 = point_double(Trace(1, 0), Trace(2, 0), Trace(1, 1), Trace(2, 1));
-        let row_add_R1 = point_add(
+        let row = |index| X - Constant(trace_generator.pow(index));
+        let all_rows = || X.pow(trace_length) - 1;
+        let on_first_row = |a: RationalExpression| a / row(0);
+        let on_ladder_rows = |a: RationalExpression| a * row(trace_length - 1) / all_rows();
+        let on_table_rows = |a: RationalExpression| a * row(0) / all_rows();
+
+        let bit = Trace(0, 0);
+        let start_double = point_double(Trace(1, 0), Trace(2, 0), Trace(3, 0), Trace(4, 0));
+        let row_double_r0 = point_double(Trace(1, 0), Trace(2, 0), Trace(1, 1), Trace(2, 1));
+        let row_double_r1 = point_double(Trace(3, 0), Trace(4, 0), Trace(3, 1), Trace(4, 1));
+        let row_add_r0 = point_add(
             Trace(1, 0),
             Trace(2, 0),
             Trace(3, 0),
             Trace(4, 0),
-            Trace(3, 1),
-            Trace(4, 1),
+            Trace(1, 1),
+            Trace(2, 1),
         );
-        let row_double_R1 = point_double(Trace(3, 0), Trace(4, 0), Trace(3, 1), Trace(4, 1));
-        let row_add_R0 = point_add(
+        let row_add_r1 = point_add(
             Trace(1, 0),
             Trace(2, 0),
             Trace(3, 0),
             Trace(4, 0),
-            Trace(1, 1),
-            Trace(2, 1),
+            Trace(3, 1),
+            Trace(4, 1),
         );
-        Constraints::from_expressions((trace_length, 5), seed, vec![
-            on_hash_loop_rows(simple_conditional(
-                row_add_R0[0].clone(),
-                Trace(1, 1) - Trace(1, 0),
-                Trace(0, 0),
-            )),
-            on_hash_loop_rows(simple_conditional(
-                row_add_R0[1].clone(),
-                Trace(2, 1) - Trace(2, 0),
-                Trace(0, 0),
-            )),
-            on_hash_loop_rows(simple_conditional(
-                row_double_R1[0].clone(),
-                Trace(3, 1) - Trace(3, 0),
-                Trace(0, 0),
-            )),
-            on_hash_loop_rows(simple_conditional(
-                row_double_R1[1].clone(),
-                Trace(4, 1) - Trace(4, 0),
-                Trace(0, 0),
-            )),
-            on_hash_loop_rows(simple_conditional(
-                row_add_R1[0].clone(),
-                Trace(3, 1) - Trace(3, 0),
-                Constant(1.into()) - Trace(0, 0),
-            )),
-            on_hash_loop_rows(simple_conditional(
-                row_add_R1[1].clone(),
-                Trace(4, 1) - Trace(4, 0),
-                Constant(1.into()) - Trace(0, 0),
-            )),
-            on_hash_loop_rows(simple_conditional(
-                row_double_R0[0].clone(),
-                Trace(1, 1) - Trace(1, 0),
-                Constant(1.into()) - Trace(0, 0),
-            )),
-            on_hash_loop_rows(simple_conditional(
-                row_double_R0[1].clone(),
-                Trace(2, 1) - Trace(2, 0),
-                Constant(1.into()) - Trace(0, 0),
-            )),
-            // Boundary Constraints
-            (Trace(1, 0) - Constant(self.x_start.clone()))*on_row(0),
-            (Trace(2, 0) - Constant(self.y_start.clone()))*on_row(0),
-            (Trace(1, 0) - Constant(self.x_result.clone()))*on_row(255),
-            (Trace(2, 0) - Constant(self.x_result.clone()))*on_row(255),
-        ]).unwrap()
+
+        let mut constraints = start_double.iter().cloned().map(on_first_row).collect::<Vec<_>>();
+        for i in 0..2 {
+            // On a one bit R0 ← R0 + R1 and R1 ← 2 R1, otherwise R1 ← R0 + R1
+            // and R0 ← 2 R0
+            constraints.push(on_ladder_rows(simple_conditional(
+                row_add_r0[i].clone(),
+                row_double_r0[i].clone(),
+                bit.clone(),
+            )));
+            constraints.push(on_ladder_rows(simple_conditional(
+                row_double_r1[i].clone(),
+                row_add_r1[i].clone(),
+                bit.clone(),
+            )));
+        }
+        // The table of valid bits
+        constraints.push(on_first_row(Trace(5, 0)));
+        constraints.push(on_table_rows(Trace(5, 0) - 1));
+        constraints
+    }
+
+    fn trace<P: PolynomialWriter>(&self, trace: &mut P, witness: &Self::Witness) {
+        for (i, (bit, r0, r1)) in Self::rows(witness).into_iter().enumerate() {
+            trace.write(0, i, if bit { FieldElement::one() } else { FieldElement::zero() });
+            trace.write(1, i, r0.0);
+            trace.write(2, i, r0.1);
+            trace.write(3, i, r1.0);
+            trace.write(4, i, r1.1);
+            trace.write(5, i, if i == 0 { FieldElement::zero() } else { FieldElement::one() });
+        }
     }
 }
 
@@ -401,19 +432,37 @@ fn main() {
     env_logger::init();
     //n_doubling_construction();
     // point_on_curve_construction();
-    println!("Start computing of Montgomery ladder trace");
-    //montgomery_scalar_mult(trace: &mut TraceTable, point: (FieldElement, FieldElement), scalar: &U256, start: usize, offset: usize)
-    let mut trace = TraceTable::new(256, 5);
-    let p = (
-        field_element!("01ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca"),
-        field_element!("005668060aa49730b7be4801df46ec62de53ecd11abe43a32873000c36e8dc1f"),
-    );
-    let c = u256h!("07374b7d69dc9825fc758b28913c8d2a27be5e7c32412f612b20c9c97afbe4dd");
-    montgomery_scalar_mult(&mut trace,  p, &c, 0, 0);
-
-    println!("Finished computing of Montgomery ladder trace");
-
-    println!("Number of set bits on the scalar: {}", c.count_ones());
+    let witness = MontgomeryWitness {
+        x_start: field_element!("01ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca"),
+        y_start: field_element!("005668060aa49730b7be4801df46ec62de53ecd11abe43a32873000c36e8dc1f"),
+        scalar:  u256h!("07374b7d69dc9825fc758b28913c8d2a27be5e7c32412f612b20c9c97afbe4dd"),
+    };
+    info!("Number of set bits on the scalar: {}", witness.scalar.count_ones());
+
+    // The lookup into the table column constrains the bits
+    let component = Lookup::new(MontgomeryLadder, 0, 5);
+    let claim = component.claim(&witness);
+    info!("Claim: {:?}", claim);
+
+    let start = Affine::new(witness.x_start.clone(), witness.y_start.clone());
+    let scalar = (U256::ONE << 255) + &witness.scalar;
+    let expected = &start * ScalarFieldElement::from(scalar);
+    assert_eq!(expected, Affine::new(claim.x_result.clone(), claim.y_result.clone()));
+
+    assert_eq!(component.check(&witness), Ok(()));
+
+    // Start timer
+    let start = Instant::now();
+
+    info!("Constructing proof...");
+    let proof = component.prove(&witness).unwrap();
+
+    // Measure time
+    let duration = start.elapsed();
+    info!("Time elapsed in proof function is: {:?}", duration);
+
+    info!("Verifying proof...");
+    component.verify(&claim, &proof).unwrap();
 }
 
 fn computeM1(x : &FieldElement, y: &FieldElement)->FieldElement{
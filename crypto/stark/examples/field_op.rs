@@ -4,7 +4,7 @@ use log::info;
 use std::time::Instant;
 use zkp_macros_decl::field_element;
 use zkp_primefield::{FieldElement, Root, One};
-use zkp_stark::{Constraints, Provable, RationalExpression, TraceTable, Verifiable};
+use zkp_stark::{Constraints, Provable, PublicInput, RationalExpression, TraceTable, Verifiable};
 use zkp_u256::U256;
 
 #[derive(Clone, Debug)]
@@ -14,6 +14,16 @@ struct ComputeMClaim {
     value: FieldElement,
 }
 
+impl PublicInput for ComputeMClaim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.x.clone(), self.y.clone(), self.value.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["x".into(), "y".into(), "value".into()]
+    }
+}
+
 impl Verifiable for ComputeMClaim{
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
@@ -22,7 +32,7 @@ impl Verifiable for ComputeMClaim{
 
         let m_trace : RationalExpression =  ((Trace(0,0) + Trace(0,0) + Trace(0,0)) * Trace(0,0) + Constant(FieldElement::one()) ) / ( Trace(0, 1) + Trace(0, 1));
 
-        Constraints::from_expressions((8, 1), self.value.as_montgomery().to_bytes_be().to_vec(), vec![
+        Constraints::from_expressions((8, 1), Vec::new(), vec![
             (Trace(0, 0) - Constant(self.x.clone())) / (X - 1),
             (Trace(0, 1) - Constant(self.y.clone())) / (X - 1),
             (Trace(0, 2) - Constant(self.value.clone())) / (X - 1),
@@ -59,12 +69,22 @@ struct InversionClaim {
     value: FieldElement,
 }
 
+impl PublicInput for InversionClaim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.value.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["value".into()]
+    }
+}
+
 impl Verifiable for InversionClaim{
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
         let trace_generator = FieldElement::root(2).unwrap();
         let g = Constant(trace_generator);
-        Constraints::from_expressions((2, 1), self.value.as_montgomery().to_bytes_be().to_vec(), vec![
+        Constraints::from_expressions((2, 1), Vec::new(), vec![
             (Trace(0, 0) * Trace(0, 1) -1) / (X - 1),
             // (Trace(0, 1) - Constant(self.value.clone())) / (X - 1), taky funguje
             (Trace(0, 0) - Constant(self.value.clone())) / (X - g),
@@ -97,14 +117,20 @@ struct AddNTimesClaim {
     index: usize,
     value: FieldElement,
 }
+impl PublicInput for AddNTimesClaim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.index.into(), self.value.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["index".into(), "value".into()]
+    }
+}
+
 impl Verifiable for AddNTimesClaim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.index.to_be_bytes().to_vec();
-        seed.extend_from_slice(&self.value.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = self.index.next_power_of_two();
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -112,7 +138,7 @@ impl Verifiable for AddNTimesClaim {
         let on_row = |index| (X - g.pow(index)).inv();
         let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1);
 
-        Constraints::from_expressions((trace_length, 2), seed, vec![
+        Constraints::from_expressions((trace_length, 2), Vec::new(), vec![
             (Trace(1, 1) - Trace(1, 0)) * every_row(),
             (Trace(0, 1) - Trace(0, 0) - Trace(1, 0)) * every_row(),
             (Trace(0, 0) - 0) * on_row(0),
@@ -5,6 +5,7 @@ use crate::{
     constraint_system::{Provable, Verifiable},
     constraints::Constraints,
     polynomial::DensePolynomial,
+    public_input::PublicInput,
     rational_expression::RationalExpression::{self, *},
 };
 use elliptic_curve_crypto as ecc;
@@ -21,12 +22,9 @@ struct Witness {
     signature: (FieldElement, FieldElement),
 }
 
-impl From<&Claim> for Vec<u8> {
-    fn from(input: &Claim) -> Self {
-        let mut ret = U256::from(&input.hash).to_bytes_be().to_vec();
-        ret.extend_from_slice(&U256::from(&input.who.0).to_bytes_be());
-        ret.extend_from_slice(&U256::from(&input.who.1).to_bytes_be());
-        ret
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.hash.clone(), self.who.0.clone(), self.who.1.clone()]
     }
 }
 
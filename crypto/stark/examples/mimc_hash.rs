@@ -3,7 +3,7 @@ use std::time::Instant;
 use zkp_macros_decl::field_element;
 use zkp_primefield::{fft::permute, Fft, FieldElement, Pow, Root, SquareInline, Zero};
 use zkp_stark::{
-    Constraints, DensePolynomial, Provable, RationalExpression, TraceTable, Verifiable,
+    Constraints, DensePolynomial, Provable, PublicInput, RationalExpression, TraceTable, Verifiable,
 };
 use zkp_u256::U256;
 
@@ -146,15 +146,24 @@ pub struct Claim {
     after:    FieldElement,
 }
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![
+            self.before_x.clone(),
+            self.before_y.clone(),
+            self.after.clone(),
+        ]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["before_x".into(), "before_y".into(), "after".into()]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.before_x.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.before_y.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.after.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = 256;
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -202,7 +211,7 @@ impl Verifiable for Claim {
 
         let _public = vec![&const_before_x, &const_before_y, &const_after];
 
-        Constraints::from_expressions((trace_length, 2), seed, expressions).unwrap()
+        Constraints::from_expressions((trace_length, 2), Vec::new(), expressions).unwrap()
     }
 }
 
@@ -2,7 +2,7 @@ use std::time::Instant;
 use zkp_macros_decl::field_element;
 use zkp_primefield::{fft::permute, Fft, FieldElement, Pow, Root, SquareInline};
 use zkp_stark::{
-    Constraints, DensePolynomial, Provable, RationalExpression, TraceTable, Verifiable,
+    Constraints, DensePolynomial, Provable, PublicInput, RationalExpression, TraceTable, Verifiable,
 };
 use zkp_u256::U256;
 
@@ -35,14 +35,20 @@ pub struct Claim {
     after:  FieldElement,
 }
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.before.clone(), self.after.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["before".into(), "after".into()]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.before.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.after.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = ROUNDS;
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -61,7 +67,7 @@ impl Verifiable for Claim {
         permute(&mut k_coef);
         let k_coef = periodic(&k_coef);
 
-        Constraints::from_expressions((trace_length, 3), seed, vec![
+        Constraints::from_expressions((trace_length, 3), Vec::new(), vec![
             // Says x_1 = x_0^2
             (Trace(0, 0) * Trace(0, 0) - Trace(1, 0)) * every_row(),
             // Says x_2 = x_1*x_0
@@ -3,7 +3,7 @@ use std::time::Instant;
 use zkp_macros_decl::field_element;
 use zkp_primefield::{fft::permute, Fft, FieldElement, One, Pow, Root, SquareInline, Zero};
 use zkp_stark::{
-    Constraints, DensePolynomial, Provable, RationalExpression, TraceTable, Verifiable,
+    Constraints, DensePolynomial, Provable, PublicInput, RationalExpression, TraceTable, Verifiable,
 };
 use zkp_u256::U256;
 
@@ -146,14 +146,24 @@ pub struct Claim {
     start_left: FieldElement,
 }
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![
+            self.element.clone(),
+            self.root.clone(),
+            self.start_left.clone(),
+        ]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["element".into(), "root".into(), "start_left".into()]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.element.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.root.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = 256 * 8192;
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -188,7 +198,7 @@ impl Verifiable for Claim {
                     - Constant(trace_generator.pow((trace_length / loop_len) * shift)))
         };
 
-        Constraints::from_expressions((trace_length, 2), seed, vec![
+        Constraints::from_expressions((trace_length, 2), Vec::new(), vec![
             ((Exp(Trace(0, 0).into(), 3)
                 + Constant(3.into()) * Constant(Q) * Trace(0, 0) * Exp(Trace(1, 0).into(), 2)
                 + k_coef)
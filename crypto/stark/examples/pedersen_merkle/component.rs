@@ -87,6 +87,10 @@ impl Component for MerkleTreeLayer {
         let on_fe_end_rows = |a: RationalExpression| a / row(field_element_bits);
 
         // Common sub-expressions
+        // The bits are differences of the shifted sources, not columns, and the
+        // layout must match the StarkWare reference proof (see
+        // `test_pedersen_merkle_small_proof`), so they are checked inline
+        // rather than with `Lookup`.
         let left_bit = Trace(0, 0) - Trace(0, 1) * 2;
         let right_bit = Trace(4, 0) - Trace(4, 1) * 2;
 
@@ -365,7 +369,7 @@ mod test {
         let trace = component.trace_table(&witness);
         let mut constraints = Constraints::from_expressions(
             (trace.num_rows(), trace.num_columns()),
-            (&claim).into(),
+            claim.starkware_seed(),
             constraints,
         )
         .unwrap();
@@ -373,6 +377,8 @@ mod test {
         constraints.pow_bits = 0;
         constraints.num_queries = 13;
         constraints.fri_layout = vec![3, 2];
+        // The reference proof predates the constraint digest
+        constraints.legacy_seed = true;
         let proof = prove(&constraints, &trace).unwrap();
 
         assert_eq!(
@@ -1,6 +1,7 @@
 use super::pedersen_points::merkle_hash;
 use std::{prelude::v1::*, vec};
 use zkp_primefield::FieldElement;
+use zkp_stark::PublicInput;
 
 #[derive(PartialEq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -43,16 +44,31 @@ impl From<&Witness> for Claim {
     }
 }
 
-impl From<&Claim> for Vec<u8> {
-    fn from(claim: &Claim) -> Self {
-        let mut bytes: Self = vec![];
-        bytes.extend_from_slice(&claim.path_length.to_be_bytes());
-        bytes.extend_from_slice(&claim.root.as_montgomery().to_bytes_be());
-        bytes.extend_from_slice(&claim.leaf.as_montgomery().to_bytes_be());
+impl Claim {
+    /// The channel seed of the Starkware reference proofs.
+    pub fn starkware_seed(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&self.path_length.to_be_bytes());
+        bytes.extend_from_slice(&self.root.as_montgomery().to_bytes_be());
+        bytes.extend_from_slice(&self.leaf.as_montgomery().to_bytes_be());
         bytes
     }
 }
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![
+            self.path_length.into(),
+            self.leaf.clone(),
+            self.root.clone(),
+        ]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["path_length".into(), "leaf".into(), "root".into()]
+    }
+}
+
 #[cfg(test)]
 use zkp_macros_decl::field_element;
 
@@ -94,6 +110,6 @@ mod tests {
 
     #[test]
     fn claim_writable_correct() {
-        assert_eq!(Vec::from(&SHORT_CLAIM), hex!("0000000000000004062b7c2734c31d5b73119a5bfdb460c0411af12fafd42af8ca041fea5ec464d00000000000000000000000000000000000000000000000000000000000000000").to_vec());
+        assert_eq!(SHORT_CLAIM.starkware_seed(), hex!("0000000000000004062b7c2734c31d5b73119a5bfdb460c0411af12fafd42af8ca041fea5ec464d00000000000000000000000000000000000000000000000000000000000000000").to_vec());
     }
 }
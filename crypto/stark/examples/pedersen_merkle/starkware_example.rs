@@ -38,12 +38,14 @@ pub(crate) fn starkware_example() {
 
     info!("Constructing proof parameters...");
     let mut constraints =
-        Constraints::from_expressions((size, polynomials), (&claim).into(), constraints)
+        Constraints::from_expressions((size, polynomials), claim.starkware_seed(), constraints)
             .expect("Could not create Constraint object");
     constraints.blowup = 16;
     constraints.pow_bits = 28;
     constraints.num_queries = 13;
     constraints.fri_layout = vec![3, 3, 3, 3, 2];
+    // The reference proof predates the constraint digest
+    constraints.legacy_seed = true;
 
     info!("Constructing proofs...");
     let proof = prove(&constraints, &trace).unwrap();
@@ -3,7 +3,7 @@ use zkp_macros_decl::u256h;
 use zkp_u256::U256;
 use zkp_primefield::{FieldElement, One, SquareInline, Zero, Root};
 use zkp_stark::RationalExpression::*;
-use zkp_stark::{Constraints, Provable, RationalExpression, TraceTable, Verifiable};
+use zkp_stark::{Constraints, Provable, PublicInput, RationalExpression, TraceTable, Verifiable};
 use zkp_primefield::u256::Binary;
 use zkp_elliptic_curve::{ScalarFieldElement, Affine, window_table_affine};
 use env_logger;
@@ -54,6 +54,21 @@ impl Provable<&Witness> for Claim {
 }
 
 // for scalar multiplication
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![
+            self.x_start.clone(),
+            self.y_start.clone(),
+            self.x_result.clone(),
+            self.y_result.clone(),
+        ]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["x_start".into(), "y_start".into(), "x_result".into(), "y_result".into()]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
@@ -62,11 +77,6 @@ impl Verifiable for Claim {
         let trace_length = 256;
         let trace_generator = FieldElement::root(trace_length).unwrap();
 
-        let mut seed = self.x_start.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.y_start.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.x_result.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.y_result.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let g = Constant(trace_generator.clone());
         let on_row = |index| (X - g.pow(index)).inv();
@@ -85,7 +95,7 @@ impl Verifiable for Claim {
             Trace(4, 1),
         );
 
-        Constraints::from_expressions((trace_length, 5), seed, vec![
+        Constraints::from_expressions((trace_length, 5), Vec::new(), vec![
             on_hash_loop_rows(row_double[0].clone()),
             on_hash_loop_rows(row_double[1].clone()),
             on_hash_loop_rows(one_or_zero((Trace(0,0) - Constant(2.into())*Trace(0, 1)))),
@@ -178,6 +188,16 @@ impl Provable<&SinsemillaWitness> for SinsemillaClaim {
     }
 }
 
+impl PublicInput for SinsemillaClaim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.x_result.clone(), self.y_result.clone(), self.blocks_num.into()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["x_result".into(), "y_result".into(), "blocks_num".into()]
+    }
+}
+
 impl Verifiable for SinsemillaClaim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
@@ -186,9 +206,6 @@ impl Verifiable for SinsemillaClaim {
         let trace_length = 512;
         let trace_generator = FieldElement::root(trace_length).unwrap();
 
-        let mut seed = self.x_result.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.y_result.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let g = Constant(trace_generator.clone());
         let on_row = |index| (X - g.pow(index)).inv();
@@ -216,7 +233,7 @@ impl Verifiable for SinsemillaClaim {
                                         Trace(9, 0), Trace(10, 0),
                                             Trace(11, 0), Trace(12, 0));
 
-        Constraints::from_expressions((trace_length, 13), seed, vec![
+        Constraints::from_expressions((trace_length, 13), Vec::new(), vec![
             on_hash_loop_rows(row_double[0].clone()),
             on_hash_loop_rows(row_double[1].clone()),
             on_hash_loop_rows(one_or_zero(Trace(2, 0))),
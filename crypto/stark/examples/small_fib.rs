@@ -4,7 +4,7 @@ use log::info;
 use std::time::Instant;
 use zkp_macros_decl::field_element;
 use zkp_primefield::{FieldElement, Root, One};
-use zkp_stark::{Constraints, Provable, RationalExpression, TraceTable, Verifiable};
+use zkp_stark::{Constraints, Provable, PublicInput, RationalExpression, TraceTable, Verifiable};
 use zkp_u256::U256;
 
 #[derive(Clone, Debug)]
@@ -18,14 +18,20 @@ struct Witness {
     secret: FieldElement,
 }
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.index.into(), self.value.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec!["index".into(), "value".into()]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.index.to_be_bytes().to_vec();
-        seed.extend_from_slice(&self.value.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = self.index.next_power_of_two();
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -33,7 +39,7 @@ impl Verifiable for Claim {
         let on_row = |index| (X - g.pow(index)).inv();
         let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1);
 
-        Constraints::from_expressions((trace_length, 2), seed, vec![
+        Constraints::from_expressions((trace_length, 2), Vec::new(), vec![
             (Trace(0, 1) - Trace(1, 0)) * every_row(),
             (Trace(1, 1) - Trace(0, 0) - Trace(1, 0)) * every_row(),
             (Trace(0, 0) - 1) * on_row(0),
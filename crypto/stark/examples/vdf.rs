@@ -3,7 +3,7 @@ use log::info;
 use std::time::Instant;
 use zkp_macros_decl::field_element;
 use zkp_primefield::{FieldElement, Root, SquareInline};
-use zkp_stark::{Constraints, Provable, RationalExpression, TraceTable, Verifiable};
+use zkp_stark::{Constraints, Provable, PublicInput, RationalExpression, TraceTable, Verifiable};
 use zkp_u256::U256;
 
 const R: FieldElement = field_element!("03");
@@ -16,16 +16,30 @@ struct Claim {
     pub c1_end:   FieldElement,
 }
 
+impl PublicInput for Claim {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![
+            self.c0_start.clone(),
+            self.c1_start.clone(),
+            self.c0_end.clone(),
+            self.c1_end.clone(),
+        ]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec![
+            "c0_start".into(),
+            "c1_start".into(),
+            "c0_end".into(),
+            "c1_end".into(),
+        ]
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.c0_start.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.c1_start.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.c0_end.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.c1_end.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = 1_048_576;
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -33,7 +47,7 @@ impl Verifiable for Claim {
         let on_row = |index| (X - g.pow(index)).inv();
         let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1);
 
-        Constraints::from_expressions((trace_length, 4), seed, vec![
+        Constraints::from_expressions((trace_length, 4), Vec::new(), vec![
             // Square (Trace(0,0), Trace(1, 0)) and check that it equals (Trace(2,0),
             // Trace(3,0))
             ((Trace(0, 0) * Trace(0, 0) + Constant(R) * Trace(1, 0) * Trace(1, 0) - Trace(2, 0))
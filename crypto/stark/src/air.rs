@@ -0,0 +1,1370 @@
+//! Text format for algebraic intermediate representations
+//!
+//! An AIR file describes the trace layout and the constraints on it, one
+//! statement per line. Lines can also be separated by `;` and newlines inside
+//! parentheses or brackets are ignored. Comments start with `#`.
+//!
+//! ```text
+//! # Fibonacci sequence
+//! rows 1024
+//! columns 2
+//! seed "fibonacci"
+//!
+//! transition trace(0, 1) = trace(1, 0)
+//! transition trace(1, 1) = trace(0, 0) + trace(1, 0)
+//! boundary trace(0, 0) = 1 at row 0
+//! boundary trace(0, 0) = 0x1234 at row 1000
+//! ```
+//!
+//! The statements are
+//!
+//! * `rows n` and `columns n` give the trace dimensions. They are required and
+//!   need to come before the first constraint.
+//! * `seed "text"` or `seed 0x0123..` sets the channel seed.
+//! * `blowup n`, `queries n` and `pow_bits n` override the proof parameters.
+//! * `fri_layout [a, b, ..]` sets the number of FRI reductions between
+//!   commitments and `extension_degree n` the degree of the field the verifier
+//!   randomness is drawn from.
+//! * `hash name` picks the hash function, one of `masked_keccak`, `keccak256`,
+//!   `blake2s`, `sha256` and `poseidon`.
+//! * `zero_knowledge` and `legacy_seed` turn on the flags of the same name.
+//! * `stage c k` adds a stage of `c` columns followed by `k` challenges. The
+//!   stages need to cover all columns. Without them, all columns are a single
+//!   stage.
+//! * `column i name` names trace column `i`. Expressions can then use `name`
+//!   for `trace(i, 0)` and `name(j)` for `trace(i, j)`.
+//! * `periodic name = [v_0, v_1, ..]` declares a periodic column that takes the
+//!   value $v_{i \bmod p}$ on row $i$. The period $p$ needs to be a power of
+//!   two no larger than the number of rows. The name stands for `periodic([v_0,
+//!   v_1, ..], p)`.
+//! * `boundary a = b at row r` constrains `a - b` to be zero on row `r`.
+//! * `transition a = b for rows s..e step k` constrains `a - b` to be zero on
+//!   rows `s`, `s + k`, .. up to `e` exclusive. The `step` defaults to one and
+//!   the domain defaults to all rows but the last. The `= b` part of boundary
+//!   and transition constraints is optional.
+//! * `constraint e` adds the rational expression `e` as is.
+//!
+//! Boundary, transition and plain constraints can be labelled with a string
+//! after the keyword, as in `boundary "start" trace(0, 0) = 1 at row 0`.
+//! Column names and labels are kept in [`Constraints`] for debugging output
+//! and do not change the proof.
+//!
+//! Expressions are made of `X`, decimal and `0x` hexadecimal constants,
+//! `trace(column, row_offset)`, `challenge(i)`, periodic column names,
+//! `periodic([v_0, v_1, ..], p)` for a column with period `p` that is zero on
+//! the rows past the listed values, `inv(e)`, `polynomial([c_0, c_1, ..], e)`,
+//! `claim(index, degree_bound, e)`,
+//! parentheses, `+`, `-`, `*`, `/` and `^` with an integer exponent. The
+//! `Display` implementation of [`RationalExpression`] prints this syntax such
+//! that parsing it gives back the same expression. Names of claim polynomials
+//! are not printed, and trace values are printed as `trace(i, j)` even when
+//! the column has a name.
+//!
+//! The `Display` implementation of [`Constraints`] prints all of the above.
+//! Only the claim polynomials are lost, as the format has no statement for
+//! them.
+use crate::{
+    constraints::{Constraints, HashFunction, Stage},
+    polynomial::DensePolynomial,
+    RationalExpression,
+};
+use std::{collections::BTreeMap, convert::TryFrom, fmt, prelude::v1::*, str::FromStr};
+use zkp_primefield::{FieldElement, Pow, Root};
+use zkp_u256::U256;
+
+/// Error in an AIR file, with the position where it was found
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Error {
+    pub line:   usize,
+    pub column: usize,
+    pub kind:   ErrorKind,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    Expected(&'static str),
+    InvalidNumber(String),
+    ConstantOutOfRange(String),
+    UnknownStatement(String),
+    UnknownIdentifier(String),
+    ReservedIdentifier(String),
+    DuplicateDeclaration(String),
+    MissingDeclaration(&'static str),
+    InvalidTraceLength(usize),
+    InvalidPeriod(usize),
+    InvalidPolynomial(usize),
+    ColumnOutOfRange(usize),
+    OffsetOutOfRange(isize),
+    RowOutOfRange(usize),
+    EmptyDomain,
+    UnknownHashFunction(String),
+    InvalidStages,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        fmt::Display::fmt(&self.kind, f)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ErrorKind::*;
+        match self {
+            UnexpectedCharacter(c) => write!(f, "Unexpected character {:?}", c),
+            UnterminatedString => write!(f, "Unterminated string"),
+            Expected(what) => write!(f, "Expected {}", what),
+            InvalidNumber(s) => write!(f, "Invalid number {}", s),
+            ConstantOutOfRange(s) => write!(f, "Constant {} is not less than the modulus", s),
+            UnknownStatement(s) => write!(f, "Unknown statement {}", s),
+            UnknownIdentifier(s) => write!(f, "Unknown identifier {}", s),
+            ReservedIdentifier(s) => write!(f, "{} is a reserved name", s),
+            DuplicateDeclaration(s) => write!(f, "{} is declared twice", s),
+            MissingDeclaration(s) => write!(f, "{} needs to be declared first", s),
+            InvalidTraceLength(n) => write!(f, "Number of rows {} is not a power of two", n),
+            InvalidPeriod(n) => {
+                write!(
+                    f,
+                    "Period {} is not a power of two dividing the number of rows",
+                    n
+                )
+            }
+            InvalidPolynomial(n) => {
+                write!(f, "Number of coefficients {} is not a power of two", n)
+            }
+            ColumnOutOfRange(i) => write!(f, "Trace column {} is out of range", i),
+            OffsetOutOfRange(i) => write!(f, "Row offset {} is out of range", i),
+            RowOutOfRange(i) => write!(f, "Row {} is out of range", i),
+            EmptyDomain => write!(f, "The row domain is empty"),
+            UnknownHashFunction(s) => write!(f, "Unknown hash function {}", s),
+            InvalidStages => write!(f, "The stages do not cover the columns"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+type Result<T> = std::result::Result<T, Error>;
+
+const HASH_FUNCTIONS: [(&str, HashFunction); 5] = [
+    ("masked_keccak", HashFunction::MaskedKeccak),
+    ("keccak256", HashFunction::Keccak256),
+    ("blake2s", HashFunction::Blake2s),
+    ("sha256", HashFunction::Sha256),
+    ("poseidon", HashFunction::Poseidon),
+];
+
+const RESERVED: [&str; 7] = [
+    "X",
+    "trace",
+    "challenge",
+    "inv",
+    "polynomial",
+    "periodic",
+    "claim",
+];
+
+/// Parse an AIR file into [`Constraints`].
+///
+/// See the [module documentation](self) for the format.
+pub fn parse_air(source: &str) -> Result<Constraints> {
+    let mut parser = Parser::new(source)?;
+    parser.air()
+}
+
+impl FromStr for RationalExpression {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let mut parser = Parser::new(source)?;
+        let expression = parser.expression()?;
+        parser.expect_end()?;
+        Ok(expression)
+    }
+}
+
+// Binding strength of the operators, from weak to strong.
+const SUM: u8 = 0;
+const PRODUCT: u8 = 1;
+const UNARY: u8 = 2;
+const POWER: u8 = 3;
+const ATOM: u8 = 4;
+
+impl RationalExpression {
+    fn precedence(&self) -> u8 {
+        use RationalExpression::*;
+        match self {
+            Add(..) => SUM,
+            Mul(..) => PRODUCT,
+            Neg(_) => UNARY,
+            Exp(..) => POWER,
+            _ => ATOM,
+        }
+    }
+
+    fn fmt_precedence(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+        use RationalExpression::*;
+        if self.precedence() < precedence {
+            write!(f, "(")?;
+            self.fmt_precedence(f, SUM)?;
+            return write!(f, ")");
+        }
+        match self {
+            X => write!(f, "X"),
+            Constant(c) => write!(f, "{}", Number(c)),
+            Trace(i, j) => write!(f, "trace({}, {})", i, j),
+            Challenge(i) => write!(f, "challenge({})", i),
+            Polynomial(p, a) => {
+                write!(f, "polynomial([")?;
+                for (i, c) in p.coefficients().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", Number(c))?;
+                }
+                write!(f, "], {})", a)
+            }
+            Periodic { values, period } => {
+                write!(f, "periodic([")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", Number(value))?;
+                }
+                write!(f, "], {})", period)
+            }
+            ClaimPolynomial(i, degree_bound, a, _) => {
+                write!(f, "claim({}, {}, {})", i, degree_bound, a)
+            }
+            Add(a, b) => {
+                a.fmt_precedence(f, SUM)?;
+                if let Neg(b) = b.as_ref() {
+                    write!(f, " - ")?;
+                    b.fmt_precedence(f, PRODUCT)
+                } else {
+                    write!(f, " + ")?;
+                    b.fmt_precedence(f, PRODUCT)
+                }
+            }
+            Mul(a, b) => {
+                a.fmt_precedence(f, PRODUCT)?;
+                if let Inv(b) = b.as_ref() {
+                    write!(f, " / ")?;
+                    b.fmt_precedence(f, UNARY)
+                } else {
+                    write!(f, " * ")?;
+                    b.fmt_precedence(f, UNARY)
+                }
+            }
+            Neg(a) => {
+                write!(f, "-")?;
+                a.fmt_precedence(f, UNARY)
+            }
+            Inv(a) => write!(f, "inv({})", a),
+            Exp(a, e) => {
+                a.fmt_precedence(f, ATOM)?;
+                write!(f, "^{}", e)
+            }
+        }
+    }
+}
+
+impl fmt::Display for RationalExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_precedence(f, SUM)
+    }
+}
+
+/// Field elements are printed in decimal when they fit a `u64` and in
+/// hexadecimal otherwise.
+pub(crate) struct Number<'a>(pub(crate) &'a FieldElement);
+
+impl fmt::Display for Number<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = U256::from(self.0);
+        if value <= U256::from(u64::MAX) {
+            write!(f, "{}", value.limb(0))
+        } else {
+            write!(f, "0x{}", value)
+        }
+    }
+}
+
+/// Prints the constraints in the AIR format.
+///
+/// All expressions are printed as `constraint` statements, with their labels.
+/// The claim polynomials are not printed.
+impl fmt::Display for Constraints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rows {}", self.trace_nrows())?;
+        writeln!(f, "columns {}", self.trace_ncolumns())?;
+        for column in 0..self.trace_ncolumns() {
+            if let Some(name) = self.column_name(column) {
+                writeln!(f, "column {} {}", column, name)?;
+            }
+        }
+        if !self.channel_seed().is_empty() {
+            writeln!(f, "seed 0x{}", hex::encode(self.channel_seed()))?;
+        }
+        writeln!(f, "blowup {}", self.blowup)?;
+        writeln!(f, "queries {}", self.num_queries)?;
+        writeln!(f, "pow_bits {}", self.pow_bits)?;
+        write!(f, "fri_layout [")?;
+        for (i, reductions) in self.fri_layout.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", reductions)?;
+        }
+        writeln!(f, "]")?;
+        if let Some((name, _)) = HASH_FUNCTIONS
+            .iter()
+            .find(|(_, hash_function)| *hash_function == self.hash_function)
+        {
+            writeln!(f, "hash {}", name)?;
+        }
+        writeln!(f, "extension_degree {}", self.extension_degree)?;
+        if self.zero_knowledge {
+            writeln!(f, "zero_knowledge")?;
+        }
+        if self.legacy_seed {
+            writeln!(f, "legacy_seed")?;
+        }
+        if self.stages().len() > 1 {
+            for stage in self.stages() {
+                writeln!(f, "stage {} {}", stage.columns, stage.challenges)?;
+            }
+        }
+        for (index, expression) in self.expressions().iter().enumerate() {
+            match self.label(index) {
+                Some(label) => writeln!(f, "constraint \"{}\" {}", label, expression)?,
+                None => writeln!(f, "constraint {}", expression)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Token {
+    Identifier(String),
+    Number(String),
+    String(String),
+    Symbol(char),
+    Range,
+    Newline,
+    End,
+}
+
+#[derive(Clone, Debug)]
+struct Located {
+    token:  Token,
+    line:   usize,
+    column: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Located>> {
+    let mut tokens = Vec::new();
+    let mut depth = 0_usize;
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let error = |column, kind| {
+            Error {
+                line: line_number,
+                column,
+                kind,
+            }
+        };
+        let chars = line.char_indices().collect::<Vec<_>>();
+        let mut i = 0;
+        while i < chars.len() {
+            let (offset, c) = chars[i];
+            let column = offset + 1;
+            let start = i;
+            let token = match c {
+                '#' => break,
+                c if c.is_whitespace() => {
+                    i += 1;
+                    continue;
+                }
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    while i < chars.len()
+                        && (chars[i].1.is_ascii_alphanumeric() || chars[i].1 == '_')
+                    {
+                        i += 1;
+                    }
+                    Token::Identifier(chars[start..i].iter().map(|(_, c)| c).collect())
+                }
+                c if c.is_ascii_digit() => {
+                    while i < chars.len() && chars[i].1.is_ascii_alphanumeric() {
+                        i += 1;
+                    }
+                    Token::Number(chars[start..i].iter().map(|(_, c)| c).collect())
+                }
+                '"' => {
+                    i += 1;
+                    while i < chars.len() && chars[i].1 != '"' {
+                        i += 1;
+                    }
+                    if i == chars.len() {
+                        return Err(error(column, ErrorKind::UnterminatedString));
+                    }
+                    i += 1;
+                    Token::String(chars[start + 1..i - 1].iter().map(|(_, c)| c).collect())
+                }
+                '.' if chars.get(i + 1).map(|(_, c)| *c) == Some('.') => {
+                    i += 2;
+                    Token::Range
+                }
+                '+' | '-' | '*' | '/' | '^' | ',' | '=' | '(' | ')' | '[' | ']' | ';' => {
+                    i += 1;
+                    match c {
+                        '(' | '[' => depth += 1,
+                        ')' | ']' => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    if c == ';' {
+                        Token::Newline
+                    } else {
+                        Token::Symbol(c)
+                    }
+                }
+                c => return Err(error(column, ErrorKind::UnexpectedCharacter(c))),
+            };
+            tokens.push(Located {
+                token,
+                line: line_number,
+                column,
+            });
+        }
+        if depth == 0 {
+            tokens.push(Located {
+                token:  Token::Newline,
+                line:   line_number,
+                column: line.len() + 1,
+            });
+        }
+    }
+    let line = source.lines().count().max(1);
+    tokens.push(Located {
+        token: Token::End,
+        line,
+        column: source.lines().last().map_or(0, str::len) + 1,
+    });
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens:   Vec<Located>,
+    position: usize,
+    rows:     Option<usize>,
+    columns:  Option<usize>,
+    periodic: BTreeMap<String, RationalExpression>,
+    names:    BTreeMap<String, usize>,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self> {
+        Ok(Self {
+            tokens:   tokenize(source)?,
+            position: 0,
+            rows:     None,
+            columns:  None,
+            periodic: BTreeMap::new(),
+            names:    BTreeMap::new(),
+        })
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].token
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token != Token::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        let located = &self.tokens[self.position];
+        Error {
+            line: located.line,
+            column: located.column,
+            kind,
+        }
+    }
+
+    // Error at the previously consumed token
+    fn error_previous(&self, kind: ErrorKind) -> Error {
+        let located = &self.tokens[self.position.saturating_sub(1)];
+        Error {
+            line: located.line,
+            column: located.column,
+            kind,
+        }
+    }
+
+    fn accept(&mut self, token: &Token) -> bool {
+        if self.peek() == token {
+            let _ = self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char, what: &'static str) -> Result<()> {
+        if self.accept(&Token::Symbol(symbol)) {
+            Ok(())
+        } else {
+            Err(self.error(ErrorKind::Expected(what)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &'static str) -> Result<()> {
+        if self.accept(&Token::Identifier(keyword.to_owned())) {
+            Ok(())
+        } else {
+            Err(self.error(ErrorKind::Expected(keyword)))
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<()> {
+        while self.accept(&Token::Newline) {}
+        if self.peek() == &Token::End {
+            Ok(())
+        } else {
+            Err(self.error(ErrorKind::Expected("end of input")))
+        }
+    }
+
+    fn end_of_statement(&mut self) -> Result<()> {
+        match self.peek() {
+            Token::Newline | Token::End => {
+                let _ = self.next();
+                Ok(())
+            }
+            _ => Err(self.error(ErrorKind::Expected("end of statement"))),
+        }
+    }
+
+    fn integer(&mut self) -> Result<usize> {
+        match self.next() {
+            Token::Number(s) => {
+                s.parse()
+                    .map_err(|_| self.error_previous(ErrorKind::InvalidNumber(s)))
+            }
+            _ => Err(self.error_previous(ErrorKind::Expected("integer"))),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        match self.next() {
+            Token::Identifier(name) => Ok(name),
+            _ => Err(self.error_previous(ErrorKind::Expected("identifier"))),
+        }
+    }
+
+    fn signed_integer(&mut self) -> Result<isize> {
+        let negative = self.accept(&Token::Symbol('-'));
+        let value = self.integer()?;
+        let value = isize::try_from(value)
+            .map_err(|_| self.error_previous(ErrorKind::InvalidNumber(value.to_string())))?;
+        Ok(if negative { -value } else { value })
+    }
+
+    fn field_element(&mut self) -> Result<FieldElement> {
+        let s = match self.next() {
+            Token::Number(s) => s,
+            _ => return Err(self.error_previous(ErrorKind::Expected("number"))),
+        };
+        let invalid = || self.error_previous(ErrorKind::InvalidNumber(s.clone()));
+        let value = if let Some(digits) = s.strip_prefix("0x") {
+            if digits.is_empty()
+                || digits.len() > 64
+                || !digits.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                return Err(invalid());
+            }
+            U256::from_hex_str(digits)
+        } else {
+            U256::from_decimal_str(&s).map_err(|_| invalid())?
+        };
+        if value >= FieldElement::modulus() {
+            return Err(self.error_previous(ErrorKind::ConstantOutOfRange(s)));
+        }
+        Ok(FieldElement::from_uint(&value))
+    }
+
+    fn signed_field_element(&mut self) -> Result<FieldElement> {
+        let negative = self.accept(&Token::Symbol('-'));
+        let value = self.field_element()?;
+        Ok(if negative { -value } else { value })
+    }
+
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        self.expect('[', "[")?;
+        let mut items = Vec::new();
+        if !self.accept(&Token::Symbol(']')) {
+            loop {
+                items.push(item(self)?);
+                if self.accept(&Token::Symbol(']')) {
+                    break;
+                }
+                self.expect(',', ", or ]")?;
+            }
+        }
+        Ok(items)
+    }
+
+    fn rows(&self) -> Result<usize> {
+        self.rows
+            .ok_or_else(|| self.error(ErrorKind::MissingDeclaration("rows")))
+    }
+
+    fn air(&mut self) -> Result<Constraints> {
+        let mut seed = None;
+        let mut blowup = None;
+        let mut queries = None;
+        let mut pow_bits = None;
+        let mut fri_layout = None;
+        let mut hash_function = None;
+        let mut extension_degree = None;
+        let mut zero_knowledge = false;
+        let mut legacy_seed = false;
+        let mut stages = Vec::new();
+        let mut expressions = Vec::new();
+        let mut labels = Vec::new();
+        loop {
+            let keyword = match self.next() {
+                Token::End => break,
+                Token::Newline => continue,
+                Token::Identifier(keyword) => keyword,
+                _ => return Err(self.error_previous(ErrorKind::Expected("statement"))),
+            };
+            let duplicate = |parser: &Self| {
+                parser.error_previous(ErrorKind::DuplicateDeclaration(keyword.clone()))
+            };
+            match keyword.as_str() {
+                "rows" => {
+                    if self.rows.is_some() {
+                        return Err(duplicate(self));
+                    }
+                    let rows = self.integer()?;
+                    if !rows.is_power_of_two() {
+                        return Err(self.error_previous(ErrorKind::InvalidTraceLength(rows)));
+                    }
+                    self.rows = Some(rows);
+                }
+                "columns" => {
+                    if self.columns.is_some() {
+                        return Err(duplicate(self));
+                    }
+                    self.columns = Some(self.integer()?);
+                }
+                "seed" => {
+                    if seed.is_some() {
+                        return Err(duplicate(self));
+                    }
+                    seed = Some(self.seed()?);
+                }
+                "blowup" | "queries" | "pow_bits" => {
+                    let target = match keyword.as_str() {
+                        "blowup" => &mut blowup,
+                        "queries" => &mut queries,
+                        _ => &mut pow_bits,
+                    };
+                    if target.is_some() {
+                        return Err(duplicate(self));
+                    }
+                    *target = Some(self.integer()?);
+                }
+                "fri_layout" => {
+                    if fri_layout.is_some() {
+                        return Err(duplicate(self));
+                    }
+                    fri_layout = Some(self.list(Self::integer)?);
+                }
+                "hash" => {
+                    if hash_function.is_some() {
+                        return Err(duplicate(self));
+                    }
+                    let name = self.identifier()?;
+                    let (_, function) = HASH_FUNCTIONS
+                        .iter()
+                        .find(|(known, _)| *known == name)
+                        .ok_or_else(|| {
+                            self.error_previous(ErrorKind::UnknownHashFunction(name.clone()))
+                        })?;
+                    hash_function = Some(*function);
+                }
+                "extension_degree" => {
+                    if extension_degree.is_some() {
+                        return Err(duplicate(self));
+                    }
+                    extension_degree = Some(self.integer()?);
+                }
+                "zero_knowledge" | "legacy_seed" => {
+                    let flag = if keyword == "zero_knowledge" {
+                        &mut zero_knowledge
+                    } else {
+                        &mut legacy_seed
+                    };
+                    if *flag {
+                        return Err(duplicate(self));
+                    }
+                    *flag = true;
+                }
+                "stage" => {
+                    let columns = self.integer()?;
+                    let challenges = self.integer()?;
+                    stages.push(Stage {
+                        columns,
+                        challenges,
+                    });
+                }
+                "periodic" => self.periodic()?,
+                "column" => self.column()?,
+                "boundary" => {
+                    let rows = self.rows()?;
+                    if let Some(label) = self.label() {
+                        labels.push((expressions.len(), label));
+                    }
+                    let constraint = self.equation()?;
+                    self.expect_keyword("at")?;
+                    self.expect_keyword("row")?;
+                    let row = self.integer()?;
+                    if row >= rows {
+                        return Err(self.error_previous(ErrorKind::RowOutOfRange(row)));
+                    }
+                    let root = FieldElement::root(rows).unwrap();
+                    expressions.push(constraint / (RationalExpression::X - root.pow(row)));
+                }
+                "transition" => {
+                    let rows = self.rows()?;
+                    if let Some(label) = self.label() {
+                        labels.push((expressions.len(), label));
+                    }
+                    let constraint = self.equation()?;
+                    let (start, end, step) = if self.accept(&Token::Identifier("for".to_owned())) {
+                        self.domain(rows)?
+                    } else {
+                        (0, rows - 1, 1)
+                    };
+                    if start >= end {
+                        return Err(self.error_previous(ErrorKind::EmptyDomain));
+                    }
+                    expressions.push(constraint * vanishing(rows, start, end, step));
+                }
+                "constraint" => {
+                    let _ = self.rows()?;
+                    if let Some(label) = self.label() {
+                        labels.push((expressions.len(), label));
+                    }
+                    expressions.push(self.expression()?);
+                }
+                _ => return Err(self.error_previous(ErrorKind::UnknownStatement(keyword))),
+            }
+            self.end_of_statement()?;
+        }
+        let rows = self.rows()?;
+        let columns = self
+            .columns
+            .ok_or_else(|| self.error(ErrorKind::MissingDeclaration("columns")))?;
+        let mut constraints =
+            Constraints::from_expressions((rows, columns), seed.unwrap_or_default(), expressions)
+                .map_err(|_| self.error(ErrorKind::InvalidTraceLength(rows)))?;
+        if let Some(blowup) = blowup {
+            constraints.blowup = blowup;
+        }
+        if let Some(queries) = queries {
+            constraints.num_queries = queries;
+        }
+        if let Some(pow_bits) = pow_bits {
+            constraints.pow_bits = pow_bits;
+        }
+        if let Some(fri_layout) = fri_layout {
+            constraints.fri_layout = fri_layout;
+        }
+        if let Some(hash_function) = hash_function {
+            constraints.hash_function = hash_function;
+        }
+        if let Some(extension_degree) = extension_degree {
+            constraints.extension_degree = extension_degree;
+        }
+        constraints.zero_knowledge = zero_knowledge;
+        constraints.legacy_seed = legacy_seed;
+        if !stages.is_empty() {
+            constraints
+                .set_stages(stages)
+                .map_err(|_| self.error(ErrorKind::InvalidStages))?;
+        }
+        for (name, &column) in &self.names {
+            constraints.set_column_name(column, name.clone());
+        }
+        for (index, label) in labels {
+            constraints.set_label(index, label);
+        }
+        Ok(constraints)
+    }
+
+    fn label(&mut self) -> Option<String> {
+        if let Token::String(label) = self.peek().clone() {
+            let _ = self.next();
+            Some(label)
+        } else {
+            None
+        }
+    }
+
+    // Reserved words and names already used by columns or periodic columns
+    fn check_name(&self, name: String) -> Result<String> {
+        if RESERVED.contains(&name.as_str()) {
+            return Err(self.error_previous(ErrorKind::ReservedIdentifier(name)));
+        }
+        if self.periodic.contains_key(&name) || self.names.contains_key(&name) {
+            return Err(self.error_previous(ErrorKind::DuplicateDeclaration(name)));
+        }
+        Ok(name)
+    }
+
+    fn column(&mut self) -> Result<()> {
+        let columns = self
+            .columns
+            .ok_or_else(|| self.error(ErrorKind::MissingDeclaration("columns")))?;
+        let column = self.integer()?;
+        if column >= columns {
+            return Err(self.error_previous(ErrorKind::ColumnOutOfRange(column)));
+        }
+        if self.names.values().any(|&named| named == column) {
+            return Err(self.error_previous(ErrorKind::DuplicateDeclaration(format!(
+                "column {}",
+                column
+            ))));
+        }
+        let name = match self.next() {
+            Token::Identifier(name) => self.check_name(name)?,
+            _ => return Err(self.error_previous(ErrorKind::Expected("name"))),
+        };
+        let _ = self.names.insert(name, column);
+        Ok(())
+    }
+
+    fn offset(&mut self) -> Result<isize> {
+        let offset = self.signed_integer()?;
+        if self.rows.is_some_and(|rows| offset.unsigned_abs() >= rows) {
+            return Err(self.error_previous(ErrorKind::OffsetOutOfRange(offset)));
+        }
+        Ok(offset)
+    }
+
+    fn seed(&mut self) -> Result<Vec<u8>> {
+        match self.next() {
+            Token::String(s) => Ok(s.into_bytes()),
+            Token::Number(s) => {
+                s.strip_prefix("0x")
+                    .and_then(|digits| hex::decode(digits).ok())
+                    .ok_or_else(|| self.error_previous(ErrorKind::InvalidNumber(s)))
+            }
+            _ => Err(self.error_previous(ErrorKind::Expected("string or hexadecimal seed"))),
+        }
+    }
+
+    fn periodic(&mut self) -> Result<()> {
+        let rows = self.rows()?;
+        let name = match self.next() {
+            Token::Identifier(name) => self.check_name(name)?,
+            _ => return Err(self.error_previous(ErrorKind::Expected("name"))),
+        };
+        self.expect('=', "=")?;
+        let values = self.list(Self::signed_field_element)?;
+        let period = values.len();
+        if !period.is_power_of_two() || period > rows {
+            return Err(self.error_previous(ErrorKind::InvalidPeriod(period)));
+        }
+        let column = RationalExpression::Periodic { values, period };
+        let _ = self.periodic.insert(name, column);
+        Ok(())
+    }
+
+    fn domain(&mut self, rows: usize) -> Result<(usize, usize, usize)> {
+        self.expect_keyword("rows")?;
+        let start = self.integer()?;
+        if !self.accept(&Token::Range) {
+            return Err(self.error(ErrorKind::Expected("..")));
+        }
+        let end = self.integer()?;
+        if end > rows {
+            return Err(self.error_previous(ErrorKind::RowOutOfRange(end)));
+        }
+        let step = if self.accept(&Token::Identifier("step".to_owned())) {
+            let step = self.integer()?;
+            if !step.is_power_of_two() || step > rows {
+                return Err(self.error_previous(ErrorKind::InvalidPeriod(step)));
+            }
+            step
+        } else {
+            1
+        };
+        Ok((start, end, step))
+    }
+
+    fn equation(&mut self) -> Result<RationalExpression> {
+        let left = self.expression()?;
+        if self.accept(&Token::Symbol('=')) {
+            Ok(left - self.expression()?)
+        } else {
+            Ok(left)
+        }
+    }
+
+    fn expression(&mut self) -> Result<RationalExpression> {
+        let mut result = self.product()?;
+        loop {
+            if self.accept(&Token::Symbol('+')) {
+                result = result + self.product()?;
+            } else if self.accept(&Token::Symbol('-')) {
+                result = result - self.product()?;
+            } else {
+                return Ok(result);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<RationalExpression> {
+        let mut result = self.unary()?;
+        loop {
+            if self.accept(&Token::Symbol('*')) {
+                result = result * self.unary()?;
+            } else if self.accept(&Token::Symbol('/')) {
+                result = result / self.unary()?;
+            } else {
+                return Ok(result);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<RationalExpression> {
+        if self.accept(&Token::Symbol('-')) {
+            Ok(self.unary()?.neg())
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<RationalExpression> {
+        let base = self.atom()?;
+        if self.accept(&Token::Symbol('^')) {
+            Ok(base.pow(self.integer()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<RationalExpression> {
+        use RationalExpression::*;
+        match self.peek().clone() {
+            Token::Number(_) => return Ok(Constant(self.field_element()?)),
+            Token::Symbol('(') => {
+                let _ = self.next();
+                let result = self.expression()?;
+                self.expect(')', ")")?;
+                return Ok(result);
+            }
+            Token::Identifier(_) => {}
+            _ => return Err(self.error(ErrorKind::Expected("expression"))),
+        }
+        let name = match self.next() {
+            Token::Identifier(name) => name,
+            _ => unreachable!(),
+        };
+        let result =
+            match name.as_str() {
+                "X" => return Ok(X),
+                "trace" => {
+                    self.expect('(', "(")?;
+                    let column = self.integer()?;
+                    if self.columns.is_some_and(|columns| column >= columns) {
+                        return Err(self.error_previous(ErrorKind::ColumnOutOfRange(column)));
+                    }
+                    self.expect(',', ",")?;
+                    Trace(column, self.offset()?)
+                }
+                "challenge" => {
+                    self.expect('(', "(")?;
+                    Challenge(self.integer()?)
+                }
+                "inv" => {
+                    self.expect('(', "(")?;
+                    self.expression()?.inv()
+                }
+                "polynomial" => {
+                    self.expect('(', "(")?;
+                    let coefficients = self.list(Self::field_element)?;
+                    if !coefficients.len().is_power_of_two() {
+                        return Err(
+                            self.error_previous(ErrorKind::InvalidPolynomial(coefficients.len()))
+                        );
+                    }
+                    self.expect(',', ",")?;
+                    Polynomial(
+                        DensePolynomial::new(&coefficients),
+                        Box::new(self.expression()?),
+                    )
+                }
+                "periodic" => {
+                    self.expect('(', "(")?;
+                    let values = self.list(Self::signed_field_element)?;
+                    self.expect(',', ",")?;
+                    let period = self.integer()?;
+                    if !period.is_power_of_two()
+                        || values.len() > period
+                        || self.rows.is_some_and(|rows| period > rows)
+                    {
+                        return Err(self.error_previous(ErrorKind::InvalidPeriod(period)));
+                    }
+                    Periodic { values, period }
+                }
+                "claim" => {
+                    self.expect('(', "(")?;
+                    let index = self.integer()?;
+                    self.expect(',', ",")?;
+                    let degree_bound = self.integer()?;
+                    self.expect(',', ",")?;
+                    ClaimPolynomial(index, degree_bound, Box::new(self.expression()?), None)
+                }
+                _ => {
+                    if let Some(&column) = self.names.get(&name) {
+                        if !self.accept(&Token::Symbol('(')) {
+                            return Ok(Trace(column, 0));
+                        }
+                        Trace(column, self.offset()?)
+                    } else {
+                        return self.periodic.get(&name).cloned().ok_or_else(|| {
+                            self.error_previous(ErrorKind::UnknownIdentifier(name))
+                        });
+                    }
+                }
+            };
+        self.expect(')', ")")?;
+        Ok(result)
+    }
+}
+
+/// Multiplier that divides out the rows `start, start + step, ..` before
+/// `end`
+///
+/// All rows congruent to `start` modulo `step` are the roots of
+/// $X^{n / \mathrm{step}} - \omega^{\mathrm{start} \cdot n / \mathrm{step}}$.
+/// The rows of that coset outside the domain are multiplied back in. When
+/// fewer rows are in the domain than outside, the product over the rows in the
+/// domain is divided out directly instead.
+fn vanishing(rows: usize, start: usize, end: usize, step: usize) -> RationalExpression {
+    use RationalExpression::*;
+    let root = FieldElement::root(rows).unwrap();
+    let size = rows / step;
+    let product = |rows: &mut dyn Iterator<Item = usize>| {
+        rows.map(|row| X - root.pow(row))
+            .reduce(|product, factor| product * factor)
+    };
+    // The coset rows in `start..end` are `start, start + step, ..` up to `last`
+    let last = end.min(rows).max(start);
+    let included = (last - start + step - 1) / step;
+    if included > 0 && included < size - included {
+        return product(&mut (start..last).step_by(step)).unwrap().inv();
+    }
+    let denominator = X.pow(size) - root.pow(start % step * size);
+    let mut excluded = (start % step..start)
+        .step_by(step)
+        .chain((start + included * step..rows).step_by(step));
+    match product(&mut excluded) {
+        None => denominator.inv(),
+        Some(numerator) => numerator / denominator,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_constraints, prove, verify, TraceTable};
+    use proptest::prelude::*;
+    use zkp_primefield::{One, Zero};
+
+    fn arb_expression() -> impl Strategy<Value = RationalExpression> {
+        use RationalExpression::*;
+        let leaf = prop_oneof![
+            Just(X),
+            any::<FieldElement>().prop_map(Constant),
+            (0_usize..10, -10_isize..10).prop_map(|(i, j)| Trace(i, j)),
+            (0_usize..4).prop_map(Challenge),
+            (0_usize..3, 0_usize..5, any::<[FieldElement; 4]>()).prop_map(
+                |(log_period, len, values)| {
+                    let period = 1 << log_period;
+                    Periodic {
+                        values: values[..len.min(period)].to_vec(),
+                        period,
+                    }
+                }
+            ),
+        ];
+        leaf.prop_recursive(4, 32, 2, |inner| {
+            prop_oneof![
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| a + b),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| a - b),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| a * b),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| a / b),
+                inner.clone().prop_map(|a| a.neg()),
+                inner.clone().prop_map(|a| a.inv()),
+                (inner.clone(), 0_usize..10).prop_map(|(a, e)| a.pow(e)),
+                (0_usize..3, any::<[FieldElement; 4]>(), inner.clone()).prop_map(
+                    |(log_size, c, a)| {
+                        Polynomial(DensePolynomial::new(&c[..1 << log_size]), Box::new(a))
+                    }
+                ),
+                (0_usize..4, 0_usize..4, inner)
+                    .prop_map(|(i, d, a)| ClaimPolynomial(i, d, Box::new(a), None)),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn display_parse_roundtrip(expression in arb_expression()) {
+            let printed = expression.to_string();
+            let parsed = printed.parse::<RationalExpression>();
+            prop_assert_eq!(parsed, Ok(expression));
+        }
+    }
+
+    #[test]
+    fn display_minimal_parentheses() {
+        use RationalExpression::*;
+        let expression = (Trace(0, 1) - Trace(1, 0) * (X + 1)) / (X.pow(1024) - 1);
+        assert_eq!(
+            expression.to_string(),
+            "(trace(0, 1) - trace(1, 0) * (X + 1)) / (X^1024 - 1)"
+        );
+        assert_eq!(
+            (Trace(0, 0).neg().pow(2) - (X - 1)).to_string(),
+            "(-trace(0, 0))^2 - (X - 1)"
+        );
+    }
+
+    const FIBONACCI: &str = r#"
+        # Fibonacci sequence
+        rows 16
+        columns 2
+        seed "fibonacci"
+        queries 20
+
+        transition trace(0, 1) = trace(1, 0)
+        transition trace(1, 1) = trace(0, 0) + trace(1, 0)
+        boundary trace(0, 0) = 1 at row 0
+        boundary trace(0, 0) = 0x59 at row 9
+    "#;
+
+    fn fibonacci_trace(secret: usize) -> TraceTable {
+        let mut trace = TraceTable::new(16, 2);
+        trace[(0, 0)] = FieldElement::one();
+        trace[(0, 1)] = FieldElement::from(secret);
+        for i in 0..15 {
+            trace[(i + 1, 0)] = trace[(i, 1)].clone();
+            trace[(i + 1, 1)] = &trace[(i, 0)] + &trace[(i, 1)];
+        }
+        trace
+    }
+
+    #[test]
+    fn fibonacci() {
+        let constraints = parse_air(FIBONACCI).unwrap();
+        assert_eq!(constraints.channel_seed(), b"fibonacci");
+        assert_eq!(constraints.num_queries, 20);
+        assert_eq!(constraints.len(), 4);
+
+        assert!(check_constraints(&constraints, &fibonacci_trace(2)).is_ok());
+        assert!(check_constraints(&constraints, &fibonacci_trace(3)).is_err());
+        let proof = prove(&constraints, &fibonacci_trace(2)).unwrap();
+        assert!(verify(&constraints, &proof).is_ok());
+
+        // Printed constraints parse back to the same expressions
+        let reparsed = parse_air(&constraints.to_string()).unwrap();
+        assert_eq!(reparsed.expressions(), constraints.expressions());
+        assert_eq!(reparsed.channel_seed(), constraints.channel_seed());
+        assert_eq!(reparsed.num_queries, constraints.num_queries);
+    }
+
+    #[test]
+    fn parameters() {
+        let constraints = parse_air(
+            "rows 16; columns 2
+             fri_layout [2, 1]
+             hash blake2s
+             extension_degree 2
+             zero_knowledge
+             legacy_seed
+             stage 1 1
+             stage 1 0
+             transition trace(1, 0) = trace(0, 0) * challenge(0)",
+        )
+        .unwrap();
+        assert_eq!(constraints.fri_layout, vec![2, 1]);
+        assert_eq!(constraints.hash_function, HashFunction::Blake2s);
+        assert_eq!(constraints.extension_degree, 2);
+        assert!(constraints.zero_knowledge);
+        assert!(constraints.legacy_seed);
+        assert_eq!(constraints.stages().len(), 2);
+
+        let reparsed = parse_air(&constraints.to_string()).unwrap();
+        assert_eq!(reparsed.digest(), constraints.digest());
+
+        let error = |source: &str| parse_air(source).unwrap_err().kind;
+        assert_eq!(
+            error("rows 4; columns 1; hash md5"),
+            ErrorKind::UnknownHashFunction("md5".to_owned())
+        );
+        assert_eq!(
+            error("rows 4; columns 2; stage 1 0"),
+            ErrorKind::InvalidStages
+        );
+    }
+
+    #[test]
+    fn names_and_labels() {
+        let constraints = parse_air(
+            r#"rows 16; columns 2
+               column 0 a; column 1 b
+               transition "next a" a(1) = b
+               transition b(1) = a + b
+               boundary "start" a = 1 at row 0"#,
+        )
+        .unwrap();
+        let plain = parse_air(FIBONACCI).unwrap();
+        assert_eq!(constraints.expressions(), &plain.expressions()[..3]);
+        assert_eq!(constraints.column_name(1), Some("b"));
+        assert_eq!(constraints.label(0), Some("next a"));
+        assert_eq!(constraints.label(1), None);
+        assert_eq!(constraints.label(2), Some("start"));
+
+        let reparsed = parse_air(&constraints.to_string()).unwrap();
+        assert_eq!(reparsed.expressions(), constraints.expressions());
+        assert_eq!(reparsed.column_name(0), Some("a"));
+        assert_eq!(reparsed.label(0), Some("next a"));
+        assert_eq!(reparsed.label(2), Some("start"));
+
+        let error = |source: &str| parse_air(source).unwrap_err().kind;
+        assert_eq!(
+            error("rows 4; columns 1; column 0 k; periodic k = [1]"),
+            ErrorKind::DuplicateDeclaration("k".to_owned())
+        );
+        assert_eq!(
+            error("rows 4; columns 1; column 1 k"),
+            ErrorKind::ColumnOutOfRange(1)
+        );
+        assert_eq!(
+            error("rows 4; columns 1; column 0 trace"),
+            ErrorKind::ReservedIdentifier("trace".to_owned())
+        );
+    }
+
+    #[test]
+    fn periodic_and_step() {
+        let constraints = parse_air(
+            "rows 16; columns 1
+             periodic k = [1, 2, 3, -4]
+             transition trace(0, 0) = k for rows 0..16
+             transition trace(0, 0) = 1 for rows 0..12 step 4",
+        )
+        .unwrap();
+        let mut trace = TraceTable::new(16, 1);
+        for i in 0..16 {
+            trace[(i, 0)] = [1.into(), 2.into(), 3.into(), -FieldElement::from(4)][i % 4].clone();
+        }
+        assert!(check_constraints(&constraints, &trace).is_ok());
+        trace[(12, 0)] = FieldElement::zero();
+        assert!(check_constraints(&constraints, &trace).is_err());
+
+        // Values past the listed ones are zero
+        let constraints = parse_air(
+            "rows 16; columns 1
+             transition trace(0, 0) = periodic([5, 6], 4) for rows 0..16",
+        )
+        .unwrap();
+        let mut trace = TraceTable::new(16, 1);
+        for i in 0..16 {
+            trace[(i, 0)] = FieldElement::from([5, 6, 0, 0][i % 4]);
+        }
+        assert!(check_constraints(&constraints, &trace).is_ok());
+        let proof = prove(&constraints, &trace).unwrap();
+        assert!(verify(&constraints, &proof).is_ok());
+        let reparsed = parse_air(&constraints.to_string()).unwrap();
+        assert_eq!(reparsed.expressions(), constraints.expressions());
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| parse_air(source).unwrap_err();
+        assert_eq!(
+            error("rows 16\ncolumns 2\nboundary trace(2, 0) = 1 at row 0"),
+            Error {
+                line:   3,
+                column: 16,
+                kind:   ErrorKind::ColumnOutOfRange(2),
+            }
+        );
+        assert_eq!(error("rows 12").kind, ErrorKind::InvalidTraceLength(12));
+        assert_eq!(
+            error("columns 1\nconstraint X").kind,
+            ErrorKind::MissingDeclaration("rows")
+        );
+        assert_eq!(
+            error("rows 4; columns 1; constraint trace(0, 0) * k").kind,
+            ErrorKind::UnknownIdentifier("k".to_owned())
+        );
+        assert_eq!(
+            error("rows 4; columns 1; periodic k = [1, 2, 3]").kind,
+            ErrorKind::InvalidPeriod(3)
+        );
+        assert_eq!(
+            error("rows 4; columns 1; constraint periodic([1, 2, 3], 2)").kind,
+            ErrorKind::InvalidPeriod(2)
+        );
+        assert_eq!(
+            error("rows 4; columns 1; constraint periodic([1], 8)").kind,
+            ErrorKind::InvalidPeriod(8)
+        );
+        assert_eq!(
+            error("rows 4; columns 1; boundary X at row 4").kind,
+            ErrorKind::RowOutOfRange(4)
+        );
+        assert_eq!(
+            error("rows 4; columns 1; constraint (X + 1").kind,
+            ErrorKind::Expected(")")
+        );
+        assert_eq!(error("rows 4\ncolumns 1\nconstraint X $ 1"), Error {
+            line:   3,
+            column: 14,
+            kind:   ErrorKind::UnexpectedCharacter('$'),
+        });
+        assert_eq!(
+            error(
+                "rows 4; columns 1; constraint \
+                 0x800000000000011000000000000000000000000000000000000000000000001"
+            )
+            .kind,
+            ErrorKind::ConstantOutOfRange(
+                "0x800000000000011000000000000000000000000000000000000000000000001".to_owned()
+            )
+        );
+        assert_eq!(
+            error("rows 4; columns 1; rows 8").kind,
+            ErrorKind::DuplicateDeclaration("rows".to_owned())
+        );
+    }
+}
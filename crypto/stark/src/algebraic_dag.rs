@@ -1,5 +1,7 @@
 use crate::{
-    polynomial::DensePolynomial, rational_expression::RationalExpression, trace_table::TraceTable,
+    polynomial::DensePolynomial,
+    rational_expression::{periodic_polynomial, RationalExpression},
+    trace_table::TraceTable,
 };
 use log::info;
 use std::{cmp::min, ops::Neg, prelude::v1::*};
@@ -40,6 +42,23 @@ const CHUNK_INIT: [FieldElement; CHUNK_SIZE] = [
 /// lookup table when the period is equal to or less than this value.
 const LOOKUP_SIZE: usize = 1024;
 
+/// Trace values on the rows of a coset, as read by `AlgebraicGraph::next`.
+pub(crate) trait TraceValues {
+    fn num_rows(&self) -> usize;
+
+    fn value(&self, row: usize, column: usize) -> &FieldElement;
+}
+
+impl TraceValues for TraceTable {
+    fn num_rows(&self) -> usize {
+        self.num_rows()
+    }
+
+    fn value(&self, row: usize, column: usize) -> &FieldElement {
+        &self[(row, column)]
+    }
+}
+
 /// Evaluation graph for algebraic expressions over a coset.
 #[derive(Clone, PartialEq)]
 pub(crate) struct AlgebraicGraph {
@@ -63,7 +82,8 @@ pub(crate) struct AlgebraicGraph {
 }
 
 /// Node in the evaluation graph.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub(crate) struct Node {
     /// The operation represented by the node
     op: Operation,
@@ -84,7 +104,8 @@ pub(crate) struct Node {
 }
 
 /// Algebraic operations supported by the graph.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
 enum Operation {
     Constant(FieldElement),
     Coset(FieldElement, usize),
@@ -117,6 +138,7 @@ impl std::fmt::Debug for Table {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::fmt::Debug for AlgebraicGraph {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(fmt, "AlgebraicGraph:")?;
@@ -340,7 +362,15 @@ impl AlgebraicGraph {
                 let a = self.expression(*a);
                 self.op(Op::Poly(p, a))
             }
+            // The period on the coset is `period * trace_blowup`, which makes
+            // it a lookup table when it is small enough.
+            RE::Periodic { values, period } => {
+                let trace_length = self.coset_size / self.trace_blowup;
+                let x = self.expression(RE::X.pow(trace_length / period));
+                self.op(Op::Poly(periodic_polynomial(&values, period), x))
+            }
             RE::ClaimPolynomial(..) => panic!("ClaimPolynomial not supported"),
+            RE::Challenge(_) => panic!("Challenge not supported"),
             RE::Add(a, b) => {
                 let a = self.expression(*a);
                 let b = self.expression(*b);
@@ -495,7 +525,7 @@ impl AlgebraicGraph {
     // We want to use `for i in 0..CHUNK_SIZE` for consistency
     #[allow(clippy::needless_range_loop)]
     #[inline(never)]
-    pub(crate) fn next(&mut self, trace_table: &TraceTable) -> FieldElement {
+    pub(crate) fn next<T: TraceValues>(&mut self, trace_table: &T) -> FieldElement {
         use Operation::*;
         if self.row % CHUNK_SIZE > 0 {
             let result = self.nodes.last().unwrap().values[self.row % CHUNK_SIZE].clone();
@@ -524,7 +554,7 @@ impl AlgebraicGraph {
                         // Sizes are small enough
                         #[allow(clippy::cast_sign_loss)]
                         let row = row as usize;
-                        values[i] = trace_table[(row, *c)].clone();
+                        values[i] = trace_table.value(row, *c).clone();
                     }
                 }
                 Add(a, b) => {
@@ -1,11 +1,10 @@
 // TODO: Naming?
 #![allow(clippy::module_name_repetitions)]
-use crate::proof_of_work;
-use std::{convert::TryInto, prelude::v1::*};
-use tiny_keccak::{Hasher, Keccak};
-use zkp_hash::Hash;
+use crate::{proof::Error, proof_of_work};
+use std::{convert::TryInto, marker::PhantomData, ops::Range, prelude::v1::*};
+use zkp_hash::{Hash, Hasher, MaskedKeccak};
 use zkp_macros_decl::u256h;
-use zkp_primefield::FieldElement;
+use zkp_primefield::{Ext, Extendable, FieldElement};
 use zkp_u256::U256;
 
 pub(crate) trait RandomGenerator<T> {
@@ -16,47 +15,53 @@ pub(crate) trait Writable<T> {
     fn write(&mut self, data: T);
 }
 
+/// Reading from the proof fails with `Error::ProofTooShort` when it ends
+/// early and with `Error::InvalidFieldElement` for field elements that are not
+/// reduced.
 pub(crate) trait Replayable<T> {
-    fn replay(&mut self) -> T;
+    fn replay(&mut self) -> Result<T>;
 
-    fn replay_many(&mut self, count: usize) -> Vec<T> {
+    fn replay_many(&mut self, count: usize) -> Result<Vec<T>> {
         (0..count).map(|_| self.replay()).collect()
     }
 }
 
+type Result<T> = std::result::Result<T, Error>;
+
 // TODO: Limit to crate
 #[derive(PartialEq, Eq, Clone, Default)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub(crate) struct PublicCoin {
+pub(crate) struct PublicCoin<H = MaskedKeccak> {
     pub(crate) digest: [u8; 32],
     counter:           u64,
+    hasher:            PhantomData<H>,
 }
 
 #[derive(PartialEq, Eq, Clone, Default)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub(crate) struct ProverChannel {
-    pub(crate) coin:  PublicCoin,
+pub(crate) struct ProverChannel<H = MaskedKeccak> {
+    pub(crate) coin:  PublicCoin<H>,
     pub(crate) proof: Vec<u8>,
 }
 
 #[derive(PartialEq, Eq, Clone, Default)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub(crate) struct VerifierChannel {
-    pub(crate) coin:  PublicCoin,
+pub(crate) struct VerifierChannel<H = MaskedKeccak> {
+    pub(crate) coin:  PublicCoin<H>,
     pub(crate) proof: Vec<u8>,
     proof_index:      usize,
 }
 
-impl PublicCoin {
+impl<H: Hasher> PublicCoin<H> {
     pub(crate) fn seed(&mut self, seed: &[u8]) {
-        let mut keccak = Keccak::v256();
-        keccak.update(seed);
-        keccak.finalize(&mut self.digest);
+        let mut hasher = H::default();
+        hasher.update(seed);
+        self.digest = hasher.finalize();
         self.counter = 0;
     }
 }
 
-impl From<Vec<u8>> for ProverChannel {
+impl<H: Hasher> From<Vec<u8>> for ProverChannel<H> {
     fn from(proof_data: Vec<u8>) -> Self {
         Self {
             coin:  PublicCoin::default(),
@@ -66,13 +71,13 @@ impl From<Vec<u8>> for ProverChannel {
 }
 
 #[cfg(feature = "prover")]
-impl ProverChannel {
+impl<H: Hasher> ProverChannel<H> {
     pub(crate) fn initialize(&mut self, seed: &[u8]) {
         self.coin.seed(seed);
     }
 }
 
-impl VerifierChannel {
+impl<H: Hasher> VerifierChannel<H> {
     pub(crate) fn new(proof: Vec<u8>) -> Self {
         Self {
             coin: PublicCoin::default(),
@@ -89,6 +94,15 @@ impl VerifierChannel {
         self.proof_index == self.proof.len()
     }
 
+    /// The number of proof bytes replayed so far.
+    pub(crate) fn position(&self) -> usize {
+        self.proof_index
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.proof.len().saturating_sub(self.proof_index)
+    }
+
     pub(crate) fn get_coefficients(&mut self, n: usize) -> Vec<FieldElement> {
         (0..n).map(|_| self.get_random()).collect()
     }
@@ -96,34 +110,49 @@ impl VerifierChannel {
     // This differs from Replayable::<FieldElement>::replay_many in that it only
     // updates the public coin once, with the contents of the entire layer, instead
     // of onces for each FieldElement in the layer.
-    pub(crate) fn replay_fri_layer(&mut self, size: usize) -> Vec<FieldElement> {
-        let start_index = self.proof_index;
-        self.proof_index += 32 * size;
-        let layer_contents = &self.proof[start_index..self.proof_index];
-
+    pub(crate) fn replay_fri_layer(&mut self, size: usize) -> Result<Vec<FieldElement>> {
+        let range = self.advance(32 * size)?;
+        let layer_contents = &self.proof[range];
+        let layer = layer_contents
+            .chunks_exact(32)
+            .map(|bytes| field_element(bytes.try_into().unwrap()))
+            .collect::<Result<Vec<_>>>()?;
         self.coin.write(layer_contents);
+        Ok(layer)
+    }
 
-        layer_contents
-            .chunks_exact(32)
-            .map(|bytes| {
-                FieldElement::from_montgomery(U256::from_bytes_be(bytes.try_into().unwrap()))
-            })
-            .collect()
+    // Advances past the next `size` bytes of the proof and returns their
+    // range, without writing them to the coin.
+    fn advance(&mut self, size: usize) -> Result<Range<usize>> {
+        if self.remaining() < size {
+            return Err(Error::ProofTooShort);
+        }
+        let from = self.proof_index;
+        self.proof_index += size;
+        Ok(from..self.proof_index)
     }
 
-    fn read_32_bytes(&mut self) -> [u8; 32] {
+    fn read_32_bytes(&mut self) -> Result<[u8; 32]> {
         let mut holder = [0_u8; 32];
-        let from = self.proof_index;
-        let to = from + 32;
-        self.proof_index = to;
         // OPT: Use arrayref crate or similar to avoid copy
-        holder.copy_from_slice(&self.proof[from..to]);
+        let range = self.advance(32)?;
+        holder.copy_from_slice(&self.proof[range]);
         self.coin.write(&holder[..]);
-        holder
+        Ok(holder)
+    }
+}
+
+// Field elements are written in Montgomery form, which needs to be reduced.
+fn field_element(bytes: &[u8; 32]) -> Result<FieldElement> {
+    let montgomery = U256::from_bytes_be(bytes);
+    if montgomery < FieldElement::MODULUS {
+        Ok(FieldElement::from_montgomery(montgomery))
+    } else {
+        Err(Error::InvalidFieldElement)
     }
 }
 
-impl RandomGenerator<proof_of_work::ChallengeSeed> for PublicCoin {
+impl<H: Hasher> RandomGenerator<proof_of_work::ChallengeSeed> for PublicCoin<H> {
     fn get_random(&mut self) -> proof_of_work::ChallengeSeed {
         self.counter += 1;
         // FIX: Use get_random::<[u8;32]>();
@@ -131,26 +160,24 @@ impl RandomGenerator<proof_of_work::ChallengeSeed> for PublicCoin {
     }
 }
 
-impl Writable<proof_of_work::Response> for ProverChannel {
+impl<H: Hasher> Writable<proof_of_work::Response> for ProverChannel<H> {
     fn write(&mut self, data: proof_of_work::Response) {
         self.write(&data.nonce().to_be_bytes()[..]);
     }
 }
 
-impl Replayable<proof_of_work::Response> for VerifierChannel {
-    fn replay(&mut self) -> proof_of_work::Response {
+impl<H: Hasher> Replayable<proof_of_work::Response> for VerifierChannel<H> {
+    fn replay(&mut self) -> Result<proof_of_work::Response> {
         let mut holder = [0_u8; 8];
-        let from = self.proof_index;
-        let to = from + 8;
-        self.proof_index = to;
-        holder.copy_from_slice(&self.proof[from..to]);
+        let range = self.advance(8)?;
+        holder.copy_from_slice(&self.proof[range]);
         self.coin.write(&holder[..]);
         let nonce = u64::from_be_bytes(holder);
-        proof_of_work::Response::from_nonce(nonce)
+        Ok(proof_of_work::Response::from_nonce(nonce))
     }
 }
 
-impl RandomGenerator<FieldElement> for PublicCoin {
+impl<H: Hasher> RandomGenerator<FieldElement> for PublicCoin<H> {
     fn get_random(&mut self) -> FieldElement {
         const MASK: U256 =
             u256h!("0FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF");
@@ -164,52 +191,58 @@ impl RandomGenerator<FieldElement> for PublicCoin {
     }
 }
 
-impl RandomGenerator<U256> for PublicCoin {
+/// Extension elements are drawn one base field coefficient at a time.
+impl<H: Hasher, const D: usize> RandomGenerator<Ext<FieldElement, D>> for PublicCoin<H>
+where
+    FieldElement: Extendable<D>,
+{
+    fn get_random(&mut self) -> Ext<FieldElement, D> {
+        Ext::new(std::array::from_fn(|_| self.get_random()))
+    }
+}
+
+impl<H: Hasher> RandomGenerator<U256> for PublicCoin<H> {
     fn get_random(&mut self) -> U256 {
         U256::from_bytes_be(&self.get_random())
     }
 }
 
-impl RandomGenerator<[u8; 32]> for PublicCoin {
+impl<H: Hasher> RandomGenerator<[u8; 32]> for PublicCoin<H> {
     fn get_random(&mut self) -> [u8; 32] {
-        let mut result = [0; 32];
-        let mut keccak = Keccak::v256();
-        keccak.update(&self.digest);
-        keccak.update(&[0_u8; 24]);
-        keccak.update(&self.counter.to_be_bytes());
-        keccak.finalize(&mut result);
+        let mut hasher = H::default();
+        hasher.update(&self.digest);
+        hasher.update(&[0_u8; 24]);
+        hasher.update(&self.counter.to_be_bytes());
         self.counter += 1;
-        result
+        hasher.finalize()
     }
 }
 
-impl<T> RandomGenerator<T> for ProverChannel
+impl<T, H: Hasher> RandomGenerator<T> for ProverChannel<H>
 where
-    PublicCoin: RandomGenerator<T>,
+    PublicCoin<H>: RandomGenerator<T>,
 {
     fn get_random(&mut self) -> T {
         self.coin.get_random()
     }
 }
 
-impl<T> RandomGenerator<T> for VerifierChannel
+impl<T, H: Hasher> RandomGenerator<T> for VerifierChannel<H>
 where
-    PublicCoin: RandomGenerator<T>,
+    PublicCoin<H>: RandomGenerator<T>,
 {
     fn get_random(&mut self) -> T {
         self.coin.get_random()
     }
 }
 
-impl Writable<&[u8]> for PublicCoin {
+impl<H: Hasher> Writable<&[u8]> for PublicCoin<H> {
     fn write(&mut self, data: &[u8]) {
-        let mut result: [u8; 32] = [0; 32];
-        let mut keccak = Keccak::v256();
-        keccak.update(&self.digest);
-        keccak.update(data);
-        keccak.finalize(&mut result);
+        let mut hasher = H::default();
+        hasher.update(&self.digest);
+        hasher.update(data);
         // FIX: Hash counter into digest.
-        self.digest = result;
+        self.digest = hasher.finalize();
         self.counter = 0;
     }
 }
@@ -218,27 +251,27 @@ impl Writable<&[u8]> for PublicCoin {
 // the proof with the same encoding for the writing and the non writing. However
 // by writing directly to the coin, other writes for the channel could separate
 // encoding from random perturbation.
-impl Writable<&[u8]> for ProverChannel {
+impl<H: Hasher> Writable<&[u8]> for ProverChannel<H> {
     fn write(&mut self, data: &[u8]) {
         self.proof.extend_from_slice(data);
         self.coin.write(data);
     }
 }
 
-impl Writable<&Hash> for ProverChannel {
+impl<H: Hasher> Writable<&Hash> for ProverChannel<H> {
     fn write(&mut self, data: &Hash) {
         self.write(data.as_bytes());
     }
 }
 
-impl Writable<&zkp_merkle_tree::Commitment> for ProverChannel {
+impl<H: Hasher> Writable<&zkp_merkle_tree::Commitment> for ProverChannel<H> {
     fn write(&mut self, data: &zkp_merkle_tree::Commitment) {
         self.write(data.hash())
     }
 }
 
-impl Writable<&zkp_merkle_tree::Proof> for ProverChannel {
-    fn write(&mut self, data: &zkp_merkle_tree::Proof) {
+impl<H: Hasher> Writable<&zkp_merkle_tree::Proof<H>> for ProverChannel<H> {
+    fn write(&mut self, data: &zkp_merkle_tree::Proof<H>) {
         for hash in data.hashes() {
             self.write(hash)
         }
@@ -246,7 +279,7 @@ impl Writable<&zkp_merkle_tree::Proof> for ProverChannel {
 }
 
 // OPT - Remove allocation of vectors
-impl Writable<&[FieldElement]> for ProverChannel {
+impl<H: Hasher> Writable<&[FieldElement]> for ProverChannel<H> {
     fn write(&mut self, data: &[FieldElement]) {
         let mut container = Vec::with_capacity(32 * data.len());
         for element in data {
@@ -258,15 +291,27 @@ impl Writable<&[FieldElement]> for ProverChannel {
     }
 }
 
-impl Writable<&FieldElement> for ProverChannel {
+impl<H: Hasher> Writable<&FieldElement> for ProverChannel<H> {
     fn write(&mut self, data: &FieldElement) {
         self.write(&data.as_montgomery().to_bytes_be()[..]);
     }
 }
 
+impl<H: Hasher, const D: usize> Writable<&Ext<FieldElement, D>> for ProverChannel<H>
+where
+    FieldElement: Extendable<D>,
+{
+    fn write(&mut self, data: &Ext<FieldElement, D>) {
+        // Coefficients are written separately to match `replay`
+        for coefficient in data.coefficients() {
+            self.write(coefficient);
+        }
+    }
+}
+
 // Note -- This method of writing is distinct from the field element, and is
 // used in the decommitment when groups are decommited from the rows
-impl Writable<Vec<U256>> for ProverChannel {
+impl<H: Hasher> Writable<Vec<U256>> for ProverChannel<H> {
     fn write(&mut self, data: Vec<U256>) {
         for element in data {
             self.write(element)
@@ -274,30 +319,40 @@ impl Writable<Vec<U256>> for ProverChannel {
     }
 }
 
-impl Writable<U256> for ProverChannel {
+impl<H: Hasher> Writable<U256> for ProverChannel<H> {
     fn write(&mut self, data: U256) {
         self.write(&data.to_bytes_be()[..]);
     }
 }
 
-impl Replayable<Hash> for VerifierChannel {
-    fn replay(&mut self) -> Hash {
-        let hash: [u8; 32] = self.read_32_bytes();
-        Hash::new(hash)
+impl<H: Hasher> Replayable<Hash> for VerifierChannel<H> {
+    fn replay(&mut self) -> Result<Hash> {
+        let hash: [u8; 32] = self.read_32_bytes()?;
+        Ok(Hash::new(hash))
     }
 }
 
-impl Replayable<U256> for VerifierChannel {
-    fn replay(&mut self) -> U256 {
-        let big_endian_bytes: [u8; 32] = self.read_32_bytes();
-        U256::from_bytes_be(&big_endian_bytes)
+impl<H: Hasher> Replayable<U256> for VerifierChannel<H> {
+    fn replay(&mut self) -> Result<U256> {
+        let big_endian_bytes: [u8; 32] = self.read_32_bytes()?;
+        Ok(U256::from_bytes_be(&big_endian_bytes))
     }
 }
 
-impl Replayable<FieldElement> for VerifierChannel {
-    fn replay(&mut self) -> FieldElement {
-        let montgomery_modulus: U256 = self.replay();
-        FieldElement::from_montgomery(montgomery_modulus)
+impl<H: Hasher> Replayable<FieldElement> for VerifierChannel<H> {
+    fn replay(&mut self) -> Result<FieldElement> {
+        let bytes: [u8; 32] = self.read_32_bytes()?;
+        field_element(&bytes)
+    }
+}
+
+impl<H: Hasher, const D: usize> Replayable<Ext<FieldElement, D>> for VerifierChannel<H>
+where
+    FieldElement: Extendable<D>,
+{
+    fn replay(&mut self) -> Result<Ext<FieldElement, D>> {
+        let coefficients: Vec<FieldElement> = self.replay_many(D)?;
+        Ok(Ext::new(std::array::from_fn(|i| coefficients[i].clone())))
     }
 }
 
@@ -310,7 +365,7 @@ mod tests {
     // the nature of the channel
     #[test]
     fn test_channel_get_random() {
-        let mut source = ProverChannel::default();
+        let mut source: ProverChannel = ProverChannel::default();
         source.initialize(hex!("0123456789abcded").to_vec().as_slice());
         let rand_bytes: [u8; 32] = source.get_random();
         assert_eq!(
@@ -335,7 +390,7 @@ mod tests {
     // the nature of the channel
     #[test]
     fn test_channel_write() {
-        let mut source = ProverChannel::default();
+        let mut source: ProverChannel = ProverChannel::default();
         source.initialize(&hex!("0123456789abcded"));
         let rand_bytes: [u8; 32] = source.get_random();
         source.write(&rand_bytes[..]);
@@ -372,9 +427,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extension_channel_test() {
+        let mut source: ProverChannel = ProverChannel::default();
+        source.initialize(&hex!("0123456789abcded"));
+        let drawn: Ext<FieldElement, 2> = source.get_random();
+        source.write(&drawn);
+
+        let mut verifier: VerifierChannel = VerifierChannel::new(source.proof.clone());
+        verifier.initialize(&hex!("0123456789abcded"));
+        let expected: Ext<FieldElement, 2> = verifier.get_random();
+        assert_eq!(expected, drawn);
+        let replayed: Ext<FieldElement, 2> = verifier.replay().unwrap();
+        assert_eq!(replayed, drawn);
+        assert_eq!(verifier.coin.digest, source.coin.digest);
+    }
+
     #[test]
     fn verifier_channel_test() {
-        let mut source = ProverChannel::default();
+        let mut source: ProverChannel = ProverChannel::default();
         source.initialize(&hex!("0123456789abcded"));
         let rand_bytes: [u8; 32] = source.get_random();
         source.write(&rand_bytes[..]);
@@ -399,40 +470,61 @@ mod tests {
         ];
         source.write(written_big_int_vec.clone());
 
-        let mut verifier = VerifierChannel::new(source.proof.clone());
+        let mut verifier: VerifierChannel = VerifierChannel::new(source.proof.clone());
         verifier.initialize(&hex!("0123456789abcded"));
-        let bytes_test: [u8; 32] = verifier.read_32_bytes();
+        let bytes_test: [u8; 32] = verifier.read_32_bytes().unwrap();
         assert_eq!(bytes_test, rand_bytes);
         assert_eq!(
             verifier.coin.digest,
             hex!("3174a00d031bc8deff799e24a78ee347b303295a6cb61986a49873d9b6f13a0d")
         );
-        let pow_response_test: proof_of_work::Response = verifier.replay();
+        let pow_response_test: proof_of_work::Response = verifier.replay().unwrap();
         assert_eq!(pow_response_test.nonce(), 11_028_357_238_u64);
         assert_eq!(
             verifier.coin.digest,
             hex!("21571e2a323daa1e6f2adda87ce912608e1325492d868e8fe41626633d6acb93")
         );
-        let field_element_test: FieldElement = verifier.replay();
+        let field_element_test: FieldElement = verifier.replay().unwrap();
         assert_eq!(field_element_test, written_field_element);
         assert_eq!(
             verifier.coin.digest,
             hex!("34a12938f047c34da72b5949434950fa2b24220270fd26e6f64b6eb5e86c6626")
         );
-        let field_element_vec_test: Vec<FieldElement> = verifier.replay_fri_layer(2);
+        let field_element_vec_test: Vec<FieldElement> = verifier.replay_fri_layer(2).unwrap();
         assert_eq!(field_element_vec_test, written_field_element_vec);
         assert_eq!(
             verifier.coin.digest,
             hex!("586b2c12cd444cfe29932fcb167fc0be2e575a8d68e4a41d35de8602b0aea929")
         );
-        let bit_int_vec_test: Vec<U256> = verifier.replay_many(2);
+        let bit_int_vec_test: Vec<U256> = verifier.replay_many(2).unwrap();
         assert_eq!(bit_int_vec_test, written_big_int_vec);
         assert_eq!(verifier.coin.digest, source.coin.digest);
     }
 
+    #[test]
+    fn verifier_channel_invalid() {
+        let mut source: ProverChannel = ProverChannel::default();
+        source.write(&FieldElement::MODULUS.to_bytes_be()[..]);
+
+        let mut verifier: VerifierChannel = VerifierChannel::new(source.proof.clone());
+        let replayed: Result<FieldElement> = verifier.replay();
+        assert_eq!(replayed, Err(Error::InvalidFieldElement));
+        let mut verifier: VerifierChannel = VerifierChannel::new(source.proof.clone());
+        assert_eq!(
+            verifier.replay_fri_layer(1),
+            Err(Error::InvalidFieldElement)
+        );
+        let mut verifier: VerifierChannel = VerifierChannel::new(source.proof.clone());
+        assert_eq!(verifier.replay_fri_layer(2), Err(Error::ProofTooShort));
+        let replayed: Result<proof_of_work::Response> = verifier.replay();
+        assert!(replayed.is_ok());
+        let replayed: Result<Hash> = verifier.replay();
+        assert_eq!(replayed, Err(Error::ProofTooShort));
+    }
+
     #[test]
     fn test_challenge_seed_from_channel() {
-        let mut rand_source = ProverChannel::default();
+        let mut rand_source: ProverChannel = ProverChannel::default();
         rand_source.initialize(&hex!("0123456789abcded"));
         // Verify that reading challenges does not depend on public coin counter.
         // FIX: Make it depend on public coin counter.
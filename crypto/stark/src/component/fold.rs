@@ -1,3 +1,9 @@
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
 use super::{Component, Mapped, PolynomialWriter};
 use crate::RationalExpression;
 use zkp_primefield::fft::permute_index;
@@ -58,6 +64,15 @@ where
         self.element.claim(witness)
     }
 
+    fn public_cells(&self, claim: &Self::Claim) -> Vec<Option<(usize, usize)>> {
+        self.element
+            .public_cells(claim)
+            .into_iter()
+            .map(|cell| cell.map(|(polynomial, location)| self.map_up(location, polynomial)))
+            .map(|cell| cell.map(|(row, column)| (column, row)))
+            .collect()
+    }
+
     fn constraints(&self, claim: &Self::Claim) -> Vec<RationalExpression> {
         use RationalExpression::*;
         let reduction = 1 << self.folds;
@@ -1,4 +1,10 @@
-use super::{Component, Mapped, PolynomialWriter};
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use super::{padded_cells, Component, Mapped, PolynomialWriter};
 use crate::RationalExpression;
 
 #[derive(Clone, PartialEq, Eq)]
@@ -53,6 +59,18 @@ where
         (self.left.claim(&witness.0), self.right.claim(&witness.1))
     }
 
+    fn public_cells(&self, claim: &Self::Claim) -> Vec<Option<(usize, usize)>> {
+        let left_polynomials = self.left().num_polynomials();
+        let mut cells = padded_cells(self.left(), &claim.0);
+        cells.extend(
+            self.right()
+                .public_cells(&claim.1)
+                .into_iter()
+                .map(|cell| cell.map(|(i, j)| (i + left_polynomials, j))),
+        );
+        cells
+    }
+
     fn constraints(&self, claim: &Self::Claim) -> Vec<RationalExpression> {
         use RationalExpression::*;
         let left_polynomials = self.left().num_polynomials();
@@ -0,0 +1,256 @@
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use super::{Component, PolynomialWriter};
+use crate::{RationalExpression, Stage, TraceTable};
+use std::collections::BTreeMap;
+use zkp_primefield::{FieldElement, One};
+
+/// Proves that every value of one polynomial of an element appears in
+/// another, Plookup style
+///
+/// The lookups $f$ and the table $t$ are polynomials of the element. The
+/// prover commits to the values of $f$ and $t$ sorted in the order of the
+/// table, interleaved over two columns $h_1, h_2$ such that the sorted vector
+/// is $h_{1,0}, h_{2,0}, h_{1,1}, h_{2,1}, \dots$. Then challenges $\beta$ and
+/// $\gamma$ are drawn and a running product column $z$ is added with
+///
+/// $$
+/// z_{i+1} \cdot (\gamma' + h_{1,i} + \beta h_{2,i})
+///   (\gamma' + h_{2,i} + \beta h_{1,i+1})
+/// = z_i \cdot (1 + \beta)(\gamma + f_i)(\gamma' + t_i + \beta t_{i+1})
+/// $$
+///
+/// where $\gamma' = \gamma (1 + \beta)$. Like the table, the sorted vector
+/// wraps around from the last row to the first.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Lookup<Element>
+where
+    Element: Component,
+{
+    element: Element,
+    lookups: usize,
+    table:   usize,
+}
+
+impl<Element> Lookup<Element>
+where
+    Element: Component,
+{
+    pub fn new(element: Element, lookups: usize, table: usize) -> Self {
+        assert!(lookups < element.num_polynomials());
+        assert!(table < element.num_polynomials());
+        Self {
+            element,
+            lookups,
+            table,
+        }
+    }
+
+    pub fn element(&self) -> &Element {
+        &self.element
+    }
+
+    // Index of the first of the two challenges
+    fn challenge(&self) -> usize {
+        self.element
+            .stages()
+            .iter()
+            .map(|stage| stage.challenges)
+            .sum()
+    }
+
+    // The lookups and the table in the order of the table. Lookups that are not
+    // in the table go at the end.
+    fn sorted(&self, previous: &TraceTable) -> Vec<FieldElement> {
+        let size = self.polynomial_size();
+        // Field elements are not ordered, so key on their representation.
+        let mut positions = BTreeMap::new();
+        for row in 0..size {
+            let _ = positions
+                .entry(previous[(row, self.table)].as_montgomery().clone())
+                .or_insert(row);
+        }
+        let mut counts = vec![0_usize; size];
+        let mut missing = Vec::new();
+        for row in 0..size {
+            let value = &previous[(row, self.lookups)];
+            match positions.get(value.as_montgomery()) {
+                Some(&position) => counts[position] += 1,
+                None => missing.push(value.clone()),
+            }
+        }
+        let mut sorted = Vec::with_capacity(2 * size);
+        for (row, &count) in counts.iter().enumerate() {
+            let value = &previous[(row, self.table)];
+            sorted.extend(std::iter::repeat(value).take(count + 1).cloned());
+        }
+        sorted.extend(missing);
+        sorted
+    }
+}
+
+impl<Element> Component for Lookup<Element>
+where
+    Element: Component,
+{
+    type Claim = Element::Claim;
+    type Witness = Element::Witness;
+
+    fn num_polynomials(&self) -> usize {
+        self.element.num_polynomials() + 3
+    }
+
+    fn polynomial_size(&self) -> usize {
+        self.element.polynomial_size()
+    }
+
+    fn claim(&self, witness: &Self::Witness) -> Self::Claim {
+        self.element.claim(witness)
+    }
+
+    fn public_cells(&self, claim: &Self::Claim) -> Vec<Option<(usize, usize)>> {
+        self.element.public_cells(claim)
+    }
+
+    fn constraints(&self, claim: &Self::Claim) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+
+        let size = self.polynomial_size();
+        let h1 = self.element.num_polynomials();
+        let (h2, z) = (h1 + 1, h1 + 2);
+        let beta = || Challenge(self.challenge());
+        let gamma = || Challenge(self.challenge() + 1);
+        let gamma_beta = || gamma() * (beta() + 1);
+
+        let numerator = (beta() + 1)
+            * (gamma() + Trace(self.lookups, 0))
+            * (gamma_beta() + Trace(self.table, 0) + beta() * Trace(self.table, 1));
+        let denominator = (gamma_beta() + Trace(h1, 0) + beta() * Trace(h2, 0))
+            * (gamma_beta() + Trace(h2, 0) + beta() * Trace(h1, 1));
+
+        let mut constraints = self.element.constraints(claim);
+        constraints.push((Trace(z, 1) * denominator - Trace(z, 0) * numerator) / (X.pow(size) - 1));
+        constraints.push((Trace(z, 0) - 1) / (X - 1));
+        constraints
+    }
+
+    fn trace<P: PolynomialWriter>(&self, trace: &mut P, witness: &Self::Witness) {
+        self.element.trace(trace, witness)
+    }
+
+    fn stages(&self) -> Vec<Stage> {
+        let mut stages = self.element.stages();
+        stages.push(Stage {
+            columns:    2,
+            challenges: 2,
+        });
+        stages.push(Stage {
+            columns:    1,
+            challenges: 0,
+        });
+        stages
+    }
+
+    fn stage_trace<P: PolynomialWriter>(
+        &self,
+        trace: &mut P,
+        stage: usize,
+        previous: &TraceTable,
+        challenges: &[FieldElement],
+        witness: &Self::Witness,
+    ) {
+        let size = self.polynomial_size();
+        let element_stages = self.element.stages().len();
+        if stage < element_stages {
+            self.element
+                .stage_trace(trace, stage, previous, challenges, witness)
+        } else if stage == element_stages {
+            for (row, pair) in self.sorted(previous).chunks(2).enumerate() {
+                trace.write(0, row, pair[0].clone());
+                trace.write(1, row, pair[1].clone());
+            }
+        } else {
+            let h1 = self.element.num_polynomials();
+            let h2 = h1 + 1;
+            let beta = &challenges[self.challenge()];
+            let gamma = &challenges[self.challenge() + 1];
+            let gamma_beta = gamma * (beta + FieldElement::one());
+            let mut product = FieldElement::one();
+            for row in 0..size {
+                let next = (row + 1) % size;
+                let numerator = (beta + FieldElement::one())
+                    * (gamma + &previous[(row, self.lookups)])
+                    * (&gamma_beta
+                        + &previous[(row, self.table)]
+                        + beta * &previous[(next, self.table)]);
+                let denominator =
+                    (&gamma_beta + &previous[(row, h1)] + beta * &previous[(row, h2)])
+                        * (&gamma_beta + &previous[(row, h2)] + beta * &previous[(next, h1)]);
+                trace.write(0, row, product.clone());
+                product *= numerator / denominator;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::permutation::tests::Pair, *};
+    use proptest::prelude::*;
+
+    fn witness(size: usize, indices: &[usize]) -> (Vec<FieldElement>, Vec<FieldElement>) {
+        let table = (0..size)
+            .map(|i| FieldElement::from(i * i))
+            .collect::<Vec<_>>();
+        let lookups = indices[..size]
+            .iter()
+            .map(|index| table[index % size].clone())
+            .collect();
+        (lookups, table)
+    }
+
+    #[test]
+    fn test_check() {
+        proptest!(|(
+            log_size in 1_usize..8,
+            indices in proptest::collection::vec(any::<usize>(), 128),
+        )| {
+            let size = 1 << log_size;
+            let component = Lookup::new(Pair(size), 0, 1);
+            prop_assert_eq!(component.check(&witness(size, &indices)), Ok(()));
+        });
+    }
+
+    #[test]
+    fn test_not_in_table() {
+        proptest!(|(
+            log_size in 1_usize..8,
+            indices in proptest::collection::vec(any::<usize>(), 128),
+            index: usize,
+        )| {
+            let size = 1 << log_size;
+            let mut witness = witness(size, &indices);
+            witness.0[index % size] = FieldElement::from(2);
+            let component = Lookup::new(Pair(size), 0, 1);
+            prop_assert!(component.check(&witness).is_err());
+            prop_assert!(!component.check_report(&witness).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_proof_verify() {
+        let config = ProptestConfig::with_cases(2);
+        proptest!(config, |(indices in proptest::collection::vec(any::<usize>(), 16))| {
+            let witness = witness(16, &indices);
+            let component = Lookup::new(Pair(16), 0, 1);
+            let proof = component.prove(&witness).unwrap();
+            let result = component.verify(&(), &proof);
+            prop_assert_eq!(result, Ok(()));
+        });
+    }
+}
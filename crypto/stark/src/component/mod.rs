@@ -1,24 +1,39 @@
 mod empty;
 mod fold;
 mod horizontal;
+mod lookup;
 mod mapped;
+mod permutation;
+mod poseidon;
 mod test;
 mod vertical;
 
+// Clippy false positive
+#[allow(clippy::useless_attribute)]
+// We want to import an alternative prelude.
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+#[cfg(feature = "std")]
+use crate::constraint_check::{check_constraints, check_constraints_report, ConstraintReport};
 use crate::{
-    constraint_check::check_constraints,
     proof::Proof,
-    prover::prove,
+    prover::prove_staged,
     verifier::{verify, Error as VerifierError},
-    Constraints, ProverError, RationalExpression, TraceTable,
+    Constraints, ProverError, PublicInput, RationalExpression, Stage, TraceTable,
 };
 use log::trace;
+#[cfg(feature = "std")]
+use rand::random;
 use zkp_primefield::{FieldElement, Root};
 
 pub use empty::Empty;
 pub use fold::Fold;
 pub use horizontal::Horizontal;
+pub use lookup::Lookup;
 pub use mapped::Mapped;
+pub use permutation::Permutation;
+pub use poseidon::Poseidon;
 pub use test::Test;
 pub use vertical::Vertical;
 
@@ -50,11 +65,21 @@ impl PolynomialWriter for TraceTable {
 }
 
 pub trait Component {
-    type Claim;
+    /// The public input. It is bound to the proof through the channel seed.
+    type Claim: PublicInput;
     type Witness;
 
     fn claim(&self, witness: &Self::Witness) -> Self::Claim;
 
+    /// Locations `(polynomial, location)` that hold the public values of the
+    /// claim, by index of the value.
+    ///
+    /// Each location gets a boundary constraint labelled with the name of the
+    /// value. See `Constraints::bind_public_input`.
+    fn public_cells(&self, _claim: &Self::Claim) -> Vec<Option<(usize, usize)>> {
+        Vec::new()
+    }
+
     /// Number of polynomials to commit to.
     fn num_polynomials(&self) -> usize;
 
@@ -66,18 +91,44 @@ pub trait Component {
 
     // TODO: add claim_polynomials function here.
 
+    /// Write the polynomials of the first stage.
     fn trace<P: PolynomialWriter>(&self, trace: &mut P, witness: &Self::Witness);
 
+    /// The stages in which the polynomials are committed.
+    ///
+    /// By default all polynomials are in a single stage. Note that `Fold`,
+    /// `Horizontal` and `Vertical` only support elements with a single stage.
+    fn stages(&self) -> Vec<Stage> {
+        vec![Stage {
+            columns:    self.num_polynomials(),
+            challenges: 0,
+        }]
+    }
+
+    /// Write the polynomials of a later stage.
+    ///
+    /// `previous` holds the polynomials of the earlier stages and
+    /// `challenges` the challenges drawn so far.
+    fn stage_trace<P: PolynomialWriter>(
+        &self,
+        _trace: &mut P,
+        _stage: usize,
+        _previous: &TraceTable,
+        _challenges: &[FieldElement],
+        _witness: &Self::Witness,
+    ) {
+    }
+
     fn trace_generator(&self) -> RationalExpression {
         FieldElement::root(self.polynomial_size())
             .expect("num_polynomials not power of 2.")
             .into()
     }
 
-    /// Construct a trace table
+    /// Construct a trace table of the first stage
     fn trace_table(&self, witness: &Self::Witness) -> TraceTable {
         trace!("BEGIN Component Trace");
-        let polynomials = self.num_polynomials();
+        let polynomials = self.stages()[0].columns;
         let size = self.polynomial_size();
         let mut trace_table = TraceTable::new(size, polynomials);
         self.trace(&mut trace_table, witness);
@@ -85,38 +136,96 @@ pub trait Component {
         trace_table
     }
 
-    fn prove(&self, witness: &Self::Witness) -> Result<Proof, ProverError> {
-        let polynomials = self.num_polynomials();
+    /// Construct a trace table of a later stage
+    fn stage_trace_table(
+        &self,
+        stage: usize,
+        previous: &TraceTable,
+        challenges: &[FieldElement],
+        witness: &Self::Witness,
+    ) -> TraceTable {
+        let polynomials = self.stages()[stage].columns;
         let size = self.polynomial_size();
+        let mut trace_table = TraceTable::new(size, polynomials);
+        self.stage_trace(&mut trace_table, stage, previous, challenges, witness);
+        trace_table
+    }
+
+    fn prove(&self, witness: &Self::Witness) -> Result<Proof, ProverError> {
         let claim = self.claim(witness);
-        let channel_seed = Vec::new();
-        let expressions = self.constraints(&claim);
+        let constraints = constraint_system(self, &claim);
         let trace = self.trace_table(witness);
-        let constraints =
-            Constraints::from_expressions((size, polynomials), channel_seed, expressions).unwrap();
-        prove(&constraints, &trace)
+        let mut stage = 0;
+        prove_staged(&constraints, &trace, |previous, challenges| {
+            stage += 1;
+            self.stage_trace_table(stage, previous, challenges, witness)
+        })
     }
 
     fn verify(&self, claim: &Self::Claim, proof: &Proof) -> Result<(), VerifierError> {
-        let polynomials = self.num_polynomials();
-        let size = self.polynomial_size();
-        let channel_seed = Vec::new();
-        let expressions = self.constraints(claim);
-        let constraints =
-            Constraints::from_expressions((size, polynomials), channel_seed, expressions).unwrap();
+        let constraints = constraint_system(self, claim);
         verify(&constraints, proof)
     }
 
+    /// Check the constraints on the trace of all stages, using random
+    /// challenges.
+    #[cfg(feature = "std")]
     fn check(&self, witness: &Self::Witness) -> Result<(), (usize, usize)> {
-        let polynomials = self.num_polynomials();
-        let size = self.polynomial_size();
-        let claim = self.claim(witness);
-        let channel_seed = Vec::new();
-        let expressions = self.constraints(&claim);
-        // TODO: Error handling
-        let constraints =
-            Constraints::from_expressions((size, polynomials), channel_seed, expressions).unwrap();
-        let trace = self.trace_table(witness);
+        let (constraints, trace) = challenged_system(self, witness);
         check_constraints(&constraints, &trace)
     }
+
+    /// Same as `check`, but reports every failing row and constraint.
+    #[cfg(feature = "std")]
+    fn check_report(&self, witness: &Self::Witness) -> ConstraintReport {
+        let (constraints, trace) = challenged_system(self, witness);
+        check_constraints_report(&constraints, &trace)
+    }
+}
+
+// The constraints and the trace of all stages, with random challenges
+// substituted.
+#[cfg(feature = "std")]
+fn challenged_system<C: Component + ?Sized>(
+    component: &C,
+    witness: &C::Witness,
+) -> (Constraints, TraceTable) {
+    let claim = component.claim(witness);
+    let mut constraints = constraint_system(component, &claim);
+    let mut trace = component.trace_table(witness);
+    let mut challenges = Vec::new();
+    for (index, stage) in component.stages().iter().enumerate() {
+        if index > 0 {
+            let stage_trace = component.stage_trace_table(index, &trace, &challenges, witness);
+            trace = trace.concat(&stage_trace);
+        }
+        challenges.extend((0..stage.challenges).map(|_| random::<FieldElement>()));
+    }
+    constraints.substitute_challenges(&challenges);
+    (constraints, trace)
+}
+
+fn constraint_system<C: Component + ?Sized>(component: &C, claim: &C::Claim) -> Constraints {
+    let polynomials = component.num_polynomials();
+    let size = component.polynomial_size();
+    let channel_seed = Vec::new();
+    let expressions = component.constraints(claim);
+    // TODO: Error handling
+    let mut constraints =
+        Constraints::from_expressions((size, polynomials), channel_seed, expressions).unwrap();
+    constraints
+        .bind_public_input(claim, &component.public_cells(claim))
+        .unwrap();
+    let stages = component.stages();
+    if constraints.stages() != stages.as_slice() {
+        constraints.set_stages(stages).unwrap();
+    }
+    constraints
+}
+
+/// The public cells of a sub-component, one for each of its public values.
+fn padded_cells<C: Component>(component: &C, claim: &C::Claim) -> Vec<Option<(usize, usize)>> {
+    let mut cells = component.public_cells(claim);
+    cells.resize(claim.public_values().len(), None);
+    cells
 }
@@ -0,0 +1,226 @@
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use super::{Component, PolynomialWriter};
+use crate::{RationalExpression, Stage, TraceTable};
+use zkp_primefield::{FieldElement, One};
+
+/// Proves that two polynomials of an element are permutations of each other
+///
+/// After the polynomials of the element are committed, a challenge $\gamma$
+/// is drawn and a running product column $z$ is added in a new stage:
+///
+/// $$
+/// z_0 = 1 \quad\quad z_{i+1} = z_i \cdot \frac{l_i + \gamma}{r_i + \gamma}
+/// $$
+///
+/// The constraint wraps around from the last row to the first, which holds
+/// exactly when the product of all fractions is one.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Permutation<Element>
+where
+    Element: Component,
+{
+    element: Element,
+    left:    usize,
+    right:   usize,
+}
+
+impl<Element> Permutation<Element>
+where
+    Element: Component,
+{
+    pub fn new(element: Element, left: usize, right: usize) -> Self {
+        assert!(left < element.num_polynomials());
+        assert!(right < element.num_polynomials());
+        Self {
+            element,
+            left,
+            right,
+        }
+    }
+
+    pub fn element(&self) -> &Element {
+        &self.element
+    }
+
+    fn challenge(&self) -> usize {
+        self.element
+            .stages()
+            .iter()
+            .map(|stage| stage.challenges)
+            .sum()
+    }
+}
+
+impl<Element> Component for Permutation<Element>
+where
+    Element: Component,
+{
+    type Claim = Element::Claim;
+    type Witness = Element::Witness;
+
+    fn num_polynomials(&self) -> usize {
+        self.element.num_polynomials() + 1
+    }
+
+    fn polynomial_size(&self) -> usize {
+        self.element.polynomial_size()
+    }
+
+    fn claim(&self, witness: &Self::Witness) -> Self::Claim {
+        self.element.claim(witness)
+    }
+
+    fn public_cells(&self, claim: &Self::Claim) -> Vec<Option<(usize, usize)>> {
+        self.element.public_cells(claim)
+    }
+
+    fn constraints(&self, claim: &Self::Claim) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+
+        let size = self.polynomial_size();
+        let z = self.element.num_polynomials();
+        let gamma = || Challenge(self.challenge());
+
+        let mut constraints = self.element.constraints(claim);
+        constraints.push(
+            (Trace(z, 1) * (Trace(self.right, 0) + gamma())
+                - Trace(z, 0) * (Trace(self.left, 0) + gamma()))
+                / (X.pow(size) - 1),
+        );
+        constraints.push((Trace(z, 0) - 1) / (X - 1));
+        constraints
+    }
+
+    fn trace<P: PolynomialWriter>(&self, trace: &mut P, witness: &Self::Witness) {
+        self.element.trace(trace, witness)
+    }
+
+    fn stages(&self) -> Vec<Stage> {
+        let mut stages = self.element.stages();
+        stages.last_mut().unwrap().challenges += 1;
+        stages.push(Stage {
+            columns:    1,
+            challenges: 0,
+        });
+        stages
+    }
+
+    fn stage_trace<P: PolynomialWriter>(
+        &self,
+        trace: &mut P,
+        stage: usize,
+        previous: &TraceTable,
+        challenges: &[FieldElement],
+        witness: &Self::Witness,
+    ) {
+        if stage < self.element.stages().len() {
+            return self
+                .element
+                .stage_trace(trace, stage, previous, challenges, witness);
+        }
+        let gamma = &challenges[self.challenge()];
+        let mut product = FieldElement::one();
+        for row in 0..self.polynomial_size() {
+            trace.write(0, row, product.clone());
+            product *=
+                (&previous[(row, self.left)] + gamma) / (&previous[(row, self.right)] + gamma);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Two unconstrained polynomials
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub(crate) struct Pair(pub(crate) usize);
+
+    impl Component for Pair {
+        type Claim = ();
+        type Witness = (Vec<FieldElement>, Vec<FieldElement>);
+
+        fn num_polynomials(&self) -> usize {
+            2
+        }
+
+        fn polynomial_size(&self) -> usize {
+            self.0
+        }
+
+        fn claim(&self, _witness: &Self::Witness) -> Self::Claim {}
+
+        fn constraints(&self, _claim: &Self::Claim) -> Vec<RationalExpression> {
+            Vec::new()
+        }
+
+        fn trace<P: PolynomialWriter>(&self, trace: &mut P, witness: &Self::Witness) {
+            for (row, (left, right)) in witness.0.iter().zip(&witness.1).enumerate() {
+                trace.write(0, row, left.clone());
+                trace.write(1, row, right.clone());
+            }
+        }
+    }
+
+    fn shuffled(values: &[FieldElement], rotation: usize) -> Vec<FieldElement> {
+        let mut result = values.to_vec();
+        result.reverse();
+        result.rotate_left(rotation % values.len());
+        result
+    }
+
+    #[test]
+    fn test_check() {
+        proptest!(|(
+            log_size in 1_usize..8,
+            values in proptest::collection::vec(any::<FieldElement>(), 128),
+            rotation: usize,
+        )| {
+            let size = 1 << log_size;
+            let left = values[..size].to_vec();
+            let right = shuffled(&left, rotation);
+            let component = Permutation::new(Pair(size), 0, 1);
+            prop_assert_eq!(component.check(&(left, right)), Ok(()));
+        });
+    }
+
+    #[test]
+    fn test_not_a_permutation() {
+        proptest!(|(
+            log_size in 1_usize..8,
+            values in proptest::collection::vec(any::<FieldElement>(), 128),
+            rotation: usize,
+            index: usize,
+        )| {
+            let size = 1 << log_size;
+            let left = values[..size].to_vec();
+            let mut right = shuffled(&left, rotation);
+            right[index % size] += FieldElement::one();
+            let component = Permutation::new(Pair(size), 0, 1);
+            prop_assert!(component.check(&(left, right)).is_err());
+        });
+    }
+
+    #[test]
+    fn test_proof_verify() {
+        let config = ProptestConfig::with_cases(2);
+        proptest!(config, |(
+            values in proptest::collection::vec(any::<FieldElement>(), 16),
+            rotation: usize,
+        )| {
+            let right = shuffled(&values, rotation);
+            let witness = (values, right);
+            let component = Permutation::new(Pair(16), 0, 1);
+            let proof = component.prove(&witness).unwrap();
+            let result = component.verify(&(), &proof);
+            prop_assert_eq!(result, Ok(()));
+        });
+    }
+}
@@ -0,0 +1,207 @@
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use super::{Component, PolynomialWriter};
+use crate::RationalExpression;
+use zkp_hash::Poseidon as Permutation;
+use zkp_primefield::{FieldElement, One, Pow, Root, Zero};
+
+/// Proves a single application of the Poseidon permutation
+///
+/// The witness is the input state and the claim is the output state. The
+/// trace has a column for each state element and a row for each round, holding
+/// the state before the round. The round constants and the full round
+/// selector are periodic columns. Rows after the last round continue with
+/// partial rounds without constants, so the transition constraint holds on
+/// every row but the last.
+#[derive(Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Poseidon;
+
+impl Poseidon {
+    const SIZE: usize = 128;
+
+    pub fn new() -> Self {
+        Self
+    }
+
+    // Round constants and full round flag for row `row`.
+    fn row_parameters(row: usize) -> ([FieldElement; 3], bool) {
+        if row < Permutation::ROUNDS {
+            (
+                Permutation::round_constants()[row].clone(),
+                Permutation::is_full_round(row),
+            )
+        } else {
+            (
+                [
+                    FieldElement::zero(),
+                    FieldElement::zero(),
+                    FieldElement::zero(),
+                ],
+                false,
+            )
+        }
+    }
+
+    fn periodic(values: &[FieldElement]) -> RationalExpression {
+        RationalExpression::Periodic {
+            values: values.to_vec(),
+            period: values.len(),
+        }
+    }
+}
+
+impl Component for Poseidon {
+    type Claim = [FieldElement; 3];
+    type Witness = [FieldElement; 3];
+
+    fn num_polynomials(&self) -> usize {
+        3
+    }
+
+    fn polynomial_size(&self) -> usize {
+        Self::SIZE
+    }
+
+    fn claim(&self, witness: &Self::Witness) -> Self::Claim {
+        let mut state = witness.clone();
+        Permutation::permute(&mut state);
+        state
+    }
+
+    /// The claim is the state after the last round.
+    fn public_cells(&self, _claim: &Self::Claim) -> Vec<Option<(usize, usize)>> {
+        (0..3)
+            .map(|column| Some((column, Permutation::ROUNDS)))
+            .collect()
+    }
+
+    fn constraints(&self, _claim: &Self::Claim) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+
+        let size = Self::SIZE;
+        let g = Constant(FieldElement::root(size).unwrap());
+        let every_row = || (X - g.pow(size - 1)) / (X.pow(size) - 1);
+
+        let parameters = (0..size).map(Self::row_parameters).collect::<Vec<_>>();
+        let constant = |column: usize| {
+            Self::periodic(
+                &parameters
+                    .iter()
+                    .map(|(constants, _)| constants[column].clone())
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let full = Self::periodic(
+            &parameters
+                .iter()
+                .map(|(_, full)| {
+                    if *full {
+                        FieldElement::one()
+                    } else {
+                        FieldElement::zero()
+                    }
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        // The S-box layer
+        let s0 = (Trace(0, 0) + constant(0)).pow(3);
+        let sbox = |column: usize| {
+            let value = Trace(column, 0) + constant(column);
+            full.clone() * value.pow(3) + (Constant(FieldElement::one()) - full.clone()) * value
+        };
+        let (s1, s2) = (sbox(1), sbox(2));
+
+        // The MDS layer
+        let next = [
+            s0.clone() * 3 + s1.clone() + s2.clone(),
+            s0.clone() - s1.clone() + s2.clone(),
+            s0 + s1 - s2 * 2,
+        ];
+
+        next.iter()
+            .enumerate()
+            .map(|(column, next)| (Trace(column, 1) - next.clone()) * every_row())
+            .collect()
+    }
+
+    fn trace<P: PolynomialWriter>(&self, trace: &mut P, witness: &Self::Witness) {
+        debug_assert_eq!(trace.num_polynomials(), self.num_polynomials());
+        debug_assert_eq!(trace.polynomial_size(), self.polynomial_size());
+
+        let mut state = witness.clone();
+        for row in 0..Self::SIZE {
+            for (column, value) in state.iter().enumerate() {
+                trace.write(column, row, value.clone());
+            }
+            if row < Permutation::ROUNDS {
+                Permutation::round(row, &mut state);
+            } else {
+                state[0] = state[0].pow(3_usize);
+                state = Permutation::mds(&state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::constraint_system, *};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_check() {
+        let config = ProptestConfig::with_cases(20);
+        proptest!(config, |(witness: (FieldElement, FieldElement, FieldElement))| {
+            let witness = [witness.0, witness.1, witness.2];
+            let component = Poseidon::new();
+            prop_assert_eq!(component.check(&witness), Ok(()));
+        });
+    }
+
+    #[test]
+    fn test_proof_verify() {
+        let config = ProptestConfig::with_cases(2);
+        proptest!(config, |(witness: (FieldElement, FieldElement, FieldElement))| {
+            let witness = [witness.0, witness.1, witness.2];
+            let component = Poseidon::new();
+            let claim = component.claim(&witness);
+            let proof = component.prove(&witness).unwrap();
+            let result = component.verify(&claim, &proof);
+            prop_assert_eq!(result, Ok(()));
+        });
+    }
+
+    #[test]
+    fn test_wrong_claim() {
+        let witness = [
+            FieldElement::zero(),
+            FieldElement::one(),
+            FieldElement::from(2_usize),
+        ];
+        let component = Poseidon::new();
+        let mut claim = component.claim(&witness);
+        let proof = component.prove(&witness).unwrap();
+        claim[0] += FieldElement::one();
+        assert!(component.verify(&claim, &proof).is_err());
+    }
+
+    #[test]
+    fn test_public_constraints() {
+        let component = Poseidon::new();
+        let claim = component.claim(&[
+            FieldElement::zero(),
+            FieldElement::one(),
+            FieldElement::one(),
+        ]);
+        let constraints = constraint_system(&component, &claim);
+        assert_eq!(constraints.len(), 6);
+        assert_eq!(constraints.label(3), Some("public 0"));
+        assert_eq!(constraints.label(5), Some("public 2"));
+    }
+}
@@ -1,3 +1,9 @@
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
 use super::{Component, PolynomialWriter};
 use crate::RationalExpression;
 use zkp_primefield::{FieldElement, Root};
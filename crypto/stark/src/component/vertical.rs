@@ -1,4 +1,10 @@
-use super::{Component, Mapped, PolynomialWriter};
+// False positive: attribute has a use
+#[allow(clippy::useless_attribute)]
+// False positive: Importing preludes is allowed
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use super::{padded_cells, Component, Mapped, PolynomialWriter};
 use crate::RationalExpression;
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -53,6 +59,23 @@ where
             .collect::<Vec<_>>()
     }
 
+    fn public_cells(&self, claim: &Self::Claim) -> Vec<Option<(usize, usize)>> {
+        let polynomial_size = self.element.polynomial_size();
+        claim
+            .iter()
+            .enumerate()
+            .flat_map(|(i, claim)| {
+                padded_cells(&self.element, claim)
+                    .into_iter()
+                    .map(move |cell| {
+                        cell.map(|(polynomial, location)| {
+                            (polynomial, location + i * polynomial_size)
+                        })
+                    })
+            })
+            .collect()
+    }
+
     // Note: Element can not have constraints depend on the claim!
     // TODO: Vectorize the claim? Encode claim in a lookup polynomial?
     fn constraints(&self, claim: &Self::Claim) -> Vec<RationalExpression> {
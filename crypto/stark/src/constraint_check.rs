@@ -1,5 +1,21 @@
-use crate::{constraints::Constraints, trace_table::TraceTable};
-use std::convert::TryInto;
+// Clippy false positive
+#[allow(clippy::useless_attribute)]
+// We want to import an alternative prelude.
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+#[cfg(feature = "std")]
+use crate::air::Number;
+use crate::{
+    constraints::Constraints, rational_expression::RationalExpression, trace_table::TraceTable,
+};
+#[cfg(feature = "std")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::fmt;
+use std::{cmp::min, convert::TryInto};
 use zkp_primefield::{FieldElement, One, Pow, Root};
 
 #[allow(clippy::doc_markdown)]
@@ -37,31 +53,223 @@ pub fn check_constraints(
 ) -> Result<(), (usize, usize)> {
     let trace_generator = FieldElement::root(table.num_rows()).unwrap();
     let mut current_root = FieldElement::one();
-    let len = table.num_rows();
+    let expressions = evaluable_expressions(constraints, table);
 
-    for row in 0..len {
-        // Note - Still in col row form
-        let trace = |i: usize, j: isize| {
-            if j.is_positive() {
-                let j: usize = j.try_into().unwrap();
-                table[((j + row) % len, i)].clone()
-            } else {
-                let j: usize = j.abs().try_into().unwrap();
-                if row < j {
-                    table[(len + row - j, i)].clone()
-                } else {
-                    table[(row - j, i)].clone()
+    for row in 0..table.num_rows() {
+        if let Some(which) = check_row(&expressions, table, row, &current_root) {
+            return Err((row, which));
+        }
+        current_root *= &trace_generator;
+    }
+    Ok(())
+}
+
+/// Same as `check_constraints`, but checks the rows in parallel.
+///
+/// The reported failure is still the first in row order. Without `std` the
+/// chunks are checked one after the other.
+pub(crate) fn par_check_constraints(
+    constraints: &Constraints,
+    table: &TraceTable,
+) -> Result<(), (usize, usize)> {
+    const CHUNK_SIZE: usize = 1024;
+    let trace_generator = FieldElement::root(table.num_rows()).unwrap();
+    let expressions = evaluable_expressions(constraints, table);
+    let chunks = table.num_rows().div_ceil(CHUNK_SIZE);
+    let check_chunk = |chunk: usize| {
+        let start = chunk * CHUNK_SIZE;
+        let end = min(start + CHUNK_SIZE, table.num_rows());
+        let mut current_root = trace_generator.pow(start);
+        for row in start..end {
+            if let Some(which) = check_row(&expressions, table, row, &current_root) {
+                return Some((row, which));
+            }
+            current_root *= &trace_generator;
+        }
+        None
+    };
+    #[cfg(feature = "std")]
+    let failure = (0..chunks).into_par_iter().find_map_first(check_chunk);
+    #[cfg(not(feature = "std"))]
+    let failure = (0..chunks).find_map(check_chunk);
+    failure.map_or(Ok(()), Err)
+}
+
+/// A trace value that a constraint refers to
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TraceCell {
+    pub column: usize,
+    /// The column name from `Constraints::column_name`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name:   Option<String>,
+    pub offset: isize,
+    pub value:  FieldElement,
+}
+
+/// A constraint that does not hold on a row of the trace
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintFailure {
+    pub row:         usize,
+    pub constraint:  usize,
+    /// The constraint label, or the expression in the AIR format if it has
+    /// none
+    pub label:       String,
+    /// The trace values the constraint refers to, relative to `row`
+    pub cells:       Vec<TraceCell>,
+    pub numerator:   FieldElement,
+    pub denominator: FieldElement,
+}
+
+/// All constraint failures of a trace, see `check_constraints_report`
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintReport {
+    pub failures: Vec<ConstraintFailure>,
+}
+
+#[cfg(feature = "std")]
+impl ConstraintReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ConstraintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.failures.is_empty() {
+            return writeln!(f, "All constraints hold");
+        }
+        writeln!(f, "{} constraint failures", self.failures.len())?;
+        for failure in &self.failures {
+            writeln!(
+                f,
+                "row {} constraint {}: {}",
+                failure.row, failure.constraint, failure.label
+            )?;
+            for cell in &failure.cells {
+                write!(f, "    trace({}, {})", cell.column, cell.offset)?;
+                if let Some(name) = &cell.name {
+                    write!(f, " {}", name)?;
                 }
+                writeln!(f, " = {}", Number(&cell.value))?;
             }
-        };
-        for (which, expression) in constraints.expressions().iter().enumerate() {
-            if !expression.check(&current_root, &trace).1 {
-                return Err((row, which));
+            writeln!(
+                f,
+                "    numerator = {}, denominator = {}",
+                Number(&failure.numerator),
+                Number(&failure.denominator)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Same as `check_constraints`, but finds every failing row and constraint.
+///
+/// Each failure records the trace values the constraint uses and the
+/// numerator and denominator it evaluates to. The rows are checked in
+/// parallel.
+#[cfg(feature = "std")]
+pub fn check_constraints_report(constraints: &Constraints, table: &TraceTable) -> ConstraintReport {
+    const CHUNK_SIZE: usize = 1024;
+    let trace_generator = FieldElement::root(table.num_rows()).unwrap();
+    let labels = constraints
+        .expressions()
+        .iter()
+        .enumerate()
+        .map(|(index, expression)| {
+            constraints
+                .label(index)
+                .map_or_else(|| expression.to_string(), ToString::to_string)
+        })
+        .collect::<Vec<_>>();
+    let expressions = evaluable_expressions(constraints, table);
+    let chunks = table.num_rows().div_ceil(CHUNK_SIZE);
+    let failures = (0..chunks)
+        .into_par_iter()
+        .flat_map_iter(|chunk| {
+            let start = chunk * CHUNK_SIZE;
+            let end = min(start + CHUNK_SIZE, table.num_rows());
+            let mut current_root = trace_generator.pow(start);
+            let mut failures = Vec::new();
+            for row in start..end {
+                let trace = |i: usize, j: isize| trace_value(table, row, i, j);
+                for (constraint, expression) in expressions.iter().enumerate() {
+                    if expression.check(&current_root, &trace).1 {
+                        continue;
+                    }
+                    let (numerator, denominator) =
+                        expression.evaluate_fraction(&current_root, &trace);
+                    let cells = expression
+                        .trace_arguments()
+                        .into_iter()
+                        .map(|(column, offset)| {
+                            TraceCell {
+                                column,
+                                name: constraints.column_name(column).map(ToString::to_string),
+                                offset,
+                                value: trace(column, offset),
+                            }
+                        })
+                        .collect();
+                    failures.push(ConstraintFailure {
+                        row,
+                        constraint,
+                        label: labels[constraint].clone(),
+                        cells,
+                        numerator,
+                        denominator,
+                    });
+                }
+                current_root *= &trace_generator;
             }
+            failures
+        })
+        .collect();
+    ConstraintReport { failures }
+}
+
+// The value in column `i` at `j` rows from `row`, wrapping around.
+fn trace_value(table: &TraceTable, row: usize, i: usize, j: isize) -> FieldElement {
+    let len = table.num_rows();
+    if j.is_positive() {
+        let j: usize = j.try_into().unwrap();
+        table[((j + row) % len, i)].clone()
+    } else {
+        let j: usize = j.abs().try_into().unwrap();
+        if row < j {
+            table[(len + row - j, i)].clone()
+        } else {
+            table[(row - j, i)].clone()
         }
-        current_root *= &trace_generator;
     }
-    Ok(())
+}
+
+// The constraint expressions with periodic columns substituted for the length
+// of `table`.
+fn evaluable_expressions(constraints: &Constraints, table: &TraceTable) -> Vec<RationalExpression> {
+    constraints
+        .expressions()
+        .iter()
+        .map(|expression| expression.substitute_periodic(table.num_rows()))
+        .collect()
+}
+
+// Returns the first constraint that fails on `row`, where `x` is the
+// corresponding root of unity.
+fn check_row(
+    expressions: &[RationalExpression],
+    table: &TraceTable,
+    row: usize,
+    x: &FieldElement,
+) -> Option<usize> {
+    let trace = |i: usize, j: isize| trace_value(table, row, i, j);
+    expressions
+        .iter()
+        .position(|expression| !expression.check(x, &trace).1)
 }
 
 pub(crate) fn check_specific_constraint(
@@ -94,6 +302,7 @@ pub(crate) fn check_specific_constraint(
     };
 
     constraints.expressions()[which_constraint]
+        .substitute_periodic(len)
         .check(&x, &trace)
         .1
 }
@@ -127,6 +336,83 @@ mod tests {
         assert_eq!(check_constraints(&constraints, &trace), Err((799, 0)));
     }
 
+    #[test]
+    fn par_checker_test() {
+        let recurrance = Recurrance {
+            index:         5000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let constraints = recurrance.claim().constraints();
+        let mut trace = recurrance.claim().trace(&recurrance.witness());
+        assert_eq!(par_check_constraints(&constraints, &trace), Ok(()));
+        trace[(4000, 0)] = FieldElement::zero();
+        trace[(3000, 1)] = FieldElement::zero();
+        assert_eq!(
+            par_check_constraints(&constraints, &trace),
+            check_constraints(&constraints, &trace)
+        );
+        assert_eq!(par_check_constraints(&constraints, &trace), Err((2999, 1)));
+    }
+
+    #[test]
+    fn report_test() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let constraints = recurrance.claim().constraints();
+        let mut trace = recurrance.claim().trace(&recurrance.witness());
+        assert!(check_constraints_report(&constraints, &trace).is_ok());
+
+        trace[(800, 0)] = FieldElement::zero();
+        let report = check_constraints_report(&constraints, &trace);
+        let failures = report
+            .failures
+            .iter()
+            .map(|failure| (failure.row, failure.constraint))
+            .collect::<Vec<_>>();
+        assert_eq!(failures, vec![(799, 0), (800, 1)]);
+        assert_eq!(Err(failures[0]), check_constraints(&constraints, &trace));
+
+        let failure = &report.failures[0];
+        assert_eq!(failure.label, constraints.expressions()[0].to_string());
+        assert!(failure.cells.contains(&TraceCell {
+            column: 0,
+            name:   None,
+            offset: 1,
+            value:  FieldElement::zero(),
+        }));
+        assert_eq!(failure.denominator, FieldElement::zero());
+        assert_ne!(failure.numerator, FieldElement::zero());
+        assert!(report
+            .to_string()
+            .starts_with("2 constraint failures\nrow 799 constraint 0: "));
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ConstraintReport>(&json).unwrap(),
+            report
+        );
+
+        let mut constraints = constraints;
+        constraints.set_column_name(0, "a");
+        constraints.set_label(0, "copy");
+        let report = check_constraints_report(&constraints, &trace);
+        let failure = &report.failures[0];
+        assert_eq!(failure.label, "copy");
+        assert_eq!(failure.cells[0].name.as_deref(), Some("a"));
+        assert!(report.to_string().starts_with(
+            "2 constraint failures\nrow 799 constraint 0: copy\n    trace(0, 1) a = "
+        ));
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ConstraintReport>(&json).unwrap(),
+            report
+        );
+    }
+
     #[test]
     fn specific_constraint_checker() {
         let recurrance = Recurrance {
@@ -1,11 +1,61 @@
-use crate::{polynomial::DensePolynomial, rational_expression::RationalExpression};
+use crate::{
+    polynomial::DensePolynomial,
+    public_input::PublicInput,
+    rational_expression::{encode_polynomial, encode_usize, RationalExpression},
+    security::{Objective, Security},
+};
 use itertools::Itertools;
-use std::{collections::BTreeSet, fmt, prelude::v1::*};
-use zkp_primefield::{FieldElement, Root};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    prelude::v1::*,
+};
+use zkp_hash::{Hash, Hasher, Keccak256};
+use zkp_primefield::{FieldElement, Pow, Root};
 
-#[derive(Clone, Debug)]
+/// Version of the byte encoding of `Constraints::encode`
+///
+/// Changing the encoding changes the channel seed and with it all proofs, so
+/// any change needs a new version.
+const ENCODING_VERSION: u8 = 2;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Error {
     InvalidTraceLength,
+    InvalidStages,
+    UnreachableSecurity,
+    InvalidBlowup(usize),
+    InvalidNumQueries,
+    InvalidFriLayout,
+    InvalidExtensionDegree(usize),
+    NoConstraints,
+    ColumnOutOfRange {
+        expression: usize,
+        column:     usize,
+    },
+    ChallengeOutOfRange {
+        expression: usize,
+        challenge:  usize,
+    },
+    MissingClaimPolynomial {
+        expression: usize,
+        index:      usize,
+    },
+    InvalidPeriod {
+        expression: usize,
+        period:     usize,
+    },
+    PublicCellOutOfRange {
+        value:  usize,
+        column: usize,
+        row:    usize,
+    },
+    PublicNamesMismatch {
+        values: usize,
+        names:  usize,
+    },
 }
 
 impl fmt::Display for Error {
@@ -13,10 +63,161 @@ impl fmt::Display for Error {
         use Error::*;
         match *self {
             InvalidTraceLength => write!(f, "Invalid trace length (must be power of two)"),
+            InvalidStages => write!(f, "Trace stages must be non-empty and cover all columns"),
+            UnreachableSecurity => {
+                write!(
+                    f,
+                    "Target security is not reachable with this field and hash function"
+                )
+            }
+            InvalidBlowup(blowup) => {
+                write!(
+                    f,
+                    "Invalid blowup {} (must be a power of two larger than one)",
+                    blowup
+                )
+            }
+            InvalidNumQueries => write!(f, "At least one query is required"),
+            InvalidFriLayout => {
+                write!(
+                    f,
+                    "FRI layout must have non-zero reductions that fit in the trace length"
+                )
+            }
+            InvalidExtensionDegree(degree) => {
+                write!(f, "Invalid extension degree {} (must be 1 or 2)", degree)
+            }
+            NoConstraints => write!(f, "There are no constraint expressions"),
+            ColumnOutOfRange { expression, column } => {
+                write!(
+                    f,
+                    "Expression {} refers to trace column {} which does not exist",
+                    expression, column
+                )
+            }
+            ChallengeOutOfRange {
+                expression,
+                challenge,
+            } => {
+                write!(
+                    f,
+                    "Expression {} refers to challenge {} which is never drawn",
+                    expression, challenge
+                )
+            }
+            MissingClaimPolynomial { expression, index } => {
+                write!(
+                    f,
+                    "Expression {} refers to claim polynomial {} which is not provided",
+                    expression, index
+                )
+            }
+            InvalidPeriod { expression, period } => {
+                write!(
+                    f,
+                    "Expression {} has a periodic column with invalid period {} (must be a power \
+                     of two dividing the trace length, with at most that many values)",
+                    expression, period
+                )
+            }
+            PublicCellOutOfRange { value, column, row } => {
+                write!(
+                    f,
+                    "Public value {} is bound to trace cell ({}, {}) which does not exist",
+                    value, column, row
+                )
+            }
+            PublicNamesMismatch { values, names } => {
+                write!(f, "Public input has {} values but {} names", values, names)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Hash function used for the commitments and the Fiat-Shamir channel
+///
+/// The proof of work always uses Keccak-256, independent of this choice, so
+/// that its difficulty means the same for every hash function.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum HashFunction {
+    /// Keccak-256 with commitment hashes masked to 160 bits, as used by the
+    /// Starkware Solidity verifier. This is the default.
+    MaskedKeccak,
+    /// Keccak-256 as used by the EVM.
+    Keccak256,
+    Blake2s,
+    Sha256,
+    /// Poseidon over the proof field, for verifying proofs in a proof.
+    Poseidon,
+}
+
+/// Calls `function::<H>(arguments)` with `H` the `Hasher` implementing a
+/// `HashFunction`.
+macro_rules! with_hasher {
+    ($hash_function:expr, $($function:ident)::+ ($($argument:expr),* $(,)?)) => {
+        match $hash_function {
+            $crate::HashFunction::MaskedKeccak => {
+                $($function)::+::<zkp_hash::MaskedKeccak>($($argument),*)
+            }
+            $crate::HashFunction::Keccak256 => {
+                $($function)::+::<zkp_hash::Keccak256>($($argument),*)
+            }
+            $crate::HashFunction::Blake2s => $($function)::+::<zkp_hash::Blake2s>($($argument),*),
+            $crate::HashFunction::Sha256 => $($function)::+::<zkp_hash::Sha256>($($argument),*),
+            $crate::HashFunction::Poseidon => $($function)::+::<zkp_hash::Poseidon>($($argument),*),
         }
+    };
+}
+
+impl Default for HashFunction {
+    fn default() -> Self {
+        Self::MaskedKeccak
     }
 }
 
+impl HashFunction {
+    /// Identifier used in the canonical encodings
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            Self::MaskedKeccak => 0,
+            Self::Keccak256 => 1,
+            Self::Blake2s => 2,
+            Self::Sha256 => 3,
+            Self::Poseidon => 4,
+        }
+    }
+
+    #[cfg(feature = "parity_codec")]
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::MaskedKeccak),
+            1 => Some(Self::Keccak256),
+            2 => Some(Self::Blake2s),
+            3 => Some(Self::Sha256),
+            4 => Some(Self::Poseidon),
+            _ => None,
+        }
+    }
+}
+
+/// A group of trace columns that is committed to at once
+///
+/// After the columns of a stage are committed, `challenges` random values are
+/// drawn from the channel. Constraints can use them as
+/// `RationalExpression::Challenge(i)`, where `i` counts the challenges of all
+/// stages so far. The columns of the next stage can depend on them, which is
+/// what permutation and lookup arguments need.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Stage {
+    pub columns:    usize,
+    pub challenges: usize,
+}
+
 /// Constraints for Stark proofs
 ///
 /// Contains the constraint expressions that apply to the trace table in
@@ -26,16 +227,25 @@ impl fmt::Display for Error {
 ///
 /// **Note**: This does not including the constraint system or anything
 /// about the claim to be proven.
+///
+/// The serde representation is a map of all fields, see
+/// [`RationalExpression`] for the expressions. It carries a format version,
+/// which is checked when deserializing. Nothing else is checked, so call
+/// `validate` on constraints from an untrusted source before using them.
 // TODO Implement PartialEq
 #[derive(Clone)]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
 pub struct Constraints {
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_version"))]
+    version:        u32,
     channel_seed:   Vec<u8>,
     trace_nrows:    usize,
     trace_ncolumns: usize,
 
     expressions: Vec<RationalExpression>,
 
+    stages: Vec<Stage>,
+
     /// The blowup factor
     ///
     /// The size of the low-degree-extension domain compared to the trace
@@ -61,15 +271,123 @@ pub struct Constraints {
     /// is written explicitly in coefficient form.
     pub fri_layout: Vec<usize>,
 
+    /// Zero-knowledge mode
+    ///
+    /// When set, the prover salts the leaves of the trace and constraint
+    /// commitments and commits to an additional random polynomial next to the
+    /// constraint polynomials. The random polynomial is included in the out of
+    /// domain combination, which makes the FRI layers independent of the
+    /// witness.
+    ///
+    /// Decommitted trace values are still evaluations of the trace
+    /// polynomials. To hide those, the constraint system needs to leave some
+    /// rows unconstrained and the prover needs to fill them with random values
+    /// using `TraceTable::randomize_rows`. The prover rejects traces with
+    /// fewer randomized rows than values it reveals of each trace polynomial.
+    pub zero_knowledge: bool,
+
+    /// Hash function for the commitments and the channel
+    ///
+    /// Pick the one that is cheapest in the target verifier environment. Only
+    /// `HashFunction::MaskedKeccak` is supported by the Solidity verifier.
+    pub hash_function: HashFunction,
+
+    /// Degree of the field the verifier randomness is drawn from
+    ///
+    /// The out of domain point, the combination coefficients and the FRI
+    /// folding challenges are drawn from the extension of degree
+    /// `extension_degree` of the trace field. The default of one draws them
+    /// from the trace field itself. A degree of two makes the sampled values,
+    /// the constraint commitment and the FRI layers twice as large, which
+    /// only pays off when the trace field is too small for the target
+    /// security. Batch proofs need the same degree for all claims.
+    #[cfg_attr(feature = "std", serde(default = "default_extension_degree"))]
+    pub extension_degree: usize,
+
     /// To make autogeneration easier we have included a 'ClaimPolynomial'
     /// these claim polynomials need to be taken out of the expressions before
     /// they can be evaluated
     /// The following Vec of dense polys can be used to substitute claim
     /// polynomials inside of the prover.
     pub claim_polynomials: Vec<DensePolynomial>,
+
+    /// Seed the channel with `channel_seed` alone
+    ///
+    /// By default the channel absorbs the `digest` of the constraint system
+    /// after the seed, so that a proof only verifies for the constraints it
+    /// was made for. Proofs for the Solidity verifier and proofs made by
+    /// earlier versions need this flag, as they do not include the digest.
+    pub legacy_seed: bool,
+
+    #[cfg_attr(
+        feature = "std",
+        serde(
+            default,
+            skip_serializing_if = "BTreeMap::is_empty",
+            deserialize_with = "deserialize_column_names"
+        )
+    )]
+    column_names: BTreeMap<usize, String>,
+    #[cfg_attr(
+        feature = "std",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    labels:       BTreeMap<usize, String>,
+}
+
+/// Whether `name` is an ASCII identifier, as required for column names.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(feature = "std")]
+fn default_extension_degree() -> usize {
+    1
+}
+
+#[cfg(feature = "std")]
+fn deserialize_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    use serde::de::Error as _;
+    let version = u32::deserialize(deserializer)?;
+    if version == Constraints::VERSION {
+        Ok(version)
+    } else {
+        Err(D::Error::custom(format!(
+            "Unsupported constraints format version {}",
+            version
+        )))
+    }
+}
+
+#[cfg(feature = "std")]
+fn deserialize_column_names<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<usize, String>, D::Error> {
+    use serde::de::Error as _;
+    let names = BTreeMap::<usize, String>::deserialize(deserializer)?;
+    match names.values().find(|name| !is_identifier(name)) {
+        None => Ok(names),
+        Some(name) => {
+            Err(D::Error::custom(format!(
+                "Column name {:?} is not an identifier",
+                name
+            )))
+        }
+    }
 }
 
 impl Constraints {
+    /// Version of the serde format. Increment when the fields change.
+    pub const VERSION: u32 = 1;
+
+    /// Conjectured security of `from_expressions` in bits, the collision
+    /// resistance of the default hash function
+    pub const DEFAULT_SECURITY: usize = 80;
+
     fn default_fri_layout(trace_nrows: usize) -> Vec<usize> {
         // The binary logarithm of the final layer polynomial degree.
         const LOG2_TARGET: usize = 8;
@@ -95,6 +413,10 @@ impl Constraints {
     /// Requires all instances of `RationalExpression::ClaimPolynomial` in the
     /// expressions to have been replaced by
     /// `RationalExpression::DensePolynomial`.
+    ///
+    /// The blowup, number of queries and proof of work difficulty are picked
+    /// by `with_target_security` for `DEFAULT_SECURITY` bits of conjectured
+    /// security at the lowest prover time.
     // False positive
     // TODO: Remove once [1] clears
     // [1]: <https://github.com/rust-lang/rust-clippy/issues/5351>
@@ -105,21 +427,33 @@ impl Constraints {
         expressions: Vec<RationalExpression>,
     ) -> Result<Self, Error> {
         let _ = FieldElement::root(trace_nrows).ok_or(Error::InvalidTraceLength)?;
-        // TODO: Hash expressions into channel seed
-        // TODO - Examine if we want to up these security params further.
-        // 22.5*4  + 0 queries = 90
-        // TODO: Sensible default for pow_bits. For small proofs it should be small.
-        Ok(Self {
+        let target = Security {
+            conjectured: Self::DEFAULT_SECURITY,
+            proven:      0,
+        };
+        Self {
+            version: Self::VERSION,
             channel_seed,
             trace_nrows,
             trace_ncolumns,
             expressions,
-            blowup: 16,
+            stages: vec![Stage {
+                columns:    trace_ncolumns,
+                challenges: 0,
+            }],
+            blowup: 2,
             pow_bits: 0,
-            num_queries: 45,
+            num_queries: 1,
             fri_layout: Self::default_fri_layout(trace_nrows),
+            zero_knowledge: false,
+            hash_function: HashFunction::default(),
+            extension_degree: 1,
             claim_polynomials: vec![],
-        })
+            legacy_seed: false,
+            column_names: BTreeMap::new(),
+            labels: BTreeMap::new(),
+        }
+        .with_target_security(target, Objective::ProverTime)
     }
 
     /// Requires all instances of `RationalExpression::ClaimPolynomial` in the
@@ -139,13 +473,17 @@ impl Constraints {
         op_fri_layout: Option<Vec<usize>>,
     ) -> Result<Self, Error> {
         let _ = FieldElement::root(trace_nrows).ok_or(Error::InvalidTraceLength)?;
-        // TODO: Hash expressions into channel seed
         // 15*4 + 30 queries = 90
         Ok(Self {
+            version: Self::VERSION,
             channel_seed,
             trace_nrows,
             trace_ncolumns,
             expressions,
+            stages: vec![Stage {
+                columns:    trace_ncolumns,
+                challenges: 0,
+            }],
             blowup: match op_blowup {
                 Some(x) => x,
                 None => 16,
@@ -168,7 +506,13 @@ impl Constraints {
                 Some(x) => x,
                 None => Self::default_fri_layout(trace_nrows),
             },
+            zero_knowledge: false,
+            hash_function: HashFunction::default(),
+            extension_degree: 1,
             claim_polynomials: vec![],
+            legacy_seed: false,
+            column_names: BTreeMap::new(),
+            labels: BTreeMap::new(),
         })
     }
 
@@ -176,6 +520,115 @@ impl Constraints {
         &self.channel_seed
     }
 
+    /// Bind the public input of a claim to the constraint system
+    ///
+    /// Appends the canonical encoding of the public values to the channel
+    /// seed. Each `Some((column, row))` in `cells` additionally adds a
+    /// boundary constraint fixing that trace cell to the public value with
+    /// the same index, labelled with the name of the value (or its index, if
+    /// the name is empty).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a cell is outside the trace or refers to a value
+    /// that does not exist, or if the input does not have exactly one name
+    /// per value. The constraint system is unchanged in that case.
+    pub fn bind_public_input<P: PublicInput + ?Sized>(
+        &mut self,
+        input: &P,
+        cells: &[Option<(usize, usize)>],
+    ) -> Result<(), Error> {
+        use RationalExpression::*;
+        let values = input.public_values();
+        let names = input.public_names();
+        if names.len() != values.len() {
+            return Err(Error::PublicNamesMismatch {
+                values: values.len(),
+                names:  names.len(),
+            });
+        }
+        for (value, cell) in cells.iter().enumerate() {
+            if let Some((column, row)) = *cell {
+                if value >= values.len() || column >= self.trace_ncolumns || row >= self.trace_nrows
+                {
+                    return Err(Error::PublicCellOutOfRange { value, column, row });
+                }
+            }
+        }
+        self.channel_seed.extend_from_slice(&input.encode_public());
+        let trace_generator = FieldElement::root(self.trace_nrows).unwrap();
+        for (index, ((value, name), cell)) in values.into_iter().zip(names).zip(cells).enumerate() {
+            if let Some((column, row)) = *cell {
+                let row = Constant(trace_generator.pow(row));
+                self.expressions
+                    .push((Trace(column, 0) - Constant(value)) / (X - row));
+                let name = if name.is_empty() {
+                    index.to_string()
+                } else {
+                    name
+                };
+                let _ = self
+                    .labels
+                    .insert(self.expressions.len() - 1, format!("public {}", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Canonical byte encoding of the constraint system
+    ///
+    /// Starts with a version byte, followed by the trace dimensions and
+    /// stages, the proof parameters, the expressions and the claim
+    /// polynomials, followed by the extension degree. The channel seed, column
+    /// names and labels are not included. See `RationalExpression::encode`
+    /// for the encoding of expressions.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![ENCODING_VERSION];
+        encode_usize(&mut out, self.trace_nrows);
+        encode_usize(&mut out, self.trace_ncolumns);
+        encode_usize(&mut out, self.stages.len());
+        for stage in &self.stages {
+            encode_usize(&mut out, stage.columns);
+            encode_usize(&mut out, stage.challenges);
+        }
+        encode_usize(&mut out, self.blowup);
+        encode_usize(&mut out, self.pow_bits);
+        encode_usize(&mut out, self.num_queries);
+        encode_usize(&mut out, self.fri_layout.len());
+        for &reductions in &self.fri_layout {
+            encode_usize(&mut out, reductions);
+        }
+        out.push(u8::from(self.zero_knowledge));
+        out.push(self.hash_function.id());
+        encode_usize(&mut out, self.expressions.len());
+        for expression in &self.expressions {
+            expression.encode_into(&mut out);
+        }
+        encode_usize(&mut out, self.claim_polynomials.len());
+        for polynomial in &self.claim_polynomials {
+            encode_polynomial(&mut out, polynomial);
+        }
+        encode_usize(&mut out, self.extension_degree);
+        out
+    }
+
+    /// Keccak-256 hash of the `encode`d constraint system
+    pub fn digest(&self) -> Hash {
+        let mut hasher = Keccak256::default();
+        hasher.update(&self.encode());
+        Hash::new(hasher.finalize())
+    }
+
+    /// The bytes the channel is initialized with: the channel seed followed
+    /// by the digest, unless `legacy_seed` is set.
+    pub(crate) fn seed(&self) -> Vec<u8> {
+        let mut seed = self.channel_seed.clone();
+        if !self.legacy_seed {
+            seed.extend_from_slice(self.digest().as_bytes());
+        }
+        seed
+    }
+
     pub fn trace_nrows(&self) -> usize {
         self.trace_nrows
     }
@@ -196,6 +649,75 @@ impl Constraints {
         &self.expressions
     }
 
+    /// The name of a trace column, if one was set.
+    ///
+    /// Names are only used in debugging output and generated code, they do
+    /// not affect the proof.
+    pub fn column_name(&self, column: usize) -> Option<&str> {
+        self.column_names.get(&column).map(String::as_str)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `column` is not a trace column or `name` is not an
+    /// identifier.
+    pub fn set_column_name(&mut self, column: usize, name: impl Into<String>) {
+        let name = name.into();
+        assert!(column < self.trace_ncolumns, "Column out of range");
+        assert!(is_identifier(&name), "Column name is not an identifier");
+        let _ = self.column_names.insert(column, name);
+    }
+
+    /// The label of a constraint expression, if one was set.
+    ///
+    /// Like column names, labels do not affect the proof.
+    pub fn label(&self, expression: usize) -> Option<&str> {
+        self.labels.get(&expression).map(String::as_str)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `expression` is not a constraint expression.
+    pub fn set_label(&mut self, expression: usize, label: impl Into<String>) {
+        assert!(
+            expression < self.expressions.len(),
+            "Expression out of range"
+        );
+        let _ = self.labels.insert(expression, label.into());
+    }
+
+    /// The label of a constraint expression, or its index if it has none.
+    pub fn describe(&self, expression: usize) -> String {
+        self.label(expression).map_or_else(
+            || format!("constraint {}", expression),
+            |label| format!("constraint {} ({})", expression, label),
+        )
+    }
+
+    /// The trace stages, by default a single stage with all columns.
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// Split the trace columns into stages that are committed one after the
+    /// other.
+    ///
+    /// The stages take the columns in order and must cover all of them.
+    pub fn set_stages(&mut self, stages: Vec<Stage>) -> Result<(), Error> {
+        if stages.iter().any(|stage| stage.columns == 0)
+            || stages.iter().map(|stage| stage.columns).sum::<usize>() != self.trace_ncolumns
+        {
+            return Err(Error::InvalidStages);
+        }
+        self.stages = stages;
+        Ok(())
+    }
+
+    /// The total number of challenges drawn over all stages.
+    pub fn num_challenges(&self) -> usize {
+        self.stages.iter().map(|stage| stage.challenges).sum()
+    }
+
     pub fn degree(&self) -> usize {
         self.expressions
             .iter()
@@ -208,6 +730,11 @@ impl Constraints {
     }
 
     // TODO: Better explanation with literature references.
+    /// Conservative security estimate of the proof parameters in bits
+    ///
+    /// Only takes the blowup, the number of queries and the proof of work into
+    /// account, not the field size or the hash function. See `security` for
+    /// the conjectured and proven soundness of all parameters.
     pub fn security_bits(&self) -> usize {
         // Our conservative formula is (1/2^blowup_log)^(queries/2)*(1/2^pow_bits)
         // So the bit security should be blowup_log*(queries/2) + pow_bits
@@ -215,33 +742,143 @@ impl Constraints {
         blowup_log * (self.num_queries / 2) + self.pow_bits
     }
 
+    /// Check the parameters and the expressions for consistency
+    ///
+    /// Returns the first problem found: an invalid trace length or stages, a
+    /// blowup that is not a power of two, no queries, a FRI layout that does
+    /// not fit the trace, an unsupported extension degree, an expression that
+    /// refers to a trace column, challenge or claim polynomial that does
+    /// not exist, or a periodic column that does not fit the trace. This
+    /// does not check whether a trace satisfies the constraints, see
+    /// `check_constraints` for that.
+    pub fn validate(&self) -> Result<(), Error> {
+        if FieldElement::root(self.trace_nrows).is_none() {
+            return Err(Error::InvalidTraceLength);
+        }
+        if self.stages.iter().any(|stage| stage.columns == 0)
+            || self.stages.iter().map(|stage| stage.columns).sum::<usize>() != self.trace_ncolumns
+        {
+            return Err(Error::InvalidStages);
+        }
+        if self.blowup < 2 || !self.blowup.is_power_of_two() {
+            return Err(Error::InvalidBlowup(self.blowup));
+        }
+        if self.num_queries == 0 {
+            return Err(Error::InvalidNumQueries);
+        }
+        let reductions = self.fri_layout.iter().sum::<usize>();
+        if self.fri_layout.contains(&0) || reductions > self.trace_nrows.trailing_zeros() as usize {
+            return Err(Error::InvalidFriLayout);
+        }
+        if self.extension_degree != 1 && self.extension_degree != 2 {
+            return Err(Error::InvalidExtensionDegree(self.extension_degree));
+        }
+        if self.expressions.is_empty() {
+            return Err(Error::NoConstraints);
+        }
+        for (index, expression) in self.expressions.iter().enumerate() {
+            self.validate_expression(index, expression)?;
+        }
+        Ok(())
+    }
+
+    fn validate_expression(
+        &self,
+        index: usize,
+        expression: &RationalExpression,
+    ) -> Result<(), Error> {
+        use RationalExpression::*;
+        match expression {
+            &Trace(column, _) if column >= self.trace_ncolumns => {
+                Err(Error::ColumnOutOfRange {
+                    expression: index,
+                    column,
+                })
+            }
+            &Challenge(challenge) if challenge >= self.num_challenges() => {
+                Err(Error::ChallengeOutOfRange {
+                    expression: index,
+                    challenge,
+                })
+            }
+            &ClaimPolynomial(claim, ..) if claim >= self.claim_polynomials.len() => {
+                Err(Error::MissingClaimPolynomial {
+                    expression: index,
+                    index:      claim,
+                })
+            }
+            Periodic { values, period } => {
+                if period.is_power_of_two()
+                    && *period <= self.trace_nrows
+                    && values.len() <= *period
+                {
+                    Ok(())
+                } else {
+                    Err(Error::InvalidPeriod {
+                        expression: index,
+                        period:     *period,
+                    })
+                }
+            }
+            X | Constant(_) | Trace(..) | Challenge(_) => Ok(()),
+            Polynomial(_, a) | ClaimPolynomial(_, _, a, _) | Exp(a, _) | Neg(a) | Inv(a) => {
+                self.validate_expression(index, a)
+            }
+            Add(a, b) | Mul(a, b) => {
+                self.validate_expression(index, a)?;
+                self.validate_expression(index, b)
+            }
+        }
+    }
+
     // Returns an upper bound on proof size in terms of bytes in the proof.
     // Note we expect that actual sizes are compressed by the removal of overlaps in
     // decommitments
     // TODO - Improve bound by removing the elements of overlap in
     // worst cases.
     pub fn max_proof_size(&self) -> usize {
-        let trace_len_log = self.trace_nrows().trailing_zeros() as usize;
+        let trace_len_log = (self.trace_nrows() * self.blowup).trailing_zeros() as usize;
         // First we decommit two proofs for each query [one which is the evaluation
         // domain decommitment and one is the constraints]
         let mut total_decommitment =
             self.num_queries * (trace_len_log * self.trace_ncolumns() + trace_len_log);
         // Now we account for the first layer which is 8 elements [assuming the worst
         // case we need to decommit 7 other elements].
-        let mut current_size = trace_len_log - 3;
-        total_decommitment += self.num_queries * (current_size + 7);
+        // FRI values have a coefficient for each degree of the extension field.
+        let extension = self.extension_degree;
+        let mut current_size = trace_len_log.saturating_sub(3);
+        total_decommitment += self.num_queries * (current_size + 7 * extension);
 
         for &i in &self.fri_layout {
             // This worst case assumes that only one in each group is from the previous
             // layer.
-            current_size -= i;
-            total_decommitment += self.num_queries * (current_size + (1 << i) - 1);
+            current_size = current_size.saturating_sub(i);
+            total_decommitment += self.num_queries * (current_size + extension * ((1 << i) - 1));
         }
         // Decommits all of the remaining elements
         let final_list = 1 << current_size;
         if final_list > self.num_queries {
             total_decommitment += final_list - self.num_queries;
         }
+        // The further coefficients of the out of domain values, the constraint
+        // values and the last layer in an extension field.
+        if extension > 1 {
+            let constraint_columns =
+                self.degree().next_power_of_two() + usize::from(self.zero_knowledge);
+            total_decommitment += (extension - 1)
+                * (final_list
+                    + self.trace_arguments().len()
+                    + (self.num_queries + 1) * constraint_columns);
+        }
+        // Every further trace stage has its own decommitment.
+        let extra_stages = self.stages.len() - 1;
+        total_decommitment += self.num_queries * trace_len_log * extra_stages;
+        // In zero-knowledge mode the decommitments carry a salt per query,
+        // the constraint decommitment has an extra column and that column has
+        // an extra out of domain value.
+        if self.zero_knowledge {
+            total_decommitment += self.num_queries * (3 + extra_stages) + 1;
+        }
         32 * total_decommitment
     }
 
@@ -295,13 +932,27 @@ impl Constraints {
                 .collect();
         }
     }
+
+    /// Replace the `RationalExpression::Challenge`s by the values drawn from
+    /// the channel.
+    pub fn substitute_challenges(&mut self, challenges: &[FieldElement]) {
+        self.expressions = self
+            .expressions
+            .iter()
+            .map(|x| x.substitute_challenges(challenges))
+            .collect();
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{prove, traits::tests::Recurrance, Provable, Verifiable};
-    use zkp_macros_decl::field_element;
-    use zkp_primefield::FieldElement;
+    use super::*;
+    use crate::{
+        check_constraints, prove, traits::tests::Recurrance, verify, Provable, PublicInput,
+        Verifiable, VerifierError,
+    };
+    use zkp_macros_decl::{field_element, hex};
+    use zkp_primefield::{FieldElement, One};
     use zkp_u256::U256;
 
     #[test]
@@ -323,4 +974,295 @@ mod tests {
         let actual = prove(&constraints, &public.trace(&private)).unwrap();
         assert!(actual.as_bytes().len() <= constraints.max_proof_size());
     }
+
+    #[test]
+    fn encode_test() {
+        use RationalExpression::*;
+        assert_eq!(
+            (X * Trace(1, -1)).encode(),
+            hex!("0800020000000000000001ffffffffffffffff").to_vec()
+        );
+        assert_eq!(
+            Constant(FieldElement::from(5)).pow(3).encode(),
+            [&[10, 1][..], &[0; 31], &[5], &3_u64.to_be_bytes()].concat()
+        );
+    }
+
+    #[test]
+    fn digest_test() {
+        use RationalExpression::*;
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let constraints = recurrance.claim().constraints();
+        let trace = recurrance.claim().trace(&recurrance.witness());
+        assert_eq!(constraints.encode()[0], ENCODING_VERSION);
+
+        // The same constraints written differently, with the same seed
+        let mut expressions = constraints.expressions().to_vec();
+        expressions[0] = expressions[0].clone() * Constant(FieldElement::from(1));
+        let mut other = Constraints::from_expressions(
+            (constraints.trace_nrows(), constraints.trace_ncolumns()),
+            constraints.channel_seed().to_vec(),
+            expressions,
+        )
+        .unwrap();
+        other.num_queries = constraints.num_queries;
+        assert_ne!(other.digest(), constraints.digest());
+
+        let proof = prove(&constraints, &trace).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+        assert_eq!(
+            verify(&other, &proof),
+            Err(VerifierError::InvalidLDECommitment)
+        );
+
+        let mut legacy = constraints.clone();
+        legacy.legacy_seed = true;
+        other.legacy_seed = true;
+        let proof = prove(&legacy, &trace).unwrap();
+        assert_eq!(verify(&other, &proof), Ok(()));
+        assert!(verify(&constraints, &proof).is_err());
+
+        // Labels and parameters that don't change the system
+        let mut labelled = constraints.clone();
+        labelled.set_label(0, "copy");
+        assert_eq!(labelled.digest(), constraints.digest());
+        labelled.pow_bits += 1;
+        assert_ne!(labelled.digest(), constraints.digest());
+
+        let mut extended = constraints.clone();
+        extended.extension_degree = 2;
+        assert_ne!(extended.digest(), constraints.digest());
+    }
+
+    // Has fewer names than values
+    struct Unnamed;
+
+    impl PublicInput for Unnamed {
+        fn public_values(&self) -> Vec<FieldElement> {
+            vec![FieldElement::one(), FieldElement::one()]
+        }
+
+        fn public_names(&self) -> Vec<String> {
+            vec!["one".to_string()]
+        }
+    }
+
+    #[test]
+    fn bind_public_input_test() {
+        let recurrance = Recurrance {
+            index:         100,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let public = recurrance.claim();
+        let mut wrong = public.clone();
+        wrong.value += FieldElement::one();
+        let constraints = public.constraints();
+        let trace = public.trace(&recurrance.witness());
+
+        let mut bound = constraints.clone();
+        bound
+            .bind_public_input(&public, &[None, Some((0, 100))])
+            .unwrap();
+        assert_eq!(bound.len(), constraints.len() + 1);
+        assert_eq!(bound.label(constraints.len()), Some("public value"));
+        assert_eq!(
+            &bound.channel_seed()[constraints.channel_seed().len()..],
+            public.encode_public().as_slice()
+        );
+        assert_eq!(check_constraints(&bound, &trace), Ok(()));
+        let mut bound = constraints.clone();
+        bound
+            .bind_public_input(&wrong, &[None, Some((0, 100))])
+            .unwrap();
+        assert_eq!(
+            check_constraints(&bound, &trace),
+            Err((100, constraints.len()))
+        );
+
+        // Binding only through the channel seed
+        let mut bound = constraints.clone();
+        bound.bind_public_input(&public, &[]).unwrap();
+        let mut other = constraints.clone();
+        other.bind_public_input(&wrong, &[]).unwrap();
+        let proof = prove(&bound, &trace).unwrap();
+        assert_eq!(verify(&bound, &proof), Ok(()));
+        assert!(verify(&other, &proof).is_err());
+
+        let mut bound = constraints.clone();
+        assert_eq!(
+            bound.bind_public_input(&public, &[Some((2, 0))]),
+            Err(Error::PublicCellOutOfRange {
+                value:  0,
+                column: 2,
+                row:    0,
+            })
+        );
+        assert_eq!(
+            bound.bind_public_input(&public, &[None, None, None, Some((0, 0))]),
+            Err(Error::PublicCellOutOfRange {
+                value:  3,
+                column: 0,
+                row:    0,
+            })
+        );
+
+        // Names have to match the values
+        assert_eq!(
+            bound.bind_public_input(&Unnamed, &[None, Some((0, 0))]),
+            Err(Error::PublicNamesMismatch {
+                values: 2,
+                names:  1,
+            })
+        );
+        assert_eq!(bound.len(), constraints.len());
+        assert_eq!(bound.channel_seed(), constraints.channel_seed());
+    }
+
+    #[test]
+    fn serde_test() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let mut constraints = recurrance.claim().constraints();
+        constraints.set_column_name(1, "b");
+        constraints.set_label(0, "step");
+        constraints.add_claim_polynomials(vec![DensePolynomial::new(&[
+            FieldElement::from(1),
+            FieldElement::from(2),
+        ])]);
+        let json = serde_json::to_string(&constraints).unwrap();
+        let decoded: Constraints = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.encode(), constraints.encode());
+        assert_eq!(decoded.column_name(1), Some("b"));
+        assert_eq!(decoded.label(0), Some("step"));
+        assert_eq!(decoded.validate(), Ok(()));
+
+        let other_version = json.replacen(
+            &format!("\"version\":{}", Constraints::VERSION),
+            "\"version\":0",
+            1,
+        );
+        assert!(serde_json::from_str::<Constraints>(&other_version).is_err());
+
+        let bad_name = json.replacen("\"1\":\"b\"", "\"1\":\"b\\n\"", 1);
+        assert_ne!(bad_name, json);
+        assert!(serde_json::from_str::<Constraints>(&bad_name).is_err());
+    }
+
+    #[test]
+    fn validate_test() {
+        use RationalExpression::*;
+        let mut constraints = Constraints::from_expressions((1024, 2), vec![], vec![
+            Trace(0, 1) - Trace(1, 0),
+            (Trace(1, 0) - Challenge(0)) / (X - 1),
+        ])
+        .unwrap();
+        assert_eq!(
+            constraints.validate(),
+            Err(Error::ChallengeOutOfRange {
+                expression: 1,
+                challenge:  0,
+            })
+        );
+        constraints
+            .set_stages(vec![
+                Stage {
+                    columns:    1,
+                    challenges: 1,
+                },
+                Stage {
+                    columns:    1,
+                    challenges: 0,
+                },
+            ])
+            .unwrap();
+        assert_eq!(constraints.validate(), Ok(()));
+
+        constraints.blowup = 12;
+        assert_eq!(constraints.validate(), Err(Error::InvalidBlowup(12)));
+        constraints.blowup = 16;
+        constraints.num_queries = 0;
+        assert_eq!(constraints.validate(), Err(Error::InvalidNumQueries));
+        constraints.num_queries = 20;
+        constraints.fri_layout = vec![2, 0];
+        assert_eq!(constraints.validate(), Err(Error::InvalidFriLayout));
+        constraints.fri_layout = vec![3, 3, 3, 2];
+        assert_eq!(constraints.validate(), Err(Error::InvalidFriLayout));
+        constraints.fri_layout = vec![3, 3, 3, 1];
+        assert_eq!(constraints.validate(), Ok(()));
+        constraints.extension_degree = 3;
+        assert_eq!(
+            constraints.validate(),
+            Err(Error::InvalidExtensionDegree(3))
+        );
+        constraints.extension_degree = 2;
+        assert_eq!(constraints.validate(), Ok(()));
+
+        let constraints =
+            Constraints::from_expressions((1024, 2), vec![], vec![Trace(0, 0), Trace(2, -1)])
+                .unwrap();
+        assert_eq!(
+            constraints.validate(),
+            Err(Error::ColumnOutOfRange {
+                expression: 1,
+                column:     2,
+            })
+        );
+        let constraints = Constraints::from_expressions((1024, 1), vec![], vec![
+            ClaimPolynomial(0, 1, Box::new(X), None) - Trace(0, 0),
+        ])
+        .unwrap();
+        assert_eq!(
+            constraints.validate(),
+            Err(Error::MissingClaimPolynomial {
+                expression: 0,
+                index:      0,
+            })
+        );
+        for (len, period) in &[(3, 2), (1, 3), (1, 2048)] {
+            let constraints = Constraints::from_expressions((1024, 1), vec![], vec![
+                Trace(0, 0)
+                    - Periodic {
+                        values: vec![FieldElement::one(); *len],
+                        period: *period,
+                    },
+            ])
+            .unwrap();
+            assert_eq!(
+                constraints.validate(),
+                Err(Error::InvalidPeriod {
+                    expression: 0,
+                    period:     *period,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn periodic_degree_test() {
+        use RationalExpression::*;
+        let values = [3, 1, 4, 1, 5, 9, 2, 6]
+            .iter()
+            .map(|&v| FieldElement::from(v))
+            .collect::<Vec<_>>();
+        for len in 0..=values.len() {
+            let expression = (Trace(0, 1)
+                - Periodic {
+                    values: values[..len].to_vec(),
+                    period: 8,
+                })
+                / (X.pow(64) - 1);
+            assert_eq!(
+                expression.degree(63),
+                expression.substitute_periodic(64).degree(63)
+            );
+        }
+    }
 }
@@ -0,0 +1,210 @@
+use crate::{channel::RandomGenerator, polynomial::DensePolynomial};
+use std::{iter::Sum, prelude::v1::*, slice};
+#[cfg(feature = "prover")]
+use zkp_mmap_vec::{InScratch, MmapVec};
+use zkp_primefield::{Ext, FieldElement, FieldLike, One, Zero};
+
+/// The field the out of domain point, the combination coefficients and the
+/// FRI folding challenges are drawn from
+///
+/// This is `FieldElement` itself or an extension of it, see
+/// `Constraints::extension_degree`. Trace values stay in `FieldElement`.
+/// Elements go through the channel as their `DEGREE` coefficients over
+/// `FieldElement`, one after the other, so for `FieldElement` proofs are
+/// unchanged.
+pub(crate) trait Extension:
+    FieldLike + From<FieldElement> + Sum<Self> + Send + Sync
+{
+    const DEGREE: usize;
+
+    /// The coefficients over `FieldElement`
+    fn components(&self) -> &[FieldElement];
+
+    /// # Panics
+    ///
+    /// Panics if there are fewer than `DEGREE` components.
+    fn from_components(components: &[FieldElement]) -> Self;
+
+    fn mul_base(&self, rhs: &FieldElement) -> Self;
+
+    /// Splits `values` into `DEGREE` columns, one for each coefficient
+    #[cfg(feature = "prover")]
+    fn into_columns(values: MmapVec<Self>) -> Vec<MmapVec<FieldElement>>;
+
+    /// # Panics
+    ///
+    /// Panics if there are not `DEGREE` columns.
+    #[cfg(feature = "prover")]
+    fn from_columns(columns: Vec<MmapVec<FieldElement>>) -> MmapVec<Self>;
+
+    /// Evaluates a polynomial over `FieldElement` at `x`
+    fn evaluate(polynomial: &DensePolynomial, x: &Self) -> Self;
+
+    /// The element with a one as `index`-th coefficient and zeros elsewhere
+    fn basis(index: usize) -> Self {
+        let mut components = vec![FieldElement::zero(); Self::DEGREE];
+        components[index] = FieldElement::one();
+        Self::from_components(&components)
+    }
+
+    /// Draws the coefficients one after the other
+    fn draw(channel: &mut impl RandomGenerator<FieldElement>) -> Self {
+        let components = (0..Self::DEGREE)
+            .map(|_| channel.get_random())
+            .collect::<Vec<_>>();
+        Self::from_components(&components)
+    }
+
+    fn draw_many(channel: &mut impl RandomGenerator<FieldElement>, count: usize) -> Vec<Self> {
+        (0..count).map(|_| Self::draw(channel)).collect()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the number of components is not a multiple of `DEGREE`.
+    fn from_components_many(components: &[FieldElement]) -> Vec<Self> {
+        assert_eq!(components.len() % Self::DEGREE, 0);
+        components
+            .chunks_exact(Self::DEGREE)
+            .map(Self::from_components)
+            .collect()
+    }
+
+    fn power(&self, mut exponent: usize) -> Self {
+        let mut result = Self::one();
+        let mut square = self.clone();
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result *= &square;
+            }
+            square = square.square_inline();
+            exponent /= 2;
+        }
+        result
+    }
+}
+
+/// The coefficients over `FieldElement` of all `values`, in order.
+pub(crate) fn flatten<E: Extension>(values: &[E]) -> Vec<FieldElement> {
+    values
+        .iter()
+        .flat_map(|value| value.components().iter().cloned())
+        .collect()
+}
+
+impl Extension for FieldElement {
+    const DEGREE: usize = 1;
+
+    fn components(&self) -> &[FieldElement] {
+        slice::from_ref(self)
+    }
+
+    fn from_components(components: &[FieldElement]) -> Self {
+        components[0].clone()
+    }
+
+    fn mul_base(&self, rhs: &FieldElement) -> Self {
+        self * rhs
+    }
+
+    #[cfg(feature = "prover")]
+    fn into_columns(values: MmapVec<Self>) -> Vec<MmapVec<FieldElement>> {
+        vec![values]
+    }
+
+    #[cfg(feature = "prover")]
+    fn from_columns(columns: Vec<MmapVec<FieldElement>>) -> MmapVec<Self> {
+        assert_eq!(columns.len(), 1);
+        columns.into_iter().next().unwrap()
+    }
+
+    fn evaluate(polynomial: &DensePolynomial, x: &Self) -> Self {
+        polynomial.evaluate(x)
+    }
+}
+
+impl Extension for Ext<FieldElement, 2> {
+    const DEGREE: usize = 2;
+
+    fn components(&self) -> &[FieldElement] {
+        self.coefficients()
+    }
+
+    fn from_components(components: &[FieldElement]) -> Self {
+        Self::new([components[0].clone(), components[1].clone()])
+    }
+
+    fn mul_base(&self, rhs: &FieldElement) -> Self {
+        Self::mul_base(self, rhs)
+    }
+
+    #[cfg(feature = "prover")]
+    fn into_columns(values: MmapVec<Self>) -> Vec<MmapVec<FieldElement>> {
+        (0..Self::DEGREE)
+            .map(|index| {
+                let mut column = MmapVec::with_capacity_in_scratch(values.len(), values.scratch());
+                column.extend(values.iter().map(|value| value.components()[index].clone()));
+                column
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "prover")]
+    fn from_columns(columns: Vec<MmapVec<FieldElement>>) -> MmapVec<Self> {
+        assert_eq!(columns.len(), Self::DEGREE);
+        let mut values = MmapVec::with_capacity_in_scratch(columns[0].len(), columns[0].scratch());
+        values.extend(
+            columns[0]
+                .iter()
+                .zip(columns[1].iter())
+                .map(|(first, second)| Self::new([first.clone(), second.clone()])),
+        );
+        values
+    }
+
+    fn evaluate(polynomial: &DensePolynomial, x: &Self) -> Self {
+        polynomial.evaluate_ext(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    type Quadratic = Ext<FieldElement, 2>;
+
+    proptest!(
+        #[test]
+        fn components_roundtrip(a: Quadratic) {
+            prop_assert_eq!(Quadratic::from_components(a.components()), a);
+        }
+
+        #[test]
+        fn basis_decomposition(a: Quadratic) {
+            let sum: Quadratic = a
+                .components()
+                .iter()
+                .enumerate()
+                .map(|(index, component)| Quadratic::basis(index).mul_base(component))
+                .sum();
+            prop_assert_eq!(sum, a);
+        }
+
+        #[cfg(feature = "prover")]
+        #[test]
+        fn columns_roundtrip(values: Vec<Quadratic>) {
+            let mut vec = MmapVec::with_capacity(values.len());
+            vec.extend_from_slice(&values);
+            let columns = Quadratic::into_columns(vec);
+            let result = Quadratic::from_columns(columns);
+            prop_assert_eq!(result.as_slice(), values.as_slice());
+        }
+
+        #[test]
+        fn pow_matches_mul(a: Quadratic, exponent in 0_usize..20) {
+            let expected = (0..exponent).fold(Quadratic::one(), |acc, _| acc * &a);
+            prop_assert_eq!(a.power(exponent), expected);
+        }
+    );
+}
@@ -47,12 +47,20 @@
 // TODO: False positives <https://github.com/rust-lang/rust-clippy/issues/5917>
 #![allow(clippy::wildcard_imports)]
 
+#[cfg(feature = "std")]
+mod air;
 mod channel;
+#[macro_use]
 mod constraints;
+mod extension;
+#[cfg(feature = "parity_codec")]
+mod parity_codec;
 mod polynomial;
 mod proof;
 mod proof_of_work;
+mod public_input;
 mod rational_expression;
+mod security;
 #[cfg(feature = "std")]
 mod solidity_seralizer;
 #[cfg(feature = "std")]
@@ -60,8 +68,8 @@ mod solidity_verifier;
 mod traits;
 mod verifier;
 
-// Optional prover functionality. Note that prover requires std.
-// TODO: Make it work without std.
+// Optional prover functionality. Without std it runs single-threaded with all
+// values in memory.
 #[cfg(feature = "prover")]
 mod algebraic_dag;
 #[cfg(feature = "prover")]
@@ -70,7 +78,7 @@ pub mod component;
 mod constraint_check;
 #[cfg(feature = "prover")]
 mod prover;
-#[cfg(feature = "prover")]
+#[cfg(all(feature = "prover", feature = "std"))]
 mod rational_equality;
 #[cfg(feature = "prover")]
 mod trace_table;
@@ -85,28 +93,41 @@ extern crate no_std_compat as std;
 pub use zkp_primefield as primefield;
 
 // Exports for verifier
-pub use constraints::{Constraints, Error as ConstraintError};
+pub use constraints::{Constraints, Error as ConstraintError, HashFunction, Stage};
 pub use polynomial::DensePolynomial;
-pub use proof::Proof;
+pub use proof::{Decommitment, Error as ProofError, Proof, StructuredProof};
+pub use public_input::PublicInput;
 pub use rational_expression::RationalExpression;
+pub use security::{Objective, Security};
 pub use traits::Verifiable;
-pub use verifier::{verify, Error as VerifierError};
+pub use verifier::{
+    verify, verify_batch, verify_detailed, Details as VerifierDetails,
+    Diagnostics as VerifierDiagnostics, Error as VerifierError, Oracle,
+};
 
+#[cfg(feature = "std")]
+pub use air::{parse_air, Error as AirError, ErrorKind as AirErrorKind};
 // We want std for this so that we can use hex encode
 #[cfg(feature = "std")]
 pub use solidity_seralizer::proof_serialize;
 #[cfg(feature = "std")]
-pub use solidity_verifier::generate;
+pub use solidity_verifier::{generate, GenerateError};
 
 // Exports for prover
 #[cfg(feature = "prover")]
 pub use constraint_check::check_constraints;
+#[cfg(all(feature = "prover", feature = "std"))]
+pub use constraint_check::{
+    check_constraints_report, ConstraintFailure, ConstraintReport, TraceCell,
+};
 #[cfg(feature = "prover")]
-pub use prover::{prove, Error as ProverError};
+pub use prover::{prove, prove_batch, prove_checked, prove_staged, Error as ProverError};
 #[cfg(feature = "prover")]
 pub use trace_table::TraceTable;
 #[cfg(feature = "prover")]
 pub use traits::Provable;
+#[cfg(feature = "prover")]
+pub use zkp_mmap_vec::Scratch;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,311 @@
+// Clippy false positive
+#[allow(clippy::useless_attribute)]
+// We want to import an alternative prelude.
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use crate::{
+    constraints::{is_identifier, Constraints, HashFunction, Stage},
+    polynomial::DensePolynomial,
+    rational_expression::RationalExpression,
+};
+use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+use std::convert::TryFrom;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// Maximum nesting of decoded expressions, to bound the recursion.
+const MAX_DEPTH: usize = 256;
+
+/// Expressions use the canonical encoding of `RationalExpression::encode`.
+impl Encode for RationalExpression {
+    fn encode_to<T: Output>(&self, dest: &mut T) {
+        dest.write(&RationalExpression::encode(self));
+    }
+}
+
+impl Decode for RationalExpression {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        decode_expression(input, MAX_DEPTH)
+    }
+}
+
+/// Polynomials are encoded as the vector of their coefficients.
+impl Encode for DensePolynomial {
+    fn encode_to<T: Output>(&self, dest: &mut T) {
+        self.coefficients().encode_to(dest);
+    }
+}
+
+impl Decode for DensePolynomial {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let coefficients = Vec::<FieldElement>::decode(input)?;
+        if !coefficients.len().is_power_of_two() {
+            return Err("Number of coefficients is not a power of two".into());
+        }
+        Ok(Self::new(&coefficients))
+    }
+}
+
+impl Encode for HashFunction {
+    fn encode_to<T: Output>(&self, dest: &mut T) {
+        dest.push_byte(self.id());
+    }
+}
+
+impl Decode for HashFunction {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let id = input.read_byte()?;
+        HashFunction::from_id(id).ok_or_else(|| "Unknown hash function".into())
+    }
+}
+
+impl Encode for Stage {
+    fn encode_to<T: Output>(&self, dest: &mut T) {
+        (self.columns as u64).encode_to(dest);
+        (self.challenges as u64).encode_to(dest);
+    }
+}
+
+impl Decode for Stage {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            columns:    decode_usize(input)?,
+            challenges: decode_usize(input)?,
+        })
+    }
+}
+
+/// All fields in declaration order, starting with `Constraints::VERSION`.
+/// Sizes are encoded as `u64`, column names and labels as lists of index and
+/// string pairs.
+impl Encode for Constraints {
+    fn encode_to<T: Output>(&self, dest: &mut T) {
+        Self::VERSION.encode_to(dest);
+        self.channel_seed().encode_to(dest);
+        (self.trace_nrows() as u64).encode_to(dest);
+        (self.trace_ncolumns() as u64).encode_to(dest);
+        self.expressions().encode_to(dest);
+        self.stages().encode_to(dest);
+        (self.blowup as u64).encode_to(dest);
+        (self.pow_bits as u64).encode_to(dest);
+        (self.num_queries as u64).encode_to(dest);
+        self.fri_layout
+            .iter()
+            .map(|&n| n as u64)
+            .collect::<Vec<_>>()
+            .encode_to(dest);
+        self.zero_knowledge.encode_to(dest);
+        self.hash_function.encode_to(dest);
+        (self.extension_degree as u64).encode_to(dest);
+        self.claim_polynomials.encode_to(dest);
+        self.legacy_seed.encode_to(dest);
+        let column_names = (0..self.trace_ncolumns())
+            .filter_map(|i| self.column_name(i).map(|name| (i as u64, name)))
+            .collect::<Vec<_>>();
+        column_names.encode_to(dest);
+        let labels = (0..self.len())
+            .filter_map(|i| self.label(i).map(|label| (i as u64, label)))
+            .collect::<Vec<_>>();
+        labels.encode_to(dest);
+    }
+}
+
+impl Decode for Constraints {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        if u32::decode(input)? != Self::VERSION {
+            return Err("Unsupported constraints format version".into());
+        }
+        let channel_seed = Vec::<u8>::decode(input)?;
+        let trace_nrows = decode_usize(input)?;
+        let trace_ncolumns = decode_usize(input)?;
+        let expressions = Vec::<RationalExpression>::decode(input)?;
+        let mut constraints =
+            Self::from_expressions((trace_nrows, trace_ncolumns), channel_seed, expressions)
+                .map_err(|_| "Invalid trace length")?;
+        constraints
+            .set_stages(Vec::<Stage>::decode(input)?)
+            .map_err(|_| "Invalid stages")?;
+        constraints.blowup = decode_usize(input)?;
+        constraints.pow_bits = decode_usize(input)?;
+        constraints.num_queries = decode_usize(input)?;
+        constraints.fri_layout = Vec::<u64>::decode(input)?
+            .into_iter()
+            .map(|n| usize::try_from(n).map_err(|_| "Integer out of range".into()))
+            .collect::<Result<_, Error>>()?;
+        constraints.zero_knowledge = bool::decode(input)?;
+        constraints.hash_function = HashFunction::decode(input)?;
+        constraints.extension_degree = decode_usize(input)?;
+        constraints.claim_polynomials = Vec::<DensePolynomial>::decode(input)?;
+        constraints.legacy_seed = bool::decode(input)?;
+        for (column, name) in Vec::<(u64, String)>::decode(input)? {
+            match usize::try_from(column) {
+                Ok(column) if column < constraints.trace_ncolumns() => {
+                    if !is_identifier(&name) {
+                        return Err("Column name is not an identifier".into());
+                    }
+                    constraints.set_column_name(column, name);
+                }
+                _ => return Err("Column name out of range".into()),
+            }
+        }
+        for (expression, label) in Vec::<(u64, String)>::decode(input)? {
+            match usize::try_from(expression) {
+                Ok(expression) if expression < constraints.len() => {
+                    constraints.set_label(expression, label);
+                }
+                _ => return Err("Constraint label out of range".into()),
+            }
+        }
+        Ok(constraints)
+    }
+}
+
+fn decode_u64_be<I: Input>(input: &mut I) -> Result<u64, Error> {
+    let mut bytes = [0_u8; 8];
+    input.read(&mut bytes)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+fn decode_i64_be<I: Input>(input: &mut I) -> Result<i64, Error> {
+    let mut bytes = [0_u8; 8];
+    input.read(&mut bytes)?;
+    Ok(i64::from_be_bytes(bytes))
+}
+
+fn decode_usize_be<I: Input>(input: &mut I) -> Result<usize, Error> {
+    usize::try_from(decode_u64_be(input)?).map_err(|_| "Integer out of range".into())
+}
+
+fn decode_usize<I: Input>(input: &mut I) -> Result<usize, Error> {
+    usize::try_from(u64::decode(input)?).map_err(|_| "Integer out of range".into())
+}
+
+fn decode_field_element_be<I: Input>(input: &mut I) -> Result<FieldElement, Error> {
+    let mut bytes = [0_u8; 32];
+    input.read(&mut bytes)?;
+    let value = U256::from_bytes_be(&bytes);
+    if value >= FieldElement::MODULUS {
+        return Err("Field element not in canonical form".into());
+    }
+    Ok(FieldElement::from_uint(&value))
+}
+
+/// Inverse of `RationalExpression::encode_into`.
+fn decode_expression<I: Input>(input: &mut I, depth: usize) -> Result<RationalExpression, Error> {
+    use RationalExpression::*;
+    let depth = depth.checked_sub(1).ok_or("Expression nested too deeply")?;
+    let sub = |input: &mut I| decode_expression(input, depth).map(Box::new);
+    Ok(match input.read_byte()? {
+        0 => X,
+        1 => Constant(decode_field_element_be(input)?),
+        2 => {
+            let column = decode_usize_be(input)?;
+            let offset =
+                isize::try_from(decode_i64_be(input)?).map_err(|_| "Integer out of range")?;
+            Trace(column, offset)
+        }
+        3 => Challenge(decode_usize_be(input)?),
+        4 => {
+            let len = decode_usize_be(input)?;
+            if !len.is_power_of_two() {
+                return Err("Number of coefficients is not a power of two".into());
+            }
+            // Do not trust `len` for the allocation, the input may be short.
+            let mut coefficients = Vec::new();
+            for _ in 0..len {
+                coefficients.push(decode_field_element_be(input)?);
+            }
+            Polynomial(DensePolynomial::new(&coefficients), sub(input)?)
+        }
+        5 => {
+            let index = decode_usize_be(input)?;
+            let degree_bound = decode_usize_be(input)?;
+            ClaimPolynomial(index, degree_bound, sub(input)?, None)
+        }
+        6 => Add(sub(input)?, sub(input)?),
+        7 => Neg(sub(input)?),
+        8 => Mul(sub(input)?, sub(input)?),
+        9 => Inv(sub(input)?),
+        10 => Exp(sub(input)?, decode_usize_be(input)?),
+        11 => {
+            let period = decode_usize_be(input)?;
+            let len = decode_usize_be(input)?;
+            let mut values = Vec::new();
+            for _ in 0..len {
+                values.push(decode_field_element_be(input)?);
+            }
+            Periodic { values, period }
+        }
+        _ => return Err("Unknown expression tag".into()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{traits::tests::Recurrance, Verifiable};
+    use zkp_macros_decl::field_element;
+    use zkp_primefield::One;
+
+    #[test]
+    fn test_expression_roundtrip() {
+        use RationalExpression::*;
+        let expression = Polynomial(
+            DensePolynomial::new(&[field_element!("05"), FieldElement::one()]),
+            Box::new(X),
+        ) * Trace(2, -1)
+            + Exp(Box::new(Challenge(1)), 3)
+            - ClaimPolynomial(0, 7, Box::new(X.inv()), Some("c"))
+            + Periodic {
+                values: vec![FieldElement::one()],
+                period: 2,
+            };
+        let serialized = Encode::encode(&expression);
+        assert_eq!(serialized, RationalExpression::encode(&expression));
+        let mut slice = serialized.as_slice();
+        let deserialized = RationalExpression::decode(&mut slice).unwrap();
+        assert_eq!(slice.len(), 0);
+        assert_eq!(
+            RationalExpression::encode(&deserialized),
+            RationalExpression::encode(&expression)
+        );
+    }
+
+    #[test]
+    fn test_expression_rejects() {
+        // Unknown tag
+        assert!(RationalExpression::decode(&mut &[12_u8][..]).is_err());
+        // Field element equal to the modulus
+        let mut bytes = vec![1_u8];
+        bytes.extend_from_slice(&FieldElement::MODULUS.to_bytes_be());
+        assert!(RationalExpression::decode(&mut bytes.as_slice()).is_err());
+        // Nested too deeply
+        let bytes = vec![7_u8; MAX_DEPTH + 1];
+        assert!(RationalExpression::decode(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_constraints_roundtrip() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: FieldElement::from(3),
+            exponent:      1,
+        };
+        let mut constraints = recurrance.claim().constraints();
+        constraints.set_column_name(0, "a");
+        constraints.set_label(1, "boundary");
+        constraints.zero_knowledge = true;
+        constraints.hash_function = HashFunction::Blake2s;
+        constraints.extension_degree = 2;
+        let serialized = Encode::encode(&constraints);
+        let mut slice = serialized.as_slice();
+        let deserialized = Constraints::decode(&mut slice).unwrap();
+        assert_eq!(slice.len(), 0);
+        assert_eq!(deserialized.digest(), constraints.digest());
+        assert_eq!(deserialized.column_name(0), Some("a"));
+        assert_eq!(deserialized.label(1), Some("boundary"));
+        assert_eq!(deserialized.legacy_seed, constraints.legacy_seed);
+        assert_eq!(deserialized.extension_degree, 2);
+    }
+}
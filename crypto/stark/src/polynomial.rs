@@ -1,13 +1,14 @@
 // TODO: Naming?
 #![allow(clippy::module_name_repetitions)]
+use crate::extension::Extension;
 #[cfg(feature = "std")]
-use log::trace;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::prelude::v1::*;
 use zkp_macros_decl::field_element;
-use zkp_mmap_vec::MmapVec;
-#[cfg(feature = "std")]
-use zkp_primefield::{fft::permute_index, Fft, Pow, Root};
-use zkp_primefield::{FieldElement, Zero};
+use zkp_mmap_vec::{InScratch, MmapVec, Scratch};
+use zkp_primefield::{
+    fft::TwiddleCache, polynomial::Polynomial, Ext, Extendable, FieldElement, Zero,
+};
 use zkp_u256::U256;
 
 #[derive(Clone)]
@@ -21,6 +22,28 @@ impl std::fmt::Debug for DensePolynomial {
     }
 }
 
+/// Serialized as the list of coefficients in order of ascending degree.
+#[cfg(feature = "std")]
+impl Serialize for DensePolynomial {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.coefficients().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for DensePolynomial {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let coefficients = Vec::<FieldElement>::deserialize(deserializer)?;
+        if !coefficients.len().is_power_of_two() {
+            return Err(D::Error::custom(format!(
+                "Number of coefficients {} is not a power of two",
+                coefficients.len()
+            )));
+        }
+        Ok(Self::new(&coefficients))
+    }
+}
+
 impl PartialEq for DensePolynomial {
     fn eq(&self, other: &Self) -> bool {
         // Check equality with evaluation
@@ -30,6 +53,22 @@ impl PartialEq for DensePolynomial {
 }
 impl Eq for DensePolynomial {}
 
+/// Pads with zero coefficients to a power of two length.
+impl From<&Polynomial<FieldElement>> for DensePolynomial {
+    fn from(polynomial: &Polynomial<FieldElement>) -> Self {
+        let coefficients = polynomial.coefficients();
+        let mut result = Self::zeros(coefficients.len().next_power_of_two());
+        result.0[..coefficients.len()].clone_from_slice(coefficients);
+        result
+    }
+}
+
+impl From<&DensePolynomial> for Polynomial<FieldElement> {
+    fn from(polynomial: &DensePolynomial) -> Self {
+        Self::new(polynomial.coefficients().to_vec())
+    }
+}
+
 impl DensePolynomial {
     pub fn from_mmap_vec(coefficients: MmapVec<FieldElement>) -> Self {
         assert!(coefficients.len().is_power_of_two());
@@ -85,48 +124,106 @@ impl DensePolynomial {
         result
     }
 
-    #[cfg(feature = "std")]
-    pub fn low_degree_extension(&self, blowup: usize) -> MmapVec<FieldElement> {
-        trace!("BEGIN Low degree extension");
-        // TODO: shift polynomial by FieldElement::generator() outside of this function.
-        // TODO: Parameterize cofactor
-        let shift_factor: FieldElement = FieldElement::generator();
-        let length = self.len() * blowup;
-        let generator =
-            FieldElement::root(length).expect("No generator for extended_domain_length.");
+    /// Evaluates the polynomial at a point of an extension field.
+    ///
+    /// This allows out of domain sampling from the extension field while the
+    /// coefficients remain in the base field.
+    pub fn evaluate_ext<const D: usize>(&self, x: &Ext<FieldElement, D>) -> Ext<FieldElement, D>
+    where
+        FieldElement: Extendable<D>,
+    {
+        let mut result = Ext::zero();
+        for coefficient in self.0.iter().rev() {
+            result *= x;
+            result += Ext::from_base(coefficient.clone());
+        }
+        result
+    }
 
-        // FieldElement is safe to initialize zero (which maps to zero)
-        #[allow(unsafe_code)]
-        let mut result: MmapVec<FieldElement> = unsafe { MmapVec::zero_initialized(length) };
+    /// The evaluations on a coset of a domain `blowup` times larger than the
+    /// number of coefficients, allocated in the same scratch as them.
+    pub fn low_degree_extension(&self, blowup: usize) -> MmapVec<FieldElement> {
+        self.low_degree_extension_with(blowup, &TwiddleCache::new(self.len()), self.0.scratch())
+    }
 
-        // Compute cosets
-        result
-            .as_mut_slice()
-            .chunks_mut(self.len())
-            .enumerate()
-            .for_each(|(i, slice)| {
-                let cofactor = &shift_factor * generator.pow(permute_index(blowup, i));
-                slice.clone_shifted(&self.coefficients(), &cofactor);
-                slice.fft();
-            });
-        trace!("END Low degree extension");
+    /// Like [`DensePolynomial::low_degree_extension`], but with precomputed
+    /// twiddles that can be shared between polynomials and allocated in
+    /// `scratch`.
+    pub fn low_degree_extension_with(
+        &self,
+        blowup: usize,
+        twiddles: &TwiddleCache<FieldElement>,
+        scratch: &Scratch,
+    ) -> MmapVec<FieldElement> {
+        let mut result = zeros_in(self.len() * blowup, scratch);
+        twiddles.lde_into(
+            &[self.coefficients()],
+            blowup,
+            &Self::lde_shift(),
+            result.as_mut_slice(),
+        );
         result
     }
 
+    /// The `coset`-th chunk of [`DensePolynomial::low_degree_extension`],
+    /// written to `destination`.
+    ///
+    /// The chunks hold the evaluations on the cosets of the domain of the
+    /// size of the polynomial, in the order of the full extension.
+    pub(crate) fn low_degree_extension_coset_into(
+        &self,
+        blowup: usize,
+        coset: usize,
+        twiddles: &TwiddleCache<FieldElement>,
+        destination: &mut [FieldElement],
+    ) {
+        twiddles.lde_coset_into(
+            &[self.coefficients()],
+            blowup,
+            &Self::lde_shift(),
+            coset,
+            destination,
+        );
+    }
+
+    /// The cofactor of the low degree extension domain
+    pub(crate) fn lde_shift() -> FieldElement {
+        // TODO: Parameterize cofactor
+        FieldElement::generator()
+    }
+
     /// Divide out a point and add the scaled result to target.
     ///
     /// target += c * (P(X) - P(z)) / (X - z)
     /// See: <https://en.wikipedia.org/wiki/Synthetic_division>
-    pub fn divide_out_point_into(&self, z: &FieldElement, c: &FieldElement, target: &mut Self) {
-        let mut remainder = FieldElement::zero();
-        for (coefficient, target) in self.0.iter().rev().zip(target.0.iter_mut().rev()) {
-            *target += c * &remainder;
+    ///
+    /// The point may be in an extension field, the coefficients of the target
+    /// are then in the extension field as well.
+    pub(crate) fn divide_out_point_into<E: Extension>(&self, z: &E, c: &E, target: &mut [E]) {
+        let mut remainder = E::zero();
+        for (coefficient, target) in self.0.iter().rev().zip(target.iter_mut().rev()) {
+            *target += c.clone() * &remainder;
             remainder *= z;
-            remainder += coefficient;
+            remainder += E::from(coefficient.clone());
         }
     }
 }
 
+/// A zero-initialized vector of `length` elements in `scratch`.
+pub(crate) fn zeros_in(length: usize, scratch: &Scratch) -> MmapVec<FieldElement> {
+    // FieldElement is safe to initialize zero (which maps to zero)
+    #[cfg(feature = "std")]
+    #[allow(unsafe_code)]
+    let result = unsafe { MmapVec::zero_initialized_in_scratch(length, scratch) };
+    #[cfg(not(feature = "std"))]
+    let result = {
+        let mut result = MmapVec::with_capacity_in_scratch(length, scratch);
+        result.resize(length, FieldElement::zero());
+        result
+    };
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,4 +242,40 @@ mod tests {
         let p = dense_polynomial(&[1, 0, 0, 2]);
         assert_eq!(p.evaluate(&FieldElement::from(2)), FieldElement::from(17));
     }
+
+    #[test]
+    fn example_evaluate_ext() {
+        type Quadratic = Ext<FieldElement, 2>;
+        let p = dense_polynomial(&[1, 0, 0, 2]);
+        assert_eq!(
+            p.evaluate_ext(&Quadratic::from(FieldElement::from(2))),
+            Quadratic::from(FieldElement::from(17))
+        );
+        // With X^2 = 3 we have 1 + 2 X^3 = 1 + 6 X
+        assert_eq!(
+            p.evaluate_ext(&Quadratic::generator()),
+            Quadratic::new([FieldElement::from(1), FieldElement::from(6)])
+        );
+    }
+
+    #[test]
+    fn polynomial_conversion() {
+        let p = dense_polynomial(&[1, 0, 3, 0]);
+        let converted = Polynomial::from(&p);
+        assert_eq!(converted.degree(), Some(2));
+        assert_eq!(DensePolynomial::from(&converted), p);
+        assert_eq!(
+            DensePolynomial::from(&Polynomial::zero()),
+            dense_polynomial(&[0])
+        );
+    }
+
+    #[test]
+    fn serde_test() {
+        let p = dense_polynomial(&[1, 0, 0, 2]);
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(serde_json::from_str::<DensePolynomial>(&json).unwrap(), p);
+        let json = serde_json::to_string(&vec![FieldElement::from(1); 3]).unwrap();
+        assert!(serde_json::from_str::<DensePolynomial>(&json).is_err());
+    }
 }
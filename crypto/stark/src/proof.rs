@@ -1,5 +1,31 @@
-use std::prelude::v1::*;
+use crate::{
+    channel::{RandomGenerator, Replayable, VerifierChannel},
+    constraints::Constraints,
+    extension::Extension,
+    proof_of_work,
+    verifier::{get_indices, Oracle, Row},
+};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Deserializer, Serialize};
+#[cfg(feature = "std")]
+use std::error;
+use std::{convert::TryFrom, fmt, prelude::v1::*};
+use zkp_hash::{Hash, Hasher};
+use zkp_merkle_tree::{Commitment, Error as MerkleError};
+use zkp_primefield::{Ext, FieldElement};
 
+type Result<T> = std::result::Result<T, Error>;
+
+/// A Stark proof as the bytes written to the prover channel.
+///
+/// The bytes are the Fiat-Shamir transcript itself: the verifier absorbs them
+/// into its channel as they are read, and the Solidity verifier expects the
+/// same layout. A header would either be absorbed as well, changing every
+/// proof and the on-chain verifier, or have to be stripped before
+/// verification, so the byte form stays unversioned. The layout is determined
+/// by the [`Constraints`], which are part of the verifier's input.
+/// [`StructuredProof`] is the versioned form for storing and exchanging
+/// proofs.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Proof(Vec<u8>);
 
@@ -12,3 +38,676 @@ impl Proof {
         &self.0
     }
 }
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Error {
+    ProofTooShort,
+    ProofTooLong,
+    InvalidSalts,
+    InvalidFieldElement,
+    /// The extension degree, saturated to `u8::MAX`
+    InvalidExtensionDegree(u8),
+    Merkle(MerkleError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+        match *self {
+            ProofTooShort => write!(f, "The proof ends before all sections are read"),
+            ProofTooLong => write!(f, "The proof has data after the last section"),
+            InvalidSalts => write!(f, "The salts do not split the decommitted values into rows"),
+            InvalidFieldElement => write!(f, "The proof holds a value that is not a field element"),
+            InvalidExtensionDegree(degree) => {
+                write!(f, "Invalid extension degree {} (must be 1 or 2)", degree)
+            }
+            // This is a wrapper, so defer to the underlying types' implementation of `fmt`.
+            Merkle(ref e) => std::fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl From<MerkleError> for Error {
+    fn from(err: MerkleError) -> Self {
+        Self::Merkle(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Self::Merkle(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Values and Merkle proof hashes revealed for the queried leaves of a
+/// committed vector.
+///
+/// The values are flattened in query order. For the trace and constraint
+/// commitments this is a full row per query, for FRI layers it is only those
+/// coset values that can not be derived from the previous layer.
+///
+/// In zero-knowledge mode the trace and constraint decommitments have a salt
+/// for each row. Otherwise `salts` is empty.
+#[derive(Clone, Default, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct Decommitment {
+    pub values: Vec<FieldElement>,
+    #[cfg_attr(feature = "std", serde(default))]
+    pub salts:  Vec<Hash>,
+    pub hashes: Vec<Hash>,
+}
+
+/// A [`Proof`] split into its sections.
+///
+/// The byte layout of a [`Proof`] is not self-describing: the length of each
+/// section depends on the [`Constraints`] and on the query indices drawn from
+/// the channel. [`StructuredProof::from_proof`] replays the channel to recover
+/// the sections and [`StructuredProof::to_proof`] converts back. The
+/// conversion is lossless in both directions. Since the sections are public
+/// fields, or deserialized, `to_proof` checks that each salted decommitment
+/// has a whole number of values per salt.
+///
+/// The serde representation carries a format version, which is checked when
+/// deserializing.
+///
+/// For a trace with several stages, `trace_commitment` and
+/// `trace_decommitment` belong to the first stage and the others are in
+/// `stage_commitments` and `stage_decommitments`.
+///
+/// With an `extension_degree` above one, the out of domain values, the last
+/// layer coefficients and the FRI values are extension field elements. They
+/// are stored as their coefficients, one after the other.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct StructuredProof {
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_version"))]
+    version:                     u32,
+    pub trace_commitment:        Hash,
+    #[cfg_attr(feature = "std", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub stage_commitments:       Vec<Hash>,
+    pub constraint_commitment:   Hash,
+    pub trace_oods_values:       Vec<FieldElement>,
+    pub constraint_oods_values:  Vec<FieldElement>,
+    pub fri_commitments:         Vec<Hash>,
+    pub last_layer_coefficients: Vec<FieldElement>,
+    pub pow_nonce:               u64,
+    pub trace_decommitment:      Decommitment,
+    #[cfg_attr(feature = "std", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub stage_decommitments:     Vec<Decommitment>,
+    pub constraint_decommitment: Decommitment,
+    pub fri_decommitments:       Vec<Decommitment>,
+}
+
+#[cfg(feature = "std")]
+fn deserialize_version<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<u32, D::Error> {
+    use serde::de::Error as _;
+    let version = u32::deserialize(deserializer)?;
+    if version == StructuredProof::VERSION {
+        Ok(version)
+    } else {
+        Err(D::Error::custom(format!(
+            "Unsupported proof format version {}",
+            version
+        )))
+    }
+}
+
+impl StructuredProof {
+    /// Version of the proof format. Increment when the sections change.
+    pub const VERSION: u32 = 1;
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Split a proof into its sections.
+    ///
+    /// This replays the proof on a channel exactly like the verifier does, but
+    /// does not check any of the values. A proof that decodes correctly is not
+    /// necessarily valid.
+    pub fn from_proof(constraints: &Constraints, proof: &Proof) -> Result<Self> {
+        with_hasher!(
+            constraints.hash_function,
+            Self::from_proof_with(constraints, proof)
+        )
+    }
+
+    fn from_proof_with<H: Hasher>(constraints: &Constraints, proof: &Proof) -> Result<Self> {
+        match constraints.extension_degree {
+            1 => Self::from_proof_in::<H, FieldElement>(constraints, proof),
+            2 => Self::from_proof_in::<H, Ext<FieldElement, 2>>(constraints, proof),
+            degree => Err(Error::InvalidExtensionDegree(saturate(degree))),
+        }
+    }
+
+    fn from_proof_in<H: Hasher, E: Extension>(
+        constraints: &Constraints,
+        proof: &Proof,
+    ) -> Result<Self> {
+        let mut channel = Transcript::<E>::channel::<H>(constraints, proof);
+        Transcript::<E>::replay(&mut channel, constraints, |_, _| Ok(()))
+            .map(|transcript| transcript.proof)
+            .map_err(|error| {
+                match error {
+                    ReplayError::Read(error) | ReplayError::Check(error) => error,
+                }
+            })
+    }
+
+    /// The trace decommitments of all stages, in order.
+    pub(crate) fn trace_decommitments(&self) -> impl Iterator<Item = &Decommitment> {
+        std::iter::once(&self.trace_decommitment).chain(&self.stage_decommitments)
+    }
+
+    /// Concatenate the sections into the byte layout used by the verifier.
+    ///
+    /// Returns `Error::InvalidSalts` when the values of a salted decommitment
+    /// can not be split into one non-empty row per salt.
+    pub fn to_proof(&self) -> Result<Proof> {
+        fn write_elements(bytes: &mut Vec<u8>, elements: &[FieldElement]) {
+            for element in elements {
+                bytes.extend_from_slice(&element.as_montgomery().to_bytes_be());
+            }
+        }
+        fn write_hashes(bytes: &mut Vec<u8>, hashes: &[Hash]) {
+            for hash in hashes {
+                bytes.extend_from_slice(hash.as_bytes());
+            }
+        }
+
+        let mut bytes = Vec::new();
+        write_hashes(&mut bytes, &[self.trace_commitment.clone()]);
+        write_hashes(&mut bytes, &self.stage_commitments);
+        write_hashes(&mut bytes, &[self.constraint_commitment.clone()]);
+        write_elements(&mut bytes, &self.trace_oods_values);
+        write_elements(&mut bytes, &self.constraint_oods_values);
+        write_hashes(&mut bytes, &self.fri_commitments);
+        write_elements(&mut bytes, &self.last_layer_coefficients);
+        bytes.extend_from_slice(&self.pow_nonce.to_be_bytes());
+        for decommitment in self
+            .trace_decommitments()
+            .chain(std::iter::once(&self.constraint_decommitment))
+            .chain(&self.fri_decommitments)
+        {
+            if decommitment.salts.is_empty() {
+                write_elements(&mut bytes, &decommitment.values);
+            } else {
+                let row_size = decommitment.values.len() / decommitment.salts.len();
+                if row_size == 0 || decommitment.values.len() % decommitment.salts.len() != 0 {
+                    return Err(Error::InvalidSalts);
+                }
+                for (row, salt) in decommitment
+                    .values
+                    .chunks(row_size)
+                    .zip(&decommitment.salts)
+                {
+                    write_elements(&mut bytes, row);
+                    write_hashes(&mut bytes, std::slice::from_ref(salt));
+                }
+            }
+            write_hashes(&mut bytes, &decommitment.hashes);
+        }
+        Ok(Proof::from_bytes(bytes))
+    }
+}
+
+impl TryFrom<&StructuredProof> for Proof {
+    type Error = Error;
+
+    fn try_from(proof: &StructuredProof) -> Result<Self> {
+        proof.to_proof()
+    }
+}
+
+/// Byte offsets in the proof of where each section starts.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Offsets {
+    pub(crate) trace_commitments:       Vec<usize>,
+    pub(crate) constraint_commitment:   usize,
+    pub(crate) oods_values:             usize,
+    pub(crate) fri_commitments:         Vec<usize>,
+    pub(crate) last_layer:              usize,
+    pub(crate) pow_nonce:               usize,
+    pub(crate) trace_decommitments:     Vec<usize>,
+    pub(crate) constraint_decommitment: usize,
+    pub(crate) fri_decommitments:       Vec<usize>,
+}
+
+/// How far a replay has read, see [`Transcript::replay`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Step {
+    /// Everything before the decommitments, up to the query indices
+    Queries,
+    /// The decommitment of a trace stage or of the constraint commitment
+    Rows(Oracle),
+}
+
+/// Why a replay stopped
+pub(crate) enum ReplayError<E> {
+    /// The proof has the wrong length or size
+    Read(Error),
+    /// The check of the sections read so far failed
+    Check(E),
+}
+
+impl<E> From<Error> for ReplayError<E> {
+    fn from(err: Error) -> Self {
+        Self::Read(err)
+    }
+}
+
+impl<E> From<MerkleError> for ReplayError<E> {
+    fn from(err: MerkleError) -> Self {
+        Self::Read(Error::Merkle(err))
+    }
+}
+
+/// A proof replayed on the verifier channel.
+///
+/// Besides the sections, this holds the randomness drawn in between and where
+/// each section starts. It is the only place that knows the proof layout, the
+/// verifier, [`StructuredProof`] and the Solidity serializer all start from it.
+///
+/// All but the stage challenges are drawn from the extension field `E`. The
+/// values of `E` in the proof sections are flattened to their coefficients.
+pub(crate) struct Transcript<E> {
+    pub(crate) proof:                   StructuredProof,
+    pub(crate) offsets:                 Offsets,
+    pub(crate) trace_commitments:       Vec<Commitment>,
+    pub(crate) constraint_commitment:   Commitment,
+    pub(crate) fri_commitments:         Vec<Commitment>,
+    pub(crate) challenges:              Vec<FieldElement>,
+    pub(crate) constraint_coefficients: Vec<E>,
+    pub(crate) oods_point:              E,
+    pub(crate) oods_coefficients:       Vec<E>,
+    pub(crate) fri_points:              Vec<E>,
+    pub(crate) pow_seed:                proof_of_work::ChallengeSeed,
+    pub(crate) queries:                 Vec<usize>,
+}
+
+impl<E: Extension> Transcript<E> {
+    /// The verifier channel for a proof of `constraints`.
+    pub(crate) fn channel<H: Hasher>(
+        constraints: &Constraints,
+        proof: &Proof,
+    ) -> VerifierChannel<H> {
+        let mut channel = VerifierChannel::<H>::new(proof.as_bytes().to_vec());
+        channel.initialize(&constraints.seed());
+        channel
+    }
+
+    /// Reads the proof from `channel` and draws the random values in between.
+    ///
+    /// The sections are passed to `check` as soon as they are read, so that
+    /// a verifier fails at the same place as it would reading the proof
+    /// itself. On failure `channel` is left where reading stopped.
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn replay<H: Hasher, C>(
+        channel: &mut VerifierChannel<H>,
+        constraints: &Constraints,
+        mut check: impl FnMut(Step, &Self) -> std::result::Result<(), C>,
+    ) -> std::result::Result<Self, ReplayError<C>> {
+        let eval_domain_size = constraints.trace_nrows() * constraints.blowup;
+        let salted = constraints.zero_knowledge;
+        let mut offsets = Offsets::default();
+
+        let mut trace_hashes: Vec<Hash> = Vec::with_capacity(constraints.stages().len());
+        let mut trace_commitments = Vec::with_capacity(constraints.stages().len());
+        let mut challenges = Vec::with_capacity(constraints.num_challenges());
+        for stage in constraints.stages() {
+            offsets.trace_commitments.push(channel.position());
+            ensure(channel, 32)?;
+            let hash: Hash = channel.replay()?;
+            trace_commitments.push(Commitment::from_size_hash(eval_domain_size, &hash)?);
+            trace_hashes.push(hash);
+            challenges.extend(channel.get_coefficients(stage.challenges));
+        }
+        let constraint_coefficients = E::draw_many(channel, 2 * constraints.len());
+
+        offsets.constraint_commitment = channel.position();
+        ensure(channel, 32)?;
+        let constraint_hash: Hash = channel.replay()?;
+        let constraint_commitment = Commitment::from_size_hash(eval_domain_size, &constraint_hash)?;
+        let oods_point = E::draw(channel);
+
+        // This hack is annoying and should be removed
+        let mut parseable_constraints = constraints.clone();
+        parseable_constraints.substitute();
+        let trace_arguments = parseable_constraints.trace_arguments();
+        offsets.oods_values = channel.position();
+        ensure(channel, 32 * E::DEGREE * trace_arguments.len())?;
+        let trace_oods_values: Vec<FieldElement> =
+            channel.replay_many(E::DEGREE * trace_arguments.len())?;
+        // Every constraint polynomial has a column for each coefficient of `E`
+        let constraint_polynomials =
+            constraints.degree().next_power_of_two() + usize::from(constraints.zero_knowledge);
+        let constraint_cols = E::DEGREE * constraint_polynomials;
+        ensure(channel, 32 * constraint_cols)?;
+        let constraint_oods_values: Vec<FieldElement> = channel.replay_many(constraint_cols)?;
+        let oods_coefficients =
+            E::draw_many(channel, trace_arguments.len() + constraint_polynomials);
+
+        let mut fri_hashes: Vec<Hash> = Vec::with_capacity(constraints.fri_layout.len());
+        let mut fri_commitments = Vec::with_capacity(constraints.fri_layout.len());
+        let mut fri_points = Vec::with_capacity(constraints.fri_layout.len());
+        let mut fri_size = eval_domain_size;
+        for &num_folds in &constraints.fri_layout {
+            fri_size >>= num_folds;
+            offsets.fri_commitments.push(channel.position());
+            ensure(channel, 32)?;
+            let hash: Hash = channel.replay()?;
+            fri_commitments.push(Commitment::from_size_hash(fri_size, &hash)?);
+            fri_hashes.push(hash);
+            fri_points.push(E::draw(channel));
+        }
+        offsets.last_layer = channel.position();
+        let last_layer_size = E::DEGREE * (fri_size / constraints.blowup);
+        ensure(channel, 32 * last_layer_size)?;
+        let last_layer_coefficients = channel.replay_fri_layer(last_layer_size)?;
+
+        let pow_seed: proof_of_work::ChallengeSeed = channel.get_random();
+        offsets.pow_nonce = channel.position();
+        ensure(channel, 8)?;
+        let pow_response: proof_of_work::Response = channel.replay()?;
+
+        let queries = get_indices(
+            constraints.num_queries,
+            eval_domain_size.trailing_zeros(),
+            channel,
+        );
+
+        let stage_commitments = trace_hashes.split_off(1);
+        let mut transcript = Self {
+            proof: StructuredProof {
+                version: StructuredProof::VERSION,
+                trace_commitment: trace_hashes.remove(0),
+                stage_commitments,
+                constraint_commitment: constraint_hash,
+                trace_oods_values,
+                constraint_oods_values,
+                fri_commitments: fri_hashes,
+                last_layer_coefficients,
+                pow_nonce: pow_response.nonce(),
+                trace_decommitment: Decommitment::default(),
+                stage_decommitments: Vec::new(),
+                constraint_decommitment: Decommitment::default(),
+                fri_decommitments: Vec::new(),
+            },
+            offsets,
+            trace_commitments,
+            constraint_commitment,
+            fri_commitments,
+            challenges,
+            constraint_coefficients,
+            oods_point,
+            oods_coefficients,
+            fri_points,
+            pow_seed,
+            queries,
+        };
+        check(Step::Queries, &transcript).map_err(ReplayError::Check)?;
+
+        for (index, stage) in constraints.stages().iter().enumerate() {
+            transcript
+                .offsets
+                .trace_decommitments
+                .push(channel.position());
+            let decommitment = Decommitment::replay_rows(
+                channel,
+                &transcript.trace_commitments[index],
+                &transcript.queries,
+                stage.columns,
+                salted,
+            )?;
+            if index == 0 {
+                transcript.proof.trace_decommitment = decommitment;
+            } else {
+                transcript.proof.stage_decommitments.push(decommitment);
+            }
+            check(Step::Rows(Oracle::Trace(index)), &transcript).map_err(ReplayError::Check)?;
+        }
+        transcript.offsets.constraint_decommitment = channel.position();
+        transcript.proof.constraint_decommitment = Decommitment::replay_rows(
+            channel,
+            &transcript.constraint_commitment,
+            &transcript.queries,
+            constraint_cols,
+            salted,
+        )?;
+        check(Step::Rows(Oracle::Constraint), &transcript).map_err(ReplayError::Check)?;
+
+        let (fri_decommitments, fri_offsets) = Decommitment::replay_fri::<H, E>(
+            channel,
+            &transcript.fri_commitments,
+            &constraints.fri_layout,
+            &transcript.queries,
+        )?;
+        transcript.proof.fri_decommitments = fri_decommitments;
+        transcript.offsets.fri_decommitments = fri_offsets;
+        if channel.remaining() > 0 {
+            return Err(Error::ProofTooLong.into());
+        }
+        Ok(transcript)
+    }
+}
+
+impl Decommitment {
+    /// Reads the rows at `indices` of a trace or constraint commitment,
+    /// followed by their Merkle proof.
+    pub(crate) fn replay_rows<H: Hasher>(
+        channel: &mut VerifierChannel<H>,
+        commitment: &Commitment,
+        indices: &[usize],
+        row_size: usize,
+        salted: bool,
+    ) -> Result<Self> {
+        ensure(
+            channel,
+            32 * (row_size + usize::from(salted)) * indices.len(),
+        )?;
+        let mut values = Vec::with_capacity(row_size * indices.len());
+        let mut salts = Vec::new();
+        for _ in indices {
+            let row = Row::replay(channel, row_size, salted)?;
+            values.extend(row.values);
+            salts.extend(row.salt);
+        }
+        let proof_length = commitment.proof_size(indices)?;
+        ensure(channel, 32 * proof_length)?;
+        let hashes = channel.replay_many(proof_length)?;
+        Ok(Self {
+            values,
+            salts,
+            hashes,
+        })
+    }
+
+    /// Reads the decommitments of the FRI layers, together with the offset
+    /// where each starts.
+    ///
+    /// A layer only holds the coset values that can not be derived from the
+    /// queries of the previous layer, each as its coefficients over
+    /// `FieldElement`.
+    pub(crate) fn replay_fri<H: Hasher, E: Extension>(
+        channel: &mut VerifierChannel<H>,
+        commitments: &[Commitment],
+        fri_layout: &[usize],
+        queries: &[usize],
+    ) -> Result<(Vec<Self>, Vec<usize>)> {
+        let mut decommitments = Vec::with_capacity(commitments.len());
+        let mut offsets = Vec::with_capacity(commitments.len());
+        let mut previous_indices = queries.to_vec();
+        for (&num_folds, commitment) in fri_layout.iter().zip(commitments) {
+            offsets.push(channel.position());
+            let coset_size = 1_usize << num_folds;
+            let mut fri_indices: Vec<usize> =
+                previous_indices.iter().map(|i| i / coset_size).collect();
+            fri_indices.dedup();
+            let mut values = Vec::new();
+            for i in &fri_indices {
+                for j in 0..coset_size {
+                    if previous_indices
+                        .binary_search(&(i * coset_size + j))
+                        .is_err()
+                    {
+                        ensure(channel, 32 * E::DEGREE)?;
+                        let value: Vec<FieldElement> = channel.replay_many(E::DEGREE)?;
+                        values.extend(value);
+                    }
+                }
+            }
+            let proof_length = commitment.proof_size(&fri_indices)?;
+            ensure(channel, 32 * proof_length)?;
+            let hashes = channel.replay_many(proof_length)?;
+            decommitments.push(Self {
+                values,
+                salts: Vec::new(),
+                hashes,
+            });
+            previous_indices = fri_indices;
+        }
+        Ok((decommitments, offsets))
+    }
+
+    /// The decommitted rows of `row_size` values, paired with their
+    /// `indices`.
+    pub(crate) fn rows(&self, indices: &[usize], row_size: usize) -> Vec<(usize, Row)> {
+        indices
+            .iter()
+            .enumerate()
+            .map(|(i, &index)| {
+                (index, Row {
+                    values: self.values[i * row_size..(i + 1) * row_size].to_vec(),
+                    salt:   self.salts.get(i).cloned(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// An extension degree for the `InvalidExtensionDegree` errors
+pub(crate) fn saturate(degree: usize) -> u8 {
+    u8::try_from(degree).unwrap_or(u8::MAX)
+}
+
+fn ensure<H: Hasher>(channel: &VerifierChannel<H>, bytes: usize) -> Result<()> {
+    if channel.remaining() < bytes {
+        Err(Error::ProofTooShort)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prove,
+        traits::tests::{Recurrance, Recurrance2},
+        Provable, Verifiable,
+    };
+    use proptest::prelude::*;
+    use zkp_macros_decl::field_element;
+    use zkp_primefield::Zero;
+    use zkp_u256::U256;
+
+    proptest!(
+        #[test]
+        fn structured_proof_roundtrip(r: Recurrance) {
+            let public = r.claim();
+            let constraints = public.constraints();
+            let proof = prove(&constraints, &public.trace(&r.witness())).unwrap();
+            let structured = StructuredProof::from_proof(&constraints, &proof).unwrap();
+            prop_assert_eq!(
+                &structured.trace_commitment.as_bytes(),
+                &&proof.as_bytes()[0..32]
+            );
+            prop_assert_eq!(
+                structured.fri_decommitments.len(),
+                constraints.fri_layout.len()
+            );
+            prop_assert_eq!(structured.to_proof(), Ok(proof));
+        }
+
+        #[test]
+        fn structured_proof_roundtrip_claim_polynomials(r: Recurrance2) {
+            let public = r.claim();
+            let constraints = public.constraints();
+            let proof = prove(&constraints, &public.trace(&r.witness())).unwrap();
+            let structured = StructuredProof::from_proof(&constraints, &proof).unwrap();
+            prop_assert_eq!(structured.to_proof(), Ok(proof));
+        }
+
+        #[test]
+        fn structured_proof_serde(r: Recurrance) {
+            let public = r.claim();
+            let constraints = public.constraints();
+            let proof = prove(&constraints, &public.trace(&r.witness())).unwrap();
+            let expected = StructuredProof::from_proof(&constraints, &proof).unwrap();
+            let json = serde_json::to_string(&expected).unwrap();
+            let actual: StructuredProof = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(actual, expected);
+        }
+    );
+
+    fn example_proof() -> (Constraints, Proof) {
+        let r = Recurrance {
+            index:         150,
+            initial_value: field_element!(
+                "42f70183f3ed560b81c4cd49a8d5f27fdb747c17eaa93f41570b012649b5a47b"
+            ),
+            exponent:      2,
+        };
+        let public = r.claim();
+        let constraints = public.constraints();
+        let proof = prove(&constraints, &public.trace(&r.witness())).unwrap();
+        (constraints, proof)
+    }
+
+    #[test]
+    fn structured_proof_wrong_length() {
+        let (constraints, proof) = example_proof();
+        let mut bytes = proof.as_bytes().to_vec();
+        bytes.push(0);
+        assert_eq!(
+            StructuredProof::from_proof(&constraints, &Proof::from_bytes(bytes.clone())),
+            Err(Error::ProofTooLong)
+        );
+        bytes.truncate(bytes.len() - 33);
+        assert_eq!(
+            StructuredProof::from_proof(&constraints, &Proof::from_bytes(bytes)),
+            Err(Error::ProofTooShort)
+        );
+    }
+
+    #[test]
+    fn structured_proof_invalid_salts() {
+        let (constraints, proof) = example_proof();
+        let mut structured = StructuredProof::from_proof(&constraints, &proof).unwrap();
+        structured.trace_decommitment.salts = vec![Hash::new([0; 32]); 3];
+        structured.trace_decommitment.values.truncate(4);
+        assert_eq!(structured.to_proof(), Err(Error::InvalidSalts));
+        structured.trace_decommitment.values.truncate(2);
+        assert_eq!(structured.to_proof(), Err(Error::InvalidSalts));
+        structured.trace_decommitment.values.clear();
+        assert_eq!(structured.to_proof(), Err(Error::InvalidSalts));
+        structured.trace_decommitment.values = vec![FieldElement::zero(); 6];
+        assert!(structured.to_proof().is_ok());
+    }
+
+    #[test]
+    fn structured_proof_version() {
+        let (constraints, proof) = example_proof();
+        let structured = StructuredProof::from_proof(&constraints, &proof).unwrap();
+        let json = serde_json::to_string(&structured)
+            .unwrap()
+            .replace("\"version\":1", "\"version\":2");
+        assert!(serde_json::from_str::<StructuredProof>(&json).is_err());
+    }
+}
@@ -1,5 +1,7 @@
+#[cfg(feature = "prover")]
+use log::info;
 #[cfg(all(feature = "std", feature = "prover"))]
-use log::{info, trace};
+use log::trace;
 #[cfg(all(feature = "std", feature = "prover"))]
 use rayon::prelude::*;
 use std::convert::TryFrom;
@@ -1,31 +1,95 @@
 use crate::{
-    algebraic_dag::AlgebraicGraph,
+    algebraic_dag::{AlgebraicGraph, TraceValues},
     channel::{ProverChannel, RandomGenerator, Writable},
-    constraints::Constraints,
-    polynomial::DensePolynomial,
-    proof_of_work, verify, Proof, TraceTable, VerifierError,
+    constraint_check::par_check_constraints,
+    constraints::{Constraints, Error as ConstraintError},
+    extension::{flatten, Extension},
+    polynomial::{zeros_in, DensePolynomial},
+    proof_of_work,
+    verifier::{batch_parameters, batch_seed, is_staged, same_parameters},
+    verify, verify_batch, Proof, TraceTable, VerifierError,
 };
 use itertools::Itertools;
 use log::{info, trace};
+#[cfg(feature = "std")]
 use rayon::prelude::*;
-use std::{fmt, prelude::v1::*, vec};
-use zkp_hash::{Hash, Hashable, MaskedKeccak};
-use zkp_merkle_tree::{Error as MerkleError, Tree, VectorCommitment};
-use zkp_mmap_vec::MmapVec;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+    fmt,
+    mem::size_of,
+    ops::Range,
+    prelude::v1::*,
+};
+use zkp_hash::{Hash, Hashable, Hasher};
+use zkp_merkle_tree::{Commitment, Error as MerkleError, Tree, VectorCommitment};
+use zkp_mmap_vec::{InScratch, MmapVec, Scratch};
 use zkp_primefield::{
-    fft::{permute, permute_index},
+    fft::{permute, permute_index, TwiddleCache},
     geometric_series::geometric_series,
-    Fft, FieldElement, Inv, One, Pow, Root, SquareInline, Zero,
+    Ext, Fft, FieldElement, Inv, One, Pow, Root,
 };
 use zkp_u256::U256;
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// Randomness for the salts and masks of zero-knowledge proofs
+#[cfg(feature = "std")]
+#[allow(clippy::unnecessary_wraps)]
+fn random<T>() -> Result<T>
+where
+    rand::distributions::Standard: rand::distributions::Distribution<T>,
+{
+    Ok(rand::random())
+}
+
+// Without `std` there is no source of randomness. The public functions reject
+// zero-knowledge proofs up front, this keeps any path they miss from
+// producing a proof without it.
+#[cfg(not(feature = "std"))]
+fn random<T>() -> Result<T> {
+    Err(Error::ZeroKnowledgeUnavailable)
+}
+
+// Utility function to parallelize iff on std
+fn for_each_chunk<F>(slice: &mut [FieldElement], chunk_size: usize, f: F)
+where
+    F: Fn((usize, &mut [FieldElement])) + Sync + Send,
+{
+    #[cfg(feature = "std")]
+    slice.par_chunks_mut(chunk_size).enumerate().for_each(f);
+
+    #[cfg(not(feature = "std"))]
+    slice.chunks_mut(chunk_size).enumerate().for_each(f);
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Error {
     RootUnavailable,
+    EmptyBatch,
+    MismatchedParameters,
+    UnsupportedStages,
+    MissingStages,
+    InvalidConstraints(ConstraintError),
+    TraceRows {
+        expected: usize,
+        actual:   usize,
+    },
+    TraceColumns {
+        expected: usize,
+        actual:   usize,
+    },
+    UnsatisfiedConstraint {
+        expression: usize,
+        row:        usize,
+    },
     MerkleFailed(MerkleError),
     VerificationFailed(VerifierError),
+    ZeroKnowledgeUnavailable,
+    RandomRows {
+        required: usize,
+        actual:   usize,
+    },
 }
 
 impl fmt::Display for Error {
@@ -33,12 +97,74 @@ impl fmt::Display for Error {
         use Error::*;
         match *self {
             RootUnavailable => write!(f, "The prime field doesn't have a root of this order"),
+            EmptyBatch => write!(f, "A batch proof needs at least one claim"),
+            MismatchedParameters => {
+                write!(
+                    f,
+                    "The claims of a batch proof use different proof parameters"
+                )
+            }
+            UnsupportedStages => write!(f, "Batch proofs of staged traces are not supported"),
+            MissingStages => write!(f, "Staged traces need to be proven with `prove_staged`"),
+            InvalidConstraints(ref e) => std::fmt::Display::fmt(e, f),
+            TraceRows { expected, actual } => {
+                write!(
+                    f,
+                    "The trace has {} rows, but the constraints require {}",
+                    actual, expected
+                )
+            }
+            TraceColumns { expected, actual } => {
+                write!(
+                    f,
+                    "The trace has {} columns, but the constraints require {}",
+                    actual, expected
+                )
+            }
+            UnsatisfiedConstraint { expression, row } => {
+                write!(
+                    f,
+                    "Expression {} does not hold on trace row {}",
+                    expression, row
+                )
+            }
             MerkleFailed(ref e) => std::fmt::Display::fmt(e, f),
             VerificationFailed(ref e) => std::fmt::Display::fmt(e, f),
+            ZeroKnowledgeUnavailable => {
+                write!(
+                    f,
+                    "Zero-knowledge proofs need a source of randomness, which requires `std`"
+                )
+            }
+            RandomRows { required, actual } => {
+                write!(
+                    f,
+                    "Zero-knowledge proofs need {} randomized trace rows, but the trace has {}",
+                    required, actual
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Self::InvalidConstraints(ref e) => Some(e),
+            Self::MerkleFailed(ref e) => Some(e),
+            Self::VerificationFailed(ref e) => Some(e),
+            _ => None,
         }
     }
 }
 
+impl From<ConstraintError> for Error {
+    fn from(err: ConstraintError) -> Self {
+        Self::InvalidConstraints(err)
+    }
+}
+
 impl From<MerkleError> for Error {
     fn from(err: MerkleError) -> Self {
         Self::MerkleFailed(err)
@@ -51,8 +177,31 @@ impl From<VerifierError> for Error {
     }
 }
 
-#[derive(Clone, Debug)]
-struct PolyLDE(Vec<MmapVec<FieldElement>>);
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+struct PolyLDE {
+    columns: Vec<MmapVec<FieldElement>>,
+    /// Random salts mixed into the leaf hashes in zero-knowledge mode.
+    salts:   Option<MmapVec<Hash>>,
+}
+
+impl PolyLDE {
+    /// Room for `width` columns of `size` values, which are computed while
+    /// they are committed to, see `commit_lde`. The salts are drawn here.
+    fn new(width: usize, size: usize, salted: bool, scratch: &Scratch) -> Result<Self> {
+        let columns = (0..width).map(|_| zeros_in(size, scratch)).collect();
+        let salts = if salted {
+            let mut salts = MmapVec::with_capacity_in_scratch(size, scratch);
+            for _ in 0..size {
+                salts.push(Hash::new(random()?));
+            }
+            Some(salts)
+        } else {
+            None
+        };
+        Ok(Self { columns, salts })
+    }
+}
 
 /// Merkle trees over trace table LDE and constraint LDE
 // Clippy false positive
@@ -62,42 +211,54 @@ impl VectorCommitment for PolyLDE {
     type Leaf = Vec<U256>;
 
     fn len(&self) -> usize {
-        self.0.first().map_or(0, MmapVec::len)
+        self.columns.first().map_or(0, MmapVec::len)
     }
 
+    // The salt is part of the leaf, so it gets written to the proof together
+    // with the values.
     fn leaf(&self, index: usize) -> Self::Leaf {
-        let mut ret = Vec::with_capacity(self.0.len());
-        for item in &self.0 {
+        let mut ret = Vec::with_capacity(self.columns.len() + 1);
+        for item in &self.columns {
             ret.push(item[index].as_montgomery().clone())
         }
+        if let Some(salts) = &self.salts {
+            ret.push(U256::from_bytes_be(
+                &salts[index].as_bytes().try_into().unwrap(),
+            ));
+        }
         ret
     }
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        if self.0.len() == 1 {
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        if self.columns.len() == 1 && self.salts.is_none() {
             // For a single element, return its hash.
-            self.0[0][index].hash()
+            self.columns[0][index].hash_with::<H>()
         } else {
             // Concatenate the element hashes and hash the result.
-            let mut hasher = MaskedKeccak::new();
-            for value in &self.0 {
-                hasher.update(value[index].hash().as_bytes());
+            let mut hasher = H::default();
+            for value in &self.columns {
+                hasher.update(value[index].hash_with::<H>().as_bytes());
+            }
+            if let Some(salts) = &self.salts {
+                hasher.update(salts[index].as_bytes());
             }
             hasher.hash()
         }
     }
 }
 
-#[derive(Clone, Debug)]
-struct FriLeaves {
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+struct FriLeaves<E: Extension> {
     coset_size: usize,
-    layer:      MmapVec<FieldElement>,
+    layer:      MmapVec<E>,
 }
 
-type FriTree = Tree<FriLeaves>;
+type FriTree<H, E> = Tree<FriLeaves<E>, H>;
 
-// Merkle tree for FRI layers with coset size
-impl VectorCommitment for FriLeaves {
+// Merkle tree for FRI layers with coset size. The leaves hold the coefficients
+// of the values.
+impl<E: Extension> VectorCommitment for FriLeaves<E> {
     type Leaf = Vec<U256>;
 
     fn len(&self) -> usize {
@@ -106,26 +267,26 @@ impl VectorCommitment for FriLeaves {
     }
 
     fn leaf(&self, index: usize) -> Self::Leaf {
-        let mut internal_leaf = Vec::with_capacity(self.coset_size);
+        let mut internal_leaf = Vec::with_capacity(self.coset_size * E::DEGREE);
         for j in 0..self.coset_size {
-            internal_leaf.push(
-                self.layer[(index * self.coset_size + j)]
-                    .as_montgomery()
-                    .clone(),
-            );
+            for component in self.layer[index * self.coset_size + j].components() {
+                internal_leaf.push(component.as_montgomery().clone());
+            }
         }
         internal_leaf
     }
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        if self.coset_size == 1 {
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        if self.coset_size * E::DEGREE == 1 {
             // For a single element, return its hash.
-            self.layer[index].hash()
+            self.layer[index].components()[0].hash_with::<H>()
         } else {
             // Concatenate the element hashes and hash the result.
-            let mut hasher = MaskedKeccak::new();
+            let mut hasher = H::default();
             for j in 0..self.coset_size {
-                hasher.update(self.layer[(index * self.coset_size + j)].hash().as_bytes());
+                for component in self.layer[index * self.coset_size + j].components() {
+                    hasher.update(component.hash_with::<H>().as_bytes());
+                }
             }
             hasher.hash()
         }
@@ -340,20 +501,200 @@ impl VectorCommitment for FriLeaves {
 ///
 /// with merkle proofs to that layer. This process is repeated for all FRI layer
 /// commitments.
+///
+/// ## Trace stages
+///
+/// When the constraints have more than one [`Stage`](crate::Stage), use
+/// [`prove_staged`] instead.
+///
+/// ## Memory
+///
+/// The low degree extensions, constraint evaluations and Merkle trees are
+/// allocated in the [`Scratch`](crate::Scratch) of the trace, see
+/// [`TraceTable::new_in`]. With a scratch directory they go to files once its
+/// memory budget is used up, and the budget also reduces the stored Merkle
+/// tree layers. The low degree extensions are computed one coset at a time
+/// and hashed into their Merkle trees right away, and the constraints are
+/// evaluated on one coset of the trace domain at a time. The working set is
+/// therefore a few vectors of the length of the trace, while the full
+/// extensions stay in the scratch directory for the decommitments.
+pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
+    if constraints.stages().len() > 1 {
+        return Err(Error::MissingStages);
+    }
+    prove_staged(constraints, trace, |_, _| unreachable!())
+}
+
+/// Same as `prove`, but checks the inputs first.
+///
+/// The constraints are checked with `Constraints::validate` and the trace
+/// dimensions are compared with those of the constraints. When
+/// `check_trace` is set, the trace is also checked to satisfy every
+/// constraint on every row, in parallel. Proving an unsatisfied trace
+/// otherwise fails late with `Error::VerificationFailed`, without saying
+/// which constraint is at fault.
+pub fn prove_checked(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    check_trace: bool,
+) -> Result<Proof> {
+    constraints.validate()?;
+    if constraints.stages().len() > 1 {
+        return Err(Error::MissingStages);
+    }
+    if trace.num_rows() != constraints.trace_nrows() {
+        return Err(Error::TraceRows {
+            expected: constraints.trace_nrows(),
+            actual:   trace.num_rows(),
+        });
+    }
+    if trace.num_columns() != constraints.trace_ncolumns() {
+        return Err(Error::TraceColumns {
+            expected: constraints.trace_ncolumns(),
+            actual:   trace.num_columns(),
+        });
+    }
+    if check_trace {
+        par_check_constraints(constraints, trace).map_err(|(row, expression)| {
+            info!("{} fails on row {}", constraints.describe(expression), row);
+            Error::UnsatisfiedConstraint { expression, row }
+        })?;
+    }
+    prove(constraints, trace)
+}
+
+/// Prove a trace that is committed in stages.
+///
+/// `trace` holds the columns of the first stage. Step 1 commits to it and
+/// draws the challenges of the stage. Then `next_stage` is called with the
+/// columns of all previous stages and all challenges drawn so far, and returns
+/// the columns of the next stage. This repeats for every stage, each with its
+/// own commitment, before the constraints are evaluated. In step 6 every stage
+/// is decommitted separately.
+pub fn prove_staged<F>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    mut next_stage: F,
+) -> Result<Proof>
+where
+    F: FnMut(&TraceTable, &[FieldElement]) -> TraceTable,
+{
+    if constraints.zero_knowledge && cfg!(not(feature = "std")) {
+        return Err(Error::ZeroKnowledgeUnavailable);
+    }
+    with_hasher!(
+        constraints.hash_function,
+        prove_with(constraints, trace, &mut next_stage)
+    )
+}
+
+// A zero-knowledge proof reveals `num_queries` values of every trace
+// polynomial in the decommitments, and one for every row offset and extension
+// coefficient at the out of domain point. Each of them needs a randomized row
+// to hide it. Only rows that no constraint reads count, see `free_rows`.
+fn check_random_rows(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    columns: Range<usize>,
+) -> Result<()> {
+    if !constraints.zero_knowledge {
+        return Ok(());
+    }
+    let mut offsets = BTreeMap::new();
+    for (column, _) in constraints.trace_arguments() {
+        *offsets.entry(column).or_insert(0) += 1;
+    }
+    let required = constraints.num_queries
+        + constraints.extension_degree * offsets.values().max().unwrap_or(&0);
+    let actual = free_rows(constraints, trace, columns)?;
+    if actual < required {
+        return Err(Error::RandomRows { required, actual });
+    }
+    Ok(())
+}
+
+// Counts the randomized rows of `trace` that no constraint reads on a row
+// where it is enforced. `trace` holds the given columns of the full trace.
+//
+// A constraint is enforced on the rows where it is not well defined for
+// arbitrary trace values, see `RationalExpression::check`. Challenges are not
+// drawn yet, so they are arbitrary as well.
+fn free_rows(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    columns: Range<usize>,
+) -> Result<usize> {
+    let trace_length = trace.num_rows();
+    let generator = trace.generator();
+    let challenges = (0..constraints.num_challenges())
+        .map(|_| random())
+        .collect::<Result<Vec<FieldElement>>>()?;
+    let mut values = BTreeMap::new();
+    for argument in constraints.trace_arguments() {
+        let _ = values.insert(argument, random::<FieldElement>()?);
+    }
+    let arbitrary = |column: usize, offset: isize| values[&(column, offset)].clone();
+    let expressions = constraints
+        .expressions()
+        .iter()
+        .map(|expression| {
+            let expression = expression
+                .substitute_claim(&constraints.claim_polynomials)
+                .substitute_periodic(trace_length)
+                .substitute_challenges(&challenges);
+            let offsets = expression
+                .trace_arguments()
+                .into_iter()
+                .filter(|(column, _)| columns.contains(column))
+                .map(|(_, offset)| offset)
+                .collect::<BTreeSet<_>>();
+            (expression, offsets)
+        })
+        .collect::<Vec<_>>();
+    Ok(trace
+        .randomized()
+        .filter(|&row| {
+            expressions.iter().all(|(expression, offsets)| {
+                offsets.iter().all(|offset| {
+                    // The row where the constraint reads `row` at `offset`
+                    let index = (row as isize - offset).rem_euclid(trace_length as isize);
+                    expression
+                        .check(&generator.pow(index as usize), &arbitrary)
+                        .1
+                })
+            })
+        })
+        .count())
+}
+
+type NextStage<'a> = &'a mut dyn FnMut(&TraceTable, &[FieldElement]) -> TraceTable;
+
+fn prove_with<H: Hasher>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    next_stage: NextStage<'_>,
+) -> Result<Proof> {
+    match constraints.extension_degree {
+        1 => prove_in::<H, FieldElement>(constraints, trace, next_stage),
+        2 => prove_in::<H, Ext<FieldElement, 2>>(constraints, trace, next_stage),
+        degree => Err(ConstraintError::InvalidExtensionDegree(degree).into()),
+    }
+}
+
 // TODO: Simplify
 #[allow(clippy::cognitive_complexity)]
 // TODO: Split up
 #[allow(clippy::too_many_lines)]
-pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
+fn prove_in<H: Hasher, E: Extension>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    next_stage: NextStage<'_>,
+) -> Result<Proof> {
     // This hack allows us to avoid changing the interface to mut for the
     // claim polynomials but is ugly and should be removed.
     let original_constraints = constraints.clone();
     let mut constraints = constraints.clone();
-    // TODO: Verify input
-    //  * Constraint trace length matches trace table length
-    //  * Fri layout is less than trace length * blowup
-    //  * Trace(_, _) items in constraint are valid.
-    //  * Trace table satisfies constraints (expensive check, should be optional)
+    // Inputs are not checked here, see `prove_checked`.
 
     info!("Starting Stark proof.");
     trace!("BEGIN Stark proof");
@@ -367,36 +708,84 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
         size_mb
     );
     info!("{} constraints", constraints.len(),);
+    for (index, expression) in constraints.expressions().iter().enumerate() {
+        let (numerator_degree, denominator_degree) = expression.trace_degree();
+        trace!(
+            "{} has degree {}",
+            constraints.describe(index),
+            numerator_degree - denominator_degree
+        );
+    }
+
+    // All intermediate values go where the trace is.
+    let scratch = trace.scratch();
 
     info!("Initialize channel with claim.");
-    let mut proof = ProverChannel::default();
-    proof.initialize(constraints.channel_seed());
+    let mut proof = ProverChannel::<H>::default();
+    proof.initialize(&constraints.seed());
 
     // 1. Trace commitment.
     trace!("BEGIN Trace commitment");
-
-    // Compute the low degree extension of the trace table.
-    info!("Compute the low degree extension of the trace table.");
-    let trace_polynomials = trace.interpolate();
-    info!(
-        "Trace degrees: {:?}",
-        trace_polynomials
-            .iter()
-            .map(DensePolynomial::degree)
-            .collect::<Vec<_>>()
-    );
-    let trace_lde = PolyLDE(
-        trace_polynomials
-            .iter()
-            .map(|p| p.low_degree_extension(constraints.blowup))
-            .collect::<Vec<_>>(),
-    );
-
-    // Construct a merkle tree over the LDE trace
-    // and write the root to the channel.
-    info!("Construct a merkle tree over the LDE trace and write the root to the channel.");
-    let (commitment, tree) = trace_lde.commit()?;
-    proof.write(&commitment);
+    let mut trace_polynomials = Vec::with_capacity(trace.num_columns());
+    let mut trees = Vec::with_capacity(constraints.stages().len());
+    let mut challenges = Vec::with_capacity(constraints.num_challenges());
+    // The columns of all stages so far, if there is more than one.
+    let mut previous_stages: Option<TraceTable> = None;
+    // The first column of the current stage
+    let mut column = 0;
+    for (index, stage) in constraints.stages().iter().enumerate() {
+        let stage_trace = if index == 0 {
+            None
+        } else {
+            info!("Compute trace stage {}.", index);
+            let previous = previous_stages.as_ref().unwrap_or(trace);
+            let stage_trace = next_stage(previous, &challenges);
+            if stage_trace.num_rows() != trace.num_rows() {
+                return Err(Error::TraceRows {
+                    expected: trace.num_rows(),
+                    actual:   stage_trace.num_rows(),
+                });
+            }
+            if stage_trace.num_columns() != stage.columns {
+                return Err(Error::TraceColumns {
+                    expected: stage.columns,
+                    actual:   stage_trace.num_columns(),
+                });
+            }
+            if index + 1 < constraints.stages().len() {
+                previous_stages = Some(previous.concat(&stage_trace));
+            }
+            Some(stage_trace)
+        };
+        let stage_trace = stage_trace.as_ref().unwrap_or(trace);
+        check_random_rows(&constraints, stage_trace, column..column + stage.columns)?;
+        column += stage.columns;
+
+        // Compute the low degree extension of the trace table.
+        info!("Compute the low degree extension of the trace table.");
+        let stage_polynomials = stage_trace.interpolate();
+        info!(
+            "Trace degrees: {:?}",
+            stage_polynomials
+                .iter()
+                .map(DensePolynomial::degree)
+                .collect::<Vec<_>>()
+        );
+        // Construct a merkle tree over the LDE trace
+        // and write the root to the channel.
+        info!("Construct a merkle tree over the LDE trace and write the root to the channel.");
+        let (commitment, tree) = commit_lde::<H>(
+            &stage_polynomials,
+            constraints.blowup,
+            constraints.zero_knowledge,
+            scratch,
+        )?;
+        proof.write(&commitment);
+        challenges.extend(get_coefficients(&mut proof, stage.challenges));
+        trace_polynomials.extend(stage_polynomials);
+        trees.push(tree);
+    }
+    constraints.substitute_challenges(&challenges);
     trace!("END Trace commitment");
 
     // 2. Constraint commitment
@@ -404,15 +793,20 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
 
     // Read constraint coefficients from the channel.
     info!("Read constraint coefficients from the channel.");
-    let constraint_coefficients = get_coefficients(&mut proof, 2 * constraints.len());
+    let constraint_coefficients: Vec<E> = E::draw_many(&mut proof, 2 * constraints.len());
 
     info!("Compute constraint polynomials.");
-    let constraint_polynomials = get_constraint_polynomials(
-        &tree.leaves(),
+    let trace_lde = trees
+        .iter()
+        .flat_map(|tree| &tree.leaves().columns)
+        .collect::<Vec<_>>();
+    let (constraint_polynomials, eval_degree) = get_extension_constraint_polynomials(
+        &trace_lde,
         &mut constraints,
         &constraint_coefficients,
         trace.num_rows(),
-    );
+        scratch,
+    )?;
     info!(
         "Constraint degrees: {:?}",
         constraint_polynomials
@@ -423,36 +817,35 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
 
     // OPT: It may be faster to compute the constraint LDE from the trace LDE,
     // instead of using an FFT.
-    info!("Compute the low degree extension of constraint polynomials.");
-    let constraint_lde = PolyLDE(
-        constraint_polynomials
-            .iter()
-            .map(|p| p.low_degree_extension(constraints.blowup))
-            .collect::<Vec<_>>(),
-    );
     // Construct a merkle tree over the LDE combined constraints
     // and write the root to the channel.
-    info!("Compute the merkle tree over the LDE constraint polynomials.");
-    let (commitment, c_tree) = constraint_lde.commit()?;
+    info!("Compute the low degree extension of constraint polynomials and its merkle tree.");
+    let (commitment, c_tree) = commit_lde::<H>(
+        &constraint_polynomials,
+        constraints.blowup,
+        constraints.zero_knowledge,
+        scratch,
+    )?;
     proof.write(&commitment);
     trace!("END Constraint commitment");
 
     // 3. Out of domain sampling
     info!("Divide out OODS point and combine polynomials.");
     trace!("BEGIN Out of domain sampling");
-    let oods_polynomial = oods_combine(
+    let oods_polynomial = oods_combine::<H, E>(
         &mut proof,
         &trace_polynomials,
         &constraints.trace_arguments(),
         &constraint_polynomials,
+        eval_degree,
+        scratch,
     );
     trace!("END Out of domain sampling");
-    info!("Oods poly degree: {}", oods_polynomial.degree());
 
     // 4. FRI layers with trees
     info!("LDE extension of final polynomial.");
     trace!("BEGIN FRI commitment");
-    let first_fri_layer = oods_polynomial.low_degree_extension(constraints.blowup);
+    let first_fri_layer = low_degree_extension(oods_polynomial, constraints.blowup);
     info!("Fri layers.");
     let fri_trees = perform_fri_layering(
         first_fri_layer,
@@ -485,10 +878,12 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
 
     // Decommit the trace table values.
     info!("Decommit the trace table values.");
-    for &index in &query_indices {
-        proof.write(tree.leaf(index));
+    for tree in &trees {
+        for &index in &query_indices {
+            proof.write(tree.leaf(index));
+        }
+        proof.write(&tree.open(&query_indices)?);
     }
-    proof.write(&tree.open(&query_indices)?);
 
     // Decommit the constraint values
     info!("Decommit the constraint values.");
@@ -511,24 +906,269 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
     Ok(proof)
 }
 
-// Constructs a trace table on a coset domain of `size`.
-fn extract_trace_coset(trace_lde: &PolyLDE, size: usize) -> TraceTable {
-    let trace_lde: &[MmapVec<FieldElement>] = &trace_lde.0;
-    let lde_size = trace_lde[0].len();
-    let mut trace_coset = TraceTable::new(size, trace_lde.len());
-    trace!("BEGIN Extract Trace Coset");
-    for i in 0..trace_coset.num_rows() {
-        let index = i * lde_size / size;
-        let index = permute_index(lde_size, index);
-        for (j, lde) in trace_lde.iter().enumerate() {
-            trace_coset[(i, j)] = lde[index].clone();
+/// Prove several claims with a single proof.
+///
+/// Every trace and every constraint polynomial is committed to separately, but
+/// the out of domain polynomials of all claims are combined with random
+/// coefficients into a single FRI instance. Shorter claims are multiplied by a
+/// power of $x$ to bring them to the degree of the longest. This makes the
+/// proof smaller than the separate proofs together, since it has only one set
+/// of FRI decommitments.
+///
+/// All claims need the same blowup, proof of work, number of queries and hash
+/// function, and the claims with the longest trace the same FRI layout.
+/// Otherwise this returns `Error::MismatchedParameters`. The proof is
+/// verified with [`verify_batch`](crate::verify_batch). Claims with trace
+/// stages or challenges are not supported.
+pub fn prove_batch(claims: &[(&Constraints, &TraceTable)]) -> Result<Proof> {
+    let batch = claims
+        .iter()
+        .map(|(constraints, _)| *constraints)
+        .collect::<Vec<_>>();
+    let parameters = batch_parameters(&batch).ok_or(Error::EmptyBatch)?;
+    if !same_parameters(&batch) {
+        return Err(Error::MismatchedParameters);
+    }
+    if batch.iter().any(|constraints| is_staged(constraints)) {
+        return Err(Error::UnsupportedStages);
+    }
+    if batch.iter().any(|constraints| constraints.zero_knowledge) && cfg!(not(feature = "std")) {
+        return Err(Error::ZeroKnowledgeUnavailable);
+    }
+    for (constraints, trace) in claims {
+        check_random_rows(constraints, trace, 0..trace.num_columns())?;
+    }
+    with_hasher!(parameters.hash_function, prove_batch_with(claims))
+}
+
+fn prove_batch_with<H: Hasher>(claims: &[(&Constraints, &TraceTable)]) -> Result<Proof> {
+    let batch = claims
+        .iter()
+        .map(|(constraints, _)| *constraints)
+        .collect::<Vec<_>>();
+    let parameters = batch_parameters(&batch).ok_or(Error::EmptyBatch)?;
+    match parameters.extension_degree {
+        1 => prove_batch_in::<H, FieldElement>(claims),
+        2 => prove_batch_in::<H, Ext<FieldElement, 2>>(claims),
+        degree => Err(ConstraintError::InvalidExtensionDegree(degree).into()),
+    }
+}
+
+// TODO: Split up
+#[allow(clippy::too_many_lines)]
+fn prove_batch_in<H: Hasher, E: Extension>(
+    claims: &[(&Constraints, &TraceTable)],
+) -> Result<Proof> {
+    let batch = claims
+        .iter()
+        .map(|(constraints, _)| *constraints)
+        .collect::<Vec<_>>();
+    let parameters = batch_parameters(&batch).ok_or(Error::EmptyBatch)?;
+    let size = parameters.trace_nrows();
+    let blowup = parameters.blowup;
+
+    info!("Starting batch Stark proof of {} claims.", claims.len());
+    trace!("BEGIN Batch Stark proof");
+    let mut proof = ProverChannel::<H>::default();
+    proof.initialize(&batch_seed(&batch));
+
+    // 1. Trace commitments. All low degree extensions have the same size, so
+    // they share the query indices.
+    info!("Commit to the traces.");
+    let mut traces = Vec::with_capacity(claims.len());
+    for (constraints, trace) in claims {
+        let trace_polynomials = trace.interpolate();
+        let (commitment, tree) = commit_lde::<H>(
+            &trace_polynomials,
+            blowup * size / trace.num_rows(),
+            constraints.zero_knowledge,
+            trace.scratch(),
+        )?;
+        proof.write(&commitment);
+        traces.push((trace_polynomials, tree));
+    }
+
+    // 2. Constraint commitments
+    info!("Commit to the constraint polynomials.");
+    let mut committed = Vec::with_capacity(claims.len());
+    for ((constraints, trace), (_, tree)) in claims.iter().zip(&traces) {
+        let mut constraints = (*constraints).clone();
+        let constraint_coefficients: Vec<E> = E::draw_many(&mut proof, 2 * constraints.len());
+        let trace_lde = tree.leaves().columns.iter().collect::<Vec<_>>();
+        let (constraint_polynomials, eval_degree) = get_extension_constraint_polynomials(
+            &trace_lde,
+            &mut constraints,
+            &constraint_coefficients,
+            trace.num_rows(),
+            trace.scratch(),
+        )?;
+        let (commitment, c_tree) = commit_lde::<H>(
+            &constraint_polynomials,
+            blowup * size / trace.num_rows(),
+            constraints.zero_knowledge,
+            trace.scratch(),
+        )?;
+        proof.write(&commitment);
+        committed.push((constraints, constraint_polynomials, eval_degree, c_tree));
+    }
+
+    // 3. Out of domain sampling, separately for each claim
+    info!("Divide out OODS points and combine polynomials.");
+    let oods_polynomials = claims
+        .iter()
+        .zip(&traces)
+        .zip(&committed)
+        .map(
+            |(
+                ((_, trace), (trace_polynomials, _)),
+                (constraints, constraint_polynomials, eval_degree, _),
+            )| {
+                oods_combine::<H, E>(
+                    &mut proof,
+                    trace_polynomials,
+                    &constraints.trace_arguments(),
+                    constraint_polynomials,
+                    *eval_degree,
+                    trace.scratch(),
+                )
+            },
+        )
+        .collect::<Vec<_>>();
+
+    // Combine the out of domain polynomials, shifting each to degree `size`.
+    // They are in the scratch of the first claim, like everything after them.
+    let batch_coefficients: Vec<E> = E::draw_many(&mut proof, claims.len());
+    let mut coefficients = MmapVec::with_capacity_in_scratch(size, claims[0].1.scratch());
+    coefficients.resize(size, E::zero());
+    for (oods_polynomial, batch_coefficient) in oods_polynomials.iter().zip(&batch_coefficients) {
+        let shift = size - oods_polynomial.len();
+        for (coefficient, term) in coefficients[shift..].iter_mut().zip(oods_polynomial.iter()) {
+            *coefficient += batch_coefficient.clone() * term;
+        }
+    }
+
+    // 4. FRI layers with trees
+    info!("Fri layers.");
+    let first_fri_layer = low_degree_extension(coefficients, blowup);
+    let fri_trees =
+        perform_fri_layering::<H, E>(first_fri_layer, &mut proof, &parameters.fri_layout, blowup)?;
+
+    // 5. Proof of work
+    info!("Proof of work.");
+    let pow_seed: proof_of_work::ChallengeSeed = proof.get_random();
+    let pow_challenge = pow_seed.with_difficulty(parameters.pow_bits);
+    let pow_response = pow_challenge.solve();
+    debug_assert!(pow_challenge.verify(pow_response));
+    proof.write(pow_response);
+
+    // 6. Query decommitments
+    info!("Decommit queries.");
+    let eval_domain_size = size * blowup;
+    let query_indices = get_indices(
+        parameters.num_queries,
+        64 - eval_domain_size.leading_zeros() - 1,
+        &mut proof,
+    );
+    for ((_, tree), (.., c_tree)) in traces.iter().zip(&committed) {
+        for &index in &query_indices {
+            proof.write(tree.leaf(index));
         }
+        proof.write(&tree.open(&query_indices)?);
+        for &index in &query_indices {
+            proof.write(c_tree.leaf(index));
+        }
+        proof.write(&c_tree.open(&query_indices)?);
     }
-    trace!("END Extract Trace Coset");
-    trace_coset
+    decommit_fri_layers_and_trees(fri_trees.as_slice(), query_indices.as_slice(), &mut proof)?;
+
+    info!("Verify proof.");
+    let proof = Proof::from_bytes(proof.proof);
+    verify_batch(&batch, &proof)?;
+
+    trace!("END Batch Stark proof");
+    Ok(proof)
 }
 
-fn get_indices(num: usize, bits: u32, proof: &mut ProverChannel) -> Vec<usize> {
+/// Number of lower Merkle tree layers that are not stored
+///
+/// Under a memory budget the stored nodes may take at most an eighth of it.
+/// Every skipped layer halves the tree, at the cost of doubling the number of
+/// leaves rehashed for each decommitted value.
+fn merkle_skip_layers(size: usize, scratch: &Scratch) -> usize {
+    const MAX_SKIP_LAYERS: usize = 8;
+    scratch.budget().map_or(1, |budget| {
+        let mut skip_layers = 1;
+        while skip_layers < MAX_SKIP_LAYERS
+            && 2 * (size >> skip_layers) * size_of::<Hash>() > budget / 8
+        {
+            skip_layers += 1;
+        }
+        skip_layers
+    })
+}
+
+/// Commits to the low degree extensions of `polynomials`.
+///
+/// The extensions are computed one coset of the length of the polynomials at
+/// a time, one column after the other. Each coset is hashed into the Merkle
+/// tree right after it is computed, so it is read while it is still in
+/// memory. The cosets that are done can be paged out to the scratch
+/// directory, where they stay for the decommitments.
+fn commit_lde<H: Hasher>(
+    polynomials: &[DensePolynomial],
+    blowup: usize,
+    salted: bool,
+    scratch: &Scratch,
+) -> Result<(Commitment, Tree<PolyLDE, H>)> {
+    let length = polynomials.first().map_or(1, DensePolynomial::len);
+    assert!(polynomials.iter().all(|p| p.len() == length));
+    let twiddles = TwiddleCache::new(length);
+    let leaves = PolyLDE::new(polynomials.len(), length * blowup, salted, scratch)?;
+    let skip_layers = merkle_skip_layers(leaves.len(), scratch);
+    let tree = Tree::from_chunks_in(leaves, skip_layers, length, scratch, |leaves, range| {
+        for coset in range.start / length..range.end / length {
+            for (column, polynomial) in leaves.columns.iter_mut().zip(polynomials) {
+                polynomial.low_degree_extension_coset_into(
+                    blowup,
+                    coset,
+                    &twiddles,
+                    &mut column[coset * length..(coset + 1) * length],
+                );
+            }
+        }
+    })?;
+    Ok((tree.commitment().clone(), tree))
+}
+
+/// The values of the trace on the `coset`-th coset of size `size` in its low
+/// degree extension, in natural order of the rows.
+struct LdeCoset<'a> {
+    columns: &'a [&'a MmapVec<FieldElement>],
+    start:   usize,
+    size:    usize,
+}
+
+impl<'a> LdeCoset<'a> {
+    fn new(columns: &'a [&'a MmapVec<FieldElement>], size: usize, coset: usize) -> Self {
+        Self {
+            columns,
+            start: coset * size,
+            size,
+        }
+    }
+}
+
+impl TraceValues for LdeCoset<'_> {
+    fn num_rows(&self) -> usize {
+        self.size
+    }
+
+    fn value(&self, row: usize, column: usize) -> &FieldElement {
+        &self.columns[column][self.start + permute_index(self.size, row)]
+    }
+}
+
+fn get_indices<H: Hasher>(num: usize, bits: u32, proof: &mut ProverChannel<H>) -> Vec<usize> {
     let mut query_indices = Vec::with_capacity(num + 3);
     while query_indices.len() < num {
         let val: U256 = proof.get_random();
@@ -543,11 +1183,20 @@ fn get_indices(num: usize, bits: u32, proof: &mut ProverChannel) -> Vec<usize> {
     query_indices
 }
 
+// The combined constraint polynomial $C$ is split as
+// $C(x) = \sum_i x^i A_i(x^d)$, where $d$ is the evaluation degree. It is
+// evaluated one coset of the trace domain at a time, on the first cosets of
+// the trace LDE, or on cosets of size $d$ if the trace is shorter than that.
+// On each of them the points come in groups $x \cdot \omega_d^t$ with the
+// same $x^d$, which determine $x^i A_i(x^d)$ with an inverse transform of
+// size $d$. Together the cosets cover all points $x^d$, so each $A_i$ is then
+// interpolated on its own.
 fn get_constraint_polynomials(
-    trace_lde: &PolyLDE,
+    trace_lde: &[&MmapVec<FieldElement>],
     constraints: &mut Constraints,
     constraint_coefficients: &[FieldElement],
     trace_length: usize,
+    scratch: &Scratch,
 ) -> Vec<DensePolynomial> {
     // OPT: Better parallelization strategies. Probably the best would be to
     // split to domain up in smaller cosets and solve their expressions
@@ -559,10 +1208,10 @@ fn get_constraint_polynomials(
     // We need to evaluate on a power of two degree
     let constraint_degree = constraints.degree();
     let eval_degree = constraint_degree.next_power_of_two();
-    let coset_size = trace_length * eval_degree;
-
-    info!("Compute offset trace table");
-    let trace_coset = extract_trace_coset(trace_lde, coset_size);
+    let lde_size = trace_lde[0].len();
+    let blowup = lde_size / trace_length;
+    assert!(eval_degree <= blowup);
+    let coset_size = trace_length.max(eval_degree);
 
     info!("Combine rational expressions");
     let mut combined_constraints = constraints.combine(constraint_coefficients);
@@ -578,143 +1227,245 @@ fn get_constraint_polynomials(
     combined_constraints = combined_constraints.substitute_claim(&constraints.claim_polynomials);
     constraints.substitute();
 
-    let mut dag = AlgebraicGraph::new(
-        &FieldElement::generator(),
-        trace_coset.num_rows(),
-        eval_degree,
-    );
-    trace!("Convert to DAG");
-    let result = dag.expression(combined_constraints);
-
-    trace!("Compute lookup tables");
-    dag.lookup_tables();
-
-    trace!("Tree-shake DAG");
-    // TODO: Track and use result reference.
-    let _ = dag.tree_shake(result);
-    dag.init(0);
-
-    // Evaluate on the coset trace table
-    info!("Evaluate DAG on the coset trace table");
-    trace!("BEGIN Evaluate");
-    let mut result: MmapVec<FieldElement> = MmapVec::with_capacity(coset_size);
-    result.resize(coset_size, FieldElement::zero());
-    let values = &mut result;
-    values
-        .par_chunks_mut(CHUNK_SIZE)
-        .enumerate()
-        .for_each(|(mut i, chunk)| {
-            i *= CHUNK_SIZE;
+    // The evaluations of the $A_i$ on $g^d$ times the trace domain, in
+    // permuted order. Coset $k$ provides the block $k$ of each.
+    let mut evaluations = (0..eval_degree)
+        .map(|_| zeros_in(trace_length, scratch))
+        .collect::<Vec<_>>();
+    let mut values = zeros_in(coset_size, scratch);
+    let block_size = coset_size / eval_degree;
+    let group_twiddles = TwiddleCache::new(eval_degree);
+    let lde_generator = FieldElement::root(lde_size).expect("No root for LDE size.");
+    let inverse_coset_generator = FieldElement::root(coset_size)
+        .expect("No root for coset size.")
+        .inv()
+        .unwrap();
+    for coset in 0..trace_length / block_size {
+        info!("Evaluate DAG on trace coset {}", coset);
+        let cofactor = DensePolynomial::lde_shift()
+            * lde_generator.pow(permute_index(lde_size / coset_size, coset));
+        let mut dag = AlgebraicGraph::new(&cofactor, coset_size, coset_size / trace_length);
+        trace!("Convert to DAG");
+        let result = dag.expression(combined_constraints.clone());
+
+        trace!("Compute lookup tables");
+        dag.lookup_tables();
+
+        trace!("Tree-shake DAG");
+        // TODO: Track and use result reference.
+        let _ = dag.tree_shake(result);
+        dag.init(0);
+
+        trace!("BEGIN Evaluate");
+        let trace_coset = LdeCoset::new(trace_lde, coset_size, coset);
+        for_each_chunk(&mut values, CHUNK_SIZE, |(i, chunk)| {
             let mut dag = dag.clone();
-            dag.init(i);
+            dag.init(i * CHUNK_SIZE);
             for value in chunk {
                 *value = dag.next(&trace_coset);
-                i += 1;
             }
         });
-    trace!("END Evaluate");
-
-    info!("Convert from values to coefficients");
-    // TODO: Re-use interpolation function form TraceTable
-    trace!("BEGIN Interpolate");
-    values.ifft();
-    permute(values);
-    trace!("END Interpolate");
-    // OPT: Merge with even-odd separation loop.
-    for (f, y) in geometric_series(
-        &FieldElement::one(),
-        &FieldElement::generator().inv().unwrap(),
-    )
-    .zip(values.iter_mut())
-    {
-        // Shift out the generator from the evaluation domain.
-        *y *= &f;
-    }
-
-    // Convert to even and odd coefficient polynomials
-    let mut constraint_polynomials: Vec<MmapVec<FieldElement>> =
-        vec![MmapVec::with_capacity(trace_length); eval_degree];
-    let (coefficients, zeros) = values.split_at(eval_degree * trace_length);
-    assert!(zeros.iter().all(|z| z == &FieldElement::zero()));
-    for chunk in coefficients.chunks_exact(eval_degree) {
-        for (i, coefficient) in chunk.iter().enumerate() {
-            constraint_polynomials[i].push(coefficient.clone());
+        trace!("END Evaluate");
+
+        // Row $t$ of the blocks holds the values on $x \cdot \omega_d^t$,
+        // the inverse transform expects them in permuted order.
+        for t in 0..eval_degree {
+            let u = permute_index(eval_degree, t);
+            if u > t {
+                let (left, right) = values.split_at_mut(u * block_size);
+                left[t * block_size..(t + 1) * block_size]
+                    .swap_with_slice(&mut right[..block_size]);
+            }
+        }
+        group_twiddles.ifft_coset(&mut values, block_size, &FieldElement::one());
+
+        // Row $i$ now holds $x^i A_i(x^d)$ for $x$ in the first block
+        let inverse_cofactor = cofactor.inv().unwrap();
+        for (i, (row, evaluation)) in values
+            .chunks_exact(block_size)
+            .zip(evaluations.iter_mut())
+            .enumerate()
+        {
+            let block = &mut evaluation[coset * block_size..(coset + 1) * block_size];
+            for ((target, value), factor) in block.iter_mut().zip(row).zip(geometric_series(
+                &inverse_cofactor.pow(i),
+                &inverse_coset_generator.pow(i),
+            )) {
+                *target = value * &factor;
+            }
+            permute(block);
         }
     }
-    let result = constraint_polynomials
+    drop(values);
+
+    info!("Convert from values to coefficients");
+    let twiddles = TwiddleCache::new(trace_length);
+    let shift = DensePolynomial::lde_shift().pow(eval_degree);
+    let result = evaluations
         .into_iter()
-        .map(DensePolynomial::from_mmap_vec)
+        .map(|mut evaluation| {
+            twiddles.ifft_coset(&mut evaluation, 1, &shift);
+            DensePolynomial::from_mmap_vec(evaluation)
+        })
         .collect();
     trace!("END Compute constraint polynomials");
     result
 }
 
-fn oods_combine(
-    proof: &mut ProverChannel,
+/// The constraint polynomials for coefficients in an extension field
+///
+/// The combination of the constraints is linear in the coefficients, so it is
+/// computed separately for each of their coefficients over `FieldElement`.
+/// Column `j * E::DEGREE + k` holds the `k`-th coefficient of the `j`-th
+/// constraint polynomial. In zero-knowledge mode the coefficients of a random
+/// masking polynomial are added last. Also returns the number of constraint
+/// polynomials without it.
+fn get_extension_constraint_polynomials<E: Extension>(
+    trace_lde: &[&MmapVec<FieldElement>],
+    constraints: &mut Constraints,
+    constraint_coefficients: &[E],
+    trace_length: usize,
+    scratch: &Scratch,
+) -> Result<(Vec<DensePolynomial>, usize)> {
+    let original = constraints.clone();
+    let mut components = Vec::with_capacity(E::DEGREE);
+    for index in 0..E::DEGREE {
+        let coefficients = constraint_coefficients
+            .iter()
+            .map(|coefficient| coefficient.components()[index].clone())
+            .collect::<Vec<_>>();
+        *constraints = original.clone();
+        components.push(
+            get_constraint_polynomials(
+                trace_lde,
+                constraints,
+                &coefficients,
+                trace_length,
+                scratch,
+            )
+            .into_iter(),
+        );
+    }
+    let eval_degree = components[0].len();
+    let mut constraint_polynomials = Vec::with_capacity(E::DEGREE * (eval_degree + 1));
+    for _ in 0..eval_degree {
+        for component in &mut components {
+            constraint_polynomials.extend(component.next());
+        }
+    }
+    if constraints.zero_knowledge {
+        // The random polynomial masks the out of domain combination, and with
+        // that the FRI layers.
+        info!("Add random masking polynomial.");
+        for _ in 0..E::DEGREE {
+            let mut coefficients = MmapVec::with_capacity_in_scratch(trace_length, scratch);
+            for _ in 0..trace_length {
+                coefficients.push(random()?);
+            }
+            constraint_polynomials.push(DensePolynomial::from_mmap_vec(coefficients));
+        }
+    }
+    Ok((constraint_polynomials, eval_degree))
+}
+
+fn oods_combine<H: Hasher, E: Extension>(
+    proof: &mut ProverChannel<H>,
     trace_polynomials: &[DensePolynomial],
     trace_arguments: &[(usize, isize)],
     constraint_polynomials: &[DensePolynomial],
-) -> DensePolynomial {
+    eval_degree: usize,
+    scratch: &Scratch,
+) -> MmapVec<E> {
     // Fetch the oods sampling point
     let trace_length = trace_polynomials[0].len();
-    let oods_point: FieldElement = proof.get_random();
-    dbg!(oods_point.clone());
+    let oods_point = E::draw(proof);
     let g = FieldElement::root(trace_length).expect("No root for trace polynomial length.");
 
-    // Write point evaluations to proof
+    // Write point evaluations to proof, one coefficient at a time
     // OPT: Parallelization
     for (column, offset) in trace_arguments {
-        proof
-            .write(&trace_polynomials[*column].evaluate(&(&oods_point * &g.pow(*offset).unwrap())));
+        let value = E::evaluate(
+            &trace_polynomials[*column],
+            &oods_point.mul_base(&g.pow(*offset).unwrap()),
+        );
+        for component in value.components() {
+            proof.write(component);
+        }
     }
 
-    let oods_point_pow = oods_point.pow(constraint_polynomials.len());
-    let mut oods_value = FieldElement::zero();
-    for (i, constraint_polynomial) in constraint_polynomials.iter().enumerate() {
-        let value = constraint_polynomial.evaluate(&oods_point_pow);
-        proof.write(&value);
-        // dbg!(oods_value);
-        oods_value += oods_point.pow(i) * value;
+    // The constraint polynomials have their coefficients in separate columns
+    let oods_point_pow = oods_point.power(eval_degree);
+    for components in constraint_polynomials.chunks_exact(E::DEGREE) {
+        let value: E = components
+            .iter()
+            .enumerate()
+            .map(|(index, polynomial)| E::basis(index) * E::evaluate(polynomial, &oods_point_pow))
+            .sum();
+        for component in value.components() {
+            proof.write(component);
+        }
     }
-    dbg!(oods_value);
 
     // Divide out points and linear sum the polynomials
     // OPT: Parallelization
-    let trace_coefficients = get_coefficients(proof, trace_arguments.len());
-    let constraint_coefficients = get_coefficients(proof, constraint_polynomials.len());
+    let trace_coefficients: Vec<E> = E::draw_many(proof, trace_arguments.len());
+    let constraint_coefficients: Vec<E> =
+        E::draw_many(proof, constraint_polynomials.len() / E::DEGREE);
 
-    let mut combined_polynomial = DensePolynomial::zeros(trace_length);
+    let mut combined_polynomial = MmapVec::with_capacity_in_scratch(trace_length, scratch);
+    combined_polynomial.resize(trace_length, E::zero());
     for ((column, offset), coefficient) in trace_arguments.iter().zip(&trace_coefficients) {
         trace_polynomials[*column].divide_out_point_into(
-            &(&oods_point * g.pow(*offset).unwrap()),
+            &oods_point.mul_base(&g.pow(*offset).unwrap()),
             coefficient,
             &mut combined_polynomial,
         );
     }
-    for (constraint_polynomial, coefficient) in
-        constraint_polynomials.iter().zip(&constraint_coefficients)
+    for (components, coefficient) in constraint_polynomials
+        .chunks_exact(E::DEGREE)
+        .zip(&constraint_coefficients)
     {
-        constraint_polynomial.divide_out_point_into(
-            &oods_point_pow,
-            coefficient,
-            &mut combined_polynomial,
-        );
+        for (index, constraint_polynomial) in components.iter().enumerate() {
+            constraint_polynomial.divide_out_point_into(
+                &oods_point_pow,
+                &(coefficient.clone() * E::basis(index)),
+                &mut combined_polynomial,
+            );
+        }
     }
     combined_polynomial
 }
 
+/// The low degree extension of a polynomial with coefficients in an extension
+/// field, see `DensePolynomial::low_degree_extension`. It is allocated in the
+/// same scratch as the coefficients.
+fn low_degree_extension<E: Extension>(coefficients: MmapVec<E>, blowup: usize) -> MmapVec<E> {
+    E::from_columns(
+        E::into_columns(coefficients)
+            .into_iter()
+            .map(|column| DensePolynomial::from_mmap_vec(column).low_degree_extension(blowup))
+            .collect(),
+    )
+}
+
+/// Folds a pair of values of a FRI layer with the folding challenge
+fn fri_fold<E: Extension>(p0: &E, p1: &E, x_inv: &FieldElement, coefficient: &E) -> E {
+    (p0.clone() + p1) + coefficient.mul_base(x_inv) * (p0.clone() - p1)
+}
+
 // TODO: remove this and refactor ProverChannel.
-fn get_coefficients(proof: &mut ProverChannel, n: usize) -> Vec<FieldElement> {
+fn get_coefficients<H: Hasher>(proof: &mut ProverChannel<H>, n: usize) -> Vec<FieldElement> {
     (0..n).map(|_| proof.get_random()).collect()
 }
 
-fn perform_fri_layering(
-    first_layer: MmapVec<FieldElement>,
-    proof: &mut ProverChannel,
+fn perform_fri_layering<H: Hasher, E: Extension>(
+    first_layer: MmapVec<E>,
+    proof: &mut ProverChannel<H>,
     fri_layout: &[usize],
     blowup: usize,
-) -> Result<Vec<FriTree>> {
-    let mut fri_trees: Vec<FriTree> = Vec::with_capacity(fri_layout.len());
+) -> Result<Vec<FriTree<H, E>>> {
+    let mut fri_trees: Vec<FriTree<H, E>> = Vec::with_capacity(fri_layout.len());
+    let scratch = first_layer.scratch().clone();
 
     // Compute 1/x for the fri layer. We only compute the even coordinates.
     // OPT: Can these be efficiently computed on the fly?
@@ -724,7 +1475,7 @@ fn perform_fri_layering(
             .ok_or(Error::RootUnavailable)?
             .inv()
             .unwrap();
-        let mut x_inv = MmapVec::with_capacity(n / 2);
+        let mut x_inv = MmapVec::with_capacity_in_scratch(n / 2, &scratch);
         let mut accumulator = FieldElement::one();
         for _ in 0..n / 2 {
             x_inv.push(accumulator.clone());
@@ -737,21 +1488,22 @@ fn perform_fri_layering(
     let mut next_layer = first_layer;
     for &n_reductions in fri_layout {
         // Allocate next and swap ownership
-        let mut layer = MmapVec::with_capacity(next_layer.len() / (1 << n_reductions));
+        let mut layer =
+            MmapVec::with_capacity_in_scratch(next_layer.len() / (1 << n_reductions), &scratch);
         std::mem::swap(&mut layer, &mut next_layer);
 
         // Create tree from layer
         // FRI layout values are small.
         #[allow(clippy::cast_possible_truncation)]
         let coset_size = 2_usize.pow(n_reductions as u32);
-        let tree = FriTree::from_leaves(FriLeaves { coset_size, layer })?;
+        let tree = FriTree::<H, E>::from_leaves_in(FriLeaves { coset_size, layer }, 1, &scratch)?;
         fri_trees.push(tree);
         let tree = fri_trees.last().unwrap();
         let layer = &tree.leaves().layer;
 
         // Write commitment and pull coefficient
         proof.write(tree.commitment());
-        let coefficient = proof.get_random();
+        let coefficient = E::draw(proof);
 
         // Fold layer up to three times
         // TODO: Capture the pattern in a macro and DRY.
@@ -765,56 +1517,56 @@ fn perform_fri_layering(
                     layer
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1)),
+                        .map(|((p0, p1), x_inv)| fri_fold(p0, p1, x_inv, &coefficient)),
                 )
             }
             2 => {
-                let coefficient_2 = coefficient.square();
+                let coefficient_2 = coefficient.square_inline();
                 next_layer.extend(
                     layer
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
+                        .map(|((p0, p1), x_inv)| fri_fold(p0, p1, x_inv, &coefficient))
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1)),
+                        .map(|((p0, p1), x_inv)| fri_fold(&p0, &p1, x_inv, &coefficient_2)),
                 )
             }
             3 => {
-                let coefficient_2 = coefficient.square();
-                let coefficient_4 = coefficient_2.square();
+                let coefficient_2 = coefficient.square_inline();
+                let coefficient_4 = coefficient_2.square_inline();
                 next_layer.extend(
                     layer
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
+                        .map(|((p0, p1), x_inv)| fri_fold(p0, p1, x_inv, &coefficient))
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1))
+                        .map(|((p0, p1), x_inv)| fri_fold(&p0, &p1, x_inv, &coefficient_2))
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_4 * x_inv * (p0 - p1)),
+                        .map(|((p0, p1), x_inv)| fri_fold(&p0, &p1, x_inv, &coefficient_4)),
                 )
             }
             // TODO: Is there a use case for 4 layer folds?
             4 => {
-                let coefficient_2 = coefficient.square();
-                let coefficient_4 = coefficient_2.square();
-                let coefficient_8 = coefficient_4.square();
+                let coefficient_2 = coefficient.square_inline();
+                let coefficient_4 = coefficient_2.square_inline();
+                let coefficient_8 = coefficient_4.square_inline();
                 next_layer.extend(
                     layer
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
+                        .map(|((p0, p1), x_inv)| fri_fold(p0, p1, x_inv, &coefficient))
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1))
+                        .map(|((p0, p1), x_inv)| fri_fold(&p0, &p1, x_inv, &coefficient_2))
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_4 * x_inv * (p0 - p1))
+                        .map(|((p0, p1), x_inv)| fri_fold(&p0, &p1, x_inv, &coefficient_4))
                         .tuples()
                         .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_8 * x_inv * (p0 - p1)),
+                        .map(|((p0, p1), x_inv)| fri_fold(&p0, &p1, x_inv, &coefficient_8)),
                 )
             }
             _ => unimplemented!(),
@@ -823,19 +1575,24 @@ fn perform_fri_layering(
 
     // Write the final layer coefficients
     let n_coefficients = next_layer.len() / blowup;
-    let points = &mut next_layer[0..n_coefficients];
-    permute(points);
-    points.ifft();
-    permute(points);
-    proof.write(&*points);
+    next_layer.truncate(n_coefficients);
+    let mut columns = E::into_columns(next_layer);
+    for column in &mut columns {
+        let points = column.as_mut_slice();
+        permute(points);
+        points.ifft();
+        permute(points);
+    }
+    let points = flatten(&E::from_columns(columns));
+    proof.write(points.as_slice());
 
     Ok(fri_trees)
 }
 
-fn decommit_fri_layers_and_trees(
-    fri_trees: &[FriTree],
+fn decommit_fri_layers_and_trees<H: Hasher, E: Extension>(
+    fri_trees: &[FriTree<H, E>],
     query_indices: &[usize],
-    proof: &mut ProverChannel,
+    proof: &mut ProverChannel<H>,
 ) -> Result<()> {
     let mut previous_indices: Vec<usize> = query_indices.to_vec();
 
@@ -854,7 +1611,11 @@ fn decommit_fri_layers_and_trees(
                 let n = i * coset_size + j;
                 match previous_indices.binary_search(&n) {
                     Ok(_) => (),
-                    Err(_) => proof.write(&tree.leaves().layer[n]),
+                    Err(_) => {
+                        for component in tree.leaves().layer[n].components() {
+                            proof.write(component)
+                        }
+                    }
                 };
             }
         }
@@ -867,8 +1628,13 @@ fn decommit_fri_layers_and_trees(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{traits::tests::Recurrance, verify, Provable, Verifiable};
+    use crate::{
+        traits::tests::Recurrance, verify, HashFunction, Provable, RationalExpression, Stage,
+        StructuredProof, Verifiable,
+    };
+    use std::convert::TryFrom;
     use tiny_keccak::{Hasher, Sha3};
+    use zkp_hash::MaskedKeccak;
     use zkp_macros_decl::{field_element, hex, u256h};
     use zkp_primefield::{fft::permute_index, geometric_series::geometric_series};
     use zkp_u256::U256;
@@ -890,6 +1656,8 @@ mod tests {
         constraints.pow_bits = 0;
         constraints.num_queries = 20;
         constraints.fri_layout = vec![3, 2];
+        // The reference proof predates the constraint digest
+        constraints.legacy_seed = true;
 
         let trace = claim.trace(&witness);
         let actual = prove(&constraints, &trace).unwrap();
@@ -931,6 +1699,7 @@ mod tests {
         constraints.pow_bits = 12;
         constraints.num_queries = 20;
         constraints.fri_layout = vec![3, 2];
+        constraints.legacy_seed = true;
         let proof = prove(&constraints, &trace).unwrap();
 
         let mut output = [0; 32];
@@ -963,6 +1732,403 @@ mod tests {
         verify(&constraints, &actual).unwrap();
     }
 
+    #[test]
+    fn small_fibonacci() {
+        let recurrance = Recurrance {
+            index:         25,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let witness = recurrance.witness();
+        let claim = recurrance.claim();
+
+        let mut constraints = claim.constraints();
+        constraints.num_queries = 20;
+        let trace = claim.trace(&witness);
+        let proof = prove(&constraints, &trace).unwrap();
+        verify(&constraints, &proof).unwrap();
+        assert!(proof.as_bytes().len() <= constraints.max_proof_size());
+    }
+
+    #[test]
+    fn zero_knowledge() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let witness = recurrance.witness();
+        let claim = recurrance.claim();
+
+        let mut constraints = claim.zero_knowledge_constraints();
+        constraints.num_queries = 20;
+        let trace = claim.zero_knowledge_trace(&witness);
+        let first = prove(&constraints, &trace).unwrap();
+        let second = prove(&constraints, &trace).unwrap();
+        verify(&constraints, &first).unwrap();
+        verify(&constraints, &second).unwrap();
+
+        // The trace commitment is salted
+        assert_ne!(first.as_bytes()[0..32], second.as_bytes()[0..32]);
+        assert!(first.as_bytes().len() <= constraints.max_proof_size());
+    }
+
+    #[test]
+    fn zero_knowledge_random_rows() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let witness = recurrance.witness();
+        let claim = recurrance.claim();
+
+        // There are 23 free rows, which hide 20 queries and 2 row offsets
+        let mut constraints = claim.zero_knowledge_constraints();
+        constraints.num_queries = 20;
+        assert_eq!(
+            prove(&constraints, &claim.trace(&witness)),
+            Err(Error::RandomRows {
+                required: 22,
+                actual:   0,
+            })
+        );
+        constraints.num_queries = 22;
+        assert_eq!(
+            prove(&constraints, &claim.zero_knowledge_trace(&witness)),
+            Err(Error::RandomRows {
+                required: 24,
+                actual:   23,
+            })
+        );
+
+        // Randomized rows that the constraints read do not count
+        let mut constraints = claim.constraints();
+        constraints.zero_knowledge = true;
+        constraints.num_queries = 20;
+        assert_eq!(
+            prove(&constraints, &claim.zero_knowledge_trace(&witness)),
+            Err(Error::RandomRows {
+                required: 22,
+                actual:   0,
+            })
+        );
+    }
+
+    #[test]
+    fn extension_field() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let witness = recurrance.witness();
+        let claim = recurrance.claim();
+
+        let mut constraints = claim.constraints();
+        constraints.num_queries = 20;
+        constraints.fri_layout = vec![1, 4, 2];
+        constraints.extension_degree = 2;
+        constraints.validate().unwrap();
+        let trace = claim.trace(&witness);
+        let proof = prove(&constraints, &trace).unwrap();
+        verify(&constraints, &proof).unwrap();
+        assert!(proof.as_bytes().len() <= constraints.max_proof_size());
+
+        let structured = StructuredProof::from_proof(&constraints, &proof).unwrap();
+        assert_eq!(Proof::try_from(&structured), Ok(proof.clone()));
+
+        // The extension degree is part of the claim
+        let mut base = constraints.clone();
+        base.extension_degree = 1;
+        assert!(verify(&base, &proof).is_err());
+
+        constraints.extension_degree = 3;
+        assert_eq!(
+            prove(&constraints, &trace),
+            Err(Error::InvalidConstraints(
+                ConstraintError::InvalidExtensionDegree(3)
+            ))
+        );
+    }
+
+    #[test]
+    fn extension_field_zero_knowledge() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let witness = recurrance.witness();
+        let claim = recurrance.claim();
+
+        // Every row offset reveals two coefficients at the out of domain point
+        let mut constraints = claim.zero_knowledge_constraints();
+        constraints.num_queries = 20;
+        constraints.extension_degree = 2;
+        let trace = claim.zero_knowledge_trace(&witness);
+        assert_eq!(
+            prove(&constraints, &trace),
+            Err(Error::RandomRows {
+                required: 24,
+                actual:   23,
+            })
+        );
+        constraints.num_queries = 19;
+        let proof = prove(&constraints, &trace).unwrap();
+        verify(&constraints, &proof).unwrap();
+        assert!(proof.as_bytes().len() <= constraints.max_proof_size());
+    }
+
+    #[test]
+    fn extension_field_batch() {
+        let claims = [(1000, "cafebabe"), (300, "deadbeef")]
+            .iter()
+            .map(|&(index, initial_value)| {
+                let recurrance = Recurrance {
+                    index,
+                    initial_value: FieldElement::from(U256::from_hex_str(initial_value)),
+                    exponent: 1,
+                };
+                let claim = recurrance.claim();
+                let mut constraints = claim.constraints();
+                constraints.num_queries = 20;
+                constraints.extension_degree = 2;
+                (constraints, claim.trace(&recurrance.witness()))
+            })
+            .collect::<Vec<_>>();
+        let batch = claims
+            .iter()
+            .map(|(constraints, trace)| (constraints, trace))
+            .collect::<Vec<_>>();
+        let proof = prove_batch(&batch).unwrap();
+        let constraints = claims
+            .iter()
+            .map(|(constraints, _)| constraints)
+            .collect::<Vec<_>>();
+        verify_batch(&constraints, &proof).unwrap();
+
+        // All claims need the same extension degree
+        let mut other = claims[1].0.clone();
+        other.extension_degree = 1;
+        let mismatched = [(&claims[0].0, &claims[0].1), (&other, &claims[1].1)];
+        assert_eq!(prove_batch(&mismatched), Err(Error::MismatchedParameters));
+    }
+
+    #[test]
+    fn checked() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let claim = recurrance.claim();
+        let constraints = claim.constraints();
+        let mut trace = claim.trace(&recurrance.witness());
+        let proof = prove_checked(&constraints, &trace, true).unwrap();
+        verify(&constraints, &proof).unwrap();
+
+        let mut invalid = constraints.clone();
+        invalid.fri_layout = vec![3, 3, 3, 3];
+        assert_eq!(
+            prove_checked(&invalid, &trace, false),
+            Err(Error::InvalidConstraints(ConstraintError::InvalidFriLayout))
+        );
+        assert_eq!(
+            prove_checked(&constraints, &TraceTable::new(512, 2), false),
+            Err(Error::TraceRows {
+                expected: 1024,
+                actual:   512,
+            })
+        );
+
+        trace[(500, 1)] += FieldElement::one();
+        assert_eq!(
+            prove_checked(&constraints, &trace, true),
+            Err(Error::UnsatisfiedConstraint {
+                expression: 1,
+                row:        499,
+            })
+        );
+    }
+
+    #[test]
+    fn hash_functions() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let witness = recurrance.witness();
+        let claim = recurrance.claim();
+
+        let mut constraints = claim.constraints();
+        constraints.num_queries = 20;
+        let trace = claim.trace(&witness);
+        let default = prove(&constraints, &trace).unwrap();
+        for &hash_function in &[
+            HashFunction::Keccak256,
+            HashFunction::Blake2s,
+            HashFunction::Sha256,
+            HashFunction::Poseidon,
+        ] {
+            constraints.hash_function = hash_function;
+            let proof = prove(&constraints, &trace).unwrap();
+            verify(&constraints, &proof).unwrap();
+            assert!(proof.as_bytes().len() <= constraints.max_proof_size());
+            assert_ne!(proof.as_bytes()[0..32], default.as_bytes()[0..32]);
+        }
+    }
+
+    #[test]
+    fn batch() {
+        let claims = [
+            (1000, "cafebabe"),
+            (100, "deadbeef"),
+            (300, "0badf00d"),
+            (300, "0ddba11"),
+        ]
+        .iter()
+        .map(|&(index, initial_value)| {
+            let recurrance = Recurrance {
+                index,
+                initial_value: FieldElement::from(U256::from_hex_str(initial_value)),
+                exponent: 1,
+            };
+            let claim = recurrance.claim();
+            let (mut constraints, trace) = if index == 100 {
+                (
+                    claim.zero_knowledge_constraints(),
+                    claim.zero_knowledge_trace(&recurrance.witness()),
+                )
+            } else {
+                (claim.constraints(), claim.trace(&recurrance.witness()))
+            };
+            constraints.num_queries = 20;
+            (constraints, trace)
+        })
+        .collect::<Vec<_>>();
+        let batch = claims
+            .iter()
+            .map(|(constraints, trace)| (constraints, trace))
+            .collect::<Vec<_>>();
+        let proof = prove_batch(&batch).unwrap();
+
+        let constraints = claims
+            .iter()
+            .map(|(constraints, _)| constraints)
+            .collect::<Vec<_>>();
+        verify_batch(&constraints, &proof).unwrap();
+
+        // Claims are bound to their position in the batch
+        let swapped = [
+            constraints[0],
+            constraints[1],
+            constraints[3],
+            constraints[2],
+        ];
+        assert!(verify_batch(&swapped, &proof).is_err());
+
+        let separate: usize = claims
+            .iter()
+            .map(|(constraints, trace)| prove(constraints, trace).unwrap().as_bytes().len())
+            .sum();
+        assert!(proof.as_bytes().len() < separate);
+
+        // All claims need the same proof parameters
+        let mut other = claims[1].0.clone();
+        other.pow_bits += 1;
+        let mismatched = [(&claims[0].0, &claims[0].1), (&other, &claims[1].1)];
+        assert_eq!(prove_batch(&mismatched), Err(Error::MismatchedParameters));
+        assert_eq!(
+            verify_batch(&[&claims[0].0, &other], &proof),
+            Err(VerifierError::MismatchedParameters)
+        );
+    }
+
+    #[test]
+    fn staged() {
+        use RationalExpression::*;
+
+        // A permutation argument: the second column is a permutation of the
+        // first, which is checked with a running product over a challenge.
+        let size = 16;
+        let g = Constant(FieldElement::root(size).unwrap());
+        let on_row = |index| (X - g.pow(index)).inv();
+        let every_row = || (X - g.pow(size - 1)) / (X.pow(size) - 1);
+        let mut constraints = Constraints::from_expressions((size, 3), b"staged".to_vec(), vec![
+            (Trace(2, 1) * (Trace(1, 0) + Challenge(0))
+                - Trace(2, 0) * (Trace(0, 0) + Challenge(0)))
+                * every_row(),
+            (Trace(2, 0) - 1) * on_row(0),
+            (Trace(2, 0) * (Trace(0, 0) + Challenge(0)) - Trace(1, 0) - Challenge(0))
+                * on_row(size - 1),
+        ])
+        .unwrap();
+        constraints
+            .set_stages(vec![
+                Stage {
+                    columns:    2,
+                    challenges: 1,
+                },
+                Stage {
+                    columns:    1,
+                    challenges: 0,
+                },
+            ])
+            .unwrap();
+        constraints.num_queries = 20;
+
+        let mut trace = TraceTable::new(size, 2);
+        for i in 0..size {
+            trace[(i, 0)] = FieldElement::from(i);
+            trace[(i, 1)] = FieldElement::from((7 * i) % size);
+        }
+        let running_product = |trace: &TraceTable, challenges: &[FieldElement]| {
+            let mut stage = TraceTable::new(size, 1);
+            let mut product = FieldElement::one();
+            for i in 0..size {
+                stage[(i, 0)] = product.clone();
+                product *= (&trace[(i, 0)] + &challenges[0]) / (&trace[(i, 1)] + &challenges[0]);
+            }
+            stage
+        };
+
+        let proof = prove_staged(&constraints, &trace, &running_product).unwrap();
+        verify(&constraints, &proof).unwrap();
+        assert_eq!(prove(&constraints, &trace), Err(Error::MissingStages));
+
+        let structured = StructuredProof::from_proof(&constraints, &proof).unwrap();
+        assert_eq!(structured.stage_commitments.len(), 1);
+        assert_eq!(structured.to_proof(), Ok(proof));
+
+        // The stage has the wrong shape
+        assert_eq!(
+            prove_staged(
+                &constraints,
+                &trace,
+                |_: &TraceTable, _: &[FieldElement]| { TraceTable::new(size, 2) }
+            ),
+            Err(Error::TraceColumns {
+                expected: 1,
+                actual:   2,
+            })
+        );
+
+        // Not a permutation
+        trace[(0, 1)] = FieldElement::from(size);
+        assert!(prove_staged(&constraints, &trace, &running_product).is_err());
+    }
+
+    #[test]
+    fn empty_batch() {
+        assert_eq!(prove_batch(&[]), Err(Error::EmptyBatch));
+        assert_eq!(
+            verify_batch(&[], &Proof::from_bytes(vec![])),
+            Err(VerifierError::EmptyBatch)
+        );
+    }
+
     // TODO: What are we actually testing here? Should we add these as debug_assert
     // to the main implementation? Should we break up the implementation so we
     // can test the individual steps?
@@ -1007,11 +2173,9 @@ mod tests {
         // Checks that the trace table polynomial interpolation is working
         assert_eq!(TPn[0].evaluate(&g.pow(1000_usize)), trace[(1000, 0)]);
 
-        let LDEn = PolyLDE(
-            TPn.iter()
-                .map(|p| p.low_degree_extension(constraints.blowup))
-                .collect::<Vec<_>>(),
-        );
+        let (commitment, tree) =
+            commit_lde::<MaskedKeccak>(&TPn, constraints.blowup, false, trace.scratch()).unwrap();
+        let LDEn = tree.leaves();
 
         // Checks that the low degree extension calculation is working
         let i = 13644_usize;
@@ -1019,8 +2183,14 @@ mod tests {
         let eval_offset_x = geometric_series(&gen, &omega)
             .take(eval_domain_size)
             .collect::<Vec<_>>();
-        assert_eq!(TPn[0].evaluate(&eval_offset_x[reverse_i]), LDEn.0[0][i]);
-        assert_eq!(TPn[1].evaluate(&eval_offset_x[reverse_i]), LDEn.0[1][i]);
+        assert_eq!(
+            TPn[0].evaluate(&eval_offset_x[reverse_i]),
+            LDEn.columns[0][i]
+        );
+        assert_eq!(
+            TPn[1].evaluate(&eval_offset_x[reverse_i]),
+            LDEn.columns[1][i]
+        );
 
         // Checks that the groupable trait is properly grouping for &[Vec<FieldElement>]
         assert_eq!(
@@ -1032,7 +2202,6 @@ mod tests {
             u256h!("03dbc6c47df0606997c2cefb20c4277caf2b76bca1d31c13432f71cdd93b3718")
         );
 
-        let (commitment, tree) = LDEn.commit().unwrap();
         // Checks that the merklelizable implementation is working [implicit check of
         // most previous steps]
         assert_eq!(
@@ -1040,7 +2209,7 @@ mod tests {
             hex!("018dc61f748b1a6c440827876f30f63cb6c4c188000000000000000000000000")
         );
 
-        let mut proof = ProverChannel::default();
+        let mut proof: ProverChannel = ProverChannel::default();
         proof.initialize(&claim.seed());
         // Checks that the channel is inited properly
         assert_eq!(
@@ -1068,26 +2237,28 @@ mod tests {
         }
 
         let constraint_polynomials = get_constraint_polynomials(
-            &tree.leaves(),
+            &tree.leaves().columns.iter().collect::<Vec<_>>(),
             &mut constraints,
             &constraint_coefficients,
             trace.num_rows(),
+            trace.scratch(),
         );
         assert_eq!(constraint_polynomials.len(), 1);
         assert_eq!(constraint_polynomials[0].len(), 1024);
-        let CC = PolyLDE(
-            constraint_polynomials
-                .iter()
-                .map(|p| p.low_degree_extension(constraints.blowup))
-                .collect::<Vec<_>>(),
-        );
+        let (commitment, c_tree) = commit_lde::<MaskedKeccak>(
+            &constraint_polynomials,
+            constraints.blowup,
+            false,
+            trace.scratch(),
+        )
+        .unwrap();
+        let CC = c_tree.leaves();
         // Checks that our constraints are properly calculated on the domain
         assert_eq!(
-            CC.0[0][permute_index(eval_domain_size, 123)].clone(),
+            CC.columns[0][permute_index(eval_domain_size, 123)].clone(),
             field_element!("05b841208b357e29ac1fe7a654efebe1ae152104571e695f311a353d4d5cabfb")
         );
 
-        let (commitment, c_tree) = CC.commit().unwrap();
         // Checks both that the merkle tree is working for this groupable type and that
         // the constraints are properly calculated on the domain
         assert_eq!(
@@ -1097,7 +2268,14 @@ mod tests {
         proof.write(&commitment);
 
         let trace_arguments = constraints.trace_arguments();
-        let CO = oods_combine(&mut proof, &TPn, &trace_arguments, &constraint_polynomials);
+        let CO = DensePolynomial::from_mmap_vec(oods_combine::<_, FieldElement>(
+            &mut proof,
+            &TPn,
+            &trace_arguments,
+            &constraint_polynomials,
+            constraint_polynomials.len(),
+            trace.scratch(),
+        ));
         // Checks that our get out of domain function call has written the right values
         // to the proof
         assert_eq!(
@@ -0,0 +1,176 @@
+// Clippy false positive
+#[allow(clippy::useless_attribute)]
+// We want to import an alternative prelude.
+#[allow(clippy::wildcard_imports)]
+use std::prelude::v1::*;
+
+use zkp_primefield::FieldElement;
+
+/// The public values of a claim
+///
+/// The values are absorbed into the channel before anything else, so a proof
+/// only verifies for the claim it was made for. `Verifiable` and
+/// `component::Component` do this automatically, and can additionally pin
+/// values to trace cells with named boundary constraints.
+pub trait PublicInput {
+    /// The public values in canonical order.
+    fn public_values(&self) -> Vec<FieldElement>;
+
+    /// Names of the public values, used to label their boundary constraints.
+    ///
+    /// Must have one name for each value. Defaults to the index of the value.
+    /// Empty names are replaced by the index as well.
+    fn public_names(&self) -> Vec<String> {
+        (0..self.public_values().len())
+            .map(|index| index.to_string())
+            .collect()
+    }
+
+    /// Canonical byte encoding of the public values
+    ///
+    /// The number of values as a big-endian `u64`, followed by each value as
+    /// 32 big-endian bytes in canonical (non-Montgomery) form. The names are
+    /// not included.
+    fn encode_public(&self) -> Vec<u8> {
+        let values = self.public_values();
+        let mut out = (values.len() as u64).to_be_bytes().to_vec();
+        for value in &values {
+            out.extend_from_slice(&value.to_uint().to_bytes_be());
+        }
+        out
+    }
+}
+
+impl PublicInput for () {
+    fn public_values(&self) -> Vec<FieldElement> {
+        Vec::new()
+    }
+}
+
+/// A single value has an empty name, so it takes the name of its position in
+/// a larger input.
+impl PublicInput for FieldElement {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![self.clone()]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec![String::new()]
+    }
+}
+
+impl PublicInput for usize {
+    fn public_values(&self) -> Vec<FieldElement> {
+        vec![FieldElement::from(*self)]
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        vec![String::new()]
+    }
+}
+
+impl<T: PublicInput + ?Sized> PublicInput for &T {
+    fn public_values(&self) -> Vec<FieldElement> {
+        (**self).public_values()
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        (**self).public_names()
+    }
+}
+
+/// Concatenates the values of the items. Names are prefixed by the index of
+/// the item, i.e. `"2.value"` for the `value` of the third item.
+impl<T: PublicInput> PublicInput for [T] {
+    fn public_values(&self) -> Vec<FieldElement> {
+        self.iter().flat_map(PublicInput::public_values).collect()
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        self.iter()
+            .enumerate()
+            .flat_map(|(index, item)| prefixed(index, item))
+            .collect()
+    }
+}
+
+impl<T: PublicInput> PublicInput for Vec<T> {
+    fn public_values(&self) -> Vec<FieldElement> {
+        self.as_slice().public_values()
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        self.as_slice().public_names()
+    }
+}
+
+impl<T: PublicInput, const N: usize> PublicInput for [T; N] {
+    fn public_values(&self) -> Vec<FieldElement> {
+        self[..].public_values()
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        self[..].public_names()
+    }
+}
+
+/// Like slices, with names prefixed by `"0."` and `"1."`.
+impl<A: PublicInput, B: PublicInput> PublicInput for (A, B) {
+    fn public_values(&self) -> Vec<FieldElement> {
+        let mut values = self.0.public_values();
+        values.extend(self.1.public_values());
+        values
+    }
+
+    fn public_names(&self) -> Vec<String> {
+        let mut names = prefixed(0, &self.0);
+        names.extend(prefixed(1, &self.1));
+        names
+    }
+}
+
+fn prefixed<T: PublicInput + ?Sized>(index: usize, item: &T) -> Vec<String> {
+    item.public_names()
+        .into_iter()
+        .map(|name| {
+            if name.is_empty() {
+                index.to_string()
+            } else {
+                format!("{}.{}", index, name)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use zkp_primefield::One;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(FieldElement::one().public_names(), vec![""]);
+        let claim = (FieldElement::one(), vec![[(); 0], [(); 0]]);
+        assert_eq!(claim.public_names(), vec!["0"]);
+        let claim = (vec![FieldElement::one(); 2], 3_usize);
+        assert_eq!(claim.public_names(), vec!["0.0", "0.1", "1"]);
+        assert_eq!(claim.public_values(), vec![
+            FieldElement::one(),
+            FieldElement::one(),
+            FieldElement::from(3)
+        ]);
+    }
+
+    proptest!(
+        #[test]
+        fn test_encoding(values: Vec<FieldElement>) {
+            let encoding = values.encode_public();
+            prop_assert_eq!(encoding.len(), 8 + 32 * values.len());
+            prop_assert_eq!(&encoding[..8], &(values.len() as u64).to_be_bytes()[..]);
+            for (value, bytes) in values.iter().zip(encoding[8..].chunks(32)) {
+                prop_assert_eq!(bytes, &value.to_uint().to_bytes_be()[..]);
+            }
+        }
+    );
+}
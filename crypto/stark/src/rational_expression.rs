@@ -1,4 +1,6 @@
-use crate::polynomial::DensePolynomial;
+use crate::{extension::Extension, polynomial::DensePolynomial};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
 use std::{cmp::Ordering, collections::hash_map::DefaultHasher};
 use std::{
@@ -9,20 +11,46 @@ use std::{
     prelude::v1::*,
 };
 use zkp_macros_decl::field_element;
-use zkp_primefield::{FieldElement, Inv, One, Pow, Zero};
+use zkp_primefield::{fft::permute, Fft, FieldElement, Inv, One, Pow, Zero};
 use zkp_u256::U256;
 
+/// Expression in `X`, the trace values and the channel challenges
+///
+/// With serde, expressions use the externally tagged enum representation,
+/// for example `{"Add": [{"Trace": [0, 1]}, {"Neg": {"Constant": "0x..."}}]}`
+/// with unit variants as plain strings like `"X"` and periodic columns as
+/// `{"Periodic": {"values": [..], "period": 4}}`. Constants are field elements
+/// in hexadecimal and polynomials are lists of coefficients. Names of claim
+/// polynomials are not serialized.
 // TODO: Rename to algebraic expression
 #[derive(Clone, Eq, PartialEq)]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
 pub enum RationalExpression {
     X,
     Constant(FieldElement),
     Trace(usize, isize),
+    /// The `i`-th random value drawn from the channel after a trace stage is
+    /// committed. See [`Stage`](crate::Stage).
+    Challenge(usize),
     Polynomial(DensePolynomial, Box<RationalExpression>),
+    /// A column that repeats every `period` rows
+    ///
+    /// Row `i` takes the value `values[i % period]`, or zero when
+    /// `i % period` is past the end of `values`. The period must be a power of
+    /// two no larger than the trace length. Evaluators that only know `X`
+    /// need the trace length, see `substitute_periodic`.
+    Periodic {
+        values: Vec<FieldElement>,
+        period: usize,
+    },
     // TODO - Make this a struct with internally named members
     // the members are (index, degree bound, expression, name)
-    ClaimPolynomial(usize, usize, Box<RationalExpression>, Option<&'static str>),
+    ClaimPolynomial(
+        usize,
+        usize,
+        Box<RationalExpression>,
+        #[cfg_attr(feature = "std", serde(skip))] Option<&'static str>,
+    ),
     Add(Box<RationalExpression>, Box<RationalExpression>),
     Neg(Box<RationalExpression>),
     Mul(Box<RationalExpression>, Box<RationalExpression>),
@@ -83,6 +111,46 @@ impl RationalExpression {
         };
         self.map(&f)
     }
+
+    /// Replace `Periodic` columns by their interpolating polynomial.
+    ///
+    /// A column with period `p` in a trace of `trace_nrows` rows is a
+    /// polynomial in `X^(trace_nrows / p)`.
+    pub fn substitute_periodic(&self, trace_nrows: usize) -> Self {
+        use RationalExpression::*;
+        let f = |x| {
+            match x {
+                Periodic { values, period } => {
+                    assert!(period <= trace_nrows);
+                    Polynomial(
+                        periodic_polynomial(&values, period),
+                        Box::new(X.pow(trace_nrows / period)),
+                    )
+                }
+                _ => x,
+            }
+        };
+        self.map(&f)
+    }
+
+    /// Replace `Challenge(i)` by `Constant(challenges[i])`.
+    pub fn substitute_challenges(&self, challenges: &[FieldElement]) -> Self {
+        use RationalExpression::*;
+        let f = |x| {
+            match x {
+                Challenge(i) => {
+                    Constant(
+                        challenges
+                            .get(i)
+                            .expect("Challenge index out of bounds")
+                            .clone(),
+                    )
+                }
+                _ => x,
+            }
+        };
+        self.map(&f)
+    }
 }
 
 impl From<i32> for RationalExpression {
@@ -167,12 +235,24 @@ impl RationalExpression {
         use RationalExpression::*;
         match self {
             X => (x_degree, 0),
-            Constant(_) => (0, 0),
+            Constant(_) | Challenge(_) => (0, 0),
             Trace(..) => (trace_degree, 0),
             Polynomial(p, a) => {
                 let (n, d) = a.degree_impl(x_degree, trace_degree);
                 (p.degree() * n, p.degree() * d)
             }
+            // Without X the column does not contribute, which also keeps
+            // `Constraints::degree` working before the period is validated.
+            Periodic { .. } if x_degree == 0 => (0, 0),
+            // Same as the result of `substitute_periodic`, where the trace
+            // has `trace_degree + 1` rows.
+            Periodic { values, period } => {
+                let exponent = (trace_degree + 1) / period;
+                (
+                    periodic_polynomial(values, *period).degree() * exponent * x_degree,
+                    0,
+                )
+            }
             ClaimPolynomial(_, degree_bound, a, _) => {
                 let (n, d) = a.degree_impl(x_degree, trace_degree);
                 (degree_bound * n, degree_bound * d)
@@ -225,6 +305,8 @@ impl RationalExpression {
                 }
             }
             ClaimPolynomial(..) => panic!("ClaimPolynomial should be substituted by Polynomial"),
+            Periodic { .. } => panic!("Periodic should be substituted by Polynomial"),
+            Challenge(_) => panic!("Challenge should be substituted by Constant"),
             Add(a, b) => {
                 let (res_a, a_ok) = a.check(x, trace);
                 let (res_b, b_ok) = b.check(x, trace);
@@ -304,6 +386,8 @@ impl RationalExpression {
                 p.evaluate(&inner)
             }
             ClaimPolynomial(..) => panic!("ClaimPolynomial should be substituted by Polynomial"),
+            Periodic { .. } => panic!("Periodic should be substituted by Polynomial"),
+            Challenge(_) => panic!("Challenge should be substituted by Constant"),
             Add(a, b) => a.evaluate(x, trace) + b.evaluate(x, trace),
             Neg(a) => -&a.evaluate(x, trace),
             Mul(a, b) => a.evaluate(x, trace) * b.evaluate(x, trace),
@@ -312,6 +396,85 @@ impl RationalExpression {
         }
     }
 
+    /// Like `evaluate`, but at a point of an extension field and with trace
+    /// values from that field.
+    pub(crate) fn evaluate_in<E: Extension>(&self, x: &E, trace: &dyn Fn(usize, isize) -> E) -> E {
+        use RationalExpression::*;
+        match self {
+            X => x.clone(),
+            Constant(c) => E::from(c.clone()),
+            &Trace(i, j) => trace(i, j),
+            Polynomial(p, a) => {
+                let inner = a.evaluate_in(x, trace);
+                E::evaluate(p, &inner)
+            }
+            ClaimPolynomial(..) => panic!("ClaimPolynomial should be substituted by Polynomial"),
+            Periodic { .. } => panic!("Periodic should be substituted by Polynomial"),
+            Challenge(_) => panic!("Challenge should be substituted by Constant"),
+            Add(a, b) => a.evaluate_in(x, trace) + b.evaluate_in(x, trace),
+            Neg(a) => a.evaluate_in(x, trace).neg_inline(),
+            Mul(a, b) => a.evaluate_in(x, trace) * b.evaluate_in(x, trace),
+            Inv(a) => {
+                let value = a.evaluate_in(x, trace);
+                assert!(!value.is_zero(), "divided by zero");
+                E::one() / value
+            }
+            Exp(a, e) => a.evaluate_in(x, trace).power(*e),
+        }
+    }
+
+    /// Evaluate as a fraction without dividing.
+    ///
+    /// Returns the numerator and denominator. Where the argument of a
+    /// `Polynomial` has a zero denominator the result is `(1, 0)`.
+    pub fn evaluate_fraction(
+        &self,
+        x: &FieldElement,
+        trace: &dyn Fn(usize, isize) -> FieldElement,
+    ) -> (FieldElement, FieldElement) {
+        use RationalExpression::*;
+        match self {
+            X => (x.clone(), FieldElement::one()),
+            Constant(c) => (c.clone(), FieldElement::one()),
+            &Trace(i, j) => (trace(i, j), FieldElement::one()),
+            Polynomial(p, a) => {
+                let (numerator, denominator) = a.evaluate_fraction(x, trace);
+                match denominator.inv() {
+                    Some(inverse) => (p.evaluate(&(numerator * inverse)), FieldElement::one()),
+                    None => (FieldElement::one(), FieldElement::zero()),
+                }
+            }
+            ClaimPolynomial(..) => panic!("ClaimPolynomial should be substituted by Polynomial"),
+            Periodic { .. } => panic!("Periodic should be substituted by Polynomial"),
+            Challenge(_) => panic!("Challenge should be substituted by Constant"),
+            Add(a, b) => {
+                let (a_numerator, a_denominator) = a.evaluate_fraction(x, trace);
+                let (b_numerator, b_denominator) = b.evaluate_fraction(x, trace);
+                (
+                    a_numerator * &b_denominator + b_numerator * &a_denominator,
+                    a_denominator * b_denominator,
+                )
+            }
+            Neg(a) => {
+                let (numerator, denominator) = a.evaluate_fraction(x, trace);
+                (-&numerator, denominator)
+            }
+            Mul(a, b) => {
+                let (a_numerator, a_denominator) = a.evaluate_fraction(x, trace);
+                let (b_numerator, b_denominator) = b.evaluate_fraction(x, trace);
+                (a_numerator * b_numerator, a_denominator * b_denominator)
+            }
+            Inv(a) => {
+                let (numerator, denominator) = a.evaluate_fraction(x, trace);
+                (denominator, numerator)
+            }
+            Exp(a, e) => {
+                let (numerator, denominator) = a.evaluate_fraction(x, trace);
+                (numerator.pow(*e), denominator.pow(*e))
+            }
+        }
+    }
+
     pub fn trace_arguments(&self) -> BTreeSet<(usize, isize)> {
         let mut arguments = BTreeSet::new();
         self.trace_arguments_impl(&mut arguments);
@@ -324,7 +487,7 @@ impl RationalExpression {
             &Trace(i, j) => {
                 let _ = s.insert((i, j));
             }
-            X | Constant(_) => (),
+            X | Constant(_) | Challenge(_) | Periodic { .. } => (),
             Polynomial(_, a) | Exp(a, _) | Neg(a) | Inv(a) => a.trace_arguments_impl(s),
             Add(a, b) | Mul(a, b) => {
                 a.trace_arguments_impl(s);
@@ -333,6 +496,111 @@ impl RationalExpression {
             ClaimPolynomial(..) => panic!("ClaimPolynomial should be substituted by Polynomial"),
         }
     }
+
+    /// Canonical byte encoding of the expression
+    ///
+    /// Every node is a tag byte followed by its fields. Integers are encoded
+    /// as eight big-endian bytes and field elements as the 32 big-endian bytes
+    /// of their canonical value. Names of claim polynomials are not encoded.
+    /// The format is versioned as part of `Constraints::encode`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    pub(crate) fn encode_into(&self, out: &mut Vec<u8>) {
+        use RationalExpression::*;
+        match self {
+            X => out.push(0),
+            Constant(c) => {
+                out.push(1);
+                encode_field_element(out, c);
+            }
+            &Trace(i, j) => {
+                out.push(2);
+                encode_usize(out, i);
+                out.extend_from_slice(&(j as i64).to_be_bytes());
+            }
+            &Challenge(i) => {
+                out.push(3);
+                encode_usize(out, i);
+            }
+            Polynomial(p, a) => {
+                out.push(4);
+                encode_polynomial(out, p);
+                a.encode_into(out);
+            }
+            ClaimPolynomial(i, n, a, _) => {
+                out.push(5);
+                encode_usize(out, *i);
+                encode_usize(out, *n);
+                a.encode_into(out);
+            }
+            Periodic { values, period } => {
+                out.push(11);
+                encode_usize(out, *period);
+                encode_usize(out, values.len());
+                for value in values {
+                    encode_field_element(out, value);
+                }
+            }
+            Add(a, b) => {
+                out.push(6);
+                a.encode_into(out);
+                b.encode_into(out);
+            }
+            Neg(a) => {
+                out.push(7);
+                a.encode_into(out);
+            }
+            Mul(a, b) => {
+                out.push(8);
+                a.encode_into(out);
+                b.encode_into(out);
+            }
+            Inv(a) => {
+                out.push(9);
+                a.encode_into(out);
+            }
+            Exp(a, e) => {
+                out.push(10);
+                a.encode_into(out);
+                encode_usize(out, *e);
+            }
+        }
+    }
+}
+
+pub(crate) fn encode_usize(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u64).to_be_bytes());
+}
+
+pub(crate) fn encode_field_element(out: &mut Vec<u8>, value: &FieldElement) {
+    out.extend_from_slice(&U256::from(value).to_bytes_be());
+}
+
+/// Interpolate the values of a periodic column
+///
+/// Returns the polynomial $P$ of degree less than `period` with
+/// $P(\omega^i) = v_i$, where $\omega$ is a primitive `period`-th root of
+/// unity and the values are padded with zeros.
+pub(crate) fn periodic_polynomial(values: &[FieldElement], period: usize) -> DensePolynomial {
+    assert!(period.is_power_of_two());
+    assert!(values.len() <= period);
+    let mut coefficients = values.to_vec();
+    coefficients.resize(period, FieldElement::zero());
+    coefficients.ifft();
+    permute(&mut coefficients);
+    DensePolynomial::new(&coefficients)
+}
+
+/// The number of coefficients followed by the coefficients
+pub(crate) fn encode_polynomial(out: &mut Vec<u8>, polynomial: &DensePolynomial) {
+    encode_usize(out, polynomial.coefficients().len());
+    for coefficient in polynomial.coefficients() {
+        encode_field_element(out, coefficient);
+    }
 }
 
 #[allow(clippy::derive_hash_xor_eq)]
@@ -351,6 +619,10 @@ impl Hash for RationalExpression {
                 i.hash(state);
                 j.hash(state);
             }
+            &Challenge(i) => {
+                "challenge".hash(state);
+                i.hash(state);
+            }
             Polynomial(..) => {
                 "poly".hash(state);
                 let x = field_element!(
@@ -382,6 +654,11 @@ impl Hash for RationalExpression {
                 a.hash(state);
                 e.hash(state);
             }
+            Periodic { values, period } => {
+                "periodic".hash(state);
+                values.hash(state);
+                period.hash(state);
+            }
             ClaimPolynomial(i, n, a, _) => {
                 "claim_polynomial".hash(state);
                 i.hash(state);
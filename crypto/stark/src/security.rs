@@ -0,0 +1,427 @@
+//! Soundness estimates and parameter selection.
+//!
+//! The conjectured security follows the ethSTARK conjecture: every query
+//! contributes `log2(blowup)` bits and grinding adds `pow_bits`. The proven
+//! security uses the Johnson bound list-decoding analysis of DEEP-FRI from
+//! [ethSTARK] and [Proximity Gaps]. Both are capped by the collision
+//! resistance of the commitment hash.
+//!
+//! [ethSTARK]: <https://eprint.iacr.org/2021/582>
+//! [Proximity Gaps]: <https://eprint.iacr.org/2020/654>
+use crate::constraints::{Constraints, Error, HashFunction};
+#[cfg(feature = "std")]
+use std::str::FromStr;
+use std::{fmt, prelude::v1::*};
+use zkp_primefield::FieldElement;
+use zkp_u256::Binary;
+
+/// Largest blowup considered by `with_target_security`, as a power of two
+const MAX_LOG_BLOWUP: usize = 6;
+
+/// Largest proof of work difficulty considered by `with_target_security`
+const MAX_POW_BITS: usize = 20;
+
+/// Most queries `with_target_security` adds to reach a proven target
+#[cfg(feature = "std")]
+const MAX_NUM_QUERIES: usize = 256;
+
+/// Soundness of a set of proof parameters in bits
+///
+/// Also the target of `Constraints::with_target_security`, where a proven
+/// target of zero asks for conjectured security only.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Security {
+    /// Security under the ethSTARK conjecture on the soundness of FRI
+    pub conjectured: usize,
+    /// Security from the Johnson bound, without conjectures
+    pub proven:      usize,
+}
+
+/// What `Constraints::with_target_security` optimizes for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Objective {
+    /// Smallest upper bound on the proof size
+    ProofSize,
+    /// Fewest operations in the LDE, commitments and proof of work
+    ProverTime,
+    /// Fewest hashes and field elements to check
+    VerifierCost,
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Objective::*;
+        match *self {
+            ProofSize => write!(f, "proof-size"),
+            ProverTime => write!(f, "prover-time"),
+            VerifierCost => write!(f, "verifier-cost"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Objective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Objective::*;
+        match s {
+            "proof-size" => Ok(ProofSize),
+            "prover-time" => Ok(ProverTime),
+            "verifier-cost" => Ok(VerifierCost),
+            _ => {
+                Err(format!(
+                    "Unknown objective {}, expected proof-size, prover-time or verifier-cost",
+                    s
+                ))
+            }
+        }
+    }
+}
+
+fn log2(n: usize) -> usize {
+    (0_usize.leading_zeros() - n.leading_zeros()).saturating_sub(1) as usize
+}
+
+/// Bit size of the field, i.e. the largest `b` with `2^b` at most the modulus
+fn field_bits() -> usize {
+    FieldElement::modulus()
+        .most_significant_bit()
+        .expect("Modulus is non-zero")
+}
+
+/// Collision resistance of the commitment hash in bits
+fn collision_bits(hash_function: HashFunction) -> usize {
+    use HashFunction::*;
+    match hash_function {
+        // Commitments are truncated to 160 bits
+        MaskedKeccak => 80,
+        Keccak256 | Blake2s | Sha256 => 128,
+        Poseidon => field_bits() / 2,
+    }
+}
+
+/// Bit size of the field the challenges are drawn from
+fn challenge_bits(constraints: &Constraints) -> usize {
+    constraints.extension_degree * field_bits()
+}
+
+fn eval_degree(constraints: &Constraints) -> usize {
+    if constraints.is_empty() {
+        1
+    } else {
+        constraints.degree().next_power_of_two()
+    }
+}
+
+pub(crate) fn conjectured_bits(constraints: &Constraints) -> usize {
+    let domain_size = constraints.trace_nrows() * constraints.blowup;
+    let query_bits = constraints.num_queries * log2(constraints.blowup) + constraints.pow_bits;
+    let field_bits = challenge_bits(constraints).saturating_sub(log2(domain_size));
+    query_bits
+        .min(field_bits)
+        .min(collision_bits(constraints.hash_function))
+}
+
+/// Sum of the soundness errors for proximity parameter `m`, in bits.
+#[cfg(feature = "std")]
+#[allow(clippy::cast_precision_loss)]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+fn proven_bits_for_m(constraints: &Constraints, m: f64) -> f64 {
+    let field = 2_f64.powi(challenge_bits(constraints) as i32);
+    let trace = constraints.trace_nrows() as f64;
+    let domain = trace * constraints.blowup as f64;
+    // The out of domain values at `z` and `gz` raise the degree by two.
+    let rate = (trace + 2.0) / domain;
+    let list_size = (2.0 * m + 1.0) / (2.0 * rate.sqrt());
+    let proximity = (1.0 + 0.5 / m) * rate.sqrt();
+    let polynomials = (constraints.trace_ncolumns() + eval_degree(constraints)) as f64;
+    let folding = constraints
+        .fri_layout
+        .iter()
+        .map(|&reductions| (1_usize << reductions) as f64)
+        .sum::<f64>();
+
+    // Combining the constraints with random coefficients
+    let ali = list_size * constraints.len() as f64 / field;
+    // Sampling the out of domain point
+    let deep = list_size * (eval_degree(constraints) as f64 * trace + trace - 1.0)
+        / (field - domain - trace);
+    // Drawing the FRI folding challenges
+    let commit = (m + 0.5).powi(7) / (3.0 * rate.powf(1.5)) * domain * domain / field
+        + (2.0 * m + 1.0) * (domain + 1.0) * (polynomials + folding) / (rate.sqrt() * field);
+    // The queries and the proof of work
+    let query =
+        proximity.powi(constraints.num_queries as i32) * 2_f64.powi(-(constraints.pow_bits as i32));
+
+    -(ali + deep + commit + query).log2()
+}
+
+#[cfg(feature = "std")]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn proven_bits(constraints: &Constraints) -> usize {
+    if constraints.blowup < 2 || constraints.trace_nrows() < 2 {
+        return 0;
+    }
+    let bits = (3..64)
+        .map(|m| proven_bits_for_m(constraints, f64::from(m)))
+        .fold(0.0, f64::max);
+    (bits.floor().max(0.0) as usize).min(collision_bits(constraints.hash_function))
+}
+
+/// Add queries until the proven security reaches `bits`. Returns whether it
+/// does within `MAX_NUM_QUERIES`.
+#[cfg(feature = "std")]
+fn add_proven_queries(constraints: &mut Constraints, bits: usize) -> bool {
+    while bits > 0 && proven_bits(constraints) < bits {
+        if constraints.num_queries >= MAX_NUM_QUERIES {
+            return false;
+        }
+        constraints.num_queries += 1;
+    }
+    true
+}
+
+// The proven estimate needs floating point functions from `std`.
+#[cfg(not(feature = "std"))]
+fn add_proven_queries(_constraints: &mut Constraints, bits: usize) -> bool {
+    bits == 0
+}
+
+/// Operations for the low degree extensions, commitments and proof of work
+fn prover_cost(constraints: &Constraints) -> usize {
+    let domain_size = constraints.trace_nrows() * constraints.blowup;
+    let polynomials = constraints.trace_ncolumns() + eval_degree(constraints);
+    domain_size * polynomials * (log2(domain_size) + 1) + (1 << constraints.pow_bits)
+}
+
+/// Hashes and field elements processed per proof
+fn verifier_cost(constraints: &Constraints) -> usize {
+    let domain_size = constraints.trace_nrows() * constraints.blowup;
+    let polynomials = constraints.trace_ncolumns() + eval_degree(constraints);
+    let commitments = constraints.stages().len() + 1 + constraints.fri_layout.len();
+    constraints.num_queries * (polynomials + log2(domain_size) * commitments)
+}
+
+#[cfg(feature = "std")]
+impl Constraints {
+    /// Conjectured and proven soundness of the proof parameters
+    ///
+    /// Unlike `security_bits`, both are limited by the field size and the
+    /// collision resistance of the hash function. The proven value does not
+    /// depend on conjectures about FRI and is considerably lower for the same
+    /// parameters.
+    pub fn security(&self) -> Security {
+        Security {
+            conjectured: conjectured_bits(self),
+            proven:      proven_bits(self),
+        }
+    }
+}
+
+impl Constraints {
+    /// Select the blowup, number of queries and proof of work difficulty
+    ///
+    /// Picks the parameters that reach at least the `target` conjectured and
+    /// proven security with the lowest cost for `objective`. Blowups from the
+    /// constraint degree up to 64 (or just the constraint degree, if it is
+    /// larger), up to 20 bits of proof of work and, for a proven target, up
+    /// to 256 queries are considered. The FRI layout and
+    /// hash function are kept. Fails if the field size or the hash function
+    /// does not allow the target. Proven targets need the `std` feature.
+    pub fn with_target_security(
+        mut self,
+        target: Security,
+        objective: Objective,
+    ) -> Result<Self, Error> {
+        let bits = target.conjectured;
+        // Cost and (blowup, pow_bits, num_queries) of the best parameters so far
+        let mut best = None;
+        // The blowup needs to fit the constraint degree
+        let min_log_blowup = log2(eval_degree(&self)).max(1);
+        for log_blowup in min_log_blowup..=MAX_LOG_BLOWUP.max(min_log_blowup) {
+            for pow_bits in 0..=MAX_POW_BITS {
+                self.blowup = 1 << log_blowup;
+                self.pow_bits = pow_bits;
+                self.num_queries = bits.saturating_sub(pow_bits).div_ceil(log_blowup).max(1);
+                if conjectured_bits(&self) < bits {
+                    continue;
+                }
+                if !add_proven_queries(&mut self, target.proven) {
+                    continue;
+                }
+                let proof_size = self.max_proof_size();
+                let cost = match objective {
+                    Objective::ProofSize => (proof_size, prover_cost(&self)),
+                    Objective::ProverTime => (prover_cost(&self), proof_size),
+                    Objective::VerifierCost => (verifier_cost(&self), proof_size),
+                };
+                let better = match best {
+                    Some((best_cost, _)) => cost < best_cost,
+                    None => true,
+                };
+                if better {
+                    best = Some((cost, (self.blowup, self.pow_bits, self.num_queries)));
+                }
+            }
+        }
+        let (_, (blowup, pow_bits, num_queries)) = best.ok_or(Error::UnreachableSecurity)?;
+        self.blowup = blowup;
+        self.pow_bits = pow_bits;
+        self.num_queries = num_queries;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prove, traits::tests::Recurrance, verify, Provable, Verifiable};
+    use proptest::prelude::*;
+    use zkp_macros_decl::field_element;
+    use zkp_primefield::FieldElement;
+    use zkp_u256::U256;
+
+    fn conjectured(bits: usize) -> Security {
+        Security {
+            conjectured: bits,
+            proven:      0,
+        }
+    }
+
+    fn constraints(index: usize) -> Constraints {
+        Recurrance {
+            index,
+            initial_value: field_element!("cafebabe"),
+            exponent: 1,
+        }
+        .claim()
+        .constraints()
+    }
+
+    #[test]
+    fn test_conjectured() {
+        let mut constraints = constraints(1000);
+        constraints.blowup = 16;
+        constraints.num_queries = 20;
+        constraints.pow_bits = 12;
+        constraints.hash_function = HashFunction::Keccak256;
+        assert_eq!(constraints.security().conjectured, 92);
+        assert_eq!(constraints.security_bits(), 62);
+        constraints.num_queries = 45;
+        assert_eq!(constraints.security().conjectured, 128);
+        constraints.hash_function = HashFunction::MaskedKeccak;
+        assert_eq!(constraints.security().conjectured, 80);
+        assert_eq!(constraints.security_bits(), 122);
+    }
+
+    #[test]
+    fn test_proven_below_conjectured() {
+        proptest!(|(log_blowup in 1_usize..8, num_queries in 1_usize..100, pow_bits in 0_usize..30)| {
+            let mut constraints = constraints(1000);
+            constraints.blowup = 1 << log_blowup;
+            constraints.num_queries = num_queries;
+            constraints.pow_bits = pow_bits;
+            constraints.hash_function = HashFunction::Sha256;
+            let security = constraints.security();
+            prop_assert!(security.proven <= security.conjectured);
+        });
+    }
+
+    #[test]
+    fn test_proven_increases_with_queries() {
+        let mut constraints = constraints(1000);
+        constraints.hash_function = HashFunction::Sha256;
+        constraints.num_queries = 20;
+        let fewer = constraints.security().proven;
+        constraints.num_queries = 40;
+        assert!(constraints.security().proven > fewer);
+    }
+
+    #[test]
+    fn test_extension_field() {
+        let mut constraints = constraints(1000);
+        constraints.hash_function = HashFunction::Sha256;
+        let base = constraints.security();
+        constraints.extension_degree = 2;
+        let extension = constraints.security();
+        assert_eq!(extension.conjectured, base.conjectured);
+        assert!(extension.proven >= base.proven);
+    }
+
+    #[test]
+    fn test_target_security() {
+        for &objective in &[
+            Objective::ProofSize,
+            Objective::ProverTime,
+            Objective::VerifierCost,
+        ] {
+            let mut constraints = constraints(1000);
+            constraints.hash_function = HashFunction::Keccak256;
+            let selected = constraints
+                .with_target_security(conjectured(100), objective)
+                .unwrap();
+            assert!(selected.security().conjectured >= 100);
+            assert_eq!(objective.to_string().parse::<Objective>(), Ok(objective));
+        }
+        let by_size = constraints(1000)
+            .with_target_security(conjectured(60), Objective::ProofSize)
+            .unwrap();
+        let by_time = constraints(1000)
+            .with_target_security(conjectured(60), Objective::ProverTime)
+            .unwrap();
+        assert!(by_size.max_proof_size() <= by_time.max_proof_size());
+        assert!(prover_cost(&by_time) <= prover_cost(&by_size));
+    }
+
+    #[test]
+    fn test_target_proven() {
+        let mut constraints = constraints(1000);
+        constraints.hash_function = HashFunction::Sha256;
+        let target = Security {
+            conjectured: 60,
+            proven:      50,
+        };
+        let selected = constraints
+            .clone()
+            .with_target_security(target, Objective::ProofSize)
+            .unwrap();
+        assert!(selected.security().proven >= 50);
+        let conjectured = constraints
+            .with_target_security(conjectured(60), Objective::ProofSize)
+            .unwrap();
+        assert!(conjectured.security().proven < 50);
+    }
+
+    #[test]
+    fn test_target_unreachable() {
+        assert_eq!(
+            constraints(1000)
+                .with_target_security(conjectured(100), Objective::ProofSize)
+                .err(),
+            Some(Error::UnreachableSecurity)
+        );
+    }
+
+    #[test]
+    fn test_target_proof_verify() {
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let constraints = recurrance
+            .claim()
+            .constraints()
+            .with_target_security(conjectured(40), Objective::ProverTime)
+            .unwrap();
+        let proof = prove(
+            &constraints,
+            &recurrance.claim().trace(&recurrance.witness()),
+        )
+        .unwrap();
+        verify(&constraints, &proof).unwrap();
+    }
+}
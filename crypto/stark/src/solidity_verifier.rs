@@ -1,5 +1,8 @@
 use crate::{
-    constraints::Constraints, polynomial::DensePolynomial, rational_expression::RationalExpression,
+    constraints::{Constraints, HashFunction},
+    polynomial::DensePolynomial,
+    rational_expression::RationalExpression,
+    verifier::is_staged,
 };
 use serde::Serialize;
 use std::{
@@ -14,7 +17,7 @@ use std::{
     prelude::v1::*,
 };
 use thiserror::Error;
-use tinytemplate::TinyTemplate;
+use tinytemplate::{format_unescaped, TinyTemplate};
 use zkp_macros_decl::field_element;
 use zkp_primefield::FieldElement;
 use zkp_u256::U256;
@@ -33,6 +36,16 @@ pub enum GenerateError {
     TemplateError(#[from] tinytemplate::error::Error),
     #[error("Bug: invalid expression ocurred")]
     InvalidExpression,
+    #[error("The Solidity verifier does not support the {0:?} hash function")]
+    UnsupportedHashFunction(HashFunction),
+    #[error("The Solidity verifier does not support zero-knowledge proofs")]
+    UnsupportedZeroKnowledge,
+    #[error("The Solidity verifier does not support traces with several stages")]
+    UnsupportedStages,
+    #[error("The Solidity verifier does not support extension degree {0}")]
+    UnsupportedExtensionDegree(usize),
+    #[error("The Solidity verifier does not bind the constraint digest, set `legacy_seed`")]
+    UnsupportedSeed,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
@@ -49,10 +62,11 @@ struct BatchInvert {
 
 #[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
 struct Constraint {
-    first_coefficient_location:  usize,
+    first_coefficient_location: usize,
     second_coefficient_location: usize,
-    degree_adjustment_location:  String,
-    expression:                  String,
+    degree_adjustment_location: String,
+    expression: String,
+    label: String,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
@@ -88,9 +102,16 @@ struct RowOffset {
     index: usize,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
+struct ColumnName {
+    index: usize,
+    name:  String,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
 struct TraceContext {
     name:               String,
+    column_names:       Vec<ColumnName>,
     constraint_degree:  usize,
     num_rows:           usize,
     num_cols:           usize,
@@ -197,7 +218,7 @@ impl RationalExpression {
         use RationalExpression::*;
 
         match self {
-            X | Constant(..) => BTreeMap::new(),
+            X | Constant(..) | Challenge(_) | Periodic { .. } => BTreeMap::new(),
             Trace(..) => [(self.clone(), true)].iter().cloned().collect(),
             Add(a, b) | Mul(a, b) => {
                 let mut first = a.trace_search();
@@ -215,7 +236,7 @@ impl RationalExpression {
         use RationalExpression::*;
 
         match self {
-            X | Constant(_) | Trace(..) => BTreeMap::new(),
+            X | Constant(_) | Challenge(_) | Trace(..) | Periodic { .. } => BTreeMap::new(),
             Add(a, b) | Mul(a, b) => {
                 let mut first = a.inv_search();
                 first.extend(b.inv_search());
@@ -231,8 +252,8 @@ impl RationalExpression {
         use RationalExpression::*;
 
         match self {
-            X | Constant(_) | Trace(..) | ClaimPolynomial(..) => BTreeMap::new(),
-            Polynomial(..) => [(self.clone(), true)].iter().cloned().collect(),
+            X | Constant(_) | Challenge(_) | Trace(..) | ClaimPolynomial(..) => BTreeMap::new(),
+            Polynomial(..) | Periodic { .. } => [(self.clone(), true)].iter().cloned().collect(),
             Add(a, b) | Mul(a, b) => {
                 let mut first = a.periodic_search();
                 first.extend(b.periodic_search());
@@ -248,7 +269,9 @@ impl RationalExpression {
 
         match self {
             ClaimPolynomial(..) => once(self).cloned().collect(),
-            X | Constant(_) | Trace(..) | Polynomial(..) => BTreeSet::new(),
+            X | Constant(_) | Challenge(_) | Trace(..) | Polynomial(..) | Periodic { .. } => {
+                BTreeSet::new()
+            }
             Add(a, b) | Mul(a, b) => {
                 let mut first = a.claim_polynomial_search();
                 first.extend(b.claim_polynomial_search());
@@ -293,16 +316,43 @@ impl Ord for DensePolynomial {
 // and a name for the constraint system
 // It produces a set of files which should be manually edited
 // to end up with a solidity verifier
+// The contracts read a single trace commitment without salts, hash with
+// masked Keccak and seed the channel without the constraint digest, so other
+// proof options are rejected.
 
 pub fn generate(
     constraints: &Constraints,
     output_directory: &str,
     system_name: &str,
 ) -> Result<(), GenerateError> {
+    if constraints.hash_function != HashFunction::MaskedKeccak {
+        return Err(GenerateError::UnsupportedHashFunction(
+            constraints.hash_function,
+        ));
+    }
+    if constraints.zero_knowledge {
+        return Err(GenerateError::UnsupportedZeroKnowledge);
+    }
+    if is_staged(constraints) {
+        return Err(GenerateError::UnsupportedStages);
+    }
+    if constraints.extension_degree != 1 {
+        return Err(GenerateError::UnsupportedExtensionDegree(
+            constraints.extension_degree,
+        ));
+    }
+    if !constraints.legacy_seed {
+        return Err(GenerateError::UnsupportedSeed);
+    }
     let blowup = constraints.blowup;
     let n_cols = constraints.trace_ncolumns();
     let trace_len = constraints.trace_nrows();
-    let constraint_expressions = constraints.expressions();
+    // Periodic columns are generated as polynomials in a power of X.
+    let constraint_expressions = &constraints
+        .expressions()
+        .iter()
+        .map(|expression| expression.substitute_periodic(trace_len))
+        .collect::<Vec<_>>();
 
     let mut traces = BTreeMap::new();
     let mut inverses = BTreeMap::new();
@@ -351,9 +401,21 @@ pub fn generate(
             target_degree + den - num
         })
         .collect();
+    // Like labels below, column names end up in comments.
+    let column_names = (0..n_cols)
+        .filter_map(|index| {
+            constraints.column_name(index).map(|name| {
+                ColumnName {
+                    index,
+                    name: name.replace(&['\n', '\r'][..], " "),
+                }
+            })
+        })
+        .collect();
     autogen_oods_contract(
         constraint_expressions,
         n_cols,
+        column_names,
         blowup,
         output_directory,
         system_name,
@@ -363,6 +425,10 @@ pub fn generate(
     let output_directory = Path::new(output_directory);
     let filename = format!("{}ConstraintPoly.sol", system_name);
     let mut file = File::create(&output_directory.join(filename))?;
+    // Labels end up in comments, so they need to stay on one line.
+    let labels = (0..constraint_expressions.len())
+        .map(|index| constraints.describe(index).replace(&['\n', '\r'][..], " "))
+        .collect::<Vec<_>>();
     write_oods_poly(
         &mut file,
         constraint_expressions.len(),
@@ -372,6 +438,7 @@ pub fn generate(
         periodic_keys.as_slice(),
         adjustment_degrees.as_slice(),
         constraint_expressions,
+        &labels,
     )?;
 
     Ok(())
@@ -533,6 +600,7 @@ fn autogen_periodic(
 fn autogen_oods_contract(
     constraints: &[RationalExpression],
     n_cols: usize,
+    column_names: Vec<ColumnName>,
     blowup: usize,
     output_directory: &str,
     system_name: &str,
@@ -558,8 +626,14 @@ fn autogen_oods_contract(
         .max()
         .expect("No constraints");
 
-    let trace_contract =
-        autogen_trace_layout(&trace_keys, n_cols, max_degree, blowup, system_name)?;
+    let trace_contract = autogen_trace_layout(
+        &trace_keys,
+        n_cols,
+        column_names,
+        max_degree,
+        blowup,
+        system_name,
+    )?;
 
     // TODO - Variable naming
     let name = format!("{}/{}Trace.sol", output_directory, system_name);
@@ -578,17 +652,21 @@ fn autogen_oods_contract(
 fn autogen_trace_layout(
     trace_keys: &[&RationalExpression],
     n_cols: usize,
+    column_names: Vec<ColumnName>,
     constraint_degree: usize,
     blowup: usize,
     system_name: &str,
 ) -> Result<String, GenerateError> {
     let mut tt = TinyTemplate::new();
+    // Column names and labels are written verbatim into comments
+    tt.set_default_formatter(&format_unescaped);
     tt.add_template("oods_poly", OODS_POLY_TEMPLATE)?;
     tt.add_template("periodic", PERIODIC_TEMPLATE)?;
     tt.add_template("trace", TRACE_TEMPLATE)?;
 
     let mut context = TraceContext {
         name: system_name.to_owned(),
+        column_names,
         constraint_degree,
         column_layout_size: 2 * trace_keys.len(),
         num_cols: n_cols,
@@ -653,8 +731,11 @@ fn write_oods_poly(
     periodic: &[&RationalExpression],
     adjustment_degrees: &[usize],
     constraint_expressions: &[RationalExpression],
+    labels: &[String],
 ) -> Result<(), GenerateError> {
     let mut tt = TinyTemplate::new();
+    // Column names and labels are written verbatim into comments
+    tt.set_default_formatter(&format_unescaped);
     tt.add_template("oods_poly", OODS_POLY_TEMPLATE)?;
     tt.add_template("periodic", PERIODIC_TEMPLATE)?;
     tt.add_template("trace", TRACE_TEMPLATE)?;
@@ -751,7 +832,11 @@ fn write_oods_poly(
 
     // Add constraints to context
     let mut coefficient_index = 1 + claim_polynomial_keys.len() + periodic.len();
-    for (exp, &degree) in constraint_expressions.iter().zip(adjustment_degrees.iter()) {
+    for ((exp, &degree), label) in constraint_expressions
+        .iter()
+        .zip(adjustment_degrees.iter())
+        .zip(labels)
+    {
         let degree_adjustment_location = memory_lookups
             .get(&RationalExpression::Exp(
                 RationalExpression::X.into(),
@@ -764,6 +849,7 @@ fn write_oods_poly(
             second_coefficient_location: (coefficient_index + 1) * 32,
             degree_adjustment_location,
             expression: exp.soldity_encode(&memory_lookups),
+            label: label.clone(),
         });
         coefficient_index += 2;
     }
@@ -1,10 +1,15 @@
 use crate::polynomial::DensePolynomial;
 use log::trace;
+#[cfg(feature = "std")]
+use rand::random;
+#[cfg(feature = "std")]
+use std::ops::Range;
 use std::{
+    collections::BTreeSet,
     ops::{Index, IndexMut},
     prelude::v1::*,
 };
-use zkp_mmap_vec::MmapVec;
+use zkp_mmap_vec::{InScratch, MmapVec, Scratch};
 use zkp_primefield::{fft::permute, Fft, FieldElement, Root, Zero};
 
 #[derive(Clone, PartialEq)]
@@ -13,12 +18,24 @@ pub struct TraceTable {
     trace_length: usize,
     num_columns:  usize,
     values:       MmapVec<FieldElement>,
+    // Rows filled by `randomize_rows`
+    randomized:   BTreeSet<usize>,
 }
 
 impl TraceTable {
     /// Constructs a zero-initialized trace table of the given size.
     pub fn new(trace_length: usize, num_columns: usize) -> Self {
-        let mut values: MmapVec<FieldElement> = MmapVec::with_capacity(trace_length * num_columns);
+        Self::new_in(trace_length, num_columns, &Scratch::default())
+    }
+
+    /// Constructs a zero-initialized trace table of the given size in
+    /// `scratch`.
+    ///
+    /// The prover allocates the low degree extensions, constraint evaluations
+    /// and Merkle trees of the trace in the same scratch.
+    pub fn new_in(trace_length: usize, num_columns: usize, scratch: &Scratch) -> Self {
+        let mut values: MmapVec<FieldElement> =
+            MmapVec::with_capacity_in_scratch(trace_length * num_columns, scratch);
         for _ in 0..(trace_length * num_columns) {
             values.push(FieldElement::zero());
         }
@@ -26,9 +43,27 @@ impl TraceTable {
             trace_length,
             num_columns,
             values,
+            randomized: BTreeSet::new(),
         }
     }
 
+    /// A copy of the trace table in `scratch`.
+    pub fn clone_in(&self, scratch: &Scratch) -> Self {
+        let mut values = MmapVec::with_capacity_in_scratch(self.values.len(), scratch);
+        values.extend_from_slice(&self.values);
+        Self {
+            trace_length: self.trace_length,
+            num_columns: self.num_columns,
+            values,
+            randomized: self.randomized.clone(),
+        }
+    }
+
+    /// The scratch the trace table is allocated in.
+    pub fn scratch(&self) -> &Scratch {
+        self.values.scratch()
+    }
+
     pub fn num_rows(&self) -> usize {
         self.trace_length
     }
@@ -41,6 +76,33 @@ impl TraceTable {
         FieldElement::root(self.trace_length).expect("No generator for trace table length.")
     }
 
+    /// Fills the given rows with random values.
+    ///
+    /// For a zero-knowledge proof the rows must not be constrained. There
+    /// should be more of them than values revealed per column, which is the
+    /// number of queries plus the number of out of domain evaluations.
+    /// The prover rejects zero-knowledge proofs of traces with fewer
+    /// randomized rows, not counting rows that a constraint reads.
+    #[cfg(feature = "std")]
+    pub fn randomize_rows(&mut self, rows: Range<usize>) {
+        for i in rows {
+            for value in &mut self[i] {
+                *value = random();
+            }
+            let _ = self.randomized.insert(i);
+        }
+    }
+
+    /// The number of rows filled by `randomize_rows`.
+    pub fn randomized_rows(&self) -> usize {
+        self.randomized.len()
+    }
+
+    /// The rows filled by `randomize_rows`, in order.
+    pub(crate) fn randomized(&self) -> impl Iterator<Item = usize> + '_ {
+        self.randomized.iter().copied()
+    }
+
     pub fn iter_row(&self, i: usize) -> impl Iterator<Item = &FieldElement> {
         // Delegate to Index<usize> which returns a row slice.
         self[i].iter()
@@ -58,13 +120,32 @@ impl TraceTable {
     /// row first.)
     // TODO: Use strides
     pub fn column_to_mmapvec(&self, j: usize) -> MmapVec<FieldElement> {
-        let mut result: MmapVec<FieldElement> = MmapVec::with_capacity(self.trace_length);
+        let mut result: MmapVec<FieldElement> =
+            MmapVec::with_capacity_in_scratch(self.trace_length, self.scratch());
         for v in self.iter_column(j) {
             result.push(v.clone());
         }
         result
     }
 
+    /// Constructs a trace table with the columns of `self` followed by those
+    /// of `other`.
+    pub fn concat(&self, other: &Self) -> Self {
+        assert_eq!(self.num_rows(), other.num_rows());
+        let mut result = Self::new_in(
+            self.num_rows(),
+            self.num_columns() + other.num_columns(),
+            self.scratch(),
+        );
+        for i in 0..self.num_rows() {
+            let (left, right) = result[i].split_at_mut(self.num_columns());
+            left.clone_from_slice(&self[i]);
+            right.clone_from_slice(&other[i]);
+        }
+        result.randomized = &self.randomized & &other.randomized;
+        result
+    }
+
     pub fn interpolate(&self) -> Vec<DensePolynomial> {
         trace!("BEGIN Interpolate");
         let result = (0..self.num_columns())
@@ -74,7 +155,7 @@ impl TraceTable {
             // so it can be replaced by a matrix of coefficients.
             .map(|j| {
                 // Copy column to vec
-                let mut vec = MmapVec::with_capacity(self.num_rows());
+                let mut vec = MmapVec::with_capacity_in_scratch(self.num_rows(), self.scratch());
                 for v in self.iter_column(j) {
                     vec.push(v.clone());
                 }
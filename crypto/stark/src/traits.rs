@@ -1,14 +1,36 @@
 #[cfg(feature = "prover")]
 use crate::constraint_check::{check_constraints, check_specific_constraint};
+#[cfg(all(feature = "prover", feature = "std"))]
+use crate::constraint_check::{check_constraints_report, ConstraintReport};
 #[cfg(feature = "prover")]
-use crate::{prove, ProverError, TraceTable};
-use crate::{verify, Constraints, Proof, VerifierError};
+use crate::{prove, ProverError, Scratch, TraceTable};
+use crate::{verify, Constraints, Proof, PublicInput, VerifierError};
+use std::prelude::v1::*;
 
-pub trait Verifiable {
+pub trait Verifiable: PublicInput {
     fn constraints(&self) -> Constraints;
 
+    /// Trace cells `(column, row)` that hold the public values, by index of
+    /// the value. See `Constraints::bind_public_input`.
+    fn public_cells(&self) -> Vec<Option<(usize, usize)>> {
+        Vec::new()
+    }
+
+    /// The `constraints` with the public input bound to them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `public_cells` refers to cells outside the trace.
+    fn bound_constraints(&self) -> Constraints {
+        let mut constraints = self.constraints();
+        constraints
+            .bind_public_input(self, &self.public_cells())
+            .expect("Public cells out of range");
+        constraints
+    }
+
     fn verify(&self, proof: &Proof) -> Result<(), VerifierError> {
-        let constraints = self.constraints();
+        let constraints = self.bound_constraints();
         verify(&constraints, proof)
     }
 }
@@ -17,20 +39,36 @@ pub trait Verifiable {
 pub trait Provable<T>: Verifiable {
     fn trace(&self, witness: T) -> TraceTable;
 
+    /// Same as `trace`, but with the trace table allocated in `scratch`.
+    ///
+    /// The default copies the result of `trace`. Implement it to build large
+    /// traces in `scratch` directly, without a second copy in memory.
+    fn trace_in(&self, witness: T, scratch: &Scratch) -> TraceTable {
+        self.trace(witness).clone_in(scratch)
+    }
+
     fn prove(&self, witness: T) -> Result<Proof, ProverError> {
-        let constraints = self.constraints();
+        let constraints = self.bound_constraints();
         let trace = self.trace(witness);
         prove(&constraints, &trace)
     }
 
     fn check(&self, witness: T) -> Result<(), (usize, usize)> {
-        let constraints = self.constraints();
+        let constraints = self.bound_constraints();
         let trace = self.trace(witness);
         check_constraints(&constraints, &trace)
     }
 
+    /// Same as `check`, but reports every failing row and constraint.
+    #[cfg(feature = "std")]
+    fn check_report(&self, witness: T) -> ConstraintReport {
+        let constraints = self.bound_constraints();
+        let trace = self.trace(witness);
+        check_constraints_report(&constraints, &trace)
+    }
+
     fn check_specified(&self, witness: T, row: usize, which_constraint: usize) -> Result<(), ()> {
-        let constraints = self.constraints();
+        let constraints = self.bound_constraints();
         let trace = self.trace(witness);
         if check_specific_constraint(&constraints, &trace, row, which_constraint) {
             Ok(())
@@ -108,6 +146,16 @@ pub(crate) mod tests {
         }
     }
 
+    impl PublicInput for Claim {
+        fn public_values(&self) -> Vec<FieldElement> {
+            vec![self.index.into(), self.value.clone(), self.exponent.into()]
+        }
+
+        fn public_names(&self) -> Vec<String> {
+            vec!["index".into(), "value".into(), "exponent".into()]
+        }
+    }
+
     impl Verifiable for Claim {
         fn constraints(&self) -> Constraints {
             use RationalExpression::*;
@@ -130,6 +178,41 @@ pub(crate) mod tests {
         }
     }
 
+    impl Claim {
+        /// Constraints that leave the rows after `index` free, so they can be
+        /// randomized for a zero-knowledge proof.
+        pub(crate) fn zero_knowledge_constraints(&self) -> Constraints {
+            use RationalExpression::*;
+
+            let trace_length = (self.index + 1).next_power_of_two();
+            let trace_generator = FieldElement::root(trace_length).unwrap();
+            let g = Constant(trace_generator);
+            let on_row = |index| (X - g.pow(index)).inv();
+            let until_index =
+                (self.index..trace_length).fold(Constant(FieldElement::one()), |product, row| {
+                    product * (X - g.pow(row))
+                }) / (X.pow(trace_length) - 1);
+
+            let mut constraints =
+                Constraints::from_expressions((trace_length, 2), self.seed(), vec![
+                    (Trace(0, 1) - Trace(1, 0).pow(self.exponent)) * until_index.clone(),
+                    (Trace(1, 1) - Trace(0, 0) - Trace(1, 0)) * until_index,
+                    (Trace(0, 0) - 1) * on_row(trace_length),
+                    (Trace(0, 0) - &self.value) * on_row(self.index),
+                ])
+                .unwrap();
+            constraints.zero_knowledge = true;
+            constraints
+        }
+
+        /// A trace for `zero_knowledge_constraints`.
+        pub(crate) fn zero_knowledge_trace(&self, witness: &Witness) -> TraceTable {
+            let mut trace = self.trace(witness);
+            trace.randomize_rows(self.index + 1..trace.num_rows());
+            trace
+        }
+    }
+
     impl Provable<&Witness> for Claim {
         fn trace(&self, witness: &Witness) -> TraceTable {
             let trace_length = (self.index + 1).next_power_of_two();
@@ -225,6 +308,15 @@ pub(crate) mod tests {
         }
     }
 
+    impl PublicInput for Claim2 {
+        fn public_values(&self) -> Vec<FieldElement> {
+            let mut values = vec![self.index.into(), self.value.clone()];
+            values.extend(self.coefficients.iter().cloned());
+            values.extend(self.exponents.iter().map(|&exponent| exponent.into()));
+            values
+        }
+    }
+
     impl Verifiable for Claim2 {
         fn constraints(&self) -> Constraints {
             use RationalExpression::*;
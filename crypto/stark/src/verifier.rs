@@ -1,24 +1,22 @@
 use crate::{
     channel::{RandomGenerator, Replayable, VerifierChannel},
     constraints::Constraints,
-    polynomial::DensePolynomial,
+    extension::{flatten, Extension},
+    proof::{saturate, Decommitment, Error as ProofError, ReplayError, Step, Transcript},
     proof_of_work, Proof,
 };
 use log::trace;
 #[cfg(feature = "std")]
 use std::error;
 use std::{collections::BTreeMap, fmt, prelude::v1::*};
-use zkp_hash::Hash;
+use zkp_hash::{Hash, Hashable, Hasher};
 use zkp_merkle_tree::{Commitment, Error as MerkleError, Proof as MerkleProof};
-use zkp_primefield::{
-    fft, geometric_series::root_series, FieldElement, One, Pow, Root, SquareInline, Zero,
-};
+use zkp_primefield::{fft, geometric_series::root_series, Ext, FieldElement, Inv, Pow, Root};
 use zkp_u256::U256;
 
 type Result<T> = std::result::Result<T, Error>;
 
-// TODO - We could parametrize root unavailable with the size asked for and fri
-// error with which layer failed.
+/// Why verification failed, see `verify_detailed` for where.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Error {
     RootUnavailable,
@@ -27,10 +25,17 @@ pub enum Error {
     InvalidConstraintCommitment,
     InvalidFriCommitment,
     HashMapFailure,
+    ProofTooShort,
     ProofTooLong,
     OodsCalculationFailure,
     OodsMismatch,
     FriCalculationFailure,
+    EmptyBatch,
+    MismatchedParameters,
+    UnsupportedStages,
+    InvalidFieldElement,
+    /// The extension degree, saturated to `u8::MAX`
+    InvalidExtensionDegree(u8),
     Merkle(MerkleError),
 }
 
@@ -49,6 +54,7 @@ impl fmt::Display for Error {
                     "Verifier attempted to look up an empty entry in the hash map"
                 )
             }
+            ProofTooShort => write!(f, "The proof ends before all sections are read"),
             ProofTooLong => write!(f, "The proof length doesn't match the specification"),
             OodsCalculationFailure => {
                 write!(
@@ -63,18 +69,194 @@ impl fmt::Display for Error {
                 )
             }
             OodsMismatch => write!(f, "Calculated oods value doesn't match the committed one"),
+            EmptyBatch => write!(f, "A batch proof needs at least one claim"),
+            MismatchedParameters => {
+                write!(
+                    f,
+                    "The claims of a batch proof use different proof parameters"
+                )
+            }
+            UnsupportedStages => write!(f, "Batch proofs of staged traces are not supported"),
+            InvalidFieldElement => write!(f, "The proof holds a value that is not a field element"),
+            InvalidExtensionDegree(degree) => {
+                write!(f, "Invalid extension degree {} (must be 1 or 2)", degree)
+            }
             // This is a wrapper, so defer to the underlying types' implementation of `fmt`.
             Merkle(ref e) => std::fmt::Display::fmt(e, f),
         }
     }
 }
 
+/// A committed oracle in the proof
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Oracle {
+    /// The trace commitment of a stage
+    Trace(usize),
+    Constraint,
+    /// The commitment of a FRI layer, counting from zero
+    Fri(usize),
+}
+
+impl fmt::Display for Oracle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Trace(stage) => write!(f, "trace commitment of stage {}", stage),
+            Self::Constraint => write!(f, "constraint commitment"),
+            Self::Fri(layer) => write!(f, "commitment of FRI layer {}", layer),
+        }
+    }
+}
+
+/// Where in the proof verification failed
+///
+/// Fields that do not apply to the failed check are `None`. When the values
+/// are in an extension field, `expected` and `computed` hold the first
+/// coefficient in which they differ.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Details {
+    /// Byte offset in the proof of the data that failed the check
+    pub offset:    usize,
+    /// The commitment that was read or checked
+    pub oracle:    Option<Oracle>,
+    /// The FRI layer, where the last layer is `fri_layout.len()`
+    pub fri_layer: Option<usize>,
+    /// The query, as an index into the evaluation domain of the layer
+    pub query:     Option<usize>,
+    /// The value the proof commits to
+    pub expected:  Option<FieldElement>,
+    /// The value the verifier computed from the decommitments
+    pub computed:  Option<FieldElement>,
+}
+
+impl Details {
+    // Forget the details of the previous check
+    fn start(&mut self, offset: usize, oracle: Option<Oracle>) {
+        *self = Self {
+            offset,
+            oracle,
+            ..Self::default()
+        };
+    }
+
+    fn mismatch<E: Extension>(&mut self, expected: &E, computed: &E) {
+        if let Some((expected, computed)) = expected
+            .components()
+            .iter()
+            .zip(computed.components())
+            .find(|(expected, computed)| expected != computed)
+        {
+            self.expected = Some(expected.clone());
+            self.computed = Some(computed.clone());
+        }
+    }
+}
+
+/// A verification error together with where it occurred
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostics {
+    pub error:   Error,
+    pub details: Details,
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let details = &self.details;
+        write!(f, "{} at proof byte {}", self.error, details.offset)?;
+        if let Some(oracle) = details.oracle {
+            write!(f, ", {}", oracle)?;
+        }
+        if let Some(layer) = details.fri_layer {
+            write!(f, ", FRI layer {}", layer)?;
+        }
+        if let Some(query) = details.query {
+            write!(f, ", query {}", query)?;
+        }
+        if let (Some(expected), Some(computed)) = (&details.expected, &details.computed) {
+            write!(f, ", expected ")?;
+            write_hex(f, &U256::from(expected))?;
+            write!(f, " but computed ")?;
+            write_hex(f, &U256::from(computed))?;
+        }
+        Ok(())
+    }
+}
+
+// `U256` only implements `Display` with std.
+fn write_hex(f: &mut fmt::Formatter<'_>, value: &U256) -> fmt::Result {
+    write!(
+        f,
+        "0x{:016x}{:016x}{:016x}{:016x}",
+        value.limb(3),
+        value.limb(2),
+        value.limb(1),
+        value.limb(0)
+    )
+}
+
 impl From<MerkleError> for Error {
     fn from(err: MerkleError) -> Self {
         Self::Merkle(err)
     }
 }
 
+impl From<ProofError> for Error {
+    fn from(err: ProofError) -> Self {
+        match err {
+            ProofError::ProofTooShort => Self::ProofTooShort,
+            ProofError::ProofTooLong => Self::ProofTooLong,
+            // Only `StructuredProof::to_proof` checks the salts, a replay reads
+            // them with the rows.
+            ProofError::InvalidSalts => Self::ProofTooShort,
+            ProofError::InvalidFieldElement => Self::InvalidFieldElement,
+            ProofError::InvalidExtensionDegree(degree) => Self::InvalidExtensionDegree(degree),
+            ProofError::Merkle(err) => Self::Merkle(err),
+        }
+    }
+}
+
+/// A decommitted leaf of the trace or constraint commitment.
+///
+/// In zero-knowledge mode every leaf carries a random salt that is hashed
+/// together with the values.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub(crate) struct Row {
+    pub(crate) values: Vec<FieldElement>,
+    pub(crate) salt:   Option<Hash>,
+}
+
+impl Row {
+    pub(crate) fn replay<H: Hasher>(
+        channel: &mut VerifierChannel<H>,
+        size: usize,
+        salted: bool,
+    ) -> std::result::Result<Self, ProofError> {
+        let values = channel.replay_fri_layer(size)?;
+        let salt = if salted {
+            Some(channel.replay()?)
+        } else {
+            None
+        };
+        Ok(Self { values, salt })
+    }
+}
+
+impl Hashable for Row {
+    fn hash_with<H: Hasher>(&self) -> Hash {
+        match &self.salt {
+            None => self.values.hash_with::<H>(),
+            Some(salt) => {
+                let mut hasher = H::default();
+                for value in &self.values {
+                    hasher.update(value.hash_with::<H>().as_bytes());
+                }
+                hasher.update(salt.as_bytes());
+                hasher.hash()
+            }
+        }
+    }
+}
+
 // False positives on the Latex math.
 #[allow(clippy::doc_markdown)]
 /// # Stark verify
@@ -89,6 +271,8 @@ impl From<MerkleError> for Error {
 ///
 /// ### Step 1: Read all commitments and draw random values
 ///
+/// * Seed the channel with the channel seed and the `digest` of the
+/// constraints, or the channel seed alone if `legacy_seed` is set.
 /// * Read the trace polynomial commitment commitment.
 /// * Draw the constraint combination coefficients $\alpha_i$ and $\beta_i$.
 /// * Read the combined constraint polynomial commitment.
@@ -173,249 +357,770 @@ impl From<MerkleError> for Error {
 /// * Evaluate the final layer
 ///
 /// <!-- TODO: ellaborate FRI verification -->
-// TODO: Refactor into smaller function
-#[allow(clippy::too_many_lines)]
+///
+/// ## Zero-knowledge mode
+///
+/// When `constraints.zero_knowledge` is set, every trace and constraint
+/// decommitment row is followed by the salt of its leaf, and the constraint
+/// commitment has one more column holding a random polynomial $R$. Its value
+/// $R(z^{\mathrm{d}})$ follows the $A_i(z^{\mathrm{d}})$ values and it takes
+/// part in the final combination like the $A_i$, but not in the check of
+/// step 5.
+///
+/// ## Trace stages
+///
+/// When the trace has several stages, step 1 reads a commitment for each
+/// stage, followed by drawing the challenges of that stage. In step 3 each
+/// stage has its own decommitment, and the rows are joined before they are
+/// used.
+///
+/// ## Extension field
+///
+/// When `constraints.extension_degree` is two, $z$ and all coefficients but
+/// the stage challenges are drawn from a quadratic extension of the field,
+/// one coefficient after the other. The values at $z$, the FRI layer values
+/// and the final FRI polynomial are read as their two coefficients. The
+/// $A_i$ are committed with a column for each of their coefficients.
 pub fn verify(constraints: &Constraints, proof: &Proof) -> Result<()> {
+    verify_detailed(constraints, proof).map_err(|diagnostics| diagnostics.error)
+}
+
+/// Same as `verify`, but reports where verification failed.
+///
+/// Besides the error, the result holds the byte offset in the proof of the
+/// data that failed to check, and where applicable the commitment, the FRI
+/// layer, the query and the expected and computed values. This helps to find
+/// where a prover or another verifier implementation diverges.
+pub fn verify_detailed(
+    constraints: &Constraints,
+    proof: &Proof,
+) -> std::result::Result<(), Diagnostics> {
+    let mut details = Details::default();
+    with_hasher!(
+        constraints.hash_function,
+        verify_with(constraints, proof, &mut details)
+    )
+    .map_err(|error| Diagnostics { error, details })
+}
+
+/// Verify a proof of several claims created by `prove_batch`.
+///
+/// The claims must be in the same order as they were proven in, and agree on
+/// the proof parameters like for `prove_batch`.
+pub fn verify_batch(batch: &[&Constraints], proof: &Proof) -> Result<()> {
+    let parameters = batch_parameters(batch).ok_or(Error::EmptyBatch)?;
+    if !same_parameters(batch) {
+        return Err(Error::MismatchedParameters);
+    }
+    if batch.iter().any(|constraints| is_staged(constraints)) {
+        return Err(Error::UnsupportedStages);
+    }
+    let details = &mut Details::default();
+    with_hasher!(
+        parameters.hash_function,
+        verify_batch_with(batch, proof, details)
+    )
+}
+
+fn verify_batch_with<H: Hasher>(
+    batch: &[&Constraints],
+    proof: &Proof,
+    details: &mut Details,
+) -> Result<()> {
+    match batch_parameters(batch).map_or(1, |parameters| parameters.extension_degree) {
+        1 => verify_batch_in::<H, FieldElement>(batch, proof, details),
+        2 => verify_batch_in::<H, Ext<FieldElement, 2>>(batch, proof, details),
+        degree => Err(Error::InvalidExtensionDegree(saturate(degree))),
+    }
+}
+
+/// The claim that provides the proof parameters for a batch.
+pub(crate) fn batch_parameters<'a>(batch: &[&'a Constraints]) -> Option<&'a Constraints> {
+    // `max_by_key` returns the last maximum, so reverse to get the first.
+    batch
+        .iter()
+        .rev()
+        .max_by_key(|constraints| constraints.trace_nrows())
+        .copied()
+}
+
+/// Whether the claims of a batch agree on the proof parameters.
+///
+/// The FRI layout depends on the trace length, so it is only compared between
+/// the claims with the longest trace.
+pub(crate) fn same_parameters(batch: &[&Constraints]) -> bool {
+    let parameters = match batch_parameters(batch) {
+        Some(parameters) => parameters,
+        None => return true,
+    };
+    batch.iter().all(|constraints| {
+        constraints.blowup == parameters.blowup
+            && constraints.num_queries == parameters.num_queries
+            && constraints.pow_bits == parameters.pow_bits
+            && constraints.hash_function == parameters.hash_function
+            && constraints.extension_degree == parameters.extension_degree
+            && (constraints.trace_nrows() < parameters.trace_nrows()
+                || constraints.fri_layout == parameters.fri_layout)
+    })
+}
+
+/// Whether the trace has more than one stage or draws challenges.
+pub(crate) fn is_staged(constraints: &Constraints) -> bool {
+    constraints.stages().len() > 1 || constraints.num_challenges() > 0
+}
+
+/// The channel seed for a batch: the length prefixed seeds of all claims.
+pub(crate) fn batch_seed(batch: &[&Constraints]) -> Vec<u8> {
+    let mut seed = Vec::new();
+    for constraints in batch {
+        let claim_seed = constraints.seed();
+        seed.extend_from_slice(&(claim_seed.len() as u64).to_be_bytes());
+        seed.extend_from_slice(&claim_seed);
+    }
+    seed
+}
+
+fn verify_with<H: Hasher>(
+    constraints: &Constraints,
+    proof: &Proof,
+    details: &mut Details,
+) -> Result<()> {
+    match constraints.extension_degree {
+        1 => verify_in::<H, FieldElement>(constraints, proof, details),
+        2 => verify_in::<H, Ext<FieldElement, 2>>(constraints, proof, details),
+        degree => Err(Error::InvalidExtensionDegree(saturate(degree))),
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+fn verify_in<H: Hasher, E: Extension>(
+    constraints: &Constraints,
+    proof: &Proof,
+    details: &mut Details,
+) -> Result<()> {
     trace!("BEGIN Verify");
-    let proof = proof.as_bytes();
     let trace_length = constraints.trace_nrows();
     let trace_cols = constraints.trace_ncolumns();
     let eval_domain_size = trace_length * constraints.blowup;
     let eval_x = root_series(eval_domain_size).collect::<Vec<_>>();
 
-    let mut channel = VerifierChannel::new(proof.to_vec());
-    channel.initialize(constraints.channel_seed());
+    // Read the proof and draw the random values in between. The proof of work
+    // and the decommitments are checked as soon as they are read, and the
+    // rows of the stages are joined.
+    let mut lde_values: Vec<(usize, Row)> = Vec::new();
+    let mut constraint_values: Vec<(usize, Row)> = Vec::new();
+    let mut channel = Transcript::<E>::channel::<H>(constraints, proof);
+    let transcript = Transcript::<E>::replay(&mut channel, constraints, |step, transcript| {
+        let Transcript {
+            proof,
+            offsets,
+            queries,
+            ..
+        } = transcript;
+        match step {
+            Step::Queries => {
+                details.start(offsets.pow_nonce, None);
+                verify_pow(
+                    transcript.pow_seed.clone(),
+                    proof_of_work::Response::from_nonce(proof.pow_nonce),
+                    constraints.pow_bits,
+                )?;
+                lde_values = queries
+                    .iter()
+                    .map(|&index| {
+                        (index, Row {
+                            values: Vec::with_capacity(trace_cols),
+                            salt:   None,
+                        })
+                    })
+                    .collect();
+            }
+            Step::Rows(Oracle::Trace(index)) => {
+                details.start(
+                    offsets.trace_decommitments[index],
+                    Some(Oracle::Trace(index)),
+                );
+                let stage_values = check_rows::<H>(
+                    &transcript.trace_commitments[index],
+                    queries,
+                    proof.trace_decommitments().nth(index).unwrap(),
+                    constraints.stages()[index].columns,
+                    Oracle::Trace(index),
+                )?;
+                for ((_, row), (_, stage_row)) in lde_values.iter_mut().zip(stage_values) {
+                    row.values.extend(stage_row.values);
+                }
+            }
+            Step::Rows(oracle) => {
+                details.start(offsets.constraint_decommitment, Some(oracle));
+                constraint_values = check_rows::<H>(
+                    &transcript.constraint_commitment,
+                    queries,
+                    &proof.constraint_decommitment,
+                    proof.constraint_oods_values.len(),
+                    oracle,
+                )?;
+            }
+        }
+        Ok(())
+    });
+    let Transcript {
+        proof,
+        offsets,
+        fri_commitments,
+        challenges,
+        constraint_coefficients,
+        oods_point,
+        oods_coefficients,
+        fri_points,
+        queries,
+        ..
+    } = match transcript {
+        Ok(transcript) => transcript,
+        Err(ReplayError::Read(error)) => {
+            details.start(channel.position(), None);
+            return Err(error.into());
+        }
+        Err(ReplayError::Check(error)) => return Err(error),
+    };
+    let mut constraints = constraints.clone();
+    constraints.substitute_challenges(&challenges);
+
+    let oods = Oods::new(
+        &constraints,
+        oods_point,
+        offsets.oods_values,
+        &proof.trace_oods_values,
+        &proof.constraint_oods_values,
+        oods_coefficients,
+    );
+    let fri = Fri {
+        commitments:             fri_commitments,
+        eval_points:             fri_points,
+        last_layer_offset:       offsets.last_layer,
+        last_layer_coefficients: E::from_components_many(&proof.last_layer_coefficients),
+    };
+    fri.verify::<H>(
+        &proof.fri_decommitments,
+        &offsets.fri_decommitments,
+        &constraints.fri_layout,
+        &eval_x,
+        &queries,
+        details,
+        |z| {
+            let z_reverse = fft::permute_index(eval_domain_size, queries[z]);
+            oods.combine(
+                &eval_x[z_reverse],
+                &lde_values[z].1,
+                &constraint_values[z].1,
+                trace_length,
+            )
+        },
+    )?;
+
+    oods.check(&constraints, &constraint_coefficients, details)?;
+    trace!("END Verify");
+    Ok(())
+}
 
-    // Get the low degree root commitment, and constraint root commitment
-    // TODO: Make it work as channel.read()
-    let low_degree_extension_root: Hash = channel.replay();
-    let lde_commitment = Commitment::from_size_hash(eval_domain_size, &low_degree_extension_root)?;
-    let constraint_coefficients = channel.get_coefficients(2 * constraints.len());
+// TODO: Refactor into smaller function
+#[allow(clippy::too_many_lines)]
+fn verify_batch_in<H: Hasher, E: Extension>(
+    batch: &[&Constraints],
+    proof: &Proof,
+    details: &mut Details,
+) -> Result<()> {
+    trace!("BEGIN Verify batch");
+    let parameters = batch_parameters(batch).ok_or(Error::EmptyBatch)?;
+    let size = parameters.trace_nrows();
+    let eval_domain_size = size * parameters.blowup;
+    let eval_x = root_series(eval_domain_size).collect::<Vec<_>>();
 
-    let constraint_evaluated_root: Hash = channel.replay();
-    let constraint_commitment =
-        Commitment::from_size_hash(eval_domain_size, &constraint_evaluated_root)?;
+    let mut channel = VerifierChannel::<H>::new(proof.as_bytes().to_vec());
+    channel.initialize(&batch_seed(batch));
 
-    // Get the oods information from the proof and random
-    let oods_point: FieldElement = channel.get_random();
+    // The trace commitments of all claims come first
+    let mut lde_commitments = Vec::with_capacity(batch.len());
+    for _ in batch {
+        let root: Hash = channel.replay()?;
+        lde_commitments.push(Commitment::from_size_hash(eval_domain_size, &root)?);
+    }
 
-    // This hack is annoying and should be removed
-    let mut parseable_constraints = constraints.clone();
-    parseable_constraints.substitute();
-    let trace_arguments = parseable_constraints.trace_arguments();
-    let trace_values: Vec<FieldElement> = channel.replay_many(trace_arguments.len());
-    let claimed_trace_map: BTreeMap<(usize, isize), FieldElement> = trace_arguments
-        .into_iter()
-        .zip(trace_values.iter().cloned())
-        .collect();
-
-    let constraints_trace_degree = constraints.degree().next_power_of_two();
-    let claimed_constraint_values: Vec<FieldElement> =
-        channel.replay_many(constraints_trace_degree);
-
-    let oods_coefficients =
-        channel.get_coefficients(claimed_trace_map.len() + claimed_constraint_values.len());
-
-    let mut fri_commitments: Vec<Commitment> = Vec::with_capacity(constraints.fri_layout.len() + 1);
-    let mut eval_points: Vec<FieldElement> = Vec::with_capacity(constraints.fri_layout.len() + 1);
-    let mut fri_size = eval_domain_size;
-    // Get fri roots and eval points from the channel random
-    for &num_folds in &constraints.fri_layout {
-        fri_size >>= num_folds;
-        fri_commitments.push(Commitment::from_size_hash(fri_size, &channel.replay())?);
-        eval_points.push(channel.get_random());
-    }
-    // Gets the last layer coeffiencts
-    let last_layer_coefficients = channel.replay_fri_layer(fri_size / constraints.blowup);
-
-    // Gets the proof of work from the proof.
-    let pow_seed: proof_of_work::ChallengeSeed = channel.get_random();
-    let pow_challenge = pow_seed.with_difficulty(constraints.pow_bits);
-    let pow_response: proof_of_work::Response = channel.replay();
-    if !pow_challenge.verify(pow_response) {
-        return Err(Error::InvalidPoW);
+    // Then the constraint commitment of each claim
+    let mut constraint_coefficients = Vec::with_capacity(batch.len());
+    let mut constraint_commitments = Vec::with_capacity(batch.len());
+    for constraints in batch {
+        constraint_coefficients.push(E::draw_many(&mut channel, 2 * constraints.len()));
+        let root: Hash = channel.replay()?;
+        constraint_commitments.push(Commitment::from_size_hash(eval_domain_size, &root)?);
     }
 
-    // Gets queries from channel
+    // Each claim has its own oods point
+    let oods = batch
+        .iter()
+        .map(|constraints| Oods::<E>::replay(&mut channel, constraints))
+        .collect::<Result<Vec<_>>>()?;
+    let batch_coefficients = E::draw_many(&mut channel, batch.len());
+
+    let fri = Fri::<E>::replay(
+        &mut channel,
+        &parameters.fri_layout,
+        eval_domain_size,
+        parameters.blowup,
+        details,
+    )?;
+    details.start(channel.position(), None);
+    let pow_seed: proof_of_work::ChallengeSeed = channel.get_random();
+    verify_pow(pow_seed, channel.replay()?, parameters.pow_bits)?;
     let queries = get_indices(
-        constraints.num_queries,
+        parameters.num_queries,
         eval_domain_size.trailing_zeros(),
         &mut channel,
     );
 
-    // Get values and check decommitment of low degree extension
-    let lde_values: Vec<(usize, Vec<FieldElement>)> = queries
+    let mut rows = Vec::with_capacity(batch.len());
+    for (((constraints, oods), lde_commitment), constraint_commitment) in batch
         .iter()
-        .map(|&index| (index, channel.replay_fri_layer(trace_cols)))
-        .collect();
-    let lde_proof_length = lde_commitment.proof_size(&queries)?;
-    let lde_hashes: Vec<Hash> = channel.replay_many(lde_proof_length);
-    let lde_proof = MerkleProof::from_hashes(&lde_commitment, &queries, &lde_hashes)?;
-    // Note - we could express this a merkle error instead but this adds specificity
-    if lde_proof.verify(&lde_values).is_err() {
-        return Err(Error::InvalidLDECommitment);
-    }
-
-    // Gets the values and checks the constraint decommitment
-    let mut constraint_values = Vec::with_capacity(queries.len());
-    for query_index in &queries {
-        constraint_values.push((
-            *query_index,
-            channel.replay_fri_layer(constraints_trace_degree),
-        ));
-    }
-    let constraint_proof_length = constraint_commitment.proof_size(&queries)?;
-    let constraint_hashes: Vec<Hash> = channel.replay_many(constraint_proof_length);
-    let constraint_proof =
-        MerkleProof::from_hashes(&constraint_commitment, &queries, &constraint_hashes)?;
-    // Note - we could express this a merkle error instead but this adds specificity
-    if constraint_proof.verify(&constraint_values).is_err() {
-        return Err(Error::InvalidConstraintCommitment);
+        .zip(&oods)
+        .zip(&lde_commitments)
+        .zip(&constraint_commitments)
+    {
+        let lde_values = replay_rows(
+            &mut channel,
+            lde_commitment,
+            &queries,
+            constraints.trace_ncolumns(),
+            constraints.zero_knowledge,
+            Oracle::Trace(0),
+            details,
+        )?;
+        let constraint_values = replay_rows(
+            &mut channel,
+            constraint_commitment,
+            &queries,
+            E::DEGREE * oods.constraint_values.len(),
+            constraints.zero_knowledge,
+            Oracle::Constraint,
+            details,
+        )?;
+        rows.push((lde_values, constraint_values));
     }
 
-    let coset_sizes = constraints
-        .fri_layout
-        .iter()
-        .map(|k| 1_usize << k)
-        .collect::<Vec<_>>();
-    let mut fri_indices: Vec<usize> = queries
-        .to_vec()
-        .iter()
-        .map(|x| x / coset_sizes[0])
-        .collect();
-
-    // Folded fri values from the previous layer
-    let mut fri_folds: BTreeMap<usize, FieldElement> = BTreeMap::new();
-
-    let mut previous_indices = queries.to_vec();
-    let mut step = 1;
-    let mut len = eval_domain_size;
-    for (k, commitment) in fri_commitments.iter().enumerate() {
-        let mut fri_layer_values = Vec::new();
-
-        fri_indices.dedup();
-        for i in &fri_indices {
-            let mut coset: Vec<FieldElement> = Vec::new();
-            for j in 0..coset_sizes[k] {
-                let n = i * coset_sizes[k] + j;
-                if let Ok(z) = previous_indices.binary_search(&n) {
-                    if k > 0 {
-                        coset.push(match fri_folds.get(&n) {
-                            Some(x) => x.clone(),
-                            None => return Err(Error::HashMapFailure),
-                        });
-                    } else {
-                        let z_reverse = fft::permute_index(eval_domain_size, queries[z]);
-                        coset.push(out_of_domain_element(
-                            &eval_x[z_reverse],
-                            &lde_values[z].1,
-                            &constraint_values[z].1,
-                            &oods_point,
-                            &claimed_trace_map,
-                            &claimed_constraint_values,
-                            &oods_coefficients,
-                            trace_length,
-                        )?);
-                    }
-                } else {
-                    coset.push(channel.replay());
-                }
+    details.start(channel.position(), None);
+    let (fri_decommitments, fri_offsets) = Decommitment::replay_fri::<H, E>(
+        &mut channel,
+        &fri.commitments,
+        &parameters.fri_layout,
+        &queries,
+    )?;
+    details.start(channel.position(), None);
+    if !channel.at_end() {
+        return Err(Error::ProofTooLong);
+    }
+
+    // The first FRI layer is the random combination of the out of domain
+    // polynomials, each shifted up to the degree of the longest trace.
+    fri.verify::<H>(
+        &fri_decommitments,
+        &fri_offsets,
+        &parameters.fri_layout,
+        &eval_x,
+        &queries,
+        details,
+        |z| {
+            let x = &eval_x[fft::permute_index(eval_domain_size, queries[z])];
+            let shifted_x = x * FieldElement::generator();
+            let mut result = E::zero();
+            for (((constraints, oods), (lde_values, constraint_values)), coefficient) in
+                batch.iter().zip(&oods).zip(&rows).zip(&batch_coefficients)
+            {
+                let trace_length = constraints.trace_nrows();
+                let value =
+                    oods.combine(x, &lde_values[z].1, &constraint_values[z].1, trace_length)?;
+                result += coefficient.mul_base(&shifted_x.pow(size - trace_length)) * value;
             }
-            fri_layer_values.push((*i, coset));
-        }
-        // Fold and record foldings
-        let mut layer_folds = BTreeMap::new();
-        for (i, coset) in &fri_layer_values {
-            let _old_value = layer_folds.insert(
-                *i,
-                fri_fold(
-                    coset.as_slice(),
-                    &eval_points[k],
-                    step,
-                    (coset_sizes[k] / 2) * i,
-                    len,
-                    eval_x.as_slice(),
-                ),
-            );
-        }
+            Ok(result)
+        },
+    )?;
+
+    for ((constraints, oods), coefficients) in batch.iter().zip(&oods).zip(&constraint_coefficients)
+    {
+        oods.check(constraints, coefficients, details)?;
+    }
+    trace!("END Verify batch");
+    Ok(())
+}
 
-        let merkle_proof_length = commitment.proof_size(&fri_indices)?;
-        let merkle_hashes: Vec<Hash> = channel.replay_many(merkle_proof_length);
-        let merkle_proof = MerkleProof::from_hashes(commitment, &fri_indices, &merkle_hashes)?;
-        fri_folds = layer_folds;
+/// The out of domain sample of a single claim.
+struct Oods<E> {
+    point:                    E,
+    offset:                   usize,
+    trace_values:             BTreeMap<(usize, isize), E>,
+    constraint_values:        Vec<E>,
+    coefficients:             Vec<E>,
+    constraints_trace_degree: usize,
+}
 
-        for _ in 0..constraints.fri_layout[k] {
-            step *= 2;
+impl<E: Extension> Oods<E> {
+    /// The values are the coefficients of the sampled values, as in the
+    /// proof.
+    fn new(
+        constraints: &Constraints,
+        point: E,
+        offset: usize,
+        trace_values: &[FieldElement],
+        constraint_values: &[FieldElement],
+        coefficients: Vec<E>,
+    ) -> Self {
+        let trace_values: BTreeMap<(usize, isize), E> = trace_arguments(constraints)
+            .into_iter()
+            .zip(E::from_components_many(trace_values))
+            .collect();
+        Self {
+            point,
+            offset,
+            trace_values,
+            constraint_values: E::from_components_many(constraint_values),
+            coefficients,
+            constraints_trace_degree: constraints.degree().next_power_of_two(),
         }
-        len /= coset_sizes[k];
+    }
 
-        // Note - we could express this a merkle error instead but this adds specificity
-        if merkle_proof.verify(&fri_layer_values).is_err() {
-            return Err(Error::InvalidFriCommitment);
-        };
+    fn replay<H: Hasher>(
+        channel: &mut VerifierChannel<H>,
+        constraints: &Constraints,
+    ) -> Result<Self> {
+        let point = E::draw(channel);
+        let offset = channel.position();
+        let trace_values: Vec<FieldElement> =
+            channel.replay_many(E::DEGREE * trace_arguments(constraints).len())?;
+        let constraint_cols = E::DEGREE
+            * (constraints.degree().next_power_of_two() + usize::from(constraints.zero_knowledge));
+        let constraint_values: Vec<FieldElement> = channel.replay_many(constraint_cols)?;
+        let coefficients = E::draw_many(
+            channel,
+            (trace_values.len() + constraint_values.len()) / E::DEGREE,
+        );
+        Ok(Self::new(
+            constraints,
+            point,
+            offset,
+            &trace_values,
+            &constraint_values,
+            coefficients,
+        ))
+    }
 
-        previous_indices = fri_indices.clone();
-        if k + 1 < constraints.fri_layout.len() {
-            fri_indices = fri_indices
-                .iter()
-                .map(|ind| ind / coset_sizes[k + 1])
-                .collect();
+    /// The value of the out of domain polynomial at `query_x`, given the
+    /// decommitted rows there.
+    fn combine(
+        &self,
+        query_x: &FieldElement,
+        trace_row: &Row,
+        constraint_row: &Row,
+        trace_length: usize,
+    ) -> Result<E> {
+        out_of_domain_element(
+            query_x,
+            &trace_row.values,
+            &constraint_row.values,
+            &self.point,
+            &self.trace_values,
+            &self.constraint_values,
+            &self.coefficients,
+            trace_length,
+            self.constraints_trace_degree,
+        )
+    }
+
+    /// Checks the sampled trace values against the sampled constraint values.
+    fn check(
+        &self,
+        constraints: &Constraints,
+        constraint_coefficients: &[E],
+        details: &mut Details,
+    ) -> Result<()> {
+        let computed = oods_value_from_trace_values(
+            constraints,
+            constraint_coefficients,
+            &self.trace_values,
+            &self.point,
+        );
+        let expected = oods_value_from_constraint_values(
+            &self.constraint_values[..self.constraints_trace_degree],
+            &self.point,
+        );
+        if computed != expected {
+            details.start(self.offset, None);
+            details.mismatch(&expected, &computed);
+            return Err(Error::OodsMismatch);
         }
+        Ok(())
     }
-    if !channel.at_end() {
-        return Err(Error::ProofTooLong);
+}
+
+/// The FRI layer commitments, folding points and last layer of a proof.
+struct Fri<E> {
+    commitments:             Vec<Commitment>,
+    eval_points:             Vec<E>,
+    last_layer_offset:       usize,
+    last_layer_coefficients: Vec<E>,
+}
+
+impl<E: Extension> Fri<E> {
+    fn replay<H: Hasher>(
+        channel: &mut VerifierChannel<H>,
+        fri_layout: &[usize],
+        eval_domain_size: usize,
+        blowup: usize,
+        details: &mut Details,
+    ) -> Result<Self> {
+        let mut commitments: Vec<Commitment> = Vec::with_capacity(fri_layout.len() + 1);
+        let mut eval_points: Vec<E> = Vec::with_capacity(fri_layout.len() + 1);
+        let mut fri_size = eval_domain_size;
+        // Get fri roots and eval points from the channel random
+        for (layer, &num_folds) in fri_layout.iter().enumerate() {
+            fri_size >>= num_folds;
+            details.start(channel.position(), Some(Oracle::Fri(layer)));
+            commitments.push(Commitment::from_size_hash(fri_size, &channel.replay()?)?);
+            eval_points.push(E::draw(channel));
+        }
+        // Gets the last layer coeffiencts
+        let last_layer_offset = channel.position();
+        let last_layer_coefficients =
+            E::from_components_many(&channel.replay_fri_layer(E::DEGREE * (fri_size / blowup))?);
+        Ok(Self {
+            commitments,
+            eval_points,
+            last_layer_offset,
+            last_layer_coefficients,
+        })
     }
 
-    // Checks that the calculated fri folded queries are the points interpolated by
-    // the decommited polynomial.
-    let interp_root = match FieldElement::root(len) {
-        Some(x) => x,
-        None => return Err(Error::RootUnavailable),
-    };
-    for key in &previous_indices {
-        let calculated = fri_folds[key].clone();
-        let x_pow = interp_root.pow(fft::permute_index(len, *key));
-        let committed = DensePolynomial::new(&last_layer_coefficients).evaluate(&x_pow);
+    /// Checks the FRI decommitments, each starting at the byte offset in
+    /// `offsets`.
+    ///
+    /// The first layer value at the `z`-th query is computed by `first_layer`.
+    #[allow(clippy::too_many_arguments)]
+    fn verify<H: Hasher>(
+        &self,
+        decommitments: &[Decommitment],
+        offsets: &[usize],
+        fri_layout: &[usize],
+        eval_x: &[FieldElement],
+        queries: &[usize],
+        details: &mut Details,
+        first_layer: impl Fn(usize) -> Result<E>,
+    ) -> Result<()> {
+        let eval_domain_size = eval_x.len();
+        let coset_sizes = fri_layout.iter().map(|k| 1_usize << k).collect::<Vec<_>>();
+        let mut fri_indices: Vec<usize> = queries
+            .to_vec()
+            .iter()
+            .map(|x| x / coset_sizes[0])
+            .collect();
+
+        // Folded fri values from the previous layer
+        let mut fri_folds: BTreeMap<usize, E> = BTreeMap::new();
 
-        if committed != calculated.clone() {
-            return Err(Error::OodsCalculationFailure);
+        let mut previous_indices = queries.to_vec();
+        let mut step = 1;
+        let mut len = eval_domain_size;
+        for (k, ((commitment, decommitment), &offset)) in self
+            .commitments
+            .iter()
+            .zip(decommitments)
+            .zip(offsets)
+            .enumerate()
+        {
+            let mut fri_layer_values = Vec::new();
+            let mut values = decommitment.values.chunks_exact(E::DEGREE);
+            details.start(offset, Some(Oracle::Fri(k)));
+            details.fri_layer = Some(k);
+
+            fri_indices.dedup();
+            for i in &fri_indices {
+                let mut coset: Vec<E> = Vec::new();
+                for j in 0..coset_sizes[k] {
+                    let n = i * coset_sizes[k] + j;
+                    details.query = Some(n);
+                    if let Ok(z) = previous_indices.binary_search(&n) {
+                        if k > 0 {
+                            coset.push(match fri_folds.get(&n) {
+                                Some(x) => x.clone(),
+                                None => return Err(Error::HashMapFailure),
+                            });
+                        } else {
+                            coset.push(first_layer(z)?);
+                        }
+                    } else {
+                        coset.push(match values.next() {
+                            Some(x) => E::from_components(x),
+                            None => return Err(Error::ProofTooShort),
+                        });
+                    }
+                }
+                fri_layer_values.push((*i, coset));
+            }
+            // Fold and record foldings
+            let mut layer_folds = BTreeMap::new();
+            for (i, coset) in &fri_layer_values {
+                let _old_value = layer_folds.insert(
+                    *i,
+                    fri_fold(
+                        coset.as_slice(),
+                        &self.eval_points[k],
+                        step,
+                        (coset_sizes[k] / 2) * i,
+                        len,
+                        eval_x,
+                    ),
+                );
+            }
+
+            details.query = None;
+            let merkle_proof =
+                MerkleProof::<H>::from_hashes(commitment, &fri_indices, &decommitment.hashes)?;
+            fri_folds = layer_folds;
+
+            for _ in 0..fri_layout[k] {
+                step *= 2;
+            }
+            len /= coset_sizes[k];
+
+            // The leaves hold the coefficients of the values
+            let leaves = fri_layer_values
+                .iter()
+                .map(|(i, coset)| (*i, flatten(coset)))
+                .collect::<Vec<_>>();
+            // Note - we could express this a merkle error instead but this adds specificity
+            if merkle_proof.verify(&leaves).is_err() {
+                return Err(Error::InvalidFriCommitment);
+            };
+
+            previous_indices = fri_indices.clone();
+            if k + 1 < fri_layout.len() {
+                fri_indices = fri_indices
+                    .iter()
+                    .map(|ind| ind / coset_sizes[k + 1])
+                    .collect();
+            }
         }
+        // Checks that the calculated fri folded queries are the points interpolated by
+        // the decommited polynomial.
+        let interp_root = match FieldElement::root(len) {
+            Some(x) => x,
+            None => return Err(Error::RootUnavailable),
+        };
+        for key in &previous_indices {
+            let calculated = fri_folds[key].clone();
+            let x_pow = interp_root.pow(fft::permute_index(len, *key));
+            let mut committed = E::zero();
+            for coefficient in self.last_layer_coefficients.iter().rev() {
+                committed = committed.mul_base(&x_pow) + coefficient;
+            }
+
+            if committed != calculated {
+                details.start(self.last_layer_offset, None);
+                details.fri_layer = Some(self.commitments.len());
+                details.query = Some(*key);
+                details.mismatch(&committed, &calculated);
+                return Err(Error::OodsCalculationFailure);
+            }
+        }
+        Ok(())
     }
+}
 
-    if oods_value_from_trace_values(
-        &constraints,
-        &constraint_coefficients,
-        &claimed_trace_map,
-        &oods_point,
-    ) != oods_value_from_constraint_values(&claimed_constraint_values, &oods_point)
-    {
-        return Err(Error::OodsMismatch);
+fn verify_pow(
+    seed: proof_of_work::ChallengeSeed,
+    response: proof_of_work::Response,
+    pow_bits: usize,
+) -> Result<()> {
+    if !seed.with_difficulty(pow_bits).verify(response) {
+        return Err(Error::InvalidPoW);
     }
-    trace!("END Verify");
     Ok(())
 }
 
-fn oods_value_from_trace_values(
+/// Reads the decommitted rows at `queries` and checks them against
+/// `commitment` of `oracle`.
+fn replay_rows<H: Hasher>(
+    channel: &mut VerifierChannel<H>,
+    commitment: &Commitment,
+    queries: &[usize],
+    columns: usize,
+    salted: bool,
+    oracle: Oracle,
+    details: &mut Details,
+) -> Result<Vec<(usize, Row)>> {
+    details.start(channel.position(), Some(oracle));
+    let decommitment = Decommitment::replay_rows(channel, commitment, queries, columns, salted)?;
+    check_rows::<H>(commitment, queries, &decommitment, columns, oracle)
+}
+
+/// Checks the decommitted rows at `queries` against `commitment` of `oracle`.
+fn check_rows<H: Hasher>(
+    commitment: &Commitment,
+    queries: &[usize],
+    decommitment: &Decommitment,
+    columns: usize,
+    oracle: Oracle,
+) -> Result<Vec<(usize, Row)>> {
+    let rows = decommitment.rows(queries, columns);
+    let proof = MerkleProof::<H>::from_hashes(commitment, queries, &decommitment.hashes)?;
+    // Note - we could express this a merkle error instead but this adds specificity
+    if proof.verify(&rows).is_err() {
+        return Err(match oracle {
+            Oracle::Constraint => Error::InvalidConstraintCommitment,
+            _ => Error::InvalidLDECommitment,
+        });
+    }
+    Ok(rows)
+}
+
+/// The `(column, row offset)` pairs of the trace values sampled at the out of
+/// domain point, in proof order.
+fn trace_arguments(constraints: &Constraints) -> Vec<(usize, isize)> {
+    // This hack is annoying and should be removed
+    let mut parseable_constraints = constraints.clone();
+    parseable_constraints.substitute();
+    parseable_constraints.trace_arguments()
+}
+
+/// The combined constraints at the out of domain point.
+///
+/// The combination is linear in the coefficients. With coefficients in an
+/// extension field it is the sum of the combinations with each of their
+/// coefficients over `FieldElement`, times the matching basis element.
+fn oods_value_from_trace_values<E: Extension>(
     constraints: &Constraints,
-    coefficients: &[FieldElement],
-    trace_values: &BTreeMap<(usize, isize), FieldElement>,
-    oods_point: &FieldElement,
-) -> FieldElement {
+    coefficients: &[E],
+    trace_values: &BTreeMap<(usize, isize), E>,
+    oods_point: &E,
+) -> E {
     let trace = |i: usize, j: isize| trace_values.get(&(i, j)).unwrap().clone();
-    constraints
-        .combine(coefficients)
-        .substitute_claim(&constraints.claim_polynomials)
-        .evaluate(oods_point, &trace)
-}
-
-fn oods_value_from_constraint_values(
-    constraint_values: &[FieldElement],
-    oods_point: &FieldElement,
-) -> FieldElement {
-    let mut result = FieldElement::zero();
-    let mut power = FieldElement::one();
+    (0..E::DEGREE)
+        .map(|component| {
+            let coefficients = coefficients
+                .iter()
+                .map(|coefficient| coefficient.components()[component].clone())
+                .collect::<Vec<_>>();
+            let value = constraints
+                .combine(&coefficients)
+                .substitute_claim(&constraints.claim_polynomials)
+                .substitute_periodic(constraints.trace_nrows())
+                .evaluate_in(oods_point, &trace);
+            E::basis(component) * value
+        })
+        .sum()
+}
+
+fn oods_value_from_constraint_values<E: Extension>(constraint_values: &[E], oods_point: &E) -> E {
+    let mut result = E::zero();
+    let mut power = E::one();
     for value in constraint_values {
-        result += value * &power;
+        result += value.clone() * &power;
         power *= oods_point;
     }
     result
@@ -423,7 +1128,11 @@ fn oods_value_from_constraint_values(
 
 // TODO: Clean up
 #[allow(clippy::cast_possible_truncation)]
-fn get_indices(num: usize, bits: u32, proof: &mut VerifierChannel) -> Vec<usize> {
+pub(crate) fn get_indices<H: Hasher>(
+    num: usize,
+    bits: u32,
+    proof: &mut VerifierChannel<H>,
+) -> Vec<usize> {
     let mut query_indices = Vec::with_capacity(num + 3);
     while query_indices.len() < num {
         let val: U256 = proof.get_random();
@@ -440,16 +1149,16 @@ fn get_indices(num: usize, bits: u32, proof: &mut VerifierChannel) -> Vec<usize>
     query_indices
 }
 
-fn fri_fold(
-    coset: &[FieldElement],
-    eval_point: &FieldElement,
+fn fri_fold<E: Extension>(
+    coset: &[E],
+    eval_point: &E,
     mut step: usize,
     mut index: usize,
     mut len: usize,
     eval_x: &[FieldElement],
-) -> FieldElement {
+) -> E {
     let mut mutable_eval_copy = eval_point.clone();
-    let mut coset_full: Vec<FieldElement> = coset.to_vec();
+    let mut coset_full: Vec<E> = coset.to_vec();
     while coset_full.len() > 1 {
         let mut next_coset = Vec::with_capacity(coset.len() / 2);
 
@@ -460,33 +1169,36 @@ fn fri_fold(
         len /= 2;
         index /= 2;
         step *= 2;
-        mutable_eval_copy = mutable_eval_copy.square();
+        mutable_eval_copy = mutable_eval_copy.square_inline();
         coset_full = next_coset;
     }
     coset_full[0].clone()
 }
 
-fn fri_single_fold(
-    poly_at_x: &FieldElement,
-    poly_at_neg_x: &FieldElement,
+fn fri_single_fold<E: Extension>(
+    poly_at_x: &E,
+    poly_at_neg_x: &E,
     x: &FieldElement,
-    eval_point: &FieldElement,
-) -> FieldElement {
-    (poly_at_x + poly_at_neg_x) + eval_point / x * (poly_at_x - poly_at_neg_x)
+    eval_point: &E,
+) -> E {
+    let x_inv = x.inv().expect("x is in the evaluation domain");
+    (poly_at_x.clone() + poly_at_neg_x)
+        + eval_point.mul_base(&x_inv) * (poly_at_x.clone() - poly_at_neg_x)
 }
 
 #[allow(clippy::too_many_arguments)]
-fn out_of_domain_element(
+fn out_of_domain_element<E: Extension>(
     query_x: &FieldElement,
     query_trace_values: &[FieldElement],
     query_constraint_values: &[FieldElement],
-    oods_point: &FieldElement,
-    oods_trace_map: &BTreeMap<(usize, isize), FieldElement>,
-    oods_constraint_values: &[FieldElement],
-    oods_coefficients: &[FieldElement],
+    oods_point: &E,
+    oods_trace_map: &BTreeMap<(usize, isize), E>,
+    oods_constraint_values: &[E],
+    oods_coefficients: &[E],
     trace_length: usize,
-) -> Result<FieldElement> {
-    let shifted_x = query_x * FieldElement::generator();
+    constraints_trace_degree: usize,
+) -> Result<E> {
+    let shifted_x = E::from(query_x * FieldElement::generator());
     let trace_generator = match FieldElement::root(trace_length) {
         Some(x) => x,
         None => return Err(Error::RootUnavailable),
@@ -495,22 +1207,23 @@ fn out_of_domain_element(
     let trace_terms = oods_trace_map
         .iter()
         .map(|((column_index, offset), oods_value)| {
-            (&query_trace_values[*column_index] - oods_value)
-                / (&shifted_x - trace_generator.pow(*offset).unwrap() * oods_point)
+            (E::from(query_trace_values[*column_index].clone()) - oods_value)
+                / (shifted_x.clone() - oods_point.mul_base(&trace_generator.pow(*offset).unwrap()))
         });
 
-    let constraints_trace_degree = query_constraint_values.len();
+    // The constraint columns hold the coefficients of the values
+    let oods_point_pow = oods_point.power(constraints_trace_degree);
     let combined_constraints_terms = query_constraint_values
-        .iter()
+        .chunks_exact(E::DEGREE)
         .zip(oods_constraint_values)
         .map(|(query_value, oods_value)| {
-            (query_value - oods_value) / (&shifted_x - oods_point.pow(constraints_trace_degree))
+            (E::from_components(query_value) - oods_value) / (shifted_x.clone() - &oods_point_pow)
         });
 
     Ok(trace_terms
         .chain(combined_constraints_terms)
         .zip(oods_coefficients)
-        .map(|(coeffient, term)| coeffient * term)
+        .map(|(term, coefficient)| coefficient.clone() * term)
         .sum())
 }
 
@@ -524,6 +1237,13 @@ impl error::Error for Error {
     }
 }
 
+#[cfg(feature = "std")]
+impl error::Error for Diagnostics {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -557,4 +1277,49 @@ mod tests {
             prop_assert!(verify(&constraints, &prove(&constraints, &trace).unwrap()).is_ok());
         }
     );
+
+    #[test]
+    fn detailed_test() {
+        use zkp_macros_decl::field_element;
+        use zkp_u256::U256;
+
+        let recurrance = Recurrance {
+            index:         1000,
+            initial_value: field_element!("cafebabe"),
+            exponent:      1,
+        };
+        let constraints = recurrance.claim().constraints();
+        let trace = recurrance.claim().trace(&recurrance.witness());
+        let proof = prove(&constraints, &trace).unwrap();
+        assert_eq!(verify_detailed(&constraints, &proof), Ok(()));
+        let length = proof.as_bytes().len();
+
+        let mut bytes = proof.as_bytes().to_vec();
+        bytes.push(0);
+        let diagnostics = verify_detailed(&constraints, &Proof::from_bytes(bytes)).unwrap_err();
+        assert_eq!(diagnostics.error, Error::ProofTooLong);
+        assert_eq!(diagnostics.details.offset, length);
+
+        // The last bytes are the decommitment of the last FRI layer
+        let last_layer = constraints.fri_layout.len() - 1;
+        let mut bytes = proof.as_bytes().to_vec();
+        bytes[length - 1] ^= 1;
+        let diagnostics = verify_detailed(&constraints, &Proof::from_bytes(bytes)).unwrap_err();
+        assert_eq!(diagnostics.error, Error::InvalidFriCommitment);
+        assert_eq!(diagnostics.details.oracle, Some(Oracle::Fri(last_layer)));
+        assert_eq!(diagnostics.details.fri_layer, Some(last_layer));
+        assert!(diagnostics.details.offset < length);
+        assert!(diagnostics
+            .to_string()
+            .starts_with("A FRI layer commitment is incorrect at proof byte"));
+
+        // Changing the trace commitment changes all randomness, so the trace
+        // decommitment is the first to fail.
+        let mut bytes = proof.as_bytes().to_vec();
+        bytes[0] ^= 1;
+        let diagnostics = verify_detailed(&constraints, &Proof::from_bytes(bytes)).unwrap_err();
+        assert_eq!(diagnostics.error, Error::InvalidLDECommitment);
+        assert_eq!(diagnostics.details.oracle, Some(Oracle::Trace(0)));
+        assert!(diagnostics.details.offset > 0);
+    }
 }
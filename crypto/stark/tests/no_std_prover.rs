@@ -0,0 +1,53 @@
+//! Proving without the `std` feature.
+//!
+//! Run with `cargo test_nostd_prover`, which builds the crate with only the
+//! `prover` feature, as on targets without `std`. With `std` it checks the same
+//! proof the usual way.
+#![cfg(feature = "prover")]
+use zkp_stark::{
+    primefield::{FieldElement, Root},
+    prove, verify, Constraints, ProverError, RationalExpression, TraceTable,
+};
+
+fn fibonacci() -> (Constraints, TraceTable) {
+    use RationalExpression::*;
+    let rows = 64;
+    let g = Constant(FieldElement::root(rows).unwrap());
+    let every_row = (X - g.pow(rows - 1)) / (X.pow(rows) - 1);
+    let mut constraints = Constraints::from_expressions((rows, 2), b"fibonacci".to_vec(), vec![
+        (Trace(0, 1) - Trace(1, 0)) * every_row.clone(),
+        (Trace(1, 1) - Trace(0, 0) - Trace(1, 0)) * every_row,
+        (Trace(0, 0) - 1) / (X - 1),
+    ])
+    .unwrap();
+    constraints.num_queries = 20;
+
+    let mut trace = TraceTable::new(rows, 2);
+    trace[(0, 0)] = 1.into();
+    trace[(0, 1)] = 0xcafe_babe_u64.into();
+    for i in 0..rows - 1 {
+        trace[(i + 1, 0)] = trace[(i, 1)].clone();
+        trace[(i + 1, 1)] = &trace[(i, 0)] + &trace[(i, 1)];
+    }
+    (constraints, trace)
+}
+
+#[test]
+fn prove_and_verify() {
+    let (constraints, trace) = fibonacci();
+    let proof = prove(&constraints, &trace).ok().unwrap();
+    assert!(verify(&constraints, &proof).is_ok());
+}
+
+#[test]
+fn zero_knowledge() {
+    let (mut constraints, trace) = fibonacci();
+    constraints.zero_knowledge = true;
+    let result = prove(&constraints, &trace);
+    if cfg!(feature = "std") {
+        // The trace has no randomized rows
+        assert!(matches!(result, Err(ProverError::RandomRows { .. })));
+    } else {
+        assert!(matches!(result, Err(ProverError::ZeroKnowledgeUnavailable)));
+    }
+}
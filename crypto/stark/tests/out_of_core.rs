@@ -0,0 +1,37 @@
+//! Proving with all allocations on disk.
+#![cfg(all(feature = "prover", feature = "std"))]
+use zkp_stark::{parse_air, prove, verify, Scratch, TraceTable};
+
+const FIBONACCI: &str = "rows 1024\ncolumns 2\nseed \"fibonacci\"\n
+    transition trace(0, 1) = trace(1, 0)
+    transition trace(1, 1) = trace(0, 0) + trace(1, 0)
+    boundary trace(0, 0) = 1 at row 0";
+
+#[test]
+fn out_of_core() {
+    let mut constraints = parse_air(FIBONACCI).unwrap();
+    constraints.num_queries = 20;
+    let mut trace = TraceTable::new(1024, 2);
+    trace[(0, 0)] = 1.into();
+    trace[(0, 1)] = 0xcafe_babe_u64.into();
+    for i in 0..1023 {
+        trace[(i + 1, 0)] = trace[(i, 1)].clone();
+        trace[(i + 1, 1)] = &trace[(i, 0)] + &trace[(i, 1)];
+    }
+
+    // Put every allocation on disk and skip most of the Merkle tree, while a
+    // proof in memory runs next to it
+    let directory = std::env::temp_dir().join(format!("stark-test-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let scratch = Scratch::new(Some(directory.clone()), Some(0)).unwrap();
+    let on_disk = trace.clone_in(&scratch);
+    let (expected, actual) = rayon::join(
+        || prove(&constraints, &trace).unwrap(),
+        || prove(&constraints, &on_disk).unwrap(),
+    );
+    assert_eq!(scratch.anonymous_bytes(), 0);
+    std::fs::remove_dir(&directory).unwrap();
+
+    verify(&constraints, &actual).unwrap();
+    assert_eq!(actual.as_bytes(), expected.as_bytes());
+}
@@ -43,9 +43,11 @@
 
 #[cfg(feature = "std")]
 mod mmap_vec;
+#[cfg(feature = "std")]
+mod scratch;
 
 #[cfg(feature = "std")]
-pub use crate::mmap_vec::MmapVec;
+pub use crate::{mmap_vec::MmapVec, scratch::Scratch};
 
 // In no std mode, substitute no_std_compat
 #[cfg(not(feature = "std"))]
@@ -54,3 +56,40 @@ extern crate no_std_compat as std;
 
 #[cfg(not(feature = "std"))]
 pub use std::vec::Vec as MmapVec;
+
+/// Allocation in a [`Scratch`].
+///
+/// Code that allocates `MmapVec`s for large intermediate values uses this to
+/// put them where its inputs are, without depending on the `std` feature.
+pub trait InScratch {
+    /// An empty vector with room for `capacity` elements, allocated in
+    /// `scratch`.
+    fn with_capacity_in_scratch(capacity: usize, scratch: &Scratch) -> Self;
+
+    /// The scratch this vector is allocated in.
+    fn scratch(&self) -> &Scratch;
+}
+
+/// Without `std` all values are in memory, there is nowhere else to put them.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Debug, Default)]
+pub struct Scratch;
+
+#[cfg(not(feature = "std"))]
+impl Scratch {
+    /// There is no memory budget without `std`.
+    pub fn budget(&self) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> InScratch for MmapVec<T> {
+    fn with_capacity_in_scratch(capacity: usize, _scratch: &Scratch) -> Self {
+        Self::with_capacity(capacity)
+    }
+
+    fn scratch(&self) -> &Scratch {
+        &Scratch
+    }
+}
@@ -7,8 +7,10 @@
 #[allow(clippy::wildcard_imports)]
 use std::prelude::v1::*;
 
-use log::trace;
-use memmap::{MmapMut, MmapOptions};
+use crate::{
+    scratch::{Allocation, Scratch},
+    InScratch,
+};
 use std::{
     cmp::max,
     marker::PhantomData,
@@ -21,9 +23,14 @@ use std::{
 // TODO: Variant of MmapVec where it switched between Vec and Mmap after
 //       a treshold size.
 
+/// Fixed capacity vector stored in a memory map
+///
+/// The map is anonymous unless it is allocated in a [`Scratch`] with a
+/// directory whose memory budget is exhausted, see
+/// [`InScratch::with_capacity_in_scratch`].
 #[derive(Debug)] // TODO: Custom implementation
 pub struct MmapVec<T: Clone> {
-    mmap:     MmapMut,
+    storage:  Allocation,
     length:   usize,
     capacity: usize,
     _t:       PhantomData<T>,
@@ -32,20 +39,7 @@ pub struct MmapVec<T: Clone> {
 impl<T: Clone> MmapVec<T> {
     #[must_use]
     pub fn with_capacity(capacity: usize) -> Self {
-        // TODO: Round up to nearest 4KB
-        // Note: mmaped files can not be empty, so we use at leas one byte.
-        let size = max(1, capacity * size_of::<T>());
-        trace!("Allocating {} MB in anonymous mmap", size / 1_000_000);
-        let mmap = MmapOptions::new()
-            .len(size)
-            .map_anon()
-            .expect("cannot access memory mapped file");
-        Self {
-            mmap,
-            length: 0,
-            capacity,
-            _t: PhantomData,
-        }
+        Self::with_capacity_in_scratch(capacity, &Scratch::default())
     }
 
     /// # Safety
@@ -57,11 +51,26 @@ impl<T: Clone> MmapVec<T> {
     // See https://github.com/rust-lang/rfcs/issues/2626
     #[must_use]
     pub unsafe fn zero_initialized(len: usize) -> Self {
-        let mut result = Self::with_capacity(len);
+        Self::zero_initialized_in_scratch(len, &Scratch::default())
+    }
+
+    /// Like [`MmapVec::zero_initialized`], but allocated in `scratch`.
+    ///
+    /// # Safety
+    /// See [`MmapVec::zero_initialized`].
+    #[must_use]
+    pub unsafe fn zero_initialized_in_scratch(len: usize, scratch: &Scratch) -> Self {
+        let mut result = Self::with_capacity_in_scratch(len, scratch);
         result.length = len;
         result
     }
 
+    /// Whether the storage is a file in the scratch directory
+    #[must_use]
+    pub fn is_file_backed(&self) -> bool {
+        self.storage.is_file_backed()
+    }
+
     #[must_use]
     pub fn is_empty(&self) -> bool {
         self.length == 0
@@ -146,9 +155,28 @@ impl<T: Clone + PartialEq> PartialEq for MmapVec<T> {
     }
 }
 
+impl<T: Clone> InScratch for MmapVec<T> {
+    fn with_capacity_in_scratch(capacity: usize, scratch: &Scratch) -> Self {
+        // TODO: Round up to nearest 4KB
+        // Note: mmaped files can not be empty, so we use at leas one byte.
+        let size = max(1, capacity * size_of::<T>());
+        let storage = Allocation::new(size, scratch);
+        Self {
+            storage,
+            length: 0,
+            capacity,
+            _t: PhantomData,
+        }
+    }
+
+    fn scratch(&self) -> &Scratch {
+        &self.storage.scratch
+    }
+}
+
 impl<T: Clone> Clone for MmapVec<T> {
     fn clone(&self) -> Self {
-        let mut clone = Self::with_capacity(self.capacity);
+        let mut clone = Self::with_capacity_in_scratch(self.capacity, self.scratch());
         clone.extend(self.iter());
         clone
     }
@@ -181,14 +209,14 @@ impl<T: Clone> Deref for MmapVec<T> {
 
     #[inline]
     fn deref(&self) -> &[T] {
-        unsafe { slice::from_raw_parts(self.mmap.as_ptr() as *const T, self.length) }
+        unsafe { slice::from_raw_parts(self.storage.mmap.as_ptr() as *const T, self.length) }
     }
 }
 
 impl<T: Clone> DerefMut for MmapVec<T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut [T] {
-        unsafe { slice::from_raw_parts_mut(self.mmap.as_mut_ptr() as *mut T, self.length) }
+        unsafe { slice::from_raw_parts_mut(self.storage.mmap.as_mut_ptr() as *mut T, self.length) }
     }
 }
 
@@ -0,0 +1,233 @@
+//! Configuration of where `MmapVec`s are stored.
+//!
+//! By default all allocations use anonymous memory maps, which are backed by
+//! RAM and swap. When a [`Scratch`] with a directory is used, allocations that
+//! would bring the total of its anonymous maps over its memory budget are
+//! instead backed by files in that directory. The operating system then pages
+//! the data in and out as it is accessed, which allows working sets larger
+//! than the available memory.
+
+// This module abstracts low-level `unsafe` behaviour
+#![allow(unsafe_code)]
+
+use log::{trace, warn};
+use memmap::{MmapMut, MmapOptions};
+use std::{
+    collections::BTreeMap,
+    fs::{remove_file, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+};
+
+/// Counter to create unique file names
+static FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Counter to create unique identifiers for the accounting of a `Scratch`
+static SCRATCHES: AtomicUsize = AtomicUsize::new(0);
+
+/// Bytes currently allocated in anonymous maps, by `Scratch` identifier
+///
+/// The accounting is kept here instead of in the `Scratch`, so that vectors
+/// allocated in it are plain values. Entries are removed once they drop to
+/// zero.
+static ANONYMOUS: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+/// Where to store allocations and how much anonymous memory they may take.
+///
+/// Clones share the budget: the anonymous memory of all allocations made with
+/// a `Scratch` or any of its clones counts towards it. Independent values, for
+/// example one per concurrent proof, do not affect each other. The default
+/// has no directory and no budget, so all allocations are anonymous.
+#[derive(Clone, Debug)]
+pub struct Scratch {
+    directory: Option<PathBuf>,
+    budget:    Option<usize>,
+    /// Identifies the accounting shared by all clones
+    id:        usize,
+}
+
+impl Default for Scratch {
+    fn default() -> Self {
+        Self::new_unchecked(None, None)
+    }
+}
+
+impl Scratch {
+    /// Allocations that would take more than `budget` bytes of anonymous
+    /// memory go to files in `directory`.
+    ///
+    /// Files are unlinked directly after they are mapped, so they do not
+    /// outlive the process even if it is killed. Without a directory the
+    /// budget has no effect on allocation, but it is still reported by
+    /// [`Scratch::budget`] so algorithms can pick smaller working sets. A
+    /// budget of `None` means no limit.
+    ///
+    /// The budget is a limit on the anonymous memory, not on the resident
+    /// memory. The operating system decides which pages of the files stay in
+    /// memory, so algorithms should work on them in chunks to keep the
+    /// working set small.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no files can be created in `directory`, for
+    /// example because it does not exist or is not writable. Should creating
+    /// a file fail later on, for example because the disk is full, the
+    /// allocation falls back to anonymous memory and logs a warning.
+    pub fn new(directory: Option<PathBuf>, budget: Option<usize>) -> io::Result<Self> {
+        if let Some(directory) = &directory {
+            let path = scratch_file(directory);
+            let _ = create_file(&path, 1)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+            remove_file(&path)?;
+        }
+        Ok(Self::new_unchecked(directory, budget))
+    }
+
+    fn new_unchecked(directory: Option<PathBuf>, budget: Option<usize>) -> Self {
+        Self {
+            directory,
+            budget,
+            id: SCRATCHES.fetch_add(1, Ordering::SeqCst),
+        }
+    }
+
+    /// The directory used for file-backed allocations, if any.
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /// The memory budget in bytes, if any.
+    pub fn budget(&self) -> Option<usize> {
+        self.budget
+    }
+
+    /// Bytes currently allocated in anonymous memory maps.
+    pub fn anonymous_bytes(&self) -> usize {
+        accounts().get(&self.id).copied().unwrap_or(0)
+    }
+}
+
+fn accounts() -> MutexGuard<'static, BTreeMap<usize, usize>> {
+    ANONYMOUS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn scratch_file(directory: &Path) -> PathBuf {
+    directory.join(format!(
+        "mmap-vec-{}-{}",
+        process::id(),
+        FILES.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+fn create_file(path: &Path, size: usize) -> io::Result<File> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    if let Err(err) = file.set_len(size as u64) {
+        let _ = remove_file(path);
+        return Err(err);
+    }
+    Ok(file)
+}
+
+/// A memory map together with its accounting.
+#[derive(Debug)]
+pub(crate) struct Allocation {
+    pub(crate) mmap:    MmapMut,
+    pub(crate) scratch: Scratch,
+    anonymous:          usize,
+}
+
+impl Allocation {
+    pub(crate) fn new(size: usize, scratch: &Scratch) -> Self {
+        if let Some(directory) = &scratch.directory {
+            let over_budget = {
+                let mut accounts = accounts();
+                let anonymous = accounts.entry(scratch.id).or_insert(0);
+                if anonymous.saturating_add(size) > scratch.budget.unwrap_or(usize::MAX) {
+                    true
+                } else {
+                    *anonymous += size;
+                    false
+                }
+            };
+            if over_budget {
+                match Self::file_backed(directory, size, scratch) {
+                    Ok(allocation) => return allocation,
+                    Err(err) => {
+                        warn!(
+                            "Cannot create scratch file in {}, allocating {} MB in memory instead: \
+                             {}",
+                            directory.display(),
+                            size / 1_000_000,
+                            err
+                        );
+                        *accounts().entry(scratch.id).or_insert(0) += size;
+                    }
+                }
+            }
+        } else {
+            *accounts().entry(scratch.id).or_insert(0) += size;
+        }
+        Self::anonymous(size, scratch)
+    }
+
+    fn file_backed(directory: &Path, size: usize, scratch: &Scratch) -> io::Result<Self> {
+        let path = scratch_file(directory);
+        trace!(
+            "Allocating {} MB in file {}",
+            size / 1_000_000,
+            path.display()
+        );
+        let file = create_file(&path, size)?;
+        // The file was just created by us and is unlinked right after mapping,
+        // so no other process can modify it underneath us.
+        let mmap = unsafe { MmapOptions::new().len(size).map_mut(&file) };
+        // Unlink the file even if it could not be mapped
+        let removed = remove_file(&path);
+        let mmap = mmap?;
+        removed?;
+        Ok(Self {
+            mmap,
+            scratch: scratch.clone(),
+            anonymous: 0,
+        })
+    }
+
+    // Caller must have added `size` to the anonymous bytes of `scratch`.
+    fn anonymous(size: usize, scratch: &Scratch) -> Self {
+        trace!("Allocating {} MB in anonymous mmap", size / 1_000_000);
+        let mmap = MmapOptions::new()
+            .len(size)
+            .map_anon()
+            .expect("cannot access memory mapped file");
+        Self {
+            mmap,
+            scratch: scratch.clone(),
+            anonymous: size,
+        }
+    }
+
+    pub(crate) fn is_file_backed(&self) -> bool {
+        self.anonymous == 0
+    }
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        let mut accounts = accounts();
+        if let Some(anonymous) = accounts.get_mut(&self.scratch.id) {
+            *anonymous -= self.anonymous;
+            if *anonymous == 0 {
+                let _ = accounts.remove(&self.scratch.id);
+            }
+        }
+    }
+}
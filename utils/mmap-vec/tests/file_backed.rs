@@ -0,0 +1,40 @@
+//! Allocations under an exhausted memory budget.
+#![cfg(feature = "std")]
+use zkp_mmap_vec::{InScratch, MmapVec, Scratch};
+
+#[test]
+fn test_file_backed() {
+    let directory = std::env::temp_dir().join(format!("mmap-vec-test-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let scratch = Scratch::new(Some(directory.clone()), Some(0)).unwrap();
+    let mut m: MmapVec<u64> = MmapVec::with_capacity_in_scratch(1000, &scratch);
+    assert!(m.is_file_backed());
+    assert!(!MmapVec::<u64>::with_capacity(1000).is_file_backed());
+    m.extend((0..1000_u64).map(|i| i * i));
+    assert!(m.iter().enumerate().all(|(i, &x)| x == (i * i) as u64));
+    assert!(m.clone().is_file_backed());
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 0);
+    std::fs::remove_dir(&directory).unwrap();
+}
+
+#[test]
+fn test_budget() {
+    let directory = std::env::temp_dir().join(format!("mmap-vec-budget-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let scratch = Scratch::new(Some(directory.clone()), Some(8000)).unwrap();
+    let first: MmapVec<u64> = MmapVec::with_capacity_in_scratch(1000, &scratch);
+    let second: MmapVec<u64> = MmapVec::with_capacity_in_scratch(1000, &scratch.clone());
+    assert!(!first.is_file_backed());
+    assert!(second.is_file_backed());
+    assert_eq!(scratch.anonymous_bytes(), 8000);
+    drop(first);
+    assert_eq!(scratch.anonymous_bytes(), 0);
+    drop(second);
+    std::fs::remove_dir(&directory).unwrap();
+}
+
+#[test]
+fn test_missing_directory() {
+    let directory = std::env::temp_dir().join(format!("mmap-vec-missing-{}", std::process::id()));
+    assert!(Scratch::new(Some(directory), None).is_err());
+}